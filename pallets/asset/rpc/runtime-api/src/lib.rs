@@ -16,14 +16,27 @@
 //! Runtime API definition for Identity module.
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use codec::Codec;
+use codec::{Codec, Decode, Encode};
 use frame_support::traits::Currency;
-use polymesh_primitives::{IdentityId, Ticker};
+use polymesh_primitives::{ClaimType, IdentityId, Ticker, TransferReport};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 use sp_std::vec::Vec;
 
 pub type Error = Vec<u8>;
 pub type CanTransferResult = Result<u8, Error>;
 
+/// A page of the holders of an asset.
+#[derive(Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct HoldersPage<Balance> {
+    /// Holders with a non-zero balance and their balances.
+    pub holders: Vec<(IdentityId, Balance)>,
+    /// Index of the holder list to request the next page from, if any.
+    pub next: Option<u64>,
+}
+
 pub trait Trait: frame_system::Trait {
     type Currency: Currency<Self::AccountId>;
 }
@@ -64,5 +77,73 @@ sp_api::decl_runtime_apis! {
             to_did: Option<IdentityId>,
             value: Balance
         ) -> CanTransferResult;
+
+         /// Returns up to `count` holders of `ticker` and their balances, starting from index
+         /// `start` of the holder list. Balances are taken at `checkpoint_id` if given, otherwise
+         /// the latest balances are returned.
+         ///
+         /// # Example
+         ///
+         /// In this example we are fetching the first 100 holders of ticker 0x01 at checkpoint 2.
+         ///
+         /// ```ignore
+         ///  curl
+         ///    -H "Content-Type: application/json"
+         ///    -d {
+         ///        "id":1, "jsonrpc":"2.0",
+         ///        "method": "asset_holders",
+         ///        "params":["0x010000000000000000000000", 2, 0, 100]}
+         ///    http://localhost:9933 | python3 -m json.tool
+         /// ```
+        fn holders(
+            ticker: Ticker,
+            checkpoint_id: Option<u64>,
+            start: u64,
+            count: u64
+        ) -> HoldersPage<Balance>;
+
+         /// Explains the result of `can_transfer`: granularity, balance, CDD status of each
+         /// side, frozen state, the result of each compliance rule with the claims and issuers
+         /// consulted, and the result of each smart extension.
+         ///
+         /// # Example
+         ///
+         /// ```ignore
+         ///  curl
+         ///    -H "Content-Type: application/json"
+         ///    -d {
+         ///        "id":1, "jsonrpc":"2.0",
+         ///        "method": "asset_transferReport",
+         ///        "params":[
+         ///            "5CoRaw9Ex4DUjGcnPbPBnc2nez5ZeTmM5WL3ZDVLZzM6eEgE",
+         ///            "0x010000000000000000000000",
+         ///            "0x2a00000000000000000000000000000000000000000000000000000000000000",
+         ///            "0x3905000000000000000000000000000000000000000000000000000000000000",
+         ///            500]}
+         ///    http://localhost:9933 | python3 -m json.tool
+         /// ```
+        fn transfer_report(
+            sender: AccountId,
+            ticker: Ticker,
+            from_did: Option<IdentityId>,
+            to_did: Option<IdentityId>,
+            value: Balance
+        ) -> TransferReport<AccountId, Balance>;
+
+         /// Returns the identities exempted from the compliance rules of `ticker`, with the claim
+         /// type of the exempted rules, or `None` if they are exempted from all the rules.
+         ///
+         /// # Example
+         ///
+         /// ```ignore
+         ///  curl
+         ///    -H "Content-Type: application/json"
+         ///    -d {
+         ///        "id":1, "jsonrpc":"2.0",
+         ///        "method": "asset_exemptions",
+         ///        "params":["0x010000000000000000000000"]}
+         ///    http://localhost:9933 | python3 -m json.tool
+         /// ```
+        fn exemptions(ticker: Ticker) -> Vec<(IdentityId, Option<ClaimType>)>;
     }
 }
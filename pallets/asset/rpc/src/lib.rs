@@ -16,11 +16,14 @@
 use codec::Codec;
 use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
 use jsonrpc_derive::rpc;
-use pallet_asset_rpc_runtime_api::{AssetApi as AssetRuntimeApi, CanTransferResult};
-use polymesh_primitives::{IdentityId, Ticker};
+use pallet_asset_rpc_runtime_api::{AssetApi as AssetRuntimeApi, CanTransferResult, HoldersPage};
+use polymesh_primitives::{ClaimType, IdentityId, Ticker, TransferReport};
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
-use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block as BlockT, UniqueSaturatedInto},
+};
 use std::sync::Arc;
 
 use frame_support::traits::Currency;
@@ -43,6 +46,41 @@ pub trait AssetApi<BlockHash, AccountId, T> {
         value: T,
         at: Option<BlockHash>,
     ) -> Result<CanTransferResult>;
+
+    /// Returns a page of the holders of `ticker` at the latest state or at `checkpoint_id`.
+    ///
+    /// Balances are capped to `u64` to avoid serde issues with `u128`.
+    #[rpc(name = "asset_holders")]
+    fn holders(
+        &self,
+        ticker: Ticker,
+        checkpoint_id: Option<u64>,
+        start: u64,
+        count: u64,
+        at: Option<BlockHash>,
+    ) -> Result<HoldersPage<u64>>;
+
+    /// Explains the result of `asset_canTransfer`.
+    ///
+    /// Balances are capped to `u64` to avoid serde issues with `u128`.
+    #[rpc(name = "asset_transferReport")]
+    fn transfer_report(
+        &self,
+        sender: AccountId,
+        ticker: Ticker,
+        from_did: Option<IdentityId>,
+        to_did: Option<IdentityId>,
+        value: T,
+        at: Option<BlockHash>,
+    ) -> Result<TransferReport<AccountId, u64>>;
+
+    /// Returns the identities exempted from the compliance rules of `ticker`.
+    #[rpc(name = "asset_exemptions")]
+    fn exemptions(
+        &self,
+        ticker: Ticker,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<(IdentityId, Option<ClaimType>)>>;
 }
 
 /// An implementation of asset specific RPC methods.
@@ -77,7 +115,7 @@ where
     C: HeaderBackend<Block>,
     C::Api: AssetRuntimeApi<Block, AccountId, T>,
     AccountId: Codec,
-    T: Codec,
+    T: Codec + UniqueSaturatedInto<u64>,
 {
     fn can_transfer(
         &self,
@@ -100,4 +138,79 @@ where
                 data: Some(format!("{:?}", e).into()),
             })
     }
+
+    fn holders(
+        &self,
+        ticker: Ticker,
+        checkpoint_id: Option<u64>,
+        start: u64,
+        count: u64,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<HoldersPage<u64>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+        api.holders(&at, ticker, checkpoint_id, start, count)
+            .map_err(|e| RpcError {
+                code: ErrorCode::ServerError(Error::RuntimeError as i64),
+                message: "Unable to query holders.".into(),
+                data: Some(format!("{:?}", e).into()),
+            })
+            .map(|page| HoldersPage {
+                holders: page
+                    .holders
+                    .into_iter()
+                    .map(|(did, balance)| (did, balance.unique_saturated_into()))
+                    .collect(),
+                next: page.next,
+            })
+    }
+
+    fn transfer_report(
+        &self,
+        sender: AccountId,
+        ticker: Ticker,
+        from_did: Option<IdentityId>,
+        to_did: Option<IdentityId>,
+        value: T,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<TransferReport<AccountId, u64>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+        api.transfer_report(&at, sender, ticker, from_did, to_did, value)
+            .map_err(|e| RpcError {
+                code: ErrorCode::ServerError(Error::RuntimeError as i64),
+                message: "Unable to build the transfer report.".into(),
+                data: Some(format!("{:?}", e).into()),
+            })
+            .map(|report| TransferReport {
+                status: report.status,
+                granularity: report.granularity,
+                sender_balance: report
+                    .sender_balance
+                    .map(|balance| balance.unique_saturated_into()),
+                sufficient_balance: report.sufficient_balance,
+                sender_cdd: report.sender_cdd,
+                receiver_cdd: report.receiver_cdd,
+                frozen: report.frozen,
+                compliance: report.compliance,
+                extensions: report.extensions,
+            })
+    }
+
+    fn exemptions(
+        &self,
+        ticker: Ticker,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<(IdentityId, Option<ClaimType>)>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+        api.exemptions(&at, ticker).map_err(|e| RpcError {
+            code: ErrorCode::ServerError(Error::RuntimeError as i64),
+            message: "Unable to query exemptions.".into(),
+            data: Some(format!("{:?}", e).into()),
+        })
+    }
 }
@@ -29,7 +29,8 @@
 //! - Creation of checkpoints on the token level.
 //! - Management of the token (Document mgt etc).
 //! - Transfer/redeem functionality of the token.
-//! - Custodian functionality.
+//! - Portfolios that segregate the holdings of an identity.
+//! - Custodian functionality on the portfolio level.
 //!
 //! ## Interface
 //!
@@ -60,9 +61,15 @@
 //! - `add_documents` - Add documents for a given token, Only be called by the token owner.
 //! - `remove_documents` - Remove documents for a given token, Only be called by the token owner.
 //! - `update_documents` - Update documents for the given token, Only be called by the token owner.
-//! - `increase_custody_allowance` - Used to increase the allowance for a given custodian.
-//! - `increase_custody_allowance_of` - Used to increase the allowance for a given custodian by providing the off chain signature.
-//! - `transfer_by_custodian` - Used to transfer the tokens by the approved custodian.
+//! - `create_checkpoint_schedule` - Schedules checkpoints to be created at regular intervals.
+//! - `remove_checkpoint_schedule` - Removes a checkpoint schedule.
+//! - `create_portfolio` - Creates a new user portfolio for the caller DID.
+//! - `delete_portfolio` - Deletes an empty user portfolio.
+//! - `rename_portfolio` - Renames a user portfolio.
+//! - `move_portfolio_funds` - Moves tokens between two portfolios of the same DID.
+//! - `portfolio_transfer` - Transfers tokens from a portfolio of one DID to a portfolio of another DID.
+//! - `accept_portfolio_custody` - Used to accept the custody of a portfolio.
+//! - `quit_portfolio_custody` - Returns the custody of a portfolio back to its owner.
 //! - `set_funding_round` - Sets the name of the current funding round.
 //! - `update_identifiers` - Updates the asset identifiers. Only called by the token owner.
 //! - `add_extension` - It is used to whitelist the Smart-Extension address for a given ticker.
@@ -78,8 +85,9 @@
 //! - `identifiers` - It provides the identifiers for a given ticker.
 //! - `total_checkpoints_of` - Returns the checkpoint Id.
 //! - `total_supply_at` - Returns the total supply at a given checkpoint.
-//! - `custodian_allowance`- Returns the allowance provided to a custodian for a given ticker and token holder.
-//! - `total_custody_allowance` - Returns the total allowance approved by the token holder.
+//! - `portfolios` - Returns the name of a user portfolio.
+//! - `portfolio_balance` - Returns the balance of a ticker held in a given portfolio.
+//! - `custodian_of` - Returns the custodian DID of a given portfolio.
 //! - `extension_details` - It provides the list of Smart extension added for the given tokens.
 //! - `extensions` - It provides the list of Smart extension added for the given tokens and for the given type.
 //! - `frozen` - It tells whether the given ticker is frozen or not.
@@ -88,6 +96,10 @@
 //! - `is_ticker_available_or_registered_to` - It provides the status of a given ticker.
 //! - `total_supply` - It provides the total supply of a ticker.
 //! - `get_balance_at` - It provides the balance of a DID at a certain checkpoint.
+//! - `checkpoint_at` - Returns the latest checkpoint created at or before a given timestamp.
+//! - `schedule_checkpoint_at` - Returns the latest checkpoint of a schedule created at or before a given timestamp.
+//! - `holder_count` - Returns the number of identities which have ever held a ticker.
+//! - `holder_at` - Returns an identity which has held a ticker, by its index in the holder list.
 //! - `verify_restriction` - It is use to verify the restriction implied by the smart extension and the Compliance Manager.
 //! - `call_extension` - A helper function that is used to call the smart extension function.
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -98,7 +110,7 @@ pub mod benchmarking;
 use pallet_identity as identity;
 use pallet_statistics as statistics;
 use polymesh_common_utilities::{
-    asset::{AcceptTransfer, Trait as AssetTrait},
+    asset::{AcceptTransfer, AssetHoldings, Trait as AssetTrait},
     balances::Trait as BalancesTrait,
     compliance_manager::Trait as ComplianceManagerTrait,
     constants::*,
@@ -106,9 +118,11 @@ use polymesh_common_utilities::{
     protocol_fee::{ChargeProtocolFee, ProtocolOp},
     CommonTrait, Context,
 };
+pub use polymesh_primitives::RestrictionResult;
 use polymesh_primitives::{
-    AccountKey, AuthorizationData, AuthorizationError, Document, IdentityId, LinkData, Signatory,
-    SmartExtension, SmartExtensionName, SmartExtensionType, Ticker,
+    migration, AccountKey, AuthorizationData, AuthorizationError, Document, IdentityId, LinkData,
+    PortfolioId, PortfolioKind, PortfolioName, PortfolioNumber, Signatory, SmartExtension,
+    SmartExtensionName, SmartExtensionType, Ticker, TransferReport,
 };
 
 use codec::{Decode, Encode};
@@ -116,19 +130,28 @@ use core::result::Result as StdResult;
 use currency::*;
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage,
-    dispatch::DispatchResult,
+    dispatch::{DispatchError, DispatchResult},
     ensure,
-    traits::Currency,
+    traits::{Currency, Get},
     weights::{DispatchClass, FunctionOf, SimpleDispatchInfo},
 };
 use frame_system::{self as system, ensure_signed};
 use hex_literal::hex;
 use pallet_contracts::{ExecReturnValue, Gas};
-use sp_runtime::traits::{CheckedAdd, CheckedSub, Verify};
+use sp_runtime::{
+    traits::{CheckedAdd, CheckedSub, One, Saturating, Zero},
+    SaturatedConversion,
+};
 
 #[cfg(feature = "std")]
 use sp_runtime::{Deserialize, Serialize};
-use sp_std::{convert::TryFrom, prelude::*};
+use sp_std::{collections::btree_map::BTreeMap, convert::TryFrom, prelude::*};
+
+/// Maximum number of checkpoint schedules of a token.
+pub const MAX_CHECKPOINT_SCHEDULES: usize = 10;
+
+/// Maximum number of checkpoint schedules indexed at a block.
+pub const MAX_SCHEDULED_CHECKPOINTS_PER_BLOCK: usize = 50;
 
 /// The module's configuration trait.
 pub trait Trait:
@@ -243,16 +266,6 @@ pub struct SecurityToken<U> {
     pub link_id: u64,
 }
 
-/// struct to store the signed data.
-#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
-pub struct SignData<U> {
-    pub custodian_did: IdentityId,
-    pub holder_did: IdentityId,
-    pub ticker: Ticker,
-    pub value: U,
-    pub nonce: u16,
-}
-
 /// struct to store the ticker registration details.
 #[derive(Encode, Decode, Clone, Default, PartialEq, Debug)]
 pub struct TickerRegistration<U> {
@@ -269,6 +282,19 @@ pub struct TickerRegistrationConfig<U> {
     pub registration_length: Option<U>,
 }
 
+/// A schedule of checkpoints created at regular intervals.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+pub struct CheckpointSchedule<U> {
+    /// Timestamp of the first checkpoint.
+    pub start: U,
+    /// Time between two checkpoints.
+    pub period: U,
+    /// Number of checkpoints left to create, or 0 if the schedule never ends.
+    pub remaining: u32,
+    /// Timestamp of the next checkpoint.
+    pub next_at: U,
+}
+
 /// Enum that represents the current status of a ticker.
 #[derive(Encode, Decode, Clone, Eq, PartialEq, Debug)]
 pub enum TickerRegistrationStatus {
@@ -277,20 +303,6 @@ pub enum TickerRegistrationStatus {
     RegisteredByDid,
 }
 
-/// Enum that uses as the return type for the restriction verification.
-#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub enum RestrictionResult {
-    Valid,
-    Invalid,
-    ForceValid,
-}
-
-impl Default for RestrictionResult {
-    fn default() -> Self {
-        RestrictionResult::Invalid
-    }
-}
-
 decl_storage! {
     trait Store for Module<T: Trait> as Asset {
         /// Ticker registration details.
@@ -305,6 +317,15 @@ decl_storage! {
         /// Used to store the securityToken balance corresponds to ticker and Identity.
         /// (ticker, DID) -> Balance
         pub BalanceOf get(fn balance_of): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) IdentityId => T::Balance;
+        /// Number of identities which have ever held a token.
+        /// (ticker) -> count
+        pub HolderCount get(fn holder_count): map hasher(blake2_128_concat) Ticker => u64;
+        /// Identities which have ever held a token, in the order in which they first received it.
+        /// (ticker, index) -> DID
+        pub Holders get(fn holder_at): double_map hasher(blake2_128_concat) Ticker, hasher(twox_64_concat) u64 => IdentityId;
+        /// Whether an identity is in the holder list of a token.
+        /// (ticker, DID) -> listed
+        IsHolderListed get(fn is_holder_listed): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) IdentityId => bool;
         /// A map of pairs of a ticker name and an `IdentifierType` to asset identifiers.
         pub Identifiers get(fn identifiers): map hasher(blake2_128_concat) (Ticker, IdentifierType) => AssetIdentifier;
         /// (ticker, sender (DID), spender(DID)) -> allowance amount
@@ -315,21 +336,47 @@ decl_storage! {
         /// Total supply of the token at the checkpoint.
         /// (ticker, checkpointId) -> total supply at given checkpoint
         pub CheckpointTotalSupply get(fn total_supply_at): map hasher(blake2_128_concat) (Ticker, u64) => T::Balance;
+        /// Timestamp of the creation of a checkpoint.
+        /// (ticker, checkpointId) -> timestamp
+        pub CheckpointTimestamps get(fn checkpoint_timestamp): map hasher(blake2_128_concat) (Ticker, u64) => T::Moment;
+        /// Checkpoint schedules of a token.
+        /// (ticker, schedule ID) -> schedule
+        pub CheckpointSchedules get(fn checkpoint_schedules): double_map hasher(blake2_128_concat) Ticker, hasher(twox_64_concat) u64 => Option<CheckpointSchedule<T::Moment>>;
+        /// Number of checkpoint schedules created for a token so far. Schedule IDs are 0-indexed.
+        /// (ticker) -> count
+        CheckpointScheduleCount get(fn checkpoint_schedule_count): map hasher(blake2_128_concat) Ticker => u64;
+        /// Checkpoint schedules to check at a block. A schedule is indexed at a block no later than
+        /// its next due time, and indexed again at a later block if it is not due yet.
+        /// (block number) -> (ticker, schedule ID)
+        ScheduledCheckpointsAt get(fn scheduled_checkpoints_at): map hasher(twox_64_concat) T::BlockNumber => Vec<(Ticker, u64)>;
+        /// Checkpoints created by a schedule, in chronological order.
+        /// (ticker, schedule ID) -> checkpoint IDs
+        pub ScheduleCheckpoints get(fn schedule_checkpoints): map hasher(blake2_128_concat) (Ticker, u64) => Vec<u64>;
         /// Balance of a DID at a checkpoint.
         /// (ticker, DID, checkpoint ID) -> Balance of a DID at a checkpoint
         CheckpointBalance get(fn balance_at_checkpoint): map hasher(blake2_128_concat) (Ticker, IdentityId, u64) => T::Balance;
         /// Last checkpoint updated for a DID's balance.
         /// (ticker, DID) -> List of checkpoints where user balance changed
         UserCheckpoints get(fn user_checkpoints): map hasher(blake2_128_concat) (Ticker, IdentityId) => Vec<u64>;
-        /// Allowance provided to the custodian.
-        /// (ticker, token holder, custodian) -> balance
-        pub CustodianAllowance get(fn custodian_allowance): map hasher(blake2_128_concat) (Ticker, IdentityId, IdentityId) => T::Balance;
-        /// Total custodian allowance for a given token holder.
-        /// (ticker, token holder) -> balance
-        pub TotalCustodyAllowance get(fn total_custody_allowance): map hasher(blake2_128_concat) (Ticker, IdentityId) => T::Balance;
-        /// Store the nonce for off chain signature to increase the custody allowance.
-        /// (ticker, token holder, nonce) -> bool
-        AuthenticationNonce get(fn authentication_nonce): map hasher(blake2_128_concat) (Ticker, IdentityId, u16) => bool;
+        /// The number of the last user portfolio created by a DID.
+        /// (DID) -> portfolio number
+        LastPortfolioNumber get(fn last_portfolio_number): map hasher(blake2_128_concat) IdentityId => PortfolioNumber;
+        /// Names of the user portfolios of a DID.
+        /// (DID, portfolio number) -> portfolio name
+        pub Portfolios get(fn portfolios): double_map hasher(blake2_128_concat) IdentityId, hasher(twox_64_concat) PortfolioNumber => PortfolioName;
+        /// Balance of a token held in a user portfolio.
+        /// The default portfolio is not stored here, it holds whatever part of `BalanceOf` is not in a user portfolio.
+        /// (portfolio, ticker) -> balance
+        PortfolioAssetBalances get(fn portfolio_asset_balances): double_map hasher(blake2_128_concat) PortfolioId, hasher(blake2_128_concat) Ticker => T::Balance;
+        /// Sum of the balances of a token held in all the user portfolios of a DID.
+        /// (ticker, DID) -> balance
+        UserPortfoliosBalance get(fn user_portfolios_balance): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) IdentityId => T::Balance;
+        /// User portfolios of a DID which hold a non-zero balance of a token.
+        /// (ticker, DID) -> portfolio numbers
+        UserPortfoliosHolding get(fn user_portfolios_holding): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) IdentityId => Vec<PortfolioNumber>;
+        /// Custodian of a portfolio. If not set, the portfolio is in the custody of its owner.
+        /// (portfolio) -> custodian DID
+        PortfolioCustodian get(fn portfolio_custodian): map hasher(blake2_128_concat) PortfolioId => Option<IdentityId>;
         /// The name of the current funding round.
         /// ticker -> funding round
         FundingRound get(fn funding_round): map hasher(blake2_128_concat) Ticker => FundingRoundName;
@@ -345,6 +392,16 @@ decl_storage! {
         /// The set of frozen assets implemented as a membership map.
         /// ticker -> bool
         pub Frozen get(fn frozen): map hasher(blake2_128_concat) Ticker => bool;
+        /// Tickers of which an identity holds a non-zero balance.
+        /// (did, ticker) -> bool
+        pub HeldTickers get(fn is_ticker_held): double_map hasher(blake2_128_concat) IdentityId, hasher(blake2_128_concat) Ticker => bool;
+        /// Tickers registered to an identity, whether their registration expired or not.
+        /// (did) -> tickers
+        pub OwnedTickers get(fn owned_tickers): map hasher(blake2_128_concat) IdentityId => Vec<Ticker>;
+        /// Version of the storage of the module, which migrations update.
+        pub StorageVersion get(fn storage_version) build(|_| 1u32): u32;
+        /// Progress of the storage migration in progress.
+        MigrationCursor get(fn migration_cursor): migration::MigrationCursor;
     }
 }
 
@@ -359,6 +416,14 @@ decl_module! {
         /// initialize the default event for this module
         fn deposit_event() = default;
 
+        /// Creates the checkpoints of the schedules which are due.
+        fn on_initialize(block_number: T::BlockNumber) {
+            if Self::storage_version() < 1 {
+                Self::migrate_storage();
+            }
+            Self::create_scheduled_checkpoints(block_number);
+        }
+
         /// This function is used to either register a new ticker or extend validity of an existing ticker.
         /// NB: Ticker validity does not get carry forward when renewing ticker.
         ///
@@ -519,7 +584,8 @@ decl_module! {
                 link_id: link,
             };
             <Tokens<T>>::insert(&ticker, token);
-            <BalanceOf<T>>::insert(ticker, did, total_supply);
+            Self::_set_balance(&ticker, did, total_supply);
+            Self::add_holder(&ticker, did);
             Self::deposit_event(RawEvent::AssetCreated(
                 did,
                 ticker,
@@ -625,14 +691,21 @@ decl_module! {
             let sender_key = AccountKey::try_from(sender.encode())?;
             let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
 
-            // Check whether the custody allowance remain intact or not
-            Self::_check_custody_allowance(&ticker, did, value)?;
+            // Tokens are sent from the default portfolio of the sender.
+            Self::_check_portfolio_spendable(&PortfolioId::default_portfolio(did), did, &ticker, value)?;
             ensure!(
                 Self::_is_valid_transfer(&ticker, sender, Some(did), Some(to_did), value)? == ERC1400_TRANSFER_SUCCESS,
                 Error::<T>::InvalidTransfer
             );
 
-            Self::_transfer(did, &ticker, did, to_did, value)
+            Self::_spend_between_portfolios(
+                &sender_key,
+                did,
+                &ticker,
+                PortfolioId::default_portfolio(did),
+                PortfolioId::default_portfolio(to_did),
+                value,
+            )
         }
 
         /// Forces a transfer between two DIDs & This can only be called by security token owner.
@@ -652,7 +725,16 @@ decl_module! {
             let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
 
             ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            ensure!(Self::check_granularity(&ticker, value), Error::<T>::InvalidGranularity);
+            ensure!(Self::balance(&ticker, &from_did) >= value, Error::<T>::InsufficientBalance);
+            ensure!(
+                Self::balance(&ticker, &to_did).checked_add(&value).is_some(),
+                Error::<T>::BalanceOverflow
+            );
 
+            // A forced transfer is not limited to the default portfolio of `from_did`.
+            let release = Self::_default_portfolio_release(&ticker, from_did, value)?;
+            Self::_release_to_default_portfolio(&ticker, release);
             Self::_transfer(did, &ticker, from_did, to_did, value)?;
 
             Self::deposit_event(RawEvent::ControllerTransfer(did, ticker, from_did, to_did, value, data, operator_data));
@@ -705,14 +787,21 @@ decl_module! {
             // using checked_sub (safe math) to avoid overflow
             let updated_allowance = allowance.checked_sub(&value)
                 .ok_or(Error::<T>::AllowanceOverflow)?;
-            // Check whether the custody allowance remain intact or not
-            Self::_check_custody_allowance(&ticker, from_did, value)?;
+            // Tokens are sent from the default portfolio of `from_did`.
+            Self::_check_portfolio_spendable(&PortfolioId::default_portfolio(from_did), from_did, &ticker, value)?;
 
             ensure!(
                 Self::_is_valid_transfer(&ticker, sender, Some(from_did), Some(to_did), value)? == ERC1400_TRANSFER_SUCCESS,
                 Error::<T>::InvalidTransfer
             );
-            Self::_transfer(did, &ticker, from_did, to_did, value)?;
+            Self::_spend_between_portfolios(
+                &sender_key,
+                did,
+                &ticker,
+                PortfolioId::default_portfolio(from_did),
+                PortfolioId::default_portfolio(to_did),
+                value,
+            )?;
 
             // Change allowance afterwards
             <Allowance<T>>::insert(&ticker_from_did_did, updated_allowance);
@@ -738,6 +827,75 @@ decl_module! {
             Ok(())
         }
 
+        /// Schedules checkpoints of a token to be created at regular intervals, from the first block
+        /// after each due time.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the token owner.
+        /// * `ticker` Ticker of the token.
+        /// * `start` Timestamp of the first checkpoint.
+        /// * `period` Time between two checkpoints.
+        /// * `remaining` Number of checkpoints to create, or 0 to create them until the schedule is removed.
+        ///
+        /// A token has at most `MAX_CHECKPOINT_SCHEDULES` schedules. If `start` has already passed,
+        /// the first checkpoint is created in the next block.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn create_checkpoint_schedule(
+            origin,
+            ticker: Ticker,
+            start: T::Moment,
+            period: T::Moment,
+            remaining: u32
+        ) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            ensure!(!period.is_zero(), Error::<T>::InvalidCheckpointPeriod);
+            ensure!(
+                <CheckpointSchedules<T>>::iter_prefix(&ticker).count() < MAX_CHECKPOINT_SCHEDULES,
+                Error::<T>::TooManyCheckpointSchedules
+            );
+            let schedule_id = Self::checkpoint_schedule_count(&ticker);
+            let next_schedule_id = schedule_id
+                .checked_add(1)
+                .ok_or(Error::<T>::CheckpointScheduleOverflow)?;
+
+            let schedule = CheckpointSchedule {
+                start,
+                period,
+                remaining,
+                next_at: start,
+            };
+            <CheckpointScheduleCount>::insert(&ticker, next_schedule_id);
+            <CheckpointSchedules<T>>::insert(&ticker, schedule_id, schedule.clone());
+            Self::index_checkpoint_schedule(ticker, schedule_id, start);
+            Self::deposit_event(RawEvent::CheckpointScheduleCreated(did, ticker, schedule_id, schedule));
+            Ok(())
+        }
+
+        /// Removes a checkpoint schedule. The checkpoints it has already created are kept.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the token owner.
+        /// * `ticker` Ticker of the token.
+        /// * `schedule_id` ID of the schedule.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn remove_checkpoint_schedule(origin, ticker: Ticker, schedule_id: u64) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            ensure!(
+                <CheckpointSchedules<T>>::contains_key(&ticker, schedule_id),
+                Error::<T>::NoSuchCheckpointSchedule
+            );
+            // The index entry of the schedule is skipped once the schedule is gone.
+            <CheckpointSchedules<T>>::remove(&ticker, schedule_id);
+            Self::deposit_event(RawEvent::CheckpointScheduleRemoved(did, ticker, schedule_id));
+            Ok(())
+        }
+
         /// Function is used to issue(or mint) new tokens for the given DID
         /// can only be executed by the token owner.
         ///
@@ -842,8 +1000,9 @@ decl_module! {
             // Update investor balances and emit events quoting the updated total token balance issued.
             for i in 0..investor_dids.len() {
                 Self::_update_checkpoint(&ticker, investor_dids[i], current_balances[i]);
-                <BalanceOf<T>>::insert(ticker, investor_dids[i], updated_balances[i]);
-                <statistics::Module<T>>::update_transfer_stats(&ticker, None, Some(updated_balances[i]), values[i]);
+                Self::_set_balance(&ticker, investor_dids[i], updated_balances[i]);
+                Self::add_holder(&ticker, investor_dids[i]);
+                <statistics::Module<T>>::update_transfer_stats(&ticker, None, Some((investor_dids[i], updated_balances[i])), values[i]);
                 Self::deposit_event(RawEvent::Issued(
                     did,
                     ticker,
@@ -881,8 +1040,8 @@ decl_module! {
             let updated_burner_balance = burner_balance
                 .checked_sub(&value)
                 .ok_or(Error::<T>::BalanceOverflow)?;
-            // Check whether the custody allowance remain intact or not
-            Self::_check_custody_allowance(&ticker, did, value)?;
+            // Tokens are redeemed from the default portfolio.
+            Self::_check_portfolio_spendable(&PortfolioId::default_portfolio(did), did, &ticker, value)?;
 
             // verify transfer check
             ensure!(
@@ -897,9 +1056,9 @@ decl_module! {
 
             Self::_update_checkpoint(&ticker, did, burner_balance);
 
-            <BalanceOf<T>>::insert(ticker, did, updated_burner_balance);
+            Self::_set_balance(&ticker, did, updated_burner_balance);
             <Tokens<T>>::insert(&ticker, token);
-            <statistics::Module<T>>::update_transfer_stats(&ticker, Some(updated_burner_balance), None, value);
+            <statistics::Module<T>>::update_transfer_stats(&ticker, Some((did, updated_burner_balance)), None, value);
 
             Self::deposit_event(RawEvent::Redeemed(did, ticker, did, value));
             Ok(())
@@ -934,8 +1093,8 @@ decl_module! {
             ensure!(<Allowance<T>>::contains_key(&ticker_from_did_did), Error::<T>::NoSuchAllowance);
             let allowance = Self::allowance(&ticker_from_did_did);
             ensure!(allowance >= value, Error::<T>::InsufficientAllowance);
-            // Check whether the custody allowance remain intact or not
-            Self::_check_custody_allowance(&ticker, did, value)?;
+            // Tokens are redeemed from the default portfolio.
+            Self::_check_portfolio_spendable(&PortfolioId::default_portfolio(did), did, &ticker, value)?;
             ensure!(
                 Self::_is_valid_transfer(&ticker, sender, Some(from_did), None, value)? == ERC1400_TRANSFER_SUCCESS,
                 Error::<T>::InvalidTransfer
@@ -952,9 +1111,9 @@ decl_module! {
             Self::_update_checkpoint(&ticker, did, burner_balance);
 
             <Allowance<T>>::insert(&ticker_from_did_did, updated_allowance);
-            <BalanceOf<T>>::insert(&ticker, &did, updated_burner_balance);
+            Self::_set_balance(&ticker, did, updated_burner_balance);
             <Tokens<T>>::insert(&ticker, token);
-            <statistics::Module<T>>::update_transfer_stats( &ticker, Some(updated_burner_balance), None, value);
+            <statistics::Module<T>>::update_transfer_stats(&ticker, Some((did, updated_burner_balance)), None, value);
 
             Self::deposit_event(RawEvent::Redeemed(did, ticker, from_did, value));
             Self::deposit_event(RawEvent::Approval(did, ticker, from_did, did, value));
@@ -977,26 +1136,7 @@ decl_module! {
             let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
 
             ensure!(Self::is_owner(&ticker, did), Error::<T>::NotAnOwner);
-            // Granularity check
-            ensure!(Self::check_granularity(&ticker, value), Error::<T>::InvalidGranularity);
-            ensure!(<BalanceOf<T>>::contains_key(&ticker, &token_holder_did), Error::<T>::NotAAssetHolder);
-            let burner_balance = Self::balance(&ticker, &token_holder_did);
-            ensure!(burner_balance >= value, Error::<T>::InsufficientBalance);
-
-            // Reduce sender's balance
-            let updated_burner_balance = burner_balance
-                .checked_sub(&value)
-                .ok_or(Error::<T>::BalanceOverflow)?;
-
-            // Decrease total supply
-            let mut token = Self::token_details(&ticker);
-            token.total_supply = token.total_supply.checked_sub(&value).ok_or(Error::<T>::BalanceOverflow)?;
-
-            Self::_update_checkpoint(&ticker, token_holder_did, burner_balance);
-
-            <BalanceOf<T>>::insert(&ticker, &token_holder_did, updated_burner_balance);
-            <Tokens<T>>::insert(&ticker, token);
-            <statistics::Module<T>>::update_transfer_stats( &ticker, Some(updated_burner_balance), None, value);
+            Self::_forced_redeem(&ticker, token_holder_did, value)?;
 
             Self::deposit_event(RawEvent::ControllerRedemption(did, ticker, token_holder_did, value, data, operator_data));
 
@@ -1174,119 +1314,192 @@ decl_module! {
             Ok(())
         }
 
-        /// ERC-2258 Implementation
+        /// Creates a new user portfolio for the caller DID.
+        /// Portfolio names must be unique among the portfolios of a DID.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the portfolio owner.
+        /// * `name` Name of the new portfolio.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn create_portfolio(origin, name: PortfolioName) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
 
-        /// Used to increase the allowance for a given custodian
-        /// Any investor/token holder can add a custodian and transfer the token transfer ownership to the custodian
-        /// Through that investor balance will remain the same but the given token are only transfer by the custodian.
-        /// This implementation make sure to have an accurate investor count from omnibus wallets.
+            Self::ensure_portfolio_name_available(did, &name)?;
+            let num = Self::last_portfolio_number(did)
+                .checked_add(1)
+                .ok_or(Error::<T>::PortfolioNumberOverflow)?;
+            <LastPortfolioNumber>::insert(did, num);
+            <Portfolios>::insert(did, num, name.clone());
+            Self::deposit_event(RawEvent::PortfolioCreated(did, num, name));
+            Ok(())
+        }
+
+        /// Deletes a user portfolio. The portfolio must not hold any tokens and must be in the
+        /// custody of its owner.
         ///
         /// # Arguments
-        /// * `origin` Signing key of the token holder.
-        /// * `ticker` Ticker of the token.
-        /// * `custodian_did` DID of the custodian (i.e whom allowance provided).
-        /// * `value` Allowance amount.
+        /// * `origin` Signing key of the portfolio owner.
+        /// * `num` Number of the portfolio to delete.
         #[weight = SimpleDispatchInfo::FixedNormal(300_000)]
-        pub fn increase_custody_allowance(origin, ticker: Ticker, custodian_did: IdentityId, value: T::Balance) -> DispatchResult {
-            let sender_key = AccountKey::try_from((ensure_signed(origin)?).encode())?;
-            let sender_did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
-            Self::_increase_custody_allowance(sender_did, ticker, sender_did, custodian_did, value)?;
+        pub fn delete_portfolio(origin, num: PortfolioNumber) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+            let portfolio = PortfolioId::user_portfolio(did, num);
+
+            Self::ensure_portfolio_exists(&portfolio)?;
+            ensure!(Self::custodian_of(&portfolio) == did, Error::<T>::UnauthorizedCustodian);
+            ensure!(
+                <PortfolioAssetBalances<T>>::iter_prefix(&portfolio).all(|balance| balance.is_zero()),
+                Error::<T>::PortfolioNotEmpty
+            );
+            <PortfolioAssetBalances<T>>::remove_prefix(&portfolio);
+            <Portfolios>::remove(did, num);
+            Self::deposit_event(RawEvent::PortfolioDeleted(did, num));
             Ok(())
         }
 
-        /// Used to increase the allowance for a given custodian by providing the off chain signature.
+        /// Renames a user portfolio.
         ///
         /// # Arguments
-        /// * `origin` Signing key of a DID who posses off chain signature.
-        /// * `ticker` Ticker of the token.
-        /// * `holder_did` DID of the token holder (i.e who wants to increase the custody allowance).
-        /// * `holder_account_id` Signing key which signs the off chain data blob.
-        /// * `custodian_did` DID of the custodian (i.e whom allowance provided).
-        /// * `value` Allowance amount.
-        /// * `nonce` A u16 number which avoid the replay attack.
-        /// * `signature` Signature provided by the holder_did.
-        #[weight = SimpleDispatchInfo::FixedNormal(450_000)]
-        pub fn increase_custody_allowance_of(
+        /// * `origin` Signing key of the portfolio owner.
+        /// * `num` Number of the portfolio to rename.
+        /// * `to_name` New name of the portfolio.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn rename_portfolio(origin, num: PortfolioNumber, to_name: PortfolioName) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            Self::ensure_portfolio_exists(&PortfolioId::user_portfolio(did, num))?;
+            Self::ensure_portfolio_name_available(did, &to_name)?;
+            <Portfolios>::insert(did, num, to_name.clone());
+            Self::deposit_event(RawEvent::PortfolioRenamed(did, num, to_name));
+            Ok(())
+        }
+
+        /// Moves tokens between two portfolios of the same DID.
+        /// The identity level balance is left untouched, so neither the compliance rules nor
+        /// the checkpoints are involved. Only the custodian of the source portfolio can move funds.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the custodian of `from`.
+        /// * `from` Portfolio from which tokens are moved.
+        /// * `to` Kind of the portfolio of the same DID to which tokens are moved.
+        /// * `items` Vector of pairs of ticker and amount to move.
+        ///
+        /// # Weight
+        /// `200_000 + 100_000 * items.len()`
+        #[weight = FunctionOf(
+            |(_, _, items): (&PortfolioId, &PortfolioKind, &Vec<(Ticker, T::Balance)>)| {
+                200_000 + 100_000 * u32::try_from(items.len()).unwrap_or_default()
+            },
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn move_portfolio_funds(
             origin,
-            ticker: Ticker,
-            holder_did: IdentityId,
-            holder_account_id: T::AccountId,
-            custodian_did: IdentityId,
-            value: T::Balance,
-            nonce: u16,
-            signature: T::OffChainSignature
+            from: PortfolioId,
+            to: PortfolioKind,
+            items: Vec<(Ticker, T::Balance)>
         ) -> DispatchResult {
-            let sender = ensure_signed(origin)?;
-            let sender_key = AccountKey::try_from(sender.encode())?;
-            let caller_did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
-            ensure!(
-                !Self::authentication_nonce((ticker, holder_did, nonce)),
-                Error::<T>::SignatureAlreadyUsed
-            );
-
-            let msg = SignData {
-                custodian_did: custodian_did,
-                holder_did: holder_did,
-                ticker,
-                value,
-                nonce
-            };
-            // holder_account_id should be a part of the holder_did
-            ensure!(
-                signature.verify(&msg.encode()[..], &holder_account_id),
-                Error::<T>::InvalidSignature
-            );
-            // Validate the holder signing key
-            let holder_signer = Signatory::AccountKey(AccountKey::try_from(holder_account_id.encode())?);
-            ensure!(
-                <identity::Module<T>>::is_signer_authorized(holder_did, &holder_signer),
-                Error::<T>::HolderMustBeSigningKeyForHolderDid
-            );
-            Self::_increase_custody_allowance(caller_did, ticker, holder_did, custodian_did, value)?;
-            <AuthenticationNonce>::insert((ticker, holder_did, nonce), true);
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+            let to = PortfolioId { did: from.did, kind: to };
+
+            ensure!(from != to, Error::<T>::DestinationIsSamePortfolio);
+            Self::ensure_portfolio_exists(&from)?;
+            Self::ensure_portfolio_exists(&to)?;
+            // Validate the total amount of each ticker before touching the storage.
+            let mut totals = BTreeMap::new();
+            for (ticker, amount) in &items {
+                let total = totals.entry(*ticker).or_insert_with(T::Balance::zero);
+                *total = total.checked_add(amount).ok_or(Error::<T>::BalanceOverflow)?;
+            }
+            for (ticker, total) in &totals {
+                Self::_check_portfolio_spendable(&from, did, ticker, *total)?;
+                ensure!(
+                    Self::portfolio_balance(&to, ticker).checked_add(total).is_some(),
+                    Error::<T>::BalanceOverflow
+                );
+                <identity::Module<T>>::ensure_spending_allowed(&sender_key, Zero::zero(), Some((*ticker, *total)))?;
+            }
+            for (ticker, amount) in items {
+                Self::_move_between_portfolios(&ticker, &from, &to, amount)?;
+                Self::deposit_event(RawEvent::MovedBetweenPortfolios(did, from, to, ticker, amount));
+            }
+            for (ticker, total) in totals {
+                <identity::Module<T>>::record_spending(&sender_key, Zero::zero(), Some((ticker, total)));
+            }
             Ok(())
         }
 
-        /// Used to transfer the tokens by the approved custodian.
+        /// Transfers tokens from a portfolio of one DID to a portfolio of another DID.
+        /// It can be called by the custodian of the source portfolio and the transfer is
+        /// validated against the compliance rules and smart extensions of the token as usual.
         ///
         /// # Arguments
-        /// * `origin` Signing key of the custodian.
+        /// * `origin` Signing key of the custodian of `from`.
         /// * `ticker` Ticker of the token.
-        /// * `holder_did` DID of the token holder (i.e whom balance get reduced).
-        /// * `receiver_did` DID of the receiver.
-        /// * `value` Amount of tokens need to transfer.
+        /// * `from` Portfolio from which tokens are sent.
+        /// * `to` Portfolio which receives the tokens.
+        /// * `value` Amount of tokens.
         #[weight = SimpleDispatchInfo::FixedNormal(750_000)]
-        pub fn transfer_by_custodian(
+        pub fn portfolio_transfer(
             origin,
             ticker: Ticker,
-            holder_did: IdentityId,
-            receiver_did: IdentityId,
+            from: PortfolioId,
+            to: PortfolioId,
             value: T::Balance
         ) -> DispatchResult {
             let sender = ensure_signed(origin)?;
             let sender_key = AccountKey::try_from(sender.encode())?;
-            let custodian_did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
-
-            let mut custodian_allowance = Self::custodian_allowance((ticker, holder_did, custodian_did));
-            // Check whether the custodian has enough allowance or not
-            ensure!(custodian_allowance >= value, Error::<T>::InsufficientAllowance);
-            // using checked_sub (safe math) to avoid underflow
-            custodian_allowance = custodian_allowance.checked_sub(&value)
-                .ok_or(Error::<T>::AllowanceUnderflow)?;
-            // using checked_sub (safe math) to avoid underflow
-            let new_total_allowance = Self::total_custody_allowance((ticker, holder_did))
-                .checked_sub(&value)
-                .ok_or(Error::<T>::TotalAllowanceUnderflow)?;
-            // Validate the transfer
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(from.did != to.did, Error::<T>::PortfolioTransferToSelf);
+            Self::ensure_portfolio_exists(&from)?;
+            Self::ensure_portfolio_exists(&to)?;
+            Self::_check_portfolio_spendable(&from, did, &ticker, value)?;
             ensure!(
-                Self::_is_valid_transfer(&ticker, sender, Some(holder_did), Some(receiver_did), value)? == ERC1400_TRANSFER_SUCCESS,
+                Self::_is_valid_transfer(&ticker, sender, Some(from.did), Some(to.did), value)? == ERC1400_TRANSFER_SUCCESS,
                 Error::<T>::InvalidTransfer
             );
-            Self::_transfer(custodian_did, &ticker, holder_did, receiver_did, value)?;
-            // Update Storage of allowance
-            <CustodianAllowance<T>>::insert((ticker, custodian_did, holder_did), &custodian_allowance);
-            <TotalCustodyAllowance<T>>::insert((ticker, holder_did), new_total_allowance);
-            Self::deposit_event(RawEvent::CustodyTransfer(custodian_did, ticker, holder_did, receiver_did, value));
+            Self::_spend_between_portfolios(&sender_key, did, &ticker, from, to, value)?;
+            Self::deposit_event(RawEvent::PortfolioTransfer(did, ticker, from, to, value));
+            Ok(())
+        }
+
+        /// Used to accept the custody of a portfolio.
+        /// The authorization must have been given by the current custodian of the portfolio.
+        /// NB: To reject the custody, call remove auth function in identity module.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the new custodian.
+        /// * `auth_id` Authorization ID of the portfolio custody authorization.
+        #[weight = SimpleDispatchInfo::FixedNormal(300_000)]
+        pub fn accept_portfolio_custody(origin, auth_id: u64) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let to_did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            Self::_accept_portfolio_custody(to_did, auth_id)
+        }
+
+        /// Returns the custody of a portfolio back to its owner.
+        /// Can only be called by the current custodian of the portfolio.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the current custodian.
+        /// * `portfolio` The portfolio under custody.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn quit_portfolio_custody(origin, portfolio: PortfolioId) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(
+                <PortfolioCustodian>::get(&portfolio) == Some(did),
+                Error::<T>::UnauthorizedCustodian
+            );
+            <PortfolioCustodian>::remove(&portfolio);
+            Self::deposit_event(RawEvent::PortfolioCustodianChanged(did, portfolio, portfolio.did));
             Ok(())
         }
 
@@ -1454,12 +1667,24 @@ decl_event! {
         /// is_issuable() output
         /// ticker, return value (true if issuable)
         IsIssuable(Ticker, bool),
-        /// Emit when tokens transferred by the custodian.
-        /// caller DID / custodian DID , ticker, holder/from did, to did, amount
-        CustodyTransfer(IdentityId, Ticker, IdentityId, IdentityId, Balance),
-        /// Emit when allowance get increased.
-        /// caller DID, ticker, holder did, custodian did, oldAllowance, newAllowance
-        CustodyAllowanceChanged(IdentityId, Ticker, IdentityId, IdentityId, Balance, Balance),
+        /// Emit when a user portfolio is created.
+        /// owner DID, portfolio number, portfolio name
+        PortfolioCreated(IdentityId, PortfolioNumber, PortfolioName),
+        /// Emit when a user portfolio is deleted.
+        /// owner DID, portfolio number
+        PortfolioDeleted(IdentityId, PortfolioNumber),
+        /// Emit when a user portfolio is renamed.
+        /// owner DID, portfolio number, new portfolio name
+        PortfolioRenamed(IdentityId, PortfolioNumber, PortfolioName),
+        /// Emit when tokens are moved between two portfolios of the same DID.
+        /// caller DID / custodian DID, from portfolio, to portfolio, ticker, amount
+        MovedBetweenPortfolios(IdentityId, PortfolioId, PortfolioId, Ticker, Balance),
+        /// Emit when tokens are transferred between portfolios of different DIDs.
+        /// caller DID / custodian DID, ticker, from portfolio, to portfolio, amount
+        PortfolioTransfer(IdentityId, Ticker, PortfolioId, PortfolioId, Balance),
+        /// Emit when the custodian of a portfolio changes.
+        /// caller DID, portfolio, new custodian DID
+        PortfolioCustodianChanged(IdentityId, PortfolioId, IdentityId),
         /// Emit when ticker is registered.
         /// caller DID / ticker owner did, ticker, ticker owner, expiry
         TickerRegistered(IdentityId, Ticker, Option<Moment>),
@@ -1493,6 +1718,15 @@ decl_event! {
         /// Emitted event for Checkpoint creation.
         /// caller DID. ticker, checkpoint count.
         CheckpointCreated(IdentityId, Ticker, u64),
+        /// Emitted when a checkpoint schedule is created.
+        /// caller DID, ticker, schedule ID, schedule
+        CheckpointScheduleCreated(IdentityId, Ticker, u64, CheckpointSchedule<Moment>),
+        /// Emitted when a checkpoint schedule is removed.
+        /// caller DID, ticker, schedule ID
+        CheckpointScheduleRemoved(IdentityId, Ticker, u64),
+        /// Emitted when a checkpoint is created by a schedule.
+        /// token owner DID, ticker, schedule ID, checkpoint ID
+        ScheduledCheckpointCreated(IdentityId, Ticker, u64, u64),
     }
 }
 
@@ -1514,8 +1748,6 @@ decl_error! {
         ExtensionAlreadyPresent,
         /// When smart extension failed to execute result.
         IncorrectResult,
-        /// The sender must be a signing key for the DID.
-        HolderMustBeSigningKeyForHolderDid,
         /// The token has already been created.
         AssetAlreadyCreated,
         /// The ticker length is over the limit.
@@ -1542,10 +1774,6 @@ decl_error! {
         AllowanceOverflow,
         /// An underflow in calculating the allowance.
         AllowanceUnderflow,
-        /// An overflow in calculating the total allowance.
-        TotalAllowanceOverflow,
-        /// An underflow in calculating the total allowance.
-        TotalAllowanceUnderflow,
         /// An overflow while calculating the current funding round total.
         FundingRoundTotalOverflow,
         /// An overflow while calculating the checkpoint.
@@ -1572,14 +1800,34 @@ decl_error! {
         InvalidTransfer,
         /// The sender balance is not sufficient.
         InsufficientBalance,
-        /// An invalid signature.
-        InvalidSignature,
-        /// The signature is already in use.
-        SignatureAlreadyUsed,
         /// The token is already divisible.
         AssetAlreadyDivisible,
-        /// An invalid custodian DID.
-        InvalidCustodianDid,
+        /// No such portfolio.
+        PortfolioDoesNotExist,
+        /// The DID already has a portfolio with this name.
+        PortfolioNameAlreadyInUse,
+        /// An overflow while calculating the next portfolio number.
+        PortfolioNumberOverflow,
+        /// The portfolio still holds tokens.
+        PortfolioNotEmpty,
+        /// The portfolio balance is not sufficient.
+        InsufficientPortfolioBalance,
+        /// The caller is not the custodian of the portfolio.
+        UnauthorizedCustodian,
+        /// Not a portfolio custody auth.
+        NotAPortfolioCustodyAuth,
+        /// The source and destination portfolios are the same.
+        DestinationIsSamePortfolio,
+        /// Portfolios of the same DID must use `move_portfolio_funds`.
+        PortfolioTransferToSelf,
+        /// The period of a checkpoint schedule cannot be zero.
+        InvalidCheckpointPeriod,
+        /// The token already has `MAX_CHECKPOINT_SCHEDULES` checkpoint schedules.
+        TooManyCheckpointSchedules,
+        /// The checkpoint schedule does not exist.
+        NoSuchCheckpointSchedule,
+        /// An overflow in the number of checkpoint schedules.
+        CheckpointScheduleOverflow,
     }
 }
 
@@ -1610,6 +1858,18 @@ impl<T: Trait> AssetTrait<T::Balance, T::AccountId> for Module<T> {
     fn get_balance_at(ticker: &Ticker, did: IdentityId, at: u64) -> T::Balance {
         Self::get_balance_at(*ticker, did, at)
     }
+
+    fn holder_count(ticker: &Ticker) -> u64 {
+        Self::holder_count(ticker)
+    }
+
+    fn holder_at(ticker: &Ticker, index: u64) -> IdentityId {
+        Self::holder_at(ticker, index)
+    }
+
+    fn schedule_checkpoint_at(ticker: &Ticker, schedule_id: u64, at: u64) -> Option<u64> {
+        Self::schedule_checkpoint_at(ticker, schedule_id, at.saturated_into())
+    }
 }
 
 impl<T: Trait> AcceptTransfer for Module<T> {
@@ -1620,6 +1880,22 @@ impl<T: Trait> AcceptTransfer for Module<T> {
     fn accept_asset_ownership_transfer(to_did: IdentityId, auth_id: u64) -> DispatchResult {
         Self::_accept_token_ownership_transfer(to_did, auth_id)
     }
+
+    fn accept_portfolio_custody(to_did: IdentityId, auth_id: u64) -> DispatchResult {
+        Self::_accept_portfolio_custody(to_did, auth_id)
+    }
+}
+
+impl<T: Trait> AssetHoldings for Module<T> {
+    fn has_asset_holdings(did: IdentityId) -> bool {
+        let now = <pallet_timestamp::Module<T>>::get();
+        <HeldTickers>::iter_prefix(did).next().is_some()
+            || Self::owned_tickers(did).into_iter().any(|ticker| {
+                Self::ticker_registration(ticker)
+                    .expiry
+                    .map_or(true, |expiry| now <= expiry)
+            })
+    }
 }
 
 /// All functions in the decl_module macro become part of the public interface of the module
@@ -1713,6 +1989,7 @@ impl<T: Trait> Module<T> {
                 Signatory::from(ticker_details.owner),
                 ticker_details.link_id,
             );
+            Self::_remove_owned_ticker(ticker_details.owner, ticker);
         }
 
         let link = <identity::Module<T>>::add_link(
@@ -1729,6 +2006,7 @@ impl<T: Trait> Module<T> {
 
         // Store ticker registration details
         <Tickers<T>>::insert(ticker, ticker_registration);
+        Self::_add_owned_ticker(to_did, *ticker);
 
         Self::deposit_event(RawEvent::TickerRegistered(to_did, *ticker, expiry));
         Ok(())
@@ -1777,6 +2055,126 @@ impl<T: Trait> Module<T> {
         Self::balance(&ticker, &did)
     }
 
+    /// Returns up to `count` holders of `ticker` with a non-zero balance, scanning the holder
+    /// list from index `start`, together with the index to resume from or `None` once the list is
+    /// exhausted.
+    ///
+    /// Balances are taken at `checkpoint_id` if given, otherwise the latest balances are used.
+    pub fn holders(
+        ticker: &Ticker,
+        checkpoint_id: Option<u64>,
+        start: u64,
+        count: u64,
+    ) -> (Vec<(IdentityId, T::Balance)>, Option<u64>) {
+        let holder_count = Self::holder_count(ticker);
+        let mut holders = Vec::new();
+        let mut index = start;
+        while index < holder_count && (holders.len() as u64) < count {
+            let did = Self::holder_at(ticker, index);
+            let balance = match checkpoint_id {
+                Some(at) => Self::get_balance_at(*ticker, did, at),
+                None => Self::balance(ticker, &did),
+            };
+            if balance != Zero::zero() {
+                holders.push((did, balance));
+            }
+            index += 1;
+        }
+        let next = if index < holder_count {
+            Some(index)
+        } else {
+            None
+        };
+        (holders, next)
+    }
+
+    /// Sets the balance of `did` in `ticker`, keeping the tickers held by `did` up to date.
+    fn _set_balance(ticker: &Ticker, did: IdentityId, balance: T::Balance) {
+        <BalanceOf<T>>::insert(ticker, did, balance);
+        if balance.is_zero() {
+            <HeldTickers>::remove(did, ticker);
+        } else {
+            <HeldTickers>::insert(did, ticker, true);
+        }
+    }
+
+    /// Adds `ticker` to the tickers owned by `did`.
+    fn _add_owned_ticker(did: IdentityId, ticker: Ticker) {
+        <OwnedTickers>::mutate(did, |tickers| {
+            if !tickers.contains(&ticker) {
+                tickers.push(ticker);
+            }
+        });
+    }
+
+    /// Removes `ticker` from the tickers owned by `did`.
+    fn _remove_owned_ticker(did: IdentityId, ticker: &Ticker) {
+        <OwnedTickers>::mutate(did, |tickers| tickers.retain(|owned| owned != ticker));
+    }
+
+    /// Runs a batch of the storage migration of the current storage version.
+    fn migrate_storage() {
+        let mut cursor = Self::migration_cursor();
+        let done = migration::run_migration_batch(
+            &mut cursor,
+            &[Self::index_held_tickers, Self::index_owned_tickers],
+        );
+        if done {
+            <StorageVersion>::mutate(|version| *version += 1);
+            <MigrationCursor>::kill();
+        } else {
+            <MigrationCursor>::put(cursor);
+        }
+    }
+
+    /// Indexes the tickers held by each identity from the balances stored before the index.
+    fn index_held_tickers(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        let prefix = migration::storage_prefix(b"Asset", b"BalanceOf");
+        let (entries, next) = migration::raw_entries_page::<T::Balance>(&prefix, after, limit);
+        for (raw_key, balance) in entries {
+            // Raw key: prefix ++ blake2_128(ticker) ++ ticker ++ blake2_128(did) ++ did.
+            let mut encoded = raw_key.get(prefix.len() + 16..).unwrap_or_default();
+            let ticker = match Ticker::decode(&mut encoded) {
+                Ok(ticker) => ticker,
+                Err(_) => continue,
+            };
+            let did = match IdentityId::decode(&mut encoded.get(16..).unwrap_or_default()) {
+                Ok(did) => did,
+                Err(_) => continue,
+            };
+            if !balance.is_zero() {
+                <HeldTickers>::insert(did, ticker, true);
+            }
+        }
+        next
+    }
+
+    /// Indexes the tickers owned by each identity from the registrations stored before the
+    /// index.
+    fn index_owned_tickers(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        let prefix = migration::storage_prefix(b"Asset", b"Tickers");
+        let (entries, next) =
+            migration::raw_entries_page::<TickerRegistration<T::Moment>>(&prefix, after, limit);
+        for (raw_key, registration) in entries {
+            // Raw key: prefix ++ blake2_128(ticker) ++ ticker.
+            let mut encoded = raw_key.get(prefix.len() + 16..).unwrap_or_default();
+            if let Ok(ticker) = Ticker::decode(&mut encoded) {
+                Self::_add_owned_ticker(registration.owner, ticker);
+            }
+        }
+        next
+    }
+
+    /// Appends `did` to the holder list of `ticker` unless it is already listed.
+    fn add_holder(ticker: &Ticker, did: IdentityId) {
+        if !<IsHolderListed>::get(ticker, did) {
+            let index = <HolderCount>::get(ticker);
+            <Holders>::insert(ticker, index, did);
+            <HolderCount>::insert(ticker, index + 1);
+            <IsHolderListed>::insert(ticker, did, true);
+        }
+    }
+
     fn find_ceiling(arr: &Vec<u64>, key: u64) -> u64 {
         // This function assumes that key <= last element of the array,
         // the array consists of unique sorted elements,
@@ -1818,50 +2216,108 @@ impl<T: Trait> Module<T> {
         Ok(if general_status_code != ERC1400_TRANSFER_SUCCESS {
             COMPLIANCE_MANAGER_FAILURE
         } else {
-            let mut final_result = true;
-            let mut is_valid = false;
-            let mut is_invalid = false;
-            let mut force_valid = false;
-            let tms = Self::extensions((ticker, SmartExtensionType::TransferManager))
-                .into_iter()
-                .filter(|tm| !Self::extension_details((ticker, tm)).is_archive)
-                .collect::<Vec<T::AccountId>>();
-            if !tms.is_empty() {
-                for tm in tms.into_iter() {
-                    let result = Self::verify_restriction(
-                        ticker,
-                        extension_caller.clone(),
-                        from_did,
-                        to_did,
-                        value,
-                        tm,
-                    );
-                    match result {
-                        RestrictionResult::Valid => is_valid = true,
-                        RestrictionResult::Invalid => is_invalid = true,
-                        RestrictionResult::ForceValid => force_valid = true,
-                    }
-                }
-                //is_valid = force_valid ? true : (is_invalid ? false : is_valid);
-                final_result = force_valid || !is_invalid && is_valid;
-            }
-            if final_result {
-                return Ok(ERC1400_TRANSFER_SUCCESS);
+            let results =
+                Self::verify_extensions(ticker, extension_caller, from_did, to_did, value);
+            if Self::is_extension_result_valid(results.iter().map(|(_, result)| result)) {
+                ERC1400_TRANSFER_SUCCESS
             } else {
-                return Ok(SMART_EXTENSION_FAILURE);
+                SMART_EXTENSION_FAILURE
             }
         })
     }
 
-    // The SimpleToken standard transfer function
-    // internal
-    fn _transfer(
+    /// It verifies the transfer against each active transfer manager extension of `ticker`.
+    fn verify_extensions(
+        ticker: &Ticker,
+        extension_caller: T::AccountId,
+        from_did: Option<IdentityId>,
+        to_did: Option<IdentityId>,
+        value: T::Balance,
+    ) -> Vec<(T::AccountId, RestrictionResult)> {
+        Self::extensions((ticker, SmartExtensionType::TransferManager))
+            .into_iter()
+            .filter(|tm| !Self::extension_details((ticker, tm)).is_archive)
+            .map(|tm| {
+                let result = Self::verify_restriction(
+                    ticker,
+                    extension_caller.clone(),
+                    from_did,
+                    to_did,
+                    value,
+                    tm.clone(),
+                );
+                (tm, result)
+            })
+            .collect()
+    }
+
+    /// It combines the results of the transfer manager extensions. The transfer is valid if
+    /// there is no extension, if any extension forces it, or if no extension invalidates it
+    /// and at least one validates it.
+    fn is_extension_result_valid<'a>(results: impl Iterator<Item = &'a RestrictionResult>) -> bool {
+        let mut is_empty = true;
+        let mut is_valid = false;
+        let mut is_invalid = false;
+        let mut force_valid = false;
+        for result in results {
+            is_empty = false;
+            match result {
+                RestrictionResult::Valid => is_valid = true,
+                RestrictionResult::Invalid => is_invalid = true,
+                RestrictionResult::ForceValid => force_valid = true,
+            }
+        }
+        //is_valid = force_valid ? true : (is_invalid ? false : is_valid);
+        is_empty || force_valid || !is_invalid && is_valid
+    }
+
+    /// Transfers tokens between the default portfolios of two DIDs.
+    /// NB: Transfer restrictions are not verified here, callers must check them beforehand.
+    pub fn _transfer(
         sender: IdentityId,
         ticker: &Ticker,
         from_did: IdentityId,
         to_did: IdentityId,
         value: T::Balance,
     ) -> DispatchResult {
+        Self::_transfer_between_portfolios(
+            sender,
+            ticker,
+            PortfolioId::default_portfolio(from_did),
+            PortfolioId::default_portfolio(to_did),
+            value,
+        )
+    }
+
+    /// Transfers tokens between portfolios of two DIDs on behalf of the signing key `sender_key`,
+    /// which must be allowed to send them under its spending limit.
+    /// NB: Transfer restrictions are not verified here, callers must check them beforehand.
+    fn _spend_between_portfolios(
+        sender_key: &AccountKey,
+        sender: IdentityId,
+        ticker: &Ticker,
+        from_portfolio: PortfolioId,
+        to_portfolio: PortfolioId,
+        value: T::Balance,
+    ) -> DispatchResult {
+        let spending = Some((*ticker, value));
+        <identity::Module<T>>::ensure_spending_allowed(sender_key, Zero::zero(), spending)?;
+        Self::_transfer_between_portfolios(sender, ticker, from_portfolio, to_portfolio, value)?;
+        <identity::Module<T>>::record_spending(sender_key, Zero::zero(), spending);
+        Ok(())
+    }
+
+    /// Transfers tokens between portfolios of two DIDs.
+    /// Checkpoints and statistics are updated using the identity level balances.
+    fn _transfer_between_portfolios(
+        sender: IdentityId,
+        ticker: &Ticker,
+        from_portfolio: PortfolioId,
+        to_portfolio: PortfolioId,
+        value: T::Balance,
+    ) -> DispatchResult {
+        let from_did = from_portfolio.did;
+        let to_did = to_portfolio.did;
         // Granularity check
         ensure!(
             Self::check_granularity(ticker, value),
@@ -1873,6 +2329,12 @@ impl<T: Trait> Module<T> {
         );
         let sender_balance = Self::balance(ticker, &from_did);
         ensure!(sender_balance >= value, Error::<T>::InsufficientBalance);
+        let updated_from_portfolio_balance = Self::portfolio_balance(&from_portfolio, ticker)
+            .checked_sub(&value)
+            .ok_or(Error::<T>::InsufficientPortfolioBalance)?;
+        let updated_to_portfolio_balance = Self::portfolio_balance(&to_portfolio, ticker)
+            .checked_add(&value)
+            .ok_or(Error::<T>::BalanceOverflow)?;
 
         let updated_from_balance = sender_balance
             .checked_sub(&value)
@@ -1885,16 +2347,20 @@ impl<T: Trait> Module<T> {
         Self::_update_checkpoint(ticker, from_did, sender_balance);
         Self::_update_checkpoint(ticker, to_did, receiver_balance);
         // reduce sender's balance
-        <BalanceOf<T>>::insert(ticker, &from_did, updated_from_balance);
+        Self::_set_balance(ticker, from_did, updated_from_balance);
 
         // increase receiver's balance
-        <BalanceOf<T>>::insert(ticker, &to_did, updated_to_balance);
+        Self::_set_balance(ticker, to_did, updated_to_balance);
+        Self::add_holder(ticker, to_did);
+
+        Self::_set_portfolio_balance(&from_portfolio, ticker, updated_from_portfolio_balance);
+        Self::_set_portfolio_balance(&to_portfolio, ticker, updated_to_portfolio_balance);
 
         // Update statistic info.
         <statistics::Module<T>>::update_transfer_stats(
             ticker,
-            Some(updated_from_balance),
-            Some(updated_to_balance),
+            Some((from_did, updated_from_balance)),
+            Some((to_did, updated_to_balance)),
             value,
         );
 
@@ -1903,24 +2369,119 @@ impl<T: Trait> Module<T> {
     }
 
     pub fn _create_checkpoint(ticker: &Ticker) -> DispatchResult {
-        if <TotalCheckpoints>::contains_key(ticker) {
-            let mut checkpoint_count = Self::total_checkpoints_of(ticker);
-            checkpoint_count = checkpoint_count
-                .checked_add(1)
-                .ok_or(Error::<T>::CheckpointOverflow)?;
-            <TotalCheckpoints>::insert(ticker, checkpoint_count);
-            <CheckpointTotalSupply<T>>::insert(
-                &(*ticker, checkpoint_count),
-                Self::token_details(ticker).total_supply,
-            );
+        let checkpoint_id = Self::total_checkpoints_of(ticker)
+            .checked_add(1)
+            .ok_or(Error::<T>::CheckpointOverflow)?;
+        <TotalCheckpoints>::insert(ticker, checkpoint_id);
+        <CheckpointTotalSupply<T>>::insert(
+            &(*ticker, checkpoint_id),
+            Self::token_details(ticker).total_supply,
+        );
+        <CheckpointTimestamps<T>>::insert(
+            &(*ticker, checkpoint_id),
+            <pallet_timestamp::Module<T>>::get(),
+        );
+        <statistics::Module<T>>::snapshot_investor_count(ticker, checkpoint_id);
+        Ok(())
+    }
+
+    /// Creates a checkpoint for each schedule indexed at `block_number` which is due, and
+    /// indexes the schedules again for their next checkpoint.
+    fn create_scheduled_checkpoints(block_number: T::BlockNumber) {
+        let now = <pallet_timestamp::Module<T>>::get();
+        for (ticker, schedule_id) in <ScheduledCheckpointsAt<T>>::take(block_number) {
+            let mut schedule = match Self::checkpoint_schedules(&ticker, schedule_id) {
+                Some(schedule) => schedule,
+                None => continue,
+            };
+            if schedule.next_at > now {
+                Self::index_checkpoint_schedule(ticker, schedule_id, schedule.next_at);
+                continue;
+            }
+            if let Err(e) = Self::_create_checkpoint(&ticker) {
+                sp_runtime::print(e);
+                Self::index_checkpoint_schedule(ticker, schedule_id, schedule.next_at);
+                continue;
+            }
+            let checkpoint_id = Self::total_checkpoints_of(&ticker);
+            <ScheduleCheckpoints>::mutate(&(ticker, schedule_id), |ids| ids.push(checkpoint_id));
+            Self::deposit_event(RawEvent::ScheduledCheckpointCreated(
+                Self::token_details(&ticker).owner_did,
+                ticker,
+                schedule_id,
+                checkpoint_id,
+            ));
+
+            // The due times which have already passed are skipped, so a schedule never creates
+            // more than one checkpoint to catch up.
+            schedule.next_at =
+                now.saturating_add(schedule.period - (now - schedule.next_at) % schedule.period);
+            let finished = match schedule.remaining {
+                0 => false,
+                1 => true,
+                _ => {
+                    schedule.remaining -= 1;
+                    false
+                }
+            };
+            if finished {
+                <CheckpointSchedules<T>>::remove(&ticker, schedule_id);
+            } else {
+                Self::index_checkpoint_schedule(ticker, schedule_id, schedule.next_at);
+                <CheckpointSchedules<T>>::insert(&ticker, schedule_id, schedule);
+            }
+        }
+    }
+
+    /// Indexes a checkpoint schedule due at `next_at` at a later block. Blocks are expected every
+    /// two minimum periods of the timestamp module, and the schedule is indexed at half the
+    /// expected number of blocks until `next_at`, or at the next block. As a schedule which is
+    /// not due yet is indexed again, slower blocks do not delay its checkpoints much. Blocks which
+    /// already have `MAX_SCHEDULED_CHECKPOINTS_PER_BLOCK` schedules are skipped.
+    fn index_checkpoint_schedule(ticker: Ticker, schedule_id: u64, next_at: T::Moment) {
+        let now = <pallet_timestamp::Module<T>>::get();
+        let expected_block_time = <T as pallet_timestamp::Trait>::MinimumPeriod::get()
+            .saturating_mul(2u32.into())
+            .max(1u32.into());
+        let blocks: u32 =
+            (next_at.saturating_sub(now) / expected_block_time / 2u32.into()).saturated_into();
+        let mut block_number =
+            <frame_system::Module<T>>::block_number() + T::BlockNumber::from(blocks.max(1));
+        while Self::scheduled_checkpoints_at(block_number).len()
+            >= MAX_SCHEDULED_CHECKPOINTS_PER_BLOCK
+        {
+            block_number += One::one();
+        }
+        <ScheduledCheckpointsAt<T>>::mutate(block_number, |schedules| {
+            schedules.push((ticker, schedule_id))
+        });
+    }
+
+    /// Returns the ID of the latest checkpoint of `ticker` created at or before `at`.
+    pub fn checkpoint_at(ticker: &Ticker, at: T::Moment) -> Option<u64> {
+        // Checkpoints are created in chronological order, so their timestamps are sorted.
+        let (mut low, mut high) = (0, Self::total_checkpoints_of(ticker));
+        while low < high {
+            let mid = low + (high - low + 1) / 2;
+            if Self::checkpoint_timestamp((*ticker, mid)) <= at {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        if low > 0 {
+            Some(low)
         } else {
-            <TotalCheckpoints>::insert(ticker, 1);
-            <CheckpointTotalSupply<T>>::insert(
-                &(*ticker, 1),
-                Self::token_details(ticker).total_supply,
-            );
+            None
         }
-        Ok(())
+    }
+
+    /// Returns the ID of the latest checkpoint created by a schedule of `ticker` at or before `at`.
+    pub fn schedule_checkpoint_at(ticker: &Ticker, schedule_id: u64, at: T::Moment) -> Option<u64> {
+        Self::schedule_checkpoints((*ticker, schedule_id))
+            .into_iter()
+            .rev()
+            .find(|id| Self::checkpoint_timestamp((*ticker, *id)) <= at)
     }
 
     fn _update_checkpoint(ticker: &Ticker, user_did: IdentityId, user_balance: T::Balance) {
@@ -1946,27 +2507,68 @@ impl<T: Trait> Module<T> {
         to_did: IdentityId,
         value: T::Balance,
         protocol_fee_data: Option<(&Signatory, ProtocolOp)>,
+    ) -> DispatchResult {
+        Self::_ensure_can_mint(ticker, caller.clone(), to_did, value)?;
+        let current_to_balance = Self::balance(ticker, &to_did);
+        let updated_to_balance = current_to_balance.saturating_add(value);
+        let mut token = Self::token_details(ticker);
+        token.total_supply = token.total_supply.saturating_add(value);
+        let round = Self::funding_round(ticker);
+        let ticker_round = (*ticker, round.clone());
+        let issued_in_this_round =
+            Self::issued_in_funding_round(&ticker_round).saturating_add(value);
+
+        // Charge the given fee.
+        if let Some((payee, op)) = protocol_fee_data {
+            <<T as IdentityTrait>::ProtocolFee>::charge_fee(payee, op)?;
+        }
+        Self::_update_checkpoint(ticker, to_did, current_to_balance);
+
+        Self::_set_balance(ticker, to_did, updated_to_balance);
+        Self::add_holder(ticker, to_did);
+        <Tokens<T>>::insert(ticker, token);
+        <statistics::Module<T>>::update_transfer_stats(
+            ticker,
+            None,
+            Some((to_did, updated_to_balance)),
+            value,
+        );
+        <IssuedInFundingRound<T>>::insert(&ticker_round, issued_in_this_round);
+        Self::deposit_event(RawEvent::Issued(
+            Context::current_identity_or::<Identity<T>>(&AccountKey::try_from(caller.encode())?)?,
+            *ticker,
+            to_did,
+            value,
+            round,
+            issued_in_this_round,
+        ));
+
+        Ok(())
+    }
+
+    /// Ensures that `value` tokens of `ticker` can be issued to `to_did` by `caller`.
+    /// Nothing is written, so callers can validate an issuance before changing other balances.
+    pub fn _ensure_can_mint(
+        ticker: &Ticker,
+        caller: T::AccountId,
+        to_did: IdentityId,
+        value: T::Balance,
     ) -> DispatchResult {
         // Granularity check
         ensure!(
             Self::check_granularity(ticker, value),
             Error::<T>::InvalidGranularity
         );
-        //Increase receiver balance
-        let current_to_balance = Self::balance(ticker, &to_did);
-        let updated_to_balance = current_to_balance
+        Self::balance(ticker, &to_did)
             .checked_add(&value)
             .ok_or(Error::<T>::BalanceOverflow)?;
         // verify transfer check
         ensure!(
-            Self::_is_valid_transfer(ticker, caller.clone(), None, Some(to_did), value)?
+            Self::_is_valid_transfer(ticker, caller, None, Some(to_did), value)?
                 == ERC1400_TRANSFER_SUCCESS,
             Error::<T>::InvalidTransfer
         );
-
-        // Read the token details
-        let mut token = Self::token_details(ticker);
-        let updated_total_supply = token
+        let updated_total_supply = Self::token_details(ticker)
             .total_supply
             .checked_add(&value)
             .ok_or(Error::<T>::TotalSupplyOverflow)?;
@@ -1974,32 +2576,65 @@ impl<T: Trait> Module<T> {
             updated_total_supply <= MAX_SUPPLY.into(),
             Error::<T>::TotalSupplyAboveLimit
         );
-        // Increase total supply
-        token.total_supply = updated_total_supply;
+        Self::issued_in_funding_round(&(*ticker, Self::funding_round(ticker)))
+            .checked_add(&value)
+            .ok_or(Error::<T>::FundingRoundTotalOverflow)?;
+        Ok(())
+    }
 
-        // Charge the given fee.
-        if let Some((payee, op)) = protocol_fee_data {
-            <<T as IdentityTrait>::ProtocolFee>::charge_fee(payee, op)?;
+    /// Rounds `value` down to the granularity of `ticker`.
+    pub fn round_to_granularity(ticker: &Ticker, value: T::Balance) -> T::Balance {
+        if Self::token_details(ticker).divisible {
+            value
+        } else {
+            value - value % ONE_UNIT.into()
         }
-        Self::_update_checkpoint(ticker, to_did, current_to_balance);
+    }
 
-        <BalanceOf<T>>::insert(ticker, &to_did, updated_to_balance);
+    /// Burns `value` tokens of `token_holder_did` without verifying any transfer restriction.
+    /// A forced redemption is not limited to the default portfolio of the holder.
+    pub fn _forced_redeem(
+        ticker: &Ticker,
+        token_holder_did: IdentityId,
+        value: T::Balance,
+    ) -> DispatchResult {
+        // Granularity check
+        ensure!(
+            Self::check_granularity(ticker, value),
+            Error::<T>::InvalidGranularity
+        );
+        ensure!(
+            <BalanceOf<T>>::contains_key(ticker, &token_holder_did),
+            Error::<T>::NotAAssetHolder
+        );
+        let burner_balance = Self::balance(ticker, &token_holder_did);
+        ensure!(burner_balance >= value, Error::<T>::InsufficientBalance);
+
+        // Reduce sender's balance
+        let updated_burner_balance = burner_balance
+            .checked_sub(&value)
+            .ok_or(Error::<T>::BalanceOverflow)?;
+
+        // Decrease total supply
+        let mut token = Self::token_details(ticker);
+        token.total_supply = token
+            .total_supply
+            .checked_sub(&value)
+            .ok_or(Error::<T>::BalanceOverflow)?;
+
+        let release = Self::_default_portfolio_release(ticker, token_holder_did, value)?;
+
+        Self::_update_checkpoint(ticker, token_holder_did, burner_balance);
+        Self::_release_to_default_portfolio(ticker, release);
+
+        Self::_set_balance(ticker, token_holder_did, updated_burner_balance);
         <Tokens<T>>::insert(ticker, token);
-        let round = Self::funding_round(ticker);
-        let ticker_round = (*ticker, round.clone());
-        let issued_in_this_round = Self::issued_in_funding_round(&ticker_round)
-            .checked_add(&value)
-            .ok_or(Error::<T>::FundingRoundTotalOverflow)?;
-        <IssuedInFundingRound<T>>::insert(&ticker_round, issued_in_this_round);
-        Self::deposit_event(RawEvent::Issued(
-            Context::current_identity_or::<Identity<T>>(&AccountKey::try_from(caller.encode())?)?,
-            *ticker,
-            to_did,
+        <statistics::Module<T>>::update_transfer_stats(
+            ticker,
+            Some((token_holder_did, updated_burner_balance)),
+            None,
             value,
-            round,
-            issued_in_this_round,
-        ));
-
+        );
         Ok(())
     }
 
@@ -2009,60 +2644,169 @@ impl<T: Trait> Module<T> {
         token.divisible || value % ONE_UNIT.into() == 0.into()
     }
 
-    fn _check_custody_allowance(
-        ticker: &Ticker,
-        holder_did: IdentityId,
-        value: T::Balance,
-    ) -> DispatchResult {
-        let remaining_balance = Self::balance(&ticker, &holder_did)
-            .checked_sub(&value)
-            .ok_or(Error::<T>::BalanceUnderflow)?;
+    /// Returns the custodian of a portfolio, which is its owner unless the custody was given away.
+    pub fn custodian_of(portfolio: &PortfolioId) -> IdentityId {
+        Self::portfolio_custodian(portfolio).unwrap_or(portfolio.did)
+    }
+
+    /// Returns the balance of `ticker` held in `portfolio`.
+    /// The default portfolio holds every token of the DID that is not held in a user portfolio.
+    pub fn portfolio_balance(portfolio: &PortfolioId, ticker: &Ticker) -> T::Balance {
+        match portfolio.kind {
+            PortfolioKind::Default => Self::balance(ticker, &portfolio.did)
+                .saturating_sub(Self::user_portfolios_balance(ticker, &portfolio.did)),
+            PortfolioKind::User(_) => Self::portfolio_asset_balances(portfolio, ticker),
+        }
+    }
+
+    fn ensure_portfolio_exists(portfolio: &PortfolioId) -> DispatchResult {
+        if let PortfolioKind::User(num) = portfolio.kind {
+            ensure!(
+                <Portfolios>::contains_key(&portfolio.did, num),
+                Error::<T>::PortfolioDoesNotExist
+            );
+        }
+        Ok(())
+    }
+
+    fn ensure_portfolio_name_available(did: IdentityId, name: &PortfolioName) -> DispatchResult {
         ensure!(
-            remaining_balance >= Self::total_custody_allowance(&(*ticker, holder_did)),
-            Error::<T>::InsufficientBalance
+            !<Portfolios>::iter_prefix(&did).any(|existing| existing == *name),
+            Error::<T>::PortfolioNameAlreadyInUse
         );
         Ok(())
     }
 
-    fn _increase_custody_allowance(
-        caller_did: IdentityId,
-        ticker: Ticker,
-        holder_did: IdentityId,
+    /// Ensures that `custodian_did` is the custodian of `portfolio` and that the portfolio holds
+    /// at least `value` tokens of `ticker`.
+    fn _check_portfolio_spendable(
+        portfolio: &PortfolioId,
         custodian_did: IdentityId,
+        ticker: &Ticker,
         value: T::Balance,
     ) -> DispatchResult {
-        let new_custody_allowance = Self::total_custody_allowance((ticker, holder_did))
-            .checked_add(&value)
-            .ok_or(Error::<T>::TotalAllowanceOverflow)?;
-        // Ensure that balance of the token holder should greater than or equal to the total custody allowance + value
         ensure!(
-            Self::balance(&ticker, &holder_did) >= new_custody_allowance,
-            Error::<T>::InsufficientBalance
+            Self::custodian_of(portfolio) == custodian_did,
+            Error::<T>::UnauthorizedCustodian
         );
-        // Ensure the valid DID
         ensure!(
-            <identity::DidRecords>::contains_key(custodian_did),
-            Error::<T>::InvalidCustodianDid
+            Self::portfolio_balance(portfolio, ticker) >= value,
+            Error::<T>::InsufficientPortfolioBalance
         );
+        Ok(())
+    }
 
-        let old_allowance = Self::custodian_allowance((ticker, holder_did, custodian_did));
-        let new_current_allowance = old_allowance
+    /// Updates the balance of a user portfolio and the user portfolio total of its owner.
+    /// Balances of the default portfolio are implied, so nothing is stored for it.
+    fn _set_portfolio_balance(portfolio: &PortfolioId, ticker: &Ticker, balance: T::Balance) {
+        if let PortfolioKind::User(num) = portfolio.kind {
+            let old_balance = Self::portfolio_asset_balances(portfolio, ticker);
+            <UserPortfoliosBalance<T>>::mutate(ticker, &portfolio.did, |total| {
+                *total = total.saturating_sub(old_balance).saturating_add(balance)
+            });
+            if old_balance.is_zero() != balance.is_zero() {
+                <UserPortfoliosHolding>::mutate(ticker, &portfolio.did, |nums| {
+                    if balance.is_zero() {
+                        nums.retain(|held| *held != num);
+                    } else {
+                        nums.push(num);
+                    }
+                });
+            }
+            <PortfolioAssetBalances<T>>::insert(portfolio, ticker, balance);
+        }
+    }
+
+    /// Moves tokens between two portfolios of the same DID.
+    fn _move_between_portfolios(
+        ticker: &Ticker,
+        from: &PortfolioId,
+        to: &PortfolioId,
+        value: T::Balance,
+    ) -> DispatchResult {
+        let from_balance = Self::portfolio_balance(from, ticker)
+            .checked_sub(&value)
+            .ok_or(Error::<T>::InsufficientPortfolioBalance)?;
+        let to_balance = Self::portfolio_balance(to, ticker)
             .checked_add(&value)
-            .ok_or(Error::<T>::AllowanceOverflow)?;
-        // Update Storage
-        <CustodianAllowance<T>>::insert(
-            (ticker, holder_did, custodian_did),
-            &new_current_allowance,
+            .ok_or(Error::<T>::BalanceOverflow)?;
+        Self::_set_portfolio_balance(from, ticker, from_balance);
+        Self::_set_portfolio_balance(to, ticker, to_balance);
+        Ok(())
+    }
+
+    /// Returns the amounts to move from the user portfolios of `did` into its default portfolio
+    /// so that the default portfolio holds at least `value`. Only the user portfolios holding
+    /// `ticker` are visited, the most recent first. Nothing is written.
+    fn _default_portfolio_release(
+        ticker: &Ticker,
+        did: IdentityId,
+        value: T::Balance,
+    ) -> StdResult<Vec<(PortfolioId, T::Balance)>, DispatchError> {
+        let default_portfolio = PortfolioId::default_portfolio(did);
+        let mut missing = value.saturating_sub(Self::portfolio_balance(&default_portfolio, ticker));
+        let mut release = Vec::new();
+        for num in Self::user_portfolios_holding(ticker, &did)
+            .into_iter()
+            .rev()
+        {
+            if missing.is_zero() {
+                break;
+            }
+            let portfolio = PortfolioId::user_portfolio(did, num);
+            let amount = Self::portfolio_balance(&portfolio, ticker).min(missing);
+            release.push((portfolio, amount));
+            missing = missing.saturating_sub(amount);
+        }
+        ensure!(missing.is_zero(), Error::<T>::InsufficientBalance);
+        Ok(release)
+    }
+
+    /// Moves the amounts returned by `_default_portfolio_release` into the default portfolio.
+    /// It is used by the forced operations of the token owner which must not be blocked by the
+    /// way a holder segregates its tokens.
+    fn _release_to_default_portfolio(ticker: &Ticker, release: Vec<(PortfolioId, T::Balance)>) {
+        for (portfolio, amount) in release {
+            let balance = Self::portfolio_balance(&portfolio, ticker).saturating_sub(amount);
+            Self::_set_portfolio_balance(&portfolio, ticker, balance);
+        }
+    }
+
+    /// Accept and process the custody of a portfolio.
+    pub fn _accept_portfolio_custody(to_did: IdentityId, auth_id: u64) -> DispatchResult {
+        ensure!(
+            <identity::Authorizations<T>>::contains_key(Signatory::from(to_did), auth_id),
+            AuthorizationError::Invalid
         );
-        <TotalCustodyAllowance<T>>::insert((ticker, holder_did), new_custody_allowance);
-        Self::deposit_event(RawEvent::CustodyAllowanceChanged(
-            caller_did,
-            ticker,
-            holder_did,
-            custodian_did,
-            old_allowance,
-            new_current_allowance,
+
+        let auth = <identity::Authorizations<T>>::get(Signatory::from(to_did), auth_id);
+
+        let portfolio = match auth.authorization_data {
+            AuthorizationData::PortfolioCustody(portfolio) => portfolio,
+            _ => return Err(Error::<T>::NotAPortfolioCustodyAuth.into()),
+        };
+
+        Self::ensure_portfolio_exists(&portfolio)?;
+        let current_custodian = Self::custodian_of(&portfolio);
+
+        <identity::Module<T>>::consume_auth(
+            Signatory::from(current_custodian),
+            Signatory::from(to_did),
+            auth_id,
+        )?;
+
+        if to_did == portfolio.did {
+            <PortfolioCustodian>::remove(&portfolio);
+        } else {
+            <PortfolioCustodian>::insert(&portfolio, to_did);
+        }
+
+        Self::deposit_event(RawEvent::PortfolioCustodianChanged(
+            current_custodian,
+            portfolio,
+            to_did,
         ));
+
         Ok(())
     }
 
@@ -2107,6 +2851,8 @@ impl<T: Trait> Module<T> {
             tr.owner = to_did;
             tr.link_id = link;
         });
+        Self::_remove_owned_ticker(ticker_details.owner, &ticker);
+        Self::_add_owned_ticker(to_did, ticker);
 
         Self::deposit_event(RawEvent::TickerTransferred(
             to_did,
@@ -2166,6 +2912,8 @@ impl<T: Trait> Module<T> {
             tr.owner = to_did;
             tr.link_id = ticker_link;
         });
+        Self::_remove_owned_ticker(ticker_details.owner, &ticker);
+        Self::_add_owned_ticker(to_did, ticker);
         <Tokens<T>>::mutate(&ticker, |tr| {
             tr.owner_did = to_did;
             tr.link_id = token_link;
@@ -2285,7 +3033,8 @@ impl<T: Trait> Module<T> {
             if Identity::<T>::has_valid_cdd(from_id) {
                 let balance = Self::balance(&ticker, &from_id);
                 if balance < amount
-                    || balance - amount < Self::total_custody_allowance((ticker, from_id))
+                    || Self::portfolio_balance(&PortfolioId::default_portfolio(from_id), &ticker)
+                        < amount
                 {
                     return Ok(ERC1400_INSUFFICIENT_BALANCE);
                 }
@@ -2305,4 +3054,57 @@ impl<T: Trait> Module<T> {
                 .unwrap_or(ERC1400_TRANSFER_FAILURE),
         )
     }
+
+    /// RPC: Function that explains the result of `unsafe_can_transfer`.
+    /// Unlike `unsafe_can_transfer`, every check is evaluated, even after a failed one.
+    pub fn transfer_report(
+        sender: T::AccountId,
+        ticker: Ticker,
+        from_did: Option<IdentityId>,
+        to_did: Option<IdentityId>,
+        amount: T::Balance,
+    ) -> TransferReport<T::AccountId, T::Balance> {
+        let granularity = Self::check_granularity(&ticker, amount);
+        let sender_balance = from_did.map(|did| Self::balance(&ticker, &did));
+        let sufficient_balance = from_did.map_or(true, |did| {
+            Self::balance(&ticker, &did) >= amount
+                && Self::portfolio_balance(&PortfolioId::default_portfolio(did), &ticker) >= amount
+        });
+        let sender_cdd = from_did.map(Identity::<T>::has_valid_cdd);
+        let receiver_cdd = to_did.map(Identity::<T>::has_valid_cdd);
+        let frozen = Self::frozen(&ticker);
+        let compliance = T::ComplianceManager::compliance_report(&ticker, from_did, to_did, amount);
+        let extensions = Self::verify_extensions(&ticker, sender, from_did, to_did, amount);
+
+        // Same precedence of checks as `unsafe_can_transfer`.
+        let status = if !granularity {
+            INVALID_GRANULARITY
+        } else if sender_cdd == Some(false) {
+            INVALID_SENDER_DID
+        } else if !sufficient_balance {
+            ERC1400_INSUFFICIENT_BALANCE
+        } else if receiver_cdd == Some(false) {
+            INVALID_RECEIVER_DID
+        } else if frozen {
+            ERC1400_TRANSFERS_HALTED
+        } else if !compliance.result {
+            COMPLIANCE_MANAGER_FAILURE
+        } else if !Self::is_extension_result_valid(extensions.iter().map(|(_, result)| result)) {
+            SMART_EXTENSION_FAILURE
+        } else {
+            ERC1400_TRANSFER_SUCCESS
+        };
+
+        TransferReport {
+            status,
+            granularity,
+            sender_balance,
+            sufficient_balance,
+            sender_cdd,
+            receiver_cdd,
+            frozen,
+            compliance,
+            extensions,
+        }
+    }
 }
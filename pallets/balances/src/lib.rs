@@ -198,8 +198,8 @@ use frame_support::{
 use frame_system::{self as system, ensure_root, ensure_signed};
 use sp_runtime::{
     traits::{
-        Bounded, CheckedAdd, CheckedSub, Hash, MaybeSerializeDeserialize, Saturating, StaticLookup,
-        Zero,
+        Bounded, CheckedAdd, CheckedSub, Hash, MaybeSerializeDeserialize, SaturatedConversion,
+        Saturating, StaticLookup, Zero,
     },
     DispatchError, DispatchResult, RuntimeDebug,
 };
@@ -638,7 +638,7 @@ impl<T: Trait> Module<T> {
     }
 
     // Polymesh modified code. New wrapper function for the transfer_core fuction that checks for CDD.
-    /// Checks CDD and then only performs the transfer
+    /// Checks CDD and the spending limit of the transactor and then only performs the transfer
     fn safe_transfer_core(
         transactor: &T::AccountId,
         dest: &T::AccountId,
@@ -651,8 +651,12 @@ impl<T: Trait> Module<T> {
             T::CddChecker::check_key_cdd(&dest_key),
             Error::<T>::ReceiverCddMissing
         );
+        let transactor_key = AccountKey::try_from((*transactor).encode())?;
+        T::Identity::ensure_polyx_spending_allowed(&transactor_key, value.saturated_into())?;
 
-        Self::transfer_core(transactor, dest, value, memo, existence_requirement)
+        Self::transfer_core(transactor, dest, value, memo, existence_requirement)?;
+        T::Identity::record_polyx_spending(&transactor_key, value.saturated_into());
+        Ok(())
     }
 
     /// Common funtionality for transfers.
@@ -739,6 +743,31 @@ where
     ) -> sp_std::result::Result<NegativeImbalance<T>, DispatchError> {
         <Self as Currency<T::AccountId>>::withdraw(who, value, reasons, liveness)
     }
+
+    fn reserve(who: &T::AccountId, value: T::Balance) -> DispatchResult {
+        <Self as ReservableCurrency<T::AccountId>>::reserve(who, value)
+    }
+
+    fn unreserve(who: &T::AccountId, value: T::Balance) -> T::Balance {
+        <Self as ReservableCurrency<T::AccountId>>::unreserve(who, value)
+    }
+
+    fn slash_reserved(who: &T::AccountId, value: T::Balance) -> (NegativeImbalance<T>, T::Balance) {
+        <Self as ReservableCurrency<T::AccountId>>::slash_reserved(who, value)
+    }
+
+    fn release_identity_balance(
+        did: &IdentityId,
+        dest: &T::AccountId,
+    ) -> sp_std::result::Result<T::Balance, DispatchError> {
+        let value = Self::identity_balance(did);
+        // Not managing imbalances because they will cancel out, as in `reclaim_identity_balance`.
+        // The deposit goes first and fails without changes, so no value is lost.
+        let _ = <Self as Currency<_>>::deposit_into_existing(dest, value)?;
+        let _ = Self::withdraw_identity_balance(did, value);
+        <IdentityBalance<T>>::remove(did);
+        Ok(value)
+    }
 }
 
 // Polymesh modified code. Managed BRR related functions.
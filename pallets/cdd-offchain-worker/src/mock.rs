@@ -30,7 +30,7 @@ use pallet_identity::{self as identity};
 use pallet_protocol_fee as protocol_fee;
 use pallet_staking::{EraIndex, Exposure, ExposureOf, StakerStatus, StashOf};
 use polymesh_common_utilities::traits::{
-    asset::AcceptTransfer,
+    asset::{AcceptTransfer, AssetHoldings},
     balances::{AccountData, CheckCdd},
     group::{GroupTrait, InactiveMember},
     identity::Trait as IdentityTrait,
@@ -277,6 +277,7 @@ impl IdentityTrait for Test {
     type Event = ();
     type Proposal = Call;
     type AddSignerMultiSigTarget = Test;
+    type AssetHoldingsTarget = Test;
     type CddServiceProviders = group::Module<Test, group::Instance2>;
     type Balances = pallet_balances::Module<Test>;
     type ChargeTxFeeTarget = Test;
@@ -296,6 +297,9 @@ impl pallet_transaction_payment::CddAndFeeDetails<Call> for Test {
         None
     }
     fn set_current_identity(_: &IdentityId) {}
+    fn charge_spending_limit(_: &Signatory, _: Balance) -> Result<(), InvalidTransaction> {
+        Ok(())
+    }
 }
 
 impl pallet_transaction_payment::ChargeTxFee for Test {
@@ -360,6 +364,15 @@ impl AcceptTransfer for Test {
     fn accept_asset_ownership_transfer(_: IdentityId, _: u64) -> DispatchResult {
         Ok(())
     }
+    fn accept_portfolio_custody(_: IdentityId, _: u64) -> DispatchResult {
+        Ok(())
+    }
+}
+
+impl AssetHoldings for Test {
+    fn has_asset_holdings(_: IdentityId) -> bool {
+        false
+    }
 }
 
 impl AddSignerMultiSig for Test {
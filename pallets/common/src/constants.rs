@@ -52,3 +52,4 @@ pub const PIP_MAX_REPORTING_SIZE: usize = 1024;
 
 /// Module ids, used for deriving sovereign account IDs for modules.
 pub const TREASURY_MODULE_ID: ModuleId = ModuleId(*b"pm/trsry");
+pub const SETTLEMENT_MODULE_ID: ModuleId = ModuleId(*b"pm/setlm");
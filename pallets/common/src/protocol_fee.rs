@@ -36,6 +36,7 @@ pub enum ProtocolOp {
     IdentityAddSigningItem,
     PipsPropose,
     VotingAddBallot,
+    IdentityRegisterCustomClaimType,
 }
 
 /// Common interface to protocol fees for runtime modules.
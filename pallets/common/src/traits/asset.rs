@@ -16,7 +16,8 @@
 use frame_support::dispatch::DispatchResult;
 use polymesh_primitives::{IdentityId, Ticker};
 
-/// This trait is used to call functions that accept transfer of a ticker or token ownership
+/// This trait is used to call functions that accept transfer of a ticker, token ownership or
+/// portfolio custody
 pub trait AcceptTransfer {
     /// Accept and process a ticker transfer
     ///
@@ -30,6 +31,18 @@ pub trait AcceptTransfer {
     /// * `to_did` did of the receiver
     /// * `auth_id` Authorization id of the authorization created by current token owner
     fn accept_asset_ownership_transfer(to_did: IdentityId, auth_id: u64) -> DispatchResult;
+    /// Accept and process the custody of a portfolio
+    ///
+    /// # Arguments
+    /// * `to_did` did of the new custodian
+    /// * `auth_id` Authorization id of the authorization created by the current custodian
+    fn accept_portfolio_custody(to_did: IdentityId, auth_id: u64) -> DispatchResult;
+}
+
+/// This trait is used to query the assets held by an identity.
+pub trait AssetHoldings {
+    /// Returns `true` if `did` has a non-zero balance of any asset, or owns any ticker.
+    fn has_asset_holdings(did: IdentityId) -> bool;
 }
 
 pub trait Trait<V, U> {
@@ -43,4 +56,11 @@ pub trait Trait<V, U> {
     ) -> DispatchResult;
     fn is_owner(ticker: &Ticker, did: IdentityId) -> bool;
     fn get_balance_at(ticker: &Ticker, did: IdentityId, at: u64) -> V;
+    /// Returns the number of identities which have ever held `ticker`.
+    fn holder_count(ticker: &Ticker) -> u64;
+    /// Returns the identity at `index` in the holder list of `ticker`.
+    fn holder_at(ticker: &Ticker, index: u64) -> IdentityId;
+    /// Returns the ID of the latest checkpoint created by schedule `schedule_id` of `ticker` at or
+    /// before the timestamp `at`.
+    fn schedule_checkpoint_at(ticker: &Ticker, schedule_id: u64, at: u64) -> Option<u64>;
 }
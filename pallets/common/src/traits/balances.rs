@@ -18,7 +18,7 @@ use crate::traits::{identity::IdentityTrait, CommonTrait, NegativeImbalance};
 use codec::{Decode, Encode};
 use frame_support::{
     decl_event,
-    dispatch::DispatchError,
+    dispatch::{DispatchError, DispatchResult},
     traits::{ExistenceRequirement, Get, OnUnbalanced, StoredMap, WithdrawReason, WithdrawReasons},
 };
 use frame_system::{self as system};
@@ -178,6 +178,24 @@ pub trait BalancesTrait<A, B, NI> {
         reasons: WithdrawReasons,
         _liveness: ExistenceRequirement,
     ) -> sp_std::result::Result<NI, DispatchError>;
+
+    /// Moves `value` from the free balance of `who` to their reserved balance.
+    fn reserve(who: &A, value: B) -> DispatchResult;
+
+    /// Moves up to `value` from the reserved balance of `who` to their free balance, returning
+    /// the amount which could not be unreserved.
+    fn unreserve(who: &A, value: B) -> B;
+
+    /// Slashes up to `value` from the reserved balance of `who`, returning the negative imbalance
+    /// created and the amount which could not be slashed.
+    fn slash_reserved(who: &A, value: B) -> (NI, B);
+
+    /// Moves the whole balance of the identity `did` to the free balance of `dest`, returning
+    /// the amount moved. Nothing is moved if `dest` can't take the balance.
+    fn release_identity_balance(
+        did: &IdentityId,
+        dest: &A,
+    ) -> sp_std::result::Result<B, DispatchError>;
 }
 
 pub trait CheckCdd {
@@ -13,9 +13,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use polymesh_primitives::{IdentityId, Ticker};
+use polymesh_primitives::{ClaimType, ComplianceReport, IdentityId, Ticker};
 
 use core::result::Result;
+use sp_std::vec::Vec;
 
 pub trait Trait<Balance> {
     fn verify_restriction(
@@ -24,4 +25,17 @@ pub trait Trait<Balance> {
         to_id: Option<IdentityId>,
         _value: Balance,
     ) -> Result<u8, &'static str>;
+
+    /// Returns the default trusted claim issuers of `ticker` which are currently trusted for
+    /// `claim_type` claims.
+    fn trusted_claim_issuers(ticker: &Ticker, claim_type: ClaimType) -> Vec<IdentityId>;
+
+    /// Evaluates the same checks as `verify_restriction` and reports the result of each
+    /// transfer restriction and rule.
+    fn compliance_report(
+        ticker: &Ticker,
+        from_id: Option<IdentityId>,
+        to_id: Option<IdentityId>,
+        value: Balance,
+    ) -> ComplianceReport;
 }
@@ -13,8 +13,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use polymesh_primitives::{IdentityId, Ticker};
+use polymesh_primitives::{ClaimType, IdentityId, Ticker};
 
 pub trait Trait {
-    fn is_exempted(ticker: &Ticker, tm: u16, did: IdentityId) -> bool;
+    /// It returns true if `did` is exempted from the compliance rules of `ticker` on
+    /// `claim_type`, or from all the rules of `ticker` if `claim_type` is `None`.
+    fn is_exempted(ticker: &Ticker, claim_type: Option<ClaimType>, did: IdentityId) -> bool;
 }
@@ -15,17 +15,19 @@
 
 use crate::{
     traits::{
-        balances, group::GroupTrait, multisig::AddSignerMultiSig, CommonTrait, NegativeImbalance,
+        asset::AssetHoldings, balances, group::GroupTrait, multisig::AddSignerMultiSig,
+        CommonTrait, NegativeImbalance,
     },
     ChargeProtocolFee, SystematicIssuers,
 };
 use polymesh_primitives::{
-    AccountKey, AuthorizationData, IdentityClaim, IdentityId, LinkData, Permission, Signatory,
-    SigningItem, Ticker,
+    AccountKey, AuthorizationData, Balance as PolymeshBalance, BlockNumber, CustomClaimTypeId,
+    IdentityClaim, IdentityId, LinkData, Permission, RecoveryConfig, Signatory, SigningItem,
+    SpendingLimit, SpendingLimitKind, Ticker,
 };
 
 use codec::{Decode, Encode};
-use frame_support::{decl_event, weights::GetDispatchInfo, Parameter};
+use frame_support::{decl_event, dispatch::DispatchResult, weights::GetDispatchInfo, Parameter};
 use pallet_transaction_payment::{CddAndFeeDetails, ChargeTxFee};
 use sp_core::H512;
 use sp_runtime::traits::{Dispatchable, IdentifyAccount, Member, Verify};
@@ -90,6 +92,8 @@ pub trait Trait: CommonTrait + pallet_timestamp::Trait + balances::Trait {
         + GetDispatchInfo;
     /// MultiSig module
     type AddSignerMultiSigTarget: AddSignerMultiSig;
+    /// Asset module
+    type AssetHoldingsTarget: AssetHoldings;
     /// Group module
     type CddServiceProviders: GroupTrait<<Self as pallet_timestamp::Trait>::Moment>;
 
@@ -115,6 +119,7 @@ decl_event!(
     where
         AccountId = <T as frame_system::Trait>::AccountId,
         Moment = <T as pallet_timestamp::Trait>::Moment,
+        Balance = <T as CommonTrait>::Balance,
     {
         /// DID, master key account ID, signing keys
         DidCreated(IdentityId, AccountId, Vec<SigningItem>),
@@ -132,6 +137,24 @@ decl_event!(
         /// DID, old master key account ID, new key
         MasterKeyUpdated(IdentityId, AccountKey, AccountKey),
 
+        /// The delay before master key rotations of an identity are enacted changed.
+        /// (DID, delay in blocks)
+        MasterKeyRotationDelaySet(IdentityId, BlockNumber),
+
+        /// A reduction of the delay before master key rotations of an identity are enacted was
+        /// scheduled. (DID, delay in blocks, block number)
+        MasterKeyRotationDelayReductionScheduled(IdentityId, BlockNumber, BlockNumber),
+
+        /// A master key rotation was accepted and is enacted at a block.
+        /// (DID, new master key, block number)
+        MasterKeyRotationScheduled(IdentityId, AccountKey, BlockNumber),
+
+        /// A pending master key rotation was cancelled. (DID, new master key)
+        MasterKeyRotationCancelled(IdentityId, AccountKey),
+
+        /// An identity was closed and its balance released. (DID, beneficiary, released balance)
+        IdentityClosed(IdentityId, AccountId, Balance),
+
         /// DID, claims
         ClaimAdded(IdentityId, IdentityClaim),
 
@@ -214,6 +237,59 @@ decl_event!(
 
         /// All Signing keys of the identity ID are unfrozen.
         SigningKeysUnfrozen(IdentityId),
+
+        /// A claim type has been registered on-chain.
+        /// (caller DID, custom claim type id, name)
+        CustomClaimTypeRegistered(IdentityId, CustomClaimTypeId, Vec<u8>),
+
+        /// A claim issuer has been registered and its deposit bonded.
+        /// (claim issuer DID, bonding account ID, deposit)
+        ClaimIssuerRegistered(IdentityId, AccountId, Balance),
+
+        /// The metadata of a claim issuer has been updated. (claim issuer DID)
+        ClaimIssuerUpdated(IdentityId),
+
+        /// A claim issuer has been deregistered and its deposit released. (claim issuer DID)
+        ClaimIssuerDeregistered(IdentityId),
+
+        /// A claim issuer has been suspended or reinstated. (claim issuer DID, suspended)
+        ClaimIssuerSuspended(IdentityId, bool),
+
+        /// Claims of an issuer added or updated from `Moment` have been revoked.
+        /// (claim issuer DID, moment)
+        ClaimIssuerClaimsRevoked(IdentityId, Moment),
+
+        /// The deposit required to register a claim issuer changed. (new deposit)
+        ClaimIssuerDepositChanged(Balance),
+
+        /// The deposit of a claim issuer has been slashed. (claim issuer DID, slashed amount)
+        ClaimIssuerSlashed(IdentityId, Balance),
+
+        /// The revocation of the claims of an issuer has been cleared. (claim issuer DID)
+        ClaimIssuerClaimsRevocationCleared(IdentityId),
+
+        /// The spending limit of a signing key was set or removed. (DID, signing key, limit)
+        SpendingLimitSet(IdentityId, AccountKey, Option<SpendingLimit<Balance, Moment>>),
+
+        /// A signing key tried to exceed one of its spending limits. (DID, signing key, limit)
+        SpendingLimitExceeded(IdentityId, AccountKey, SpendingLimitKind),
+
+        /// The recovery configuration of an identity was set or removed. (DID, configuration)
+        RecoveryConfigSet(IdentityId, Option<RecoveryConfig>),
+
+        /// A guardian started the recovery of the master key of an identity.
+        /// (DID, guardian, new master key)
+        RecoveryInitiated(IdentityId, Signatory, AccountKey),
+
+        /// A guardian approved the recovery of the master key of an identity.
+        /// (DID, guardian, new master key)
+        RecoveryApproved(IdentityId, Signatory, AccountKey),
+
+        /// The master key cancelled the recoveries of its identity. (DID)
+        RecoveryCancelled(IdentityId),
+
+        /// A recovery installed the new master key of an identity. (DID, new master key)
+        RecoveryCompleted(IdentityId, AccountKey),
     }
 );
 
@@ -232,6 +308,12 @@ pub trait IdentityTrait {
     ) -> bool;
     fn is_master_key(did: IdentityId, key: &AccountKey) -> bool;
 
+    /// Checks that the spending limit of `key` allows it to spend `amount` of POLYX.
+    fn ensure_polyx_spending_allowed(key: &AccountKey, amount: PolymeshBalance) -> DispatchResult;
+
+    /// Adds `amount` of POLYX to what `key` has spent under its spending limit.
+    fn record_polyx_spending(key: &AccountKey, amount: PolymeshBalance);
+
     /// It adds a systematic CDD claim for each `target` identity.
     ///
     /// It is used when we add a new member to CDD providers or Governance Committee.
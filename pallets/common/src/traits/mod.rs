@@ -63,3 +63,4 @@ pub mod governance_group;
 pub mod group;
 pub mod identity;
 pub mod multisig;
+pub mod statistics;
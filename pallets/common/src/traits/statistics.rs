@@ -0,0 +1,32 @@
+// This file is part of the Polymesh distribution (https://github.com/PolymathNetwork/Polymesh).
+// Copyright (c) 2020 Polymath
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use polymesh_primitives::{ClaimType, IdentityId, JurisdictionName, Scope, Ticker};
+
+/// Statistics of an asset which can be used by transfer restrictions.
+pub trait Trait<Balance, Moment> {
+    /// Number of investors of `ticker`.
+    fn investor_count(ticker: &Ticker) -> u64;
+
+    /// Number of investors of `ticker` holding a claim of `claim_type` in `scope`.
+    /// It is only tracked for claims registered through `statistics::track_claim`.
+    fn claim_investor_count(ticker: &Ticker, claim_type: ClaimType, scope: Scope) -> u64;
+
+    /// Aggregate balance of `ticker` held by investors from `jurisdiction`.
+    fn jurisdiction_balance(ticker: &Ticker, jurisdiction: &JurisdictionName) -> Balance;
+
+    /// Time when `did` acquired `ticker` after last holding no balance of it.
+    fn holding_since(ticker: &Ticker, did: IdentityId) -> Option<Moment>;
+}
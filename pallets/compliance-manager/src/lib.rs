@@ -45,6 +45,11 @@
 //! - **receiver rules:** These are rules that the receiver of security tokens must follow
 //! - **Valid transfer:** For a transfer to be valid,
 //!     All receiver and sender rules of any of the active asset rule must be followed.
+//! - **Rule template:** A versioned list of asset rules published by any identity, which tickers
+//!     can subscribe to. Template rules are evaluated along with the active rules of the ticker,
+//!     so a template which allows a transfer makes it valid. A subscription which follows the
+//!     latest version lets the template owner loosen the compliance of the ticker at any time;
+//!     pin a version to only use rules which have been reviewed.
 //!
 //! ## Interface
 //!
@@ -53,6 +58,9 @@
 //! - `add_active_rule` - Adds a new asset rule to ticker's active rules
 //! - `remove_active_rule` - Removes an asset rule from ticker's active rules
 //! - `reset_active_rules` - Reset(remove) all active rules of a ticker
+//! - `add_transfer_restriction` - Adds a restriction on the investor count or ownership of a ticker
+//! - `remove_transfer_restriction` - Removes a restriction from a ticker
+//! - `modify_ownership_exemption` - Exempts an identity from the maximum ownership restriction
 //!
 //! ### Public Functions
 //!
@@ -66,12 +74,17 @@ use polymesh_common_utilities::{
     balances::Trait as BalancesTrait,
     compliance_manager::Trait as ComplianceManagerTrait,
     constants::*,
+    exemption::Trait as ExemptionTrait,
     identity::Trait as IdentityTrait,
     protocol_fee::{ChargeProtocolFee, ProtocolOp},
+    statistics::Trait as StatisticsTrait,
     Context,
 };
 use polymesh_primitives::{
-    predicate, AccountKey, Claim, IdentityId, Rule, RuleType, Signatory, Ticker,
+    migration::{self, RuleV1},
+    predicate::{self, Predicate},
+    AccountKey, AssetTransferRuleReport, Claim, ClaimType, ComplianceReport, IdentityClaim,
+    IdentityId, JurisdictionName, Moment, Rule, RuleReport, RuleType, Scope, Signatory, Ticker,
 };
 
 use codec::Encode;
@@ -80,9 +93,14 @@ use frame_support::{
     decl_error, decl_event, decl_module, decl_storage,
     dispatch::DispatchResult,
     ensure,
+    storage::unhashed,
     weights::{DispatchClass, FunctionOf, SimpleDispatchInfo},
 };
 use frame_system::{self as system, ensure_signed};
+use sp_runtime::{
+    traits::{Saturating, Zero},
+    Permill, SaturatedConversion,
+};
 use sp_std::{
     convert::{From, TryFrom},
     prelude::*,
@@ -97,8 +115,29 @@ pub trait Trait:
 
     /// Asset module
     type Asset: AssetTrait<Self::Balance, Self::AccountId>;
+
+    /// Statistics module
+    type Statistics: StatisticsTrait<Self::Balance, Self::Moment>;
+
+    /// Exemption module, which exempts identities from the asset rules.
+    type Exemption: ExemptionTrait;
 }
 
+/// Maximum depth of a rule tree.
+pub const MAX_RULE_DEPTH: usize = 4;
+
+/// Maximum number of rules, including nested ones, of an asset rule.
+pub const MAX_RULE_SIZE: usize = 32;
+
+/// Maximum number of transfer restrictions per ticker.
+pub const MAX_TRANSFER_RESTRICTIONS: usize = 10;
+
+/// Maximum number of asset rules of a rule template.
+pub const MAX_TEMPLATE_RULES: usize = 10;
+
+/// Maximum number of rule templates which a ticker can subscribe to.
+pub const MAX_TEMPLATE_SUBSCRIPTIONS: usize = 5;
+
 /// An asset rule.
 /// All sender and receiver rules of the same asset rule must be true for transfer to be valid
 #[derive(codec::Encode, codec::Decode, Default, Clone, PartialEq, Eq, Debug)]
@@ -109,12 +148,119 @@ pub struct AssetTransferRule {
     pub rule_id: u32,
 }
 
+impl AssetTransferRule {
+    /// Number of sender and receiver rules, including nested ones.
+    pub fn size(&self) -> usize {
+        rules_size(&self.sender_rules) + rules_size(&self.receiver_rules)
+    }
+}
+
+/// Number of `rules`, including nested ones.
+fn rules_size(rules: &[Rule]) -> usize {
+    rules.iter().map(Rule::size).sum()
+}
+
 #[derive(codec::Encode, codec::Decode, Default, Clone, PartialEq, Eq, Debug)]
 pub struct AssetTransferRules {
     pub is_paused: bool,
     pub rules: Vec<AssetTransferRule>,
 }
 
+/// `AssetTransferRule` as it was encoded when `Scope` was an `IdentityId`.
+type AssetTransferRuleV1 = (Vec<RuleV1>, Vec<RuleV1>, u32);
+
+fn asset_transfer_rule_from_v1(
+    (sender_rules, receiver_rules, rule_id): AssetTransferRuleV1,
+) -> AssetTransferRule {
+    AssetTransferRule {
+        sender_rules: sender_rules.into_iter().map(Rule::from).collect(),
+        receiver_rules: receiver_rules.into_iter().map(Rule::from).collect(),
+        rule_id,
+    }
+}
+
+/// `TransferRestriction` as it was encoded when `Scope` was an `IdentityId`.
+#[derive(codec::Encode, codec::Decode)]
+enum TransferRestrictionV1 {
+    MaxInvestorCount(u64),
+    MaxInvestorOwnership(Permill, bool),
+    MaxClaimInvestorCount(ClaimType, IdentityId, u64),
+}
+
+impl From<TransferRestrictionV1> for TransferRestriction {
+    fn from(old: TransferRestrictionV1) -> Self {
+        match old {
+            TransferRestrictionV1::MaxInvestorCount(max) => {
+                TransferRestriction::MaxInvestorCount(max)
+            }
+            TransferRestrictionV1::MaxInvestorOwnership(max, primary_issuance) => {
+                TransferRestriction::MaxInvestorOwnership(max, primary_issuance)
+            }
+            TransferRestrictionV1::MaxClaimInvestorCount(claim_type, did, max) => {
+                TransferRestriction::MaxClaimInvestorCount(claim_type, did.into(), max)
+            }
+        }
+    }
+}
+
+/// A restriction on the investors of an asset, checked on every transfer after the asset rules.
+#[derive(codec::Encode, codec::Decode, Clone, PartialEq, Eq, Debug)]
+pub enum TransferRestriction {
+    /// Rejects transfers to a new investor once the asset has this many investors.
+    MaxInvestorCount(u64),
+    /// Rejects transfers which leave the receiver with more than this share of the total supply,
+    /// unless the receiver is exempted. Primary issuance is allowed if the flag is set.
+    MaxInvestorOwnership(Permill, bool),
+    /// Rejects transfers to a new investor holding a claim of this type and scope once this many
+    /// investors hold it. The claim must be tracked by the statistics module.
+    MaxClaimInvestorCount(ClaimType, Scope, u64),
+}
+
+/// A wrapper for the name of a rule template.
+#[derive(
+    codec::Encode, codec::Decode, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub struct RuleTemplateName(pub Vec<u8>);
+
+impl<T: AsRef<[u8]>> From<T> for RuleTemplateName {
+    fn from(s: T) -> Self {
+        RuleTemplateName(s.as_ref().to_vec())
+    }
+}
+
+/// Subscription of a ticker to a rule template.
+#[derive(codec::Encode, codec::Decode, Clone, PartialEq, Eq, Debug)]
+pub struct RuleTemplateSubscription {
+    /// Owner of the rule template.
+    pub owner: IdentityId,
+    /// Name of the rule template.
+    pub name: RuleTemplateName,
+    /// Version of the rule template used by the ticker. If it is `None`, the latest version is
+    /// used, so template updates apply to the ticker.
+    pub pinned_version: Option<u32>,
+}
+
+/// Claim types and time window for which a claim issuer is trusted by an asset.
+/// The default scope trusts the issuer for any claim type at any time.
+#[derive(codec::Encode, codec::Decode, Default, Clone, PartialEq, Eq, Debug)]
+pub struct TrustedIssuerScope {
+    /// Claim types trusted from the issuer. An empty list trusts any claim type.
+    pub claim_types: Vec<ClaimType>,
+    /// Moment since the issuer is trusted.
+    pub valid_from: Option<Moment>,
+    /// Moment since the issuer is not trusted anymore.
+    pub valid_until: Option<Moment>,
+}
+
+impl TrustedIssuerScope {
+    /// It returns true if claims of `claim_type` are trusted at `now`.
+    pub fn is_trusted_for(&self, claim_type: ClaimType, now: Moment) -> bool {
+        (self.claim_types.is_empty() || self.claim_types.contains(&claim_type))
+            && self.valid_from.map_or(true, |from| from <= now)
+            && self.valid_until.map_or(true, |until| now < until)
+    }
+}
+
 type Identity<T> = identity::Module<T>;
 
 decl_storage! {
@@ -123,6 +269,23 @@ decl_storage! {
         pub AssetRulesMap get(fn asset_rules): map hasher(blake2_128_concat) Ticker => AssetTransferRules;
         /// List of trusted claim issuer Ticker -> Issuer Identity
         pub TrustedClaimIssuer get(fn trusted_claim_issuer): map hasher(blake2_128_concat) Ticker => Vec<IdentityId>;
+        /// List of transfer restrictions for a ticker (Ticker -> Array of TransferRestriction)
+        pub TransferRestrictions get(fn transfer_restrictions): map hasher(blake2_128_concat) Ticker => Vec<TransferRestriction>;
+        /// Identities exempted from the maximum ownership restriction (Ticker -> DID -> exempted)
+        pub OwnershipExemptions get(fn ownership_exempted): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) IdentityId => bool;
+        /// Claim types and time window for which a claim issuer is trusted (Ticker -> Issuer DID -> TrustedIssuerScope).
+        /// It applies to the default trusted claim issuers and to the issuers of each rule.
+        pub TrustedIssuerScopes get(fn trusted_issuer_scope): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) IdentityId => TrustedIssuerScope;
+        /// Latest version of each rule template ((owner DID, template name) -> version). Versions start at 1.
+        pub RuleTemplateVersion get(fn rule_template_version): map hasher(blake2_128_concat) (IdentityId, RuleTemplateName) => u32;
+        /// Asset rules of each version of a rule template ((owner DID, template name) -> version -> asset rules).
+        pub RuleTemplates get(fn rule_template): double_map hasher(blake2_128_concat) (IdentityId, RuleTemplateName), hasher(twox_64_concat) u32 => Vec<AssetTransferRule>;
+        /// Rule templates which a ticker is subscribed to (Ticker -> Array of RuleTemplateSubscription).
+        pub RuleTemplateSubscriptions get(fn rule_template_subscriptions): map hasher(blake2_128_concat) Ticker => Vec<RuleTemplateSubscription>;
+        /// Version of the storage layout. Version 1 stores claims with `Scope` as an enum.
+        pub StorageVersion get(fn storage_version) build(|_| 1u32): u32;
+        /// Progress of the migration to the next storage version.
+        MigrationCursor get(fn migration_cursor): migration::MigrationCursor;
     }
 }
 
@@ -141,7 +304,35 @@ decl_error! {
         /// Issuer exist but trying to add it again
         IncorrectOperationOnTrustedIssuer,
         /// Missing current DID
-        MissingCurrentIdentity
+        MissingCurrentIdentity,
+        /// The transfer restriction already exists.
+        DuplicateTransferRestriction,
+        /// The transfer restriction doesn't exist.
+        NoSuchTransferRestriction,
+        /// The maximum number of transfer restrictions has been reached.
+        TooManyTransferRestrictions,
+        /// There is no change in the state.
+        NoChange,
+        /// A rule tree is deeper than `MAX_RULE_DEPTH`.
+        RuleTooDeep,
+        /// An asset rule has more than `MAX_RULE_SIZE` rules, including nested ones.
+        TooManyRules,
+        /// The validity window of a trusted issuer ends before it starts.
+        InvalidValidityWindow,
+        /// A rule template has more than `MAX_TEMPLATE_RULES` asset rules.
+        TooManyTemplateRules,
+        /// The rule template doesn't exist.
+        NoSuchRuleTemplate,
+        /// The version of the rule template doesn't exist.
+        NoSuchRuleTemplateVersion,
+        /// The ticker is subscribed to `MAX_TEMPLATE_SUBSCRIPTIONS` rule templates.
+        TooManyTemplateSubscriptions,
+        /// The ticker is not subscribed to the rule template.
+        NotSubscribedToRuleTemplate,
+        /// A rule refers to a custom claim type which is not registered.
+        CustomClaimTypeDoesNotExist,
+        /// An asset rule of a rule template has neither sender nor receiver rules.
+        EmptyTemplateRule
     }
 }
 
@@ -152,6 +343,12 @@ decl_module! {
 
         fn deposit_event() = default;
 
+        fn on_initialize(_block_number: T::BlockNumber) {
+            if Self::storage_version() < 1 {
+                Self::migrate_storage();
+            }
+        }
+
         /// Adds an asset rule to active rules for a ticker
         ///
         /// # Arguments
@@ -159,11 +356,27 @@ decl_module! {
         /// * ticker - Symbol of the asset
         /// * sender_rules - Sender transfer rule.
         /// * receiver_rules - Receiver transfer rule.
+        ///
+        /// # Weight
+        /// `100_000 + 50_000 * number of rules, including nested ones`
+        #[weight = FunctionOf(
+            |(_, sender_rules, receiver_rules): (
+                &Ticker,
+                &Vec<Rule>,
+                &Vec<Rule>,
+            )| {
+                let size = rules_size(sender_rules) + rules_size(receiver_rules);
+                100_000 + 50_000 * u32::try_from(size).unwrap_or(u32::max_value() / 50_000)
+            },
+            DispatchClass::Normal,
+            true
+        )]
         pub fn add_active_rule(origin, ticker: Ticker, sender_rules: Vec<Rule>, receiver_rules: Vec<Rule>) -> DispatchResult {
             let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
             let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
 
             ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            Self::ensure_valid_rules(&sender_rules, &receiver_rules)?;
             <<T as IdentityTrait>::ProtocolFee>::charge_fee(
                 &Signatory::AccountKey(sender_key),
                 ProtocolOp::ComplianceManagerAddActiveRule
@@ -328,12 +541,22 @@ decl_module! {
         /// * origin - Signer of the dispatchable. It should be the owner of the ticker.
         /// * ticker - Symbol of the asset.
         /// * asset_rule - Asset rule.
-        #[weight = SimpleDispatchInfo::FixedNormal(150_000)]
+        ///
+        /// # Weight
+        /// `100_000 + 50_000 * number of rules, including nested ones`
+        #[weight = FunctionOf(
+            |(_, asset_rule): (&Ticker, &AssetTransferRule)| {
+                100_000 + 50_000 * u32::try_from(asset_rule.size()).unwrap_or(u32::max_value() / 50_000)
+            },
+            DispatchClass::Normal,
+            true
+        )]
         pub fn change_asset_rule(origin, ticker: Ticker, asset_rule: AssetTransferRule) -> DispatchResult {
             let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
             let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
 
             ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            Self::ensure_valid_rules(&asset_rule.sender_rules, &asset_rule.receiver_rules)?;
             ensure!(Self::get_latest_rule_id(ticker) >= asset_rule.rule_id, Error::<T>::InvalidRuleId);
             Self::unsafe_change_asset_rule(did, ticker, asset_rule);
             Ok(())
@@ -347,13 +570,15 @@ decl_module! {
         /// * asset_rules - Vector of asset rule.
         ///
         /// # Weight
-        /// `100_000 + 100_000 * asset_rules.len().max(values.len())`
+        /// `100_000 + 100_000 * asset_rules.len() + 50_000 * number of rules, including nested ones`
         #[weight = FunctionOf(
             |(_, asset_rules): (
                 &Ticker,
                 &Vec<AssetTransferRule>,
             )| {
+                let size = asset_rules.iter().map(AssetTransferRule::size).sum::<usize>();
                 100_000 + 100_000 * u32::try_from(asset_rules.len()).unwrap_or_default()
+                    + 50_000 * u32::try_from(size).unwrap_or(u32::max_value() / 50_000)
             },
             DispatchClass::Normal,
             true
@@ -365,12 +590,250 @@ decl_module! {
             ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
             let latest_rule_id = Self::get_latest_rule_id(ticker);
             ensure!(asset_rules.iter().any(|rule| latest_rule_id >= rule.rule_id), Error::<T>::InvalidRuleId);
+            for asset_rule in asset_rules.iter() {
+                Self::ensure_valid_rules(&asset_rule.sender_rules, &asset_rule.receiver_rules)?;
+            }
 
             asset_rules.into_iter().for_each(|asset_rule| {
                 Self::unsafe_change_asset_rule(did, ticker, asset_rule);
             });
             Ok(())
         }
+
+        /// Adds a transfer restriction to a ticker.
+        ///
+        /// # Arguments
+        /// * origin - Signer of the dispatchable. It should be the owner of the ticker.
+        /// * ticker - Symbol of the asset.
+        /// * restriction - Transfer restriction.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn add_transfer_restriction(origin, ticker: Ticker, restriction: TransferRestriction) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            let mut restrictions = Self::transfer_restrictions(&ticker);
+            ensure!(!restrictions.contains(&restriction), Error::<T>::DuplicateTransferRestriction);
+            ensure!(restrictions.len() < MAX_TRANSFER_RESTRICTIONS, Error::<T>::TooManyTransferRestrictions);
+
+            restrictions.push(restriction.clone());
+            <TransferRestrictions>::insert(&ticker, restrictions);
+            Self::deposit_event(Event::TransferRestrictionAdded(did, ticker, restriction));
+            Ok(())
+        }
+
+        /// Removes a transfer restriction from a ticker.
+        ///
+        /// # Arguments
+        /// * origin - Signer of the dispatchable. It should be the owner of the ticker.
+        /// * ticker - Symbol of the asset.
+        /// * restriction - Transfer restriction.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn remove_transfer_restriction(origin, ticker: Ticker, restriction: TransferRestriction) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            let mut restrictions = Self::transfer_restrictions(&ticker);
+            let index = restrictions
+                .iter()
+                .position(|r| *r == restriction)
+                .ok_or(Error::<T>::NoSuchTransferRestriction)?;
+
+            restrictions.remove(index);
+            <TransferRestrictions>::insert(&ticker, restrictions);
+            Self::deposit_event(Event::TransferRestrictionRemoved(did, ticker, restriction));
+            Ok(())
+        }
+
+        /// Exempts an identity from the maximum ownership restriction of a ticker, or revokes
+        /// its exemption.
+        ///
+        /// # Arguments
+        /// * origin - Signer of the dispatchable. It should be the owner of the ticker.
+        /// * ticker - Symbol of the asset.
+        /// * holder - Identity whose exemption changes.
+        /// * exempted - New exemption status of `holder`.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn modify_ownership_exemption(origin, ticker: Ticker, holder: IdentityId, exempted: bool) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            ensure!(Self::ownership_exempted(&ticker, &holder) != exempted, Error::<T>::NoChange);
+
+            if exempted {
+                <OwnershipExemptions>::insert(&ticker, &holder, true);
+            } else {
+                <OwnershipExemptions>::remove(&ticker, &holder);
+            }
+            Self::deposit_event(Event::OwnershipExemptionModified(did, ticker, holder, exempted));
+            Ok(())
+        }
+
+        /// Sets the claim types and time window for which `issuer` is trusted by `ticker`, either
+        /// as a default trusted claim issuer or as an issuer of a rule.
+        /// Setting the default scope removes any restriction on `issuer`.
+        ///
+        /// # Arguments
+        /// * origin - Signer of the dispatchable. It should be the owner of the ticker.
+        /// * ticker - Symbol of the asset.
+        /// * issuer - IdentityId of the claim issuer.
+        /// * scope - Trusted claim types and validity window of `issuer`.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn set_trusted_issuer_scope(origin, ticker: Ticker, issuer: IdentityId, scope: TrustedIssuerScope) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            if let (Some(from), Some(until)) = (scope.valid_from, scope.valid_until) {
+                ensure!(from < until, Error::<T>::InvalidValidityWindow);
+            }
+
+            if scope == TrustedIssuerScope::default() {
+                <TrustedIssuerScopes>::remove(&ticker, &issuer);
+            } else {
+                <TrustedIssuerScopes>::insert(&ticker, &issuer, scope.clone());
+            }
+            Self::deposit_event(Event::TrustedIssuerScopeSet(did, ticker, issuer, scope));
+            Ok(())
+        }
+
+        /// Creates a rule template owned by the caller, or publishes a new version of it.
+        /// Tickers subscribed to the latest version of the template use the new version
+        /// immediately. Each asset rule of the template needs at least one sender or receiver
+        /// rule, as an empty asset rule would allow any transfer of the subscribed tickers.
+        ///
+        /// # Arguments
+        /// * origin - Signer of the dispatchable. It becomes the owner of the template.
+        /// * name - Name of the template, unique per owner.
+        /// * rules - Sender and receiver rules of each asset rule of the template.
+        ///
+        /// # Weight
+        /// `100_000 + 50_000 * number of rules, including nested ones`
+        #[weight = FunctionOf(
+            |(_, rules): (&RuleTemplateName, &Vec<(Vec<Rule>, Vec<Rule>)>)| {
+                let size = rules
+                    .iter()
+                    .map(|(sender_rules, receiver_rules)| rules_size(sender_rules) + rules_size(receiver_rules))
+                    .sum::<usize>();
+                100_000 + 50_000 * u32::try_from(size).unwrap_or(u32::max_value() / 50_000)
+            },
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn set_rule_template(origin, name: RuleTemplateName, rules: Vec<(Vec<Rule>, Vec<Rule>)>) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(!rules.is_empty(), Error::<T>::InvalidLength);
+            ensure!(rules.len() <= MAX_TEMPLATE_RULES, Error::<T>::TooManyTemplateRules);
+            for (sender_rules, receiver_rules) in rules.iter() {
+                ensure!(
+                    !sender_rules.is_empty() || !receiver_rules.is_empty(),
+                    Error::<T>::EmptyTemplateRule
+                );
+                Self::ensure_valid_rules(sender_rules, receiver_rules)?;
+            }
+
+            let key = (did, name.clone());
+            let version = Self::rule_template_version(&key) + 1;
+            let asset_rules = rules
+                .into_iter()
+                .zip(1..)
+                .map(|((sender_rules, receiver_rules), rule_id)| AssetTransferRule {
+                    sender_rules,
+                    receiver_rules,
+                    rule_id,
+                })
+                .collect::<Vec<_>>();
+            <RuleTemplates>::insert(&key, version, asset_rules);
+            <RuleTemplateVersion>::insert(&key, version);
+            Self::deposit_event(Event::RuleTemplateSet(did, name, version));
+            Ok(())
+        }
+
+        /// Subscribes a ticker to a rule template, or changes the version used by an existing
+        /// subscription. The asset rules of the template are evaluated along with the asset rules
+        /// of the ticker, so any template rule which is satisfied allows a transfer.
+        ///
+        /// Without `pinned_version`, every new version published by the template owner applies to
+        /// the ticker immediately, including versions which allow more transfers. The ticker
+        /// owner thus trusts the template owner with the compliance of the ticker.
+        ///
+        /// # Arguments
+        /// * origin - Signer of the dispatchable. It should be the owner of the ticker.
+        /// * ticker - Symbol of the asset.
+        /// * owner - Owner of the rule template.
+        /// * name - Name of the rule template.
+        /// * pinned_version - Version of the template to use, or `None` to follow its updates.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn subscribe_rule_template(
+            origin,
+            ticker: Ticker,
+            owner: IdentityId,
+            name: RuleTemplateName,
+            pinned_version: Option<u32>
+        ) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            let latest_version = Self::rule_template_version((owner, name.clone()));
+            ensure!(latest_version > 0, Error::<T>::NoSuchRuleTemplate);
+            if let Some(version) = pinned_version {
+                ensure!(
+                    version > 0 && version <= latest_version,
+                    Error::<T>::NoSuchRuleTemplateVersion
+                );
+            }
+
+            let subscription = RuleTemplateSubscription {
+                owner,
+                name: name.clone(),
+                pinned_version,
+            };
+            let mut subscriptions = Self::rule_template_subscriptions(&ticker);
+            if let Some(existing) = subscriptions
+                .iter_mut()
+                .find(|s| s.owner == owner && s.name == name)
+            {
+                *existing = subscription;
+            } else {
+                ensure!(
+                    subscriptions.len() < MAX_TEMPLATE_SUBSCRIPTIONS,
+                    Error::<T>::TooManyTemplateSubscriptions
+                );
+                subscriptions.push(subscription);
+            }
+            <RuleTemplateSubscriptions>::insert(&ticker, subscriptions);
+            Self::deposit_event(Event::RuleTemplateSubscribed(did, ticker, owner, name, pinned_version));
+            Ok(())
+        }
+
+        /// Unsubscribes a ticker from a rule template.
+        ///
+        /// # Arguments
+        /// * origin - Signer of the dispatchable. It should be the owner of the ticker.
+        /// * ticker - Symbol of the asset.
+        /// * owner - Owner of the rule template.
+        /// * name - Name of the rule template.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn unsubscribe_rule_template(origin, ticker: Ticker, owner: IdentityId, name: RuleTemplateName) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+            ensure!(Self::is_owner(&ticker, did), Error::<T>::Unauthorized);
+            let mut subscriptions = Self::rule_template_subscriptions(&ticker);
+            let index = subscriptions
+                .iter()
+                .position(|s| s.owner == owner && s.name == name)
+                .ok_or(Error::<T>::NotSubscribedToRuleTemplate)?;
+            subscriptions.remove(index);
+            <RuleTemplateSubscriptions>::insert(&ticker, subscriptions);
+            Self::deposit_event(Event::RuleTemplateUnsubscribed(did, ticker, owner, name));
+            Ok(())
+        }
     }
 }
 
@@ -400,6 +863,33 @@ decl_event!(
         /// Emitted when default claim issuer list for a given ticker get removed.
         /// (caller DID, Ticker, Removed Claim issuer DID).
         TrustedDefaultClaimIssuerRemoved(IdentityId, Ticker, IdentityId),
+        /// Emitted when a transfer restriction is added to a ticker.
+        /// (caller DID, Ticker, TransferRestriction).
+        TransferRestrictionAdded(IdentityId, Ticker, TransferRestriction),
+        /// Emitted when a transfer restriction is removed from a ticker.
+        /// (caller DID, Ticker, TransferRestriction).
+        TransferRestrictionRemoved(IdentityId, Ticker, TransferRestriction),
+        /// Emitted when the ownership exemption of an identity changes.
+        /// (caller DID, Ticker, holder DID, exempted).
+        OwnershipExemptionModified(IdentityId, Ticker, IdentityId, bool),
+        /// Emitted when the trusted scope of a claim issuer changes.
+        /// (caller DID, Ticker, issuer DID, TrustedIssuerScope).
+        TrustedIssuerScopeSet(IdentityId, Ticker, IdentityId, TrustedIssuerScope),
+        /// Emitted when a rule template is created or updated.
+        /// (owner DID, template name, new version).
+        RuleTemplateSet(IdentityId, RuleTemplateName, u32),
+        /// Emitted when a ticker subscribes to a rule template or changes its pinned version.
+        /// (caller DID, Ticker, owner DID, template name, pinned version).
+        RuleTemplateSubscribed(
+            IdentityId,
+            Ticker,
+            IdentityId,
+            RuleTemplateName,
+            Option<u32>,
+        ),
+        /// Emitted when a ticker unsubscribes from a rule template.
+        /// (caller DID, Ticker, owner DID, template name).
+        RuleTemplateUnsubscribed(IdentityId, Ticker, IdentityId, RuleTemplateName),
     }
 );
 
@@ -408,51 +898,292 @@ impl<T: Trait> Module<T> {
         T::Asset::is_owner(ticker, sender_did)
     }
 
+    /// Runs a batch of the migration to the next storage version, and bumps the storage version
+    /// once the migration is done.
+    fn migrate_storage() {
+        let mut cursor = Self::migration_cursor();
+        let done = migration::run_migration_batch(
+            &mut cursor,
+            &[
+                Self::migrate_asset_rules,
+                Self::migrate_rule_templates,
+                Self::migrate_transfer_restrictions,
+            ],
+        );
+        if done {
+            <StorageVersion>::mutate(|version| *version += 1);
+            <MigrationCursor>::kill();
+        } else {
+            <MigrationCursor>::put(cursor);
+        }
+    }
+
+    /// Migrates the asset rules stored when `Scope` was an `IdentityId` to `Scope::Identity`.
+    fn migrate_asset_rules(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        let prefix = migration::storage_prefix(b"ComplianceManager", b"AssetRulesMap");
+        let (entries, next) =
+            migration::raw_entries_page::<(bool, Vec<AssetTransferRuleV1>)>(&prefix, after, limit);
+        for (raw_key, (is_paused, rules)) in entries {
+            let asset_rules = AssetTransferRules {
+                is_paused,
+                rules: rules.into_iter().map(asset_transfer_rule_from_v1).collect(),
+            };
+            unhashed::put(&raw_key, &asset_rules);
+        }
+        next
+    }
+
+    /// Migrates the rule templates stored when `Scope` was an `IdentityId` to `Scope::Identity`.
+    fn migrate_rule_templates(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        let prefix = migration::storage_prefix(b"ComplianceManager", b"RuleTemplates");
+        let (entries, next) =
+            migration::raw_entries_page::<Vec<AssetTransferRuleV1>>(&prefix, after, limit);
+        for (raw_key, rules) in entries {
+            let rules = rules
+                .into_iter()
+                .map(asset_transfer_rule_from_v1)
+                .collect::<Vec<_>>();
+            unhashed::put(&raw_key, &rules);
+        }
+        next
+    }
+
+    /// Migrates the transfer restrictions stored when `Scope` was an `IdentityId` to
+    /// `Scope::Identity`.
+    fn migrate_transfer_restrictions(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        let prefix = migration::storage_prefix(b"ComplianceManager", b"TransferRestrictions");
+        let (entries, next) =
+            migration::raw_entries_page::<Vec<TransferRestrictionV1>>(&prefix, after, limit);
+        for (raw_key, restrictions) in entries {
+            let restrictions = restrictions
+                .into_iter()
+                .map(TransferRestriction::from)
+                .collect::<Vec<_>>();
+            unhashed::put(&raw_key, &restrictions);
+        }
+        next
+    }
+
     /// It fetches all claims of `target` identity with type and scope from `claim` and generated
-    /// by any of `issuers`.
-    fn fetch_claims(target: IdentityId, claim: &Claim, issuers: &[IdentityId]) -> Vec<Claim> {
+    /// by any of `issuers` which is trusted by `ticker` for that claim type at `now`.
+    fn fetch_claims(
+        ticker: &Ticker,
+        target: IdentityId,
+        claim: &Claim,
+        issuers: &[IdentityId],
+        now: Moment,
+    ) -> Vec<IdentityClaim> {
         let claim_type = claim.claim_type();
         let scope = claim.as_scope().cloned();
 
         issuers
             .iter()
+            .filter(|issuer| {
+                Self::trusted_issuer_scope(ticker, *issuer).is_trusted_for(claim_type, now)
+            })
             .flat_map(|issuer| {
-                <identity::Module<T>>::fetch_claim(target, claim_type, *issuer, scope)
-                    .map(|id_claim| id_claim.claim)
+                <identity::Module<T>>::fetch_claim(target, claim_type, *issuer, scope.clone())
             })
             .collect::<Vec<_>>()
     }
 
-    /// It fetches the predicate context for target `id` and specific `rule`.
-    fn fetch_context(ticker: &Ticker, id: IdentityId, rule: &Rule) -> predicate::Context {
+    /// It fetches the predicate context for target `id` and the simple `rule`, using the claims
+    /// generated by any of `issuers`. `balance` is the balance of `id` after the transfer.
+    fn fetch_context(
+        ticker: &Ticker,
+        id: IdentityId,
+        rule: &Rule,
+        issuers: &[IdentityId],
+        balance: u128,
+    ) -> predicate::Context {
+        let now = <pallet_timestamp::Module<T>>::get().saturated_into::<Moment>();
+        let fetch_claims = |claim: &Claim| Self::fetch_claims(ticker, id, claim, issuers, now);
+        let claims = match rule.rule_type {
+            RuleType::IsPresent(ref claim) => fetch_claims(claim),
+            RuleType::IsAbsent(ref claim) => fetch_claims(claim),
+            RuleType::IsAnyOf(ref claims) => {
+                claims.iter().flat_map(fetch_claims).collect::<Vec<_>>()
+            }
+            RuleType::IsNoneOf(ref claims) => {
+                claims.iter().flat_map(fetch_claims).collect::<Vec<_>>()
+            }
+            RuleType::IsJurisdictionIn(ref scope, _)
+            | RuleType::IsJurisdictionNotIn(ref scope, _) => {
+                let claim = Claim::Jurisdiction(JurisdictionName::default(), scope.clone());
+                fetch_claims(&claim)
+            }
+            RuleType::IsIssuedDaysAgo(ref claim, _) | RuleType::IsNotLockedUp(ref claim) => {
+                fetch_claims(claim)
+            }
+            // Nested rules load their own context.
+            RuleType::All(..)
+            | RuleType::Any(..)
+            | RuleType::Not(..)
+            | RuleType::IsBalanceBelow(..) => Vec::new(),
+        };
+
+        let mut context = predicate::Context::from(claims);
+        context.now = now;
+        context.balance = balance;
+        context
+    }
+
+    /// It evaluates `rule` for `did`. Simple rules are evaluated as predicates over a context
+    /// loaded from their own issuers, or from the issuers of the closest enclosing rule which
+    /// defines them, or otherwise from `default_issuers`.
+    fn is_rule_satisfied(
+        ticker: &Ticker,
+        did: IdentityId,
+        rule: &Rule,
+        default_issuers: &[IdentityId],
+        balance: u128,
+    ) -> bool {
         let issuers = if !rule.issuers.is_empty() {
-            rule.issuers.clone()
+            rule.issuers.as_slice()
         } else {
-            Self::trusted_claim_issuer(ticker)
+            default_issuers
         };
 
-        let claims = match rule.rule_type {
-            RuleType::IsPresent(ref claim) => Self::fetch_claims(id, claim, &issuers),
-            RuleType::IsAbsent(ref claim) => Self::fetch_claims(id, claim, &issuers),
-            RuleType::IsAnyOf(ref claims) => claims
+        match rule.rule_type {
+            RuleType::All(ref rules) => rules
                 .iter()
-                .flat_map(|claim| Self::fetch_claims(id, claim, &issuers))
-                .collect::<Vec<_>>(),
-            RuleType::IsNoneOf(ref claims) => claims
+                .all(|rule| Self::is_rule_satisfied(ticker, did, rule, issuers, balance)),
+            RuleType::Any(ref rules) => rules
                 .iter()
-                .flat_map(|claim| Self::fetch_claims(id, claim, &issuers))
-                .collect::<Vec<_>>(),
+                .any(|rule| Self::is_rule_satisfied(ticker, did, rule, issuers, balance)),
+            RuleType::Not(ref rule) => {
+                !Self::is_rule_satisfied(ticker, did, rule, issuers, balance)
+            }
+            _ => {
+                let context = Self::fetch_context(ticker, did, rule, issuers, balance);
+                predicate::rule(rule).evaluate(&context)
+            }
+        }
+    }
+
+    /// It evaluates `rule` for `did` as `is_rule_satisfied` does, and reports the issuers and
+    /// claims used to evaluate it and each of its nested rules.
+    fn rule_report(
+        ticker: &Ticker,
+        did: IdentityId,
+        rule: &Rule,
+        default_issuers: &[IdentityId],
+        balance: u128,
+    ) -> RuleReport {
+        let issuers = if !rule.issuers.is_empty() {
+            rule.issuers.as_slice()
+        } else {
+            default_issuers
+        };
+        let nested = rule
+            .nested_rules()
+            .iter()
+            .map(|nested_rule| Self::rule_report(ticker, did, nested_rule, issuers, balance))
+            .collect::<Vec<_>>();
+
+        let (result, claims) = match rule.rule_type {
+            RuleType::All(..) => (nested.iter().all(|report| report.result), Vec::new()),
+            RuleType::Any(..) => (nested.iter().any(|report| report.result), Vec::new()),
+            RuleType::Not(..) => (!nested.iter().all(|report| report.result), Vec::new()),
+            _ => {
+                let context = Self::fetch_context(ticker, did, rule, issuers, balance);
+                let result = predicate::rule(rule).evaluate(&context);
+                (result, context.identity_claims)
+            }
         };
 
-        predicate::Context::from(claims)
+        RuleReport {
+            rule: rule.clone(),
+            result,
+            exempted: false,
+            issuers: issuers.to_vec(),
+            claims,
+            nested,
+        }
     }
 
-    /// It loads a context for each rule in `rules` and verify if any of them is evaluated as a
-    /// false predicate. In that case, rule is considered as a "broken rule".
-    fn is_any_rule_broken(ticker: &Ticker, did: IdentityId, rules: Vec<Rule>) -> bool {
-        rules.into_iter().any(|rule| {
-            let context = Self::fetch_context(ticker, did, &rule);
-            !predicate::run(rule, &context)
+    /// It returns the balances of the sender and the receiver after the transfer of `value`.
+    fn balances_after_transfer(
+        ticker: &Ticker,
+        from_did_opt: Option<IdentityId>,
+        to_did_opt: Option<IdentityId>,
+        value: T::Balance,
+    ) -> (T::Balance, T::Balance) {
+        let from_balance = from_did_opt
+            .map(|did| T::Asset::balance(ticker, did).saturating_sub(value))
+            .unwrap_or_else(Zero::zero);
+        let to_balance = to_did_opt
+            .map(|did| T::Asset::balance(ticker, did).saturating_add(value))
+            .unwrap_or_else(Zero::zero);
+        (from_balance, to_balance)
+    }
+
+    /// It evaluates each rule in `rules` and verify if any of them is evaluated as a false
+    /// predicate. In that case, rule is considered as a "broken rule".
+    /// `balance` is the balance of `did` after the transfer.
+    fn is_any_rule_broken(
+        ticker: &Ticker,
+        did: IdentityId,
+        rules: Vec<Rule>,
+        balance: T::Balance,
+    ) -> bool {
+        let default_issuers = Self::trusted_claim_issuer(ticker);
+        let balance = balance.saturated_into::<u128>();
+        rules
+            .iter()
+            .filter(|rule| !Self::is_exempted(ticker, did, rule))
+            .any(|rule| !Self::is_rule_satisfied(ticker, did, rule, &default_issuers, balance))
+    }
+
+    /// It returns true if `did` is exempted from all the rules of `ticker`, or from every claim
+    /// type checked by `rule` and its nested rules. Exemptions only cover claims, so a rule tree
+    /// with a rule which checks no claim, like `IsBalanceBelow`, is never exempted.
+    fn is_exempted(ticker: &Ticker, did: IdentityId, rule: &Rule) -> bool {
+        if rule.has_rule_without_claims() {
+            return false;
+        }
+        if T::Exemption::is_exempted(ticker, None, did) {
+            return true;
+        }
+        let claim_types = rule.claim_types();
+        !claim_types.is_empty()
+            && claim_types
+                .into_iter()
+                .all(|claim_type| T::Exemption::is_exempted(ticker, Some(claim_type), did))
+    }
+
+    /// Ensures that every rule tree of an asset rule is within `MAX_RULE_DEPTH`, that the
+    /// asset rule has no more than `MAX_RULE_SIZE` rules, and that every custom claim type it
+    /// refers to is registered.
+    fn ensure_valid_rules(sender_rules: &[Rule], receiver_rules: &[Rule]) -> DispatchResult {
+        ensure!(
+            sender_rules
+                .iter()
+                .chain(receiver_rules)
+                .all(|rule| rule.depth() <= MAX_RULE_DEPTH),
+            Error::<T>::RuleTooDeep
+        );
+        ensure!(
+            rules_size(sender_rules) + rules_size(receiver_rules) <= MAX_RULE_SIZE,
+            Error::<T>::TooManyRules
+        );
+        ensure!(
+            Self::are_claim_types_registered(sender_rules)
+                && Self::are_claim_types_registered(receiver_rules),
+            Error::<T>::CustomClaimTypeDoesNotExist
+        );
+        Ok(())
+    }
+
+    /// It returns true if the custom claim types of the claims in `rules` and their nested rules
+    /// are registered.
+    fn are_claim_types_registered(rules: &[Rule]) -> bool {
+        rules.iter().all(|rule| {
+            rule.rule_type
+                .claims()
+                .iter()
+                .all(|claim| Identity::<T>::is_claim_type_registered(claim.claim_type()))
+                && Self::are_claim_types_registered(rule.nested_rules())
         })
     }
 
@@ -575,6 +1306,96 @@ impl<T: Trait> Module<T> {
         Self::deposit_event(Event::AssetRuleChanged(caller_did, ticker, new_asset_rule));
     }
 
+    /// Checks if a transfer of `value` from `from_did` to `to_did` breaks any transfer
+    /// restriction of `ticker`. Missing identities denote issuance and redemption.
+    fn is_any_restriction_broken(
+        ticker: &Ticker,
+        from_did: Option<IdentityId>,
+        to_did: Option<IdentityId>,
+        value: T::Balance,
+    ) -> bool {
+        let restrictions = Self::transfer_restrictions(ticker);
+        let to_did = match to_did {
+            // Redemptions can only lower the investor count and the ownership.
+            None => return false,
+            Some(_) if restrictions.is_empty() || value.is_zero() => return false,
+            Some(did) => did,
+        };
+        let balance_to = T::Asset::balance(ticker, to_did);
+        // The transfer adds a new investor unless the receiver already holds tokens or the sender
+        // transfers all of its tokens.
+        let is_new_investor = balance_to.is_zero()
+            && from_did.map_or(true, |did| T::Asset::balance(ticker, did) > value);
+
+        restrictions
+            .into_iter()
+            .any(|restriction| match restriction {
+                TransferRestriction::MaxInvestorCount(max) => {
+                    is_new_investor && T::Statistics::investor_count(ticker) >= max
+                }
+                TransferRestriction::MaxInvestorOwnership(max, allow_primary_issuance) => {
+                    if from_did.is_none() && allow_primary_issuance
+                        || Self::ownership_exempted(ticker, &to_did)
+                    {
+                        return false;
+                    }
+                    let mut total_supply = T::Asset::total_supply(ticker);
+                    if from_did.is_none() {
+                        total_supply = total_supply.saturating_add(value);
+                    }
+                    balance_to.saturating_add(value) > max * total_supply
+                }
+                TransferRestriction::MaxClaimInvestorCount(claim_type, scope, max) => {
+                    is_new_investor
+                        && Self::has_trusted_claim(ticker, to_did, claim_type, &scope)
+                        && T::Statistics::claim_investor_count(ticker, claim_type, scope) >= max
+                }
+            })
+    }
+
+    /// Checks if `did` holds a claim of `claim_type` in `scope` issued by a trusted claim issuer
+    /// of `ticker`.
+    fn has_trusted_claim(
+        ticker: &Ticker,
+        did: IdentityId,
+        claim_type: ClaimType,
+        scope: &Scope,
+    ) -> bool {
+        Self::trusted_claim_issuers_for(ticker, claim_type)
+            .into_iter()
+            .any(|issuer| {
+                <identity::Module<T>>::fetch_claim(did, claim_type, issuer, Some(scope.clone()))
+                    .is_some()
+            })
+    }
+
+    /// Returns the asset rules of the rule templates which `ticker` is subscribed to, at the
+    /// version pinned by each subscription or otherwise at the latest version.
+    pub fn template_rules(ticker: &Ticker) -> Vec<AssetTransferRule> {
+        Self::rule_template_subscriptions(ticker)
+            .into_iter()
+            .flat_map(|subscription| {
+                let key = (subscription.owner, subscription.name);
+                let version = subscription
+                    .pinned_version
+                    .unwrap_or_else(|| Self::rule_template_version(&key));
+                Self::rule_template(&key, version)
+            })
+            .collect()
+    }
+
+    /// Returns the default trusted claim issuers of `ticker` which are currently trusted for
+    /// `claim_type` claims.
+    pub fn trusted_claim_issuers_for(ticker: &Ticker, claim_type: ClaimType) -> Vec<IdentityId> {
+        let now = <pallet_timestamp::Module<T>>::get().saturated_into::<Moment>();
+        Self::trusted_claim_issuer(ticker)
+            .into_iter()
+            .filter(|issuer| {
+                Self::trusted_issuer_scope(ticker, issuer).is_trusted_for(claim_type, now)
+            })
+            .collect()
+    }
+
     // TODO: Cache the latest_rule_id to avoid loading of all asset_rules in memory.
     fn get_latest_rule_id(ticker: Ticker) -> u32 {
         let length = Self::asset_rules(ticker).rules.len();
@@ -591,19 +1412,38 @@ impl<T: Trait> ComplianceManagerTrait<T::Balance> for Module<T> {
         ticker: &Ticker,
         from_did_opt: Option<IdentityId>,
         to_did_opt: Option<IdentityId>,
-        _value: T::Balance,
+        value: T::Balance,
     ) -> StdResult<u8, &'static str> {
+        // Transfer restrictions apply even if the asset rules are paused.
+        if Self::is_any_restriction_broken(ticker, from_did_opt, to_did_opt, value) {
+            sp_runtime::print("Transfer restrictions not satisfied");
+            return Ok(ERC1400_TRANSFER_FAILURE);
+        }
+
         // Transfer is valid if ALL receiver AND sender rules of ANY asset rule are valid.
         let asset_rules = Self::asset_rules(ticker);
         if asset_rules.is_paused {
             return Ok(ERC1400_TRANSFER_SUCCESS);
         }
 
-        for active_rule in asset_rules.rules {
+        let (from_balance, to_balance) =
+            Self::balances_after_transfer(ticker, from_did_opt, to_did_opt, value);
+
+        // Asset rules of the ticker are merged with the asset rules of its templates.
+        for active_rule in asset_rules
+            .rules
+            .into_iter()
+            .chain(Self::template_rules(ticker))
+        {
             let mut rule_broken = false;
 
             if let Some(from_did) = from_did_opt {
-                rule_broken = Self::is_any_rule_broken(ticker, from_did, active_rule.sender_rules);
+                rule_broken = Self::is_any_rule_broken(
+                    ticker,
+                    from_did,
+                    active_rule.sender_rules,
+                    from_balance,
+                );
                 if rule_broken {
                     // Skips checking receiver rules because sender rules are not satisfied.
                     continue;
@@ -611,7 +1451,8 @@ impl<T: Trait> ComplianceManagerTrait<T::Balance> for Module<T> {
             }
 
             if let Some(to_did) = to_did_opt {
-                rule_broken = Self::is_any_rule_broken(ticker, to_did, active_rule.receiver_rules)
+                rule_broken =
+                    Self::is_any_rule_broken(ticker, to_did, active_rule.receiver_rules, to_balance)
             }
 
             if !rule_broken {
@@ -622,4 +1463,73 @@ impl<T: Trait> ComplianceManagerTrait<T::Balance> for Module<T> {
         sp_runtime::print("Identity TM restrictions not satisfied");
         Ok(ERC1400_TRANSFER_FAILURE)
     }
+
+    fn trusted_claim_issuers(ticker: &Ticker, claim_type: ClaimType) -> Vec<IdentityId> {
+        Self::trusted_claim_issuers_for(ticker, claim_type)
+    }
+
+    fn compliance_report(
+        ticker: &Ticker,
+        from_did_opt: Option<IdentityId>,
+        to_did_opt: Option<IdentityId>,
+        value: T::Balance,
+    ) -> ComplianceReport {
+        let restrictions_satisfied =
+            !Self::is_any_restriction_broken(ticker, from_did_opt, to_did_opt, value);
+        let asset_rules = Self::asset_rules(ticker);
+        let default_issuers = Self::trusted_claim_issuer(ticker);
+        let (from_balance, to_balance) =
+            Self::balances_after_transfer(ticker, from_did_opt, to_did_opt, value);
+
+        let rules_report = |did_opt: Option<IdentityId>, rules: &[Rule], balance: T::Balance| {
+            let balance = balance.saturated_into::<u128>();
+            did_opt
+                .map(|did| {
+                    rules
+                        .iter()
+                        .map(|rule| {
+                            let mut report =
+                                Self::rule_report(ticker, did, rule, &default_issuers, balance);
+                            if Self::is_exempted(ticker, did, rule) {
+                                report.result = true;
+                                report.exempted = true;
+                            }
+                            report
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+        let template_rules = Self::template_rules(ticker);
+        let rules = asset_rules
+            .rules
+            .iter()
+            .chain(template_rules.iter())
+            .map(|active_rule| {
+                let sender_rules =
+                    rules_report(from_did_opt, &active_rule.sender_rules, from_balance);
+                let receiver_rules =
+                    rules_report(to_did_opt, &active_rule.receiver_rules, to_balance);
+                let result = sender_rules
+                    .iter()
+                    .chain(receiver_rules.iter())
+                    .all(|report| report.result);
+                AssetTransferRuleReport {
+                    rule_id: active_rule.rule_id,
+                    sender_rules,
+                    receiver_rules,
+                    result,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let result = restrictions_satisfied
+            && (asset_rules.is_paused || rules.iter().any(|report| report.result));
+        ComplianceReport {
+            restrictions_satisfied,
+            paused: asset_rules.is_paused,
+            rules,
+            result,
+        }
+    }
 }
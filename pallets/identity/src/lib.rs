@@ -58,6 +58,14 @@
 //! - `set_master_key` - Sets a new master key for a DID.
 //! - `accept_master_key` - Accept authorization to become the new master key of an identity.
 //! - `change_cdd_requirement_for_mk_rotation` - Sets if CDD authorization is required for updating master key of an identity.
+//! - `set_master_key_rotation_delay` - Sets the delay before master key rotations of the caller's identity are enacted.
+//! - `cancel_master_key_rotation` - Cancels the pending master key rotation of an identity.
+//! - `close_identity` - Closes the caller's identity permanently and releases its balance.
+//! - `set_recovery_config` - Sets the guardians able to recover the master key of the caller's identity.
+//! - `initiate_recovery` - Starts the recovery of the master key of an identity as one of its guardians.
+//! - `approve_recovery` - Approves the recovery of the master key of an identity as one of its guardians.
+//! - `cancel_recovery` - Cancels the recoveries of the master key of the caller's identity.
+//! - `finalize_recovery` - Installs the new master key of an identity once its recovery is approved and delayed.
 //! - `join_identity_as_key` - Join an identity as a signing key.
 //! - `join_identity_as_identity` - Join an identity as a signing identity.
 //! - `add_claim` - Adds a new claim record or edits an existing one.
@@ -66,6 +74,7 @@
 //! - `revoke_claim` - Marks the specified claim as revoked.
 //! - `revoke_claims_batch` - Revokes multiple claims in a batch.
 //! - `set_permission_to_signer` - Sets permissions for an specific `target_key` key.
+//! - `set_spending_limit` - Sets the POLYX, asset and daily transaction limits of a signing key.
 //! - `freeze_signing_keys` - Disables all signing keys at `did` identity.
 //! - `unfreeze_signing_keys` - Re-enables all signing keys of the caller's identity.
 //! - `add_authorization` - Adds an authorization.
@@ -77,6 +86,16 @@
 //! - `batch_accept_authorization` - Accepts an array of authorizations.
 //! - `add_signing_items_with_authorization` - Adds signing keys to target identity `id`.
 //! - `revoke_offchain_authorization` - Revokes the `auth` off-chain authorization of `signer`.
+//! - `register_custom_claim_type` - Registers a claim type which can be issued as a custom claim.
+//! - `register_claim_issuer` - Registers the caller as a claim issuer and bonds its deposit.
+//! - `update_claim_issuer` - Updates the metadata of the caller as a claim issuer.
+//! - `deregister_claim_issuer` - Deregisters the caller as a claim issuer and releases its deposit.
+//! - `suspend_claim_issuer` - Suspends or reinstates a claim issuer, registered or not.
+//! - `revoke_claims_since` - Revokes the claims added or updated by the caller from a moment.
+//! - `revoke_issuer_claims_since` - Revokes the claims added or updated by an issuer from a moment.
+//! - `clear_claims_revocation` - Clears the revocation of the claims of an issuer.
+//! - `slash_claim_issuer` - Slashes the deposit of a claim issuer.
+//! - `set_claim_issuer_deposit` - Sets the deposit required to register a claim issuer.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![recursion_limit = "256"]
@@ -93,7 +112,7 @@ use polymesh_common_utilities::{
     },
     protocol_fee::{ChargeProtocolFee, ProtocolOp},
     traits::{
-        asset::AcceptTransfer,
+        asset::{AcceptTransfer, AssetHoldings},
         group::{GroupTrait, InactiveMember},
         identity::{
             AuthorizationNonce, IdentityTrait, LinkedKeyInfo, RawEvent, SigningItemWithAuth,
@@ -104,9 +123,13 @@ use polymesh_common_utilities::{
     Context, SystematicIssuers,
 };
 use polymesh_primitives::{
-    AccountKey, AuthIdentifier, Authorization, AuthorizationData, AuthorizationError, Claim,
-    ClaimType, Identity as DidRecord, IdentityClaim, IdentityId, Link, LinkData, Permission, Scope,
-    Signatory, SignatoryType, SigningItem, Ticker,
+    migration::{self, IdentityClaimV1, IdentityV1},
+    predicate, AccountKey, ActiveRecovery, AuthIdentifier, Authorization, AuthorizationData,
+    AuthorizationError, Balance as PolymeshBalance, BlockNumber, Claim, ClaimType,
+    CustomClaimTypeId, DispatchScope, Identity as DidRecord, IdentityClaim, IdentityId,
+    JurisdictionName, Link, LinkData, PendingMasterKeyRotation, PendingMasterKeyRotationDelay,
+    Permission, RecoveryConfig, Scope, Signatory, SignatoryType, SigningItem, SpendingLimit,
+    SpendingLimitKind, SpendingUsage, Ticker, MAX_RECOVERY_GUARDIANS,
 };
 
 use codec::{Decode, Encode};
@@ -118,7 +141,8 @@ use sp_core::sr25519::{Public, Signature};
 use sp_io::hashing::blake2_256;
 use sp_runtime::{
     traits::{
-        AccountIdConversion, CheckedAdd, Dispatchable, Hash, SaturatedConversion, Verify, Zero,
+        AccountIdConversion, CheckedAdd, Dispatchable, Hash, SaturatedConversion, Saturating,
+        Verify, Zero,
     },
     AnySignature,
 };
@@ -136,6 +160,12 @@ use frame_system::{self as system, ensure_root, ensure_signed};
 
 pub type Event<T> = polymesh_common_utilities::traits::identity::Event<T>;
 
+/// Maximum length of the name of a custom claim type.
+pub const MAX_CUSTOM_CLAIM_TYPE_NAME_LEN: usize = 64;
+
+/// Maximum length of each field of the metadata of a claim issuer.
+pub const MAX_CLAIM_ISSUER_METADATA_LEN: usize = 256;
+
 #[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, PartialOrd, Ord)]
 pub struct Claim1stKey {
     pub target: IdentityId,
@@ -161,6 +191,28 @@ pub struct BatchRevokeClaimItem {
     pub claim: Claim,
 }
 
+/// Public information of a claim issuer.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, Default)]
+pub struct ClaimIssuerMetadata {
+    /// Name of the claim issuer.
+    pub name: Vec<u8>,
+    /// Website of the claim issuer.
+    pub website: Vec<u8>,
+    /// Jurisdiction where the claim issuer operates.
+    pub jurisdiction: JurisdictionName,
+}
+
+/// A registered claim issuer.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct ClaimIssuer<AccountId, Balance> {
+    /// Public information of the claim issuer.
+    pub metadata: ClaimIssuerMetadata,
+    /// Account whose balance bonds the deposit.
+    pub bonded_by: AccountId,
+    /// Deposit bonded on registration, less the slashed amounts, released on deregistration.
+    pub deposit: Balance,
+}
+
 decl_storage! {
     trait Store for Module<T: Trait> as identity {
 
@@ -202,6 +254,58 @@ decl_storage! {
 
         /// It defines if authorization from a CDD provider is needed to change master key of an identity
         pub CddAuthForMasterKeyRotation get(fn cdd_auth_for_master_key_rotation): bool;
+
+        /// Registered claim issuers (DID -> claim issuer).
+        pub ClaimIssuers get(fn claim_issuer): map hasher(twox_64_concat) IdentityId => Option<ClaimIssuer<T::AccountId, T::Balance>>;
+        /// Deposit bonded by a claim issuer on registration.
+        pub ClaimIssuerDeposit get(fn claim_issuer_deposit): T::Balance;
+        /// Claim issuers suspended by the governance (DID -> suspended). Claims of a suspended
+        /// issuer are not valid, and it cannot add claims.
+        /// It applies to any issuer, registered or not.
+        pub SuspendedClaimIssuers get(fn is_claim_issuer_suspended): map hasher(twox_64_concat) IdentityId => bool;
+        /// Moment from which the claims added or updated by an issuer are revoked (DID -> moment).
+        /// It applies to any issuer, registered or not.
+        pub ClaimsRevokedSince get(fn claims_revoked_since): map hasher(twox_64_concat) IdentityId => Option<u64>;
+
+        /// Spending limits of signing keys (DID, signing key -> limit).
+        pub SpendingLimits get(fn spending_limit): double_map hasher(twox_64_concat) IdentityId, hasher(blake2_128_concat) AccountKey => Option<SpendingLimit<T::Balance, T::Moment>>;
+        /// What signing keys have spent under their spending limits (DID, signing key -> usage).
+        pub SpendingUsages get(fn spending_usage): double_map hasher(twox_64_concat) IdentityId, hasher(blake2_128_concat) AccountKey => SpendingUsage<T::Balance, T::Moment>;
+
+        /// Guardians able to recover the master key of an identity (DID -> configuration).
+        pub RecoveryConfigs get(fn recovery_config): map hasher(twox_64_concat) IdentityId => Option<RecoveryConfig>;
+        /// Recoveries of master keys in progress (DID, new master key -> recovery).
+        pub ActiveRecoveries get(fn active_recovery): double_map hasher(twox_64_concat) IdentityId, hasher(blake2_128_concat) AccountKey => Option<ActiveRecovery>;
+
+        /// Names of the claim types registered on-chain (custom claim type id -> name).
+        pub CustomClaimTypes get(fn custom_claim_type): map hasher(twox_64_concat) CustomClaimTypeId => Option<Vec<u8>>;
+        /// Ids of the claim types registered on-chain (name -> custom claim type id).
+        pub CustomClaimTypeIds get(fn custom_claim_type_id): map hasher(blake2_128_concat) Vec<u8> => Option<CustomClaimTypeId>;
+        /// Id of the next claim type registered on-chain.
+        pub CustomClaimTypeIdSequence get(fn custom_claim_type_id_sequence): u32;
+        /// Custom claim types of the claims issued to an identity (DID -> custom claim type ids).
+        pub CustomClaimTypesOf get(fn custom_claim_types_of): map hasher(twox_64_concat) IdentityId => Vec<CustomClaimTypeId>;
+
+        /// Blocks between the acceptance and the enactment of master key rotations (DID -> delay).
+        /// Rotations are enacted immediately without a delay.
+        pub MasterKeyRotationDelay get(fn master_key_rotation_delay): map hasher(twox_64_concat) IdentityId => BlockNumber;
+        /// Identities whose master key rotation is enacted at a block (block number -> DIDs).
+        pub ScheduledMasterKeyRotations get(fn scheduled_master_key_rotations): map hasher(twox_64_concat) BlockNumber => Vec<IdentityId>;
+        /// Reductions of the delay of master key rotations waiting for the current delay to
+        /// expire (DID -> pending delay).
+        pub PendingMasterKeyRotationDelays get(fn pending_master_key_rotation_delay): map hasher(twox_64_concat) IdentityId => Option<PendingMasterKeyRotationDelay>;
+        /// Identities whose delay of master key rotations is reduced at a block
+        /// (block number -> DIDs).
+        pub ScheduledMasterKeyRotationDelays get(fn scheduled_master_key_rotation_delays): map hasher(twox_64_concat) BlockNumber => Vec<IdentityId>;
+
+        /// Identities closed permanently, whose DIDs cannot be reused (DID -> closed).
+        pub ClosedIdentities get(fn is_identity_closed): map hasher(twox_64_concat) IdentityId => bool;
+
+        /// Version of the storage layout. Version 1 stores claims with `Scope` as an enum.
+        /// Version 2 stores identities with their pending master key rotation.
+        pub StorageVersion get(fn storage_version) build(|_| 2u32): u32;
+        /// Progress of the migration to the next storage version.
+        MigrationCursor get(fn migration_cursor): migration::MigrationCursor;
     }
     add_extra_genesis {
         config(identities): Vec<(T::AccountId, IdentityId, IdentityId, Option<u64>)>;
@@ -307,6 +411,15 @@ decl_module! {
         // this is needed only if you are using events in your module
         fn deposit_event() = default;
 
+        fn on_initialize(block_number: T::BlockNumber) {
+            if Self::storage_version() < 2 {
+                Self::migrate_storage();
+            }
+            let block_number = block_number.saturated_into::<BlockNumber>();
+            Self::enact_master_key_rotations(block_number);
+            Self::enact_master_key_rotation_delays(block_number);
+        }
+
         // TODO: Remove this function before mainnet. cdd_register_did should be used instead.
         /// Register a new did with a CDD claim for the caller.
         pub fn register_did(origin, signing_items: Vec<SigningItem>) -> DispatchResult {
@@ -351,6 +464,7 @@ decl_module! {
 
             let cdd_providers = T::CddServiceProviders::get_members();
             ensure!(cdd_providers.contains(&cdd_id), Error::<T>::UnAuthorizedCddProvider);
+            ensure!(!Self::is_claim_issuer_suspended(cdd_id), Error::<T>::ClaimIssuerSuspended);
             // Register Identity and add claim.
             let new_id = Self::_register_did(
                 target_account,
@@ -406,7 +520,11 @@ decl_module! {
             // Remove links and get all authorization IDs per signer.
             let signer_and_auth_id_list = signers_to_remove.iter().map(|signer| {
                 match signer {
-                    Signatory::AccountKey(ref key) => Self::unlink_key_to_did(key, did),
+                    Signatory::AccountKey(ref key) => {
+                        Self::unlink_key_to_did(key, did);
+                        <SpendingLimits<T>>::remove(did, key);
+                        <SpendingUsages<T>>::remove(did, key);
+                    }
                     _ => {}
                 };
 
@@ -440,17 +558,22 @@ decl_module! {
             Ok(())
         }
 
-        /// Sets a new master key for a DID.
+        /// Sets a new master key for a DID. If the identity has a master key rotation delay, the
+        /// new master key is pending until the delay expires, like accepted rotations.
         ///
         /// # Failure
         /// Only called by master key owner.
         #[weight = SimpleDispatchInfo::FixedNormal(300_000)]
-        fn set_master_key(origin, new_key: AccountKey) -> DispatchResult {
+        pub fn set_master_key(origin, new_key: AccountKey) -> DispatchResult {
             let sender = ensure_signed(origin)?;
             let sender_key = AccountKey::try_from( sender.encode())?;
             let did = Context::current_identity_or::<Self>(&sender_key)?;
-            let _grants_checked = Self::grant_check_only_master_key(&sender_key, did)?;
+            let record = Self::grant_check_only_master_key(&sender_key, did)?;
 
+            ensure!(
+                record.pending_master_key_rotation.is_none(),
+                Error::<T>::MasterKeyRotationPending
+            );
             ensure!(
                 Self::can_key_be_linked_to_did(&new_key, SignatoryType::External),
                 Error::<T>::AlreadyLinked
@@ -459,6 +582,11 @@ decl_module! {
                 &Signatory::AccountKey(sender_key),
                 ProtocolOp::IdentitySetMasterKey
             )?;
+            let delay = Self::master_key_rotation_delay(did);
+            if delay > 0 {
+                Self::unsafe_schedule_master_key_rotation(did, new_key, delay);
+                return Ok(());
+            }
             <DidRecords>::mutate(did,
             |record| {
                 (*record).master_key = new_key.clone();
@@ -498,6 +626,267 @@ decl_module! {
             Ok(())
         }
 
+        /// Sets the number of blocks between the acceptance and the enactment of rotations of
+        /// the master key of the caller's identity. Rotations are enacted immediately with a zero
+        /// delay. Only the master key can call it.
+        ///
+        /// An increase is applied immediately. A reduction is enacted once the current delay
+        /// expires, so that a stolen master key cannot skip the delay. A new call replaces a
+        /// pending reduction.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn set_master_key_rotation_delay(origin, delay: BlockNumber) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Self>(&sender_key)?;
+            let _grants_checked = Self::grant_check_only_master_key(&sender_key, did)?;
+
+            if let Some(pending) = <PendingMasterKeyRotationDelays>::take(did) {
+                <ScheduledMasterKeyRotationDelays>::mutate(pending.enact_at, |dids| {
+                    dids.retain(|scheduled| *scheduled != did)
+                });
+            }
+            let current_delay = Self::master_key_rotation_delay(did);
+            if delay >= current_delay {
+                <MasterKeyRotationDelay>::insert(did, delay);
+                Self::deposit_event(RawEvent::MasterKeyRotationDelaySet(did, delay));
+            } else {
+                let enact_at = <system::Module<T>>::block_number()
+                    .saturated_into::<BlockNumber>()
+                    .saturating_add(current_delay);
+                let pending = PendingMasterKeyRotationDelay { delay, enact_at };
+                <PendingMasterKeyRotationDelays>::insert(did, pending);
+                <ScheduledMasterKeyRotationDelays>::mutate(enact_at, |dids| dids.push(did));
+                Self::deposit_event(RawEvent::MasterKeyRotationDelayReductionScheduled(
+                    did, delay, enact_at
+                ));
+            }
+            Ok(())
+        }
+
+        /// Cancels the pending rotation of the master key of `did`. The caller must be the master
+        /// key of `did` or a signer with full permissions.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn cancel_master_key_rotation(origin, did: IdentityId) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let signer = match Self::get_identity(&sender_key) {
+                Some(sender_did) if sender_did != did => Signatory::from(sender_did),
+                _ => Signatory::from(sender_key),
+            };
+            ensure!(
+                Self::is_signer_authorized_with_permissions(did, &signer, vec![Permission::Full]),
+                Error::<T>::KeyNotAllowed
+            );
+            let rotation = Self::did_records(&did)
+                .pending_master_key_rotation
+                .ok_or(Error::<T>::NoPendingMasterKeyRotation)?;
+
+            <DidRecords>::mutate(&did, |record| (*record).pending_master_key_rotation = None);
+            <ScheduledMasterKeyRotations>::mutate(rotation.enact_at, |dids| {
+                dids.retain(|scheduled| *scheduled != did)
+            });
+            Self::deposit_event(RawEvent::MasterKeyRotationCancelled(did, rotation.new_master_key));
+            Ok(())
+        }
+
+        /// Sets the guardians able to recover the master key of the caller's identity, or
+        /// removes them with `None`. Only the master key can call it, and not while a recovery
+        /// is in progress.
+        ///
+        /// # Weight
+        /// `200_000 + 10_000 * guardians.len()`
+        #[weight = FunctionOf(
+            |(config,): (&Option<RecoveryConfig>,)| {
+                200_000 + 10_000 * config.as_ref().map_or(0, |config| {
+                    u32::try_from(config.guardians.len()).unwrap_or_default()
+                })
+            },
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn set_recovery_config(origin, config: Option<RecoveryConfig>) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Self>(&sender_key)?;
+            let _grants_checked = Self::grant_check_only_master_key(&sender_key, did)?;
+            ensure!(!Self::has_active_recovery(did), Error::<T>::RecoveryInProgress);
+
+            match config {
+                Some(ref config) => {
+                    ensure!(
+                        config.is_valid() && !config.guardians.contains(&Signatory::from(did)),
+                        Error::<T>::InvalidRecoveryConfig
+                    );
+                    <RecoveryConfigs<T>>::insert(did, config);
+                }
+                None => <RecoveryConfigs<T>>::remove(did),
+            }
+            Self::deposit_event(RawEvent::RecoveryConfigSet(did, config));
+            Ok(())
+        }
+
+        /// Starts the recovery of the master key of `did`, installing `new_master_key` once
+        /// enough guardians approve it and the delay passes. The caller must be a guardian of
+        /// `did`, and its call counts as an approval. Several keys can be proposed at the same
+        /// time, and each guardian approves at most one of them.
+        ///
+        /// # Weight
+        /// It is charged for as many recoveries in progress as guardians.
+        #[weight = SimpleDispatchInfo::FixedNormal(300_000 + 20_000 * MAX_RECOVERY_GUARDIANS as u32)]
+        pub fn initiate_recovery(origin, did: IdentityId, new_master_key: AccountKey) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let (guardian, config) = Self::recovery_guardian(did, &sender_key)?;
+            ensure!(
+                !<ActiveRecoveries<T>>::contains_key(did, &new_master_key),
+                Error::<T>::RecoveryInProgress
+            );
+            ensure!(
+                Self::can_key_be_linked_to_did(&new_master_key, SignatoryType::External),
+                Error::<T>::AlreadyLinked
+            );
+
+            Self::unsafe_withdraw_recovery_approval(did, &config, &guardian);
+            let recovery = ActiveRecovery {
+                new_master_key,
+                approvals: vec![guardian],
+                approved_at: None,
+            };
+            Self::deposit_event(RawEvent::RecoveryInitiated(did, guardian, new_master_key));
+            Self::unsafe_update_recovery(did, &config, recovery);
+            Ok(())
+        }
+
+        /// Approves the recovery of the master key of `did` to `new_master_key`. The caller must
+        /// be a guardian of `did` which has not approved it yet. An approval of the caller for
+        /// another key is withdrawn.
+        ///
+        /// # Weight
+        /// It is charged for as many recoveries in progress as guardians.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000 + 20_000 * MAX_RECOVERY_GUARDIANS as u32)]
+        pub fn approve_recovery(origin, did: IdentityId, new_master_key: AccountKey) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let (guardian, config) = Self::recovery_guardian(did, &sender_key)?;
+            ensure!(
+                <ActiveRecoveries<T>>::contains_key(did, &new_master_key),
+                Error::<T>::NoActiveRecovery
+            );
+            ensure!(
+                !Self::active_recovery(did, &new_master_key)
+                    .map_or(false, |recovery| recovery.approvals.contains(&guardian)),
+                Error::<T>::RecoveryAlreadyApproved
+            );
+
+            Self::unsafe_withdraw_recovery_approval(did, &config, &guardian);
+            let mut recovery = Self::active_recovery(did, &new_master_key)
+                .ok_or(Error::<T>::NoActiveRecovery)?;
+            recovery.approvals.push(guardian);
+            Self::deposit_event(RawEvent::RecoveryApproved(did, guardian, new_master_key));
+            Self::unsafe_update_recovery(did, &config, recovery);
+            Ok(())
+        }
+
+        /// Cancels every recovery of the master key of the caller's identity. Only the master
+        /// key can call it.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000 + 20_000 * MAX_RECOVERY_GUARDIANS as u32)]
+        pub fn cancel_recovery(origin) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Self>(&sender_key)?;
+            let _grants_checked = Self::grant_check_only_master_key(&sender_key, did)?;
+            ensure!(Self::has_active_recovery(did), Error::<T>::NoActiveRecovery);
+
+            <ActiveRecoveries<T>>::remove_prefix(did);
+            Self::deposit_event(RawEvent::RecoveryCancelled(did));
+            Ok(())
+        }
+
+        /// Installs the caller as the master key of `did` once its recovery has been approved by
+        /// the guardians and the longer of the recovery delay and the master key rotation delay
+        /// of `did` has passed. The caller must be the new master key of the
+        /// recovery, and it provides `optional_cdd_auth_id` if CDD authorization is required for
+        /// master key rotations.
+        #[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+        pub fn finalize_recovery(origin, did: IdentityId, optional_cdd_auth_id: Option<u64>) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let recovery = Self::active_recovery(did, &sender_key)
+                .ok_or(Error::<T>::NoActiveRecovery)?;
+            let config = Self::recovery_config(did).ok_or(Error::<T>::RecoveryConfigNotFound)?;
+            let approved_at = recovery.approved_at.ok_or(Error::<T>::RecoveryNotApproved)?;
+            // A recovery rotates the master key, so it can't skip the rotation delay.
+            let delay = config.delay.max(Self::master_key_rotation_delay(did));
+            ensure!(
+                <system::Module<T>>::block_number().saturated_into::<BlockNumber>()
+                    >= approved_at.saturating_add(delay),
+                Error::<T>::RecoveryDelayNotElapsed
+            );
+            ensure!(
+                Self::can_key_be_linked_to_did(&sender_key, SignatoryType::External),
+                Error::<T>::AlreadyLinked
+            );
+
+            Self::unsafe_master_key_rotation(sender_key, did, optional_cdd_auth_id)?;
+            Self::link_key_to_did(&sender_key, SignatoryType::External, did);
+            <ActiveRecoveries<T>>::remove_prefix(did);
+            Self::deposit_event(RawEvent::RecoveryCompleted(did, sender_key));
+            Ok(())
+        }
+
+        /// Closes the caller's identity permanently. Its keys are unlinked, the claims issued to
+        /// it are revoked and its balance is released to `beneficiary`. Only the master key can
+        /// call it, once the identity holds no assets or tickers and neither the identity nor its
+        /// keys have open authorizations. Nothing is closed if `beneficiary` can't take the
+        /// balance.
+        #[weight = SimpleDispatchInfo::FixedNormal(3_000_000)]
+        pub fn close_identity(origin, beneficiary: T::AccountId) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Self>(&sender_key)?;
+            let record = Self::grant_check_only_master_key(&sender_key, did)?;
+            ensure!(
+                !T::AssetHoldingsTarget::has_asset_holdings(did),
+                Error::<T>::IdentityHasAssetHoldings
+            );
+            // Authorizations of the signing keys would outlive the unlinked keys as well.
+            let has_open_authorizations = [Signatory::from(did), Signatory::from(sender_key)]
+                .iter()
+                .chain(record.signing_items.iter().filter_map(|si| match si.signer {
+                    Signatory::AccountKey(_) => Some(&si.signer),
+                    Signatory::Identity(_) => None,
+                }))
+                .any(|signer| {
+                    <Authorizations<T>>::iter_prefix(signer).next().is_some()
+                        || <AuthorizationsGiven>::iter_prefix(signer).next().is_some()
+                });
+            ensure!(!has_open_authorizations, Error::<T>::IdentityHasOpenAuthorizations);
+            ensure!(!Self::is_claim_issuer_suspended(did), Error::<T>::ClaimIssuerSuspended);
+            let claim_issuer = Self::claim_issuer(did);
+            // Releasing the identity balance is the only step which can fail, so it goes first.
+            let released = T::Balances::release_identity_balance(&did, &beneficiary)?;
+
+            // Unlink keys.
+            Self::unlink_key_to_did(&record.master_key, did);
+            for si in record.signing_items.iter() {
+                if let Signatory::AccountKey(ref key) = si.signer {
+                    Self::unlink_key_to_did(key, did);
+                }
+            }
+            <SpendingLimits<T>>::remove_prefix(did);
+            <SpendingUsages<T>>::remove_prefix(did);
+
+            // Release the claim issuer deposit.
+            if let Some(claim_issuer) = claim_issuer {
+                T::Balances::unreserve(&claim_issuer.bonded_by, claim_issuer.deposit);
+                <ClaimIssuers<T>>::remove(did);
+            }
+
+            Self::unsafe_revoke_claims_to(did);
+            <Links<T>>::remove_prefix(Signatory::from(did));
+            <RecoveryConfigs<T>>::remove(did);
+            <ActiveRecoveries<T>>::remove_prefix(did);
+            <PendingMasterKeyRotationDelays>::remove(did);
+            <IsDidFrozen>::remove(did);
+            <DidRecords>::remove(did);
+            <ClosedIdentities>::insert(did, true);
+
+            Self::deposit_event(RawEvent::IdentityClosed(did, beneficiary, released));
+            Ok(())
+        }
+
         /// Join an identity as a signing key.
         #[weight = SimpleDispatchInfo::FixedNormal(300_000)]
         pub fn join_identity_as_key(origin, auth_id: u64) -> DispatchResult {
@@ -524,6 +913,11 @@ decl_module! {
             let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
             let issuer = Context::current_identity_or::<Self>(&sender_key)?;
             ensure!(<DidRecords>::contains_key(target), Error::<T>::DidMustAlreadyExist);
+            ensure!(
+                Self::is_claim_type_registered(claim.claim_type()),
+                Error::<T>::CustomClaimTypeDoesNotExist
+            );
+            ensure!(!Self::is_claim_issuer_suspended(issuer), Error::<T>::ClaimIssuerSuspended);
 
             match claim {
                 Claim::CustomerDueDiligence => Self::unsafe_add_cdd_claim(target, claim, issuer, expiry)?,
@@ -560,6 +954,11 @@ decl_module! {
             ensure!( claims.iter().all(
                 |batch_claim_item| <DidRecords>::contains_key(batch_claim_item.target)),
                 Error::<T>::DidMustAlreadyExist);
+            ensure!(
+                claims.iter().all(|bci| Self::is_claim_type_registered(bci.claim.claim_type())),
+                Error::<T>::CustomClaimTypeDoesNotExist
+            );
+            ensure!(!Self::is_claim_issuer_suspended(issuer), Error::<T>::ClaimIssuerSuspended);
 
             let cdd_count: usize = claims
                 .iter()
@@ -629,6 +1028,166 @@ decl_module! {
             proposal.dispatch(new_origin)
         }
 
+        /// Registers a claim type named `name`. Claims of this type are issued as `Claim::Custom`
+        /// with the id assigned to the claim type. The caller pays the protocol fee of the
+        /// registration, as registered claim types are kept forever.
+        ///
+        /// # Errors
+        /// * `InvalidCustomClaimTypeName` if `name` is empty or too long.
+        /// * `CustomClaimTypeAlreadyExists` if a claim type named `name` is already registered.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn register_custom_claim_type(origin, name: Vec<u8>) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Self>(&sender_key)?;
+            ensure!(
+                !name.is_empty() && name.len() <= MAX_CUSTOM_CLAIM_TYPE_NAME_LEN,
+                Error::<T>::InvalidCustomClaimTypeName
+            );
+            ensure!(
+                !<CustomClaimTypeIds>::contains_key(&name),
+                Error::<T>::CustomClaimTypeAlreadyExists
+            );
+            let id = CustomClaimTypeId(Self::custom_claim_type_id_sequence());
+            let next_id = id.0.checked_add(1).ok_or(Error::<T>::CustomClaimTypeIdOverflow)?;
+            T::ProtocolFee::charge_fee(
+                &Signatory::AccountKey(sender_key),
+                ProtocolOp::IdentityRegisterCustomClaimType
+            )?;
+
+            <CustomClaimTypeIdSequence>::put(next_id);
+            <CustomClaimTypes>::insert(id, name.clone());
+            <CustomClaimTypeIds>::insert(&name, id);
+            Self::deposit_event(RawEvent::CustomClaimTypeRegistered(did, id, name));
+            Ok(())
+        }
+
+        /// Registers the caller identity as a claim issuer with `metadata`. The deposit set by
+        /// `set_claim_issuer_deposit` is reserved from the caller account until the issuer is
+        /// deregistered.
+        #[weight = SimpleDispatchInfo::FixedNormal(300_000)]
+        pub fn register_claim_issuer(origin, metadata: ClaimIssuerMetadata) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let sender_key = AccountKey::try_from(sender.encode())?;
+            let did = Context::current_identity_or::<Self>(&sender_key)?;
+            ensure!(!<ClaimIssuers<T>>::contains_key(did), Error::<T>::ClaimIssuerAlreadyRegistered);
+            Self::ensure_valid_claim_issuer_metadata(&metadata)?;
+
+            let deposit = Self::claim_issuer_deposit();
+            T::Balances::reserve(&sender, deposit)
+                .map_err(|_| Error::<T>::InsufficientClaimIssuerDeposit)?;
+            <ClaimIssuers<T>>::insert(did, ClaimIssuer {
+                metadata,
+                bonded_by: sender.clone(),
+                deposit,
+            });
+            Self::deposit_event(RawEvent::ClaimIssuerRegistered(did, sender, deposit));
+            Ok(())
+        }
+
+        /// Updates the metadata of the caller identity as a claim issuer.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn update_claim_issuer(origin, metadata: ClaimIssuerMetadata) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Self>(&sender_key)?;
+            let mut claim_issuer = Self::claim_issuer(did).ok_or(Error::<T>::ClaimIssuerNotRegistered)?;
+            Self::ensure_valid_claim_issuer_metadata(&metadata)?;
+
+            claim_issuer.metadata = metadata;
+            <ClaimIssuers<T>>::insert(did, claim_issuer);
+            Self::deposit_event(RawEvent::ClaimIssuerUpdated(did));
+            Ok(())
+        }
+
+        /// Deregisters the caller identity as a claim issuer and releases its deposit.
+        /// Suspended issuers cannot deregister.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn deregister_claim_issuer(origin) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Self>(&sender_key)?;
+            let claim_issuer = Self::claim_issuer(did).ok_or(Error::<T>::ClaimIssuerNotRegistered)?;
+            ensure!(!Self::is_claim_issuer_suspended(did), Error::<T>::ClaimIssuerSuspended);
+
+            T::Balances::unreserve(&claim_issuer.bonded_by, claim_issuer.deposit);
+            <ClaimIssuers<T>>::remove(did);
+            Self::deposit_event(RawEvent::ClaimIssuerDeregistered(did));
+            Ok(())
+        }
+
+        /// Suspends or reinstates a claim issuer, registered or not. While suspended, its claims
+        /// are not valid and it cannot add claims. Callable via root (governance).
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn suspend_claim_issuer(origin, issuer: IdentityId, suspended: bool) -> DispatchResult {
+            ensure_root(origin)?;
+            ensure!(Self::is_claim_issuer_suspended(issuer) != suspended, Error::<T>::NoChange);
+
+            if suspended {
+                <SuspendedClaimIssuers>::insert(issuer, true);
+            } else {
+                <SuspendedClaimIssuers>::remove(issuer);
+            }
+            Self::deposit_event(RawEvent::ClaimIssuerSuspended(issuer, suspended));
+            Ok(())
+        }
+
+        /// Revokes the claims added or updated by the caller identity from `since`, including
+        /// those added later.
+        /// Only the governance can clear the revocation, with `clear_claims_revocation`.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn revoke_claims_since(origin, since: T::Moment) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Self>(&sender_key)?;
+            Self::unsafe_revoke_claims_since(did, since);
+            Ok(())
+        }
+
+        /// Revokes the claims added or updated by `issuer` from `since`, including those added
+        /// later. Callable via root (governance).
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn revoke_issuer_claims_since(origin, issuer: IdentityId, since: T::Moment) -> DispatchResult {
+            ensure_root(origin)?;
+            Self::unsafe_revoke_claims_since(issuer, since);
+            Ok(())
+        }
+
+        /// Clears the revocation of the claims of `issuer` set by `revoke_claims_since` or
+        /// `revoke_issuer_claims_since`, so its claims are valid again. Callable via root
+        /// (governance).
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn clear_claims_revocation(origin, issuer: IdentityId) -> DispatchResult {
+            ensure_root(origin)?;
+            ensure!(<ClaimsRevokedSince>::contains_key(issuer), Error::<T>::NoChange);
+
+            <ClaimsRevokedSince>::remove(issuer);
+            Self::deposit_event(RawEvent::ClaimIssuerClaimsRevocationCleared(issuer));
+            Ok(())
+        }
+
+        /// Slashes up to `amount` from the deposit of the claim issuer `issuer`. The slashed
+        /// amount is burned, and the rest of the deposit is released on deregistration.
+        /// Callable via root (governance).
+        #[weight = SimpleDispatchInfo::FixedNormal(300_000)]
+        pub fn slash_claim_issuer(origin, issuer: IdentityId, amount: T::Balance) -> DispatchResult {
+            ensure_root(origin)?;
+            let mut claim_issuer = Self::claim_issuer(issuer).ok_or(Error::<T>::ClaimIssuerNotRegistered)?;
+            let amount = amount.min(claim_issuer.deposit);
+            ensure!(!amount.is_zero(), Error::<T>::NoChange);
+
+            let (_, unslashed) = T::Balances::slash_reserved(&claim_issuer.bonded_by, amount);
+            claim_issuer.deposit -= amount;
+            <ClaimIssuers<T>>::insert(issuer, claim_issuer);
+            Self::deposit_event(RawEvent::ClaimIssuerSlashed(issuer, amount - unslashed));
+            Ok(())
+        }
+
+        /// Sets the deposit bonded by new claim issuers. Callable via root (governance).
+        #[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+        pub fn set_claim_issuer_deposit(origin, deposit: T::Balance) -> DispatchResult {
+            ensure_root(origin)?;
+            <ClaimIssuerDeposit<T>>::put(deposit);
+            Self::deposit_event(RawEvent::ClaimIssuerDepositChanged(deposit));
+            Ok(())
+        }
+
         /// Marks the specified claim as revoked.
         #[weight = SimpleDispatchInfo::FixedNormal(250_000)]
         pub fn revoke_claim(origin,
@@ -699,6 +1258,40 @@ decl_module! {
             }
         }
 
+        /// Sets the spending limit of the signing key `signer`, or removes it with `None`.
+        /// Only the master key of an identity is able to set spending limits.
+        ///
+        /// # Weight
+        /// `300_000 + 30_000 * max_assets.len()`
+        #[weight = FunctionOf(
+            |(_, limit): (&AccountKey, &Option<SpendingLimit<T::Balance, T::Moment>>)| {
+                300_000 + 30_000 * limit.as_ref().map_or(0, |limit| {
+                    u32::try_from(limit.max_assets.len()).unwrap_or_default()
+                })
+            },
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn set_spending_limit(origin, signer: AccountKey, limit: Option<SpendingLimit<T::Balance, T::Moment>>) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Self>(&sender_key)?;
+            let record = Self::grant_check_only_master_key(&sender_key, did)?;
+            ensure!(
+                record.signing_items.iter().any(|si| si.signer == signer),
+                Error::<T>::InvalidSender
+            );
+
+            match limit {
+                Some(ref limit) => <SpendingLimits<T>>::insert(did, signer, limit),
+                None => {
+                    <SpendingLimits<T>>::remove(did, signer);
+                    <SpendingUsages<T>>::remove(did, signer);
+                }
+            }
+            Self::deposit_event(RawEvent::SpendingLimitSet(did, signer, limit));
+            Ok(())
+        }
+
         /// It disables all signing keys at `did` identity.
         ///
         /// # Errors
@@ -902,6 +1495,8 @@ decl_module! {
                             T::AcceptTransferTarget::accept_ticker_transfer(did, auth_id),
                         AuthorizationData::TransferAssetOwnership(_) =>
                             T::AcceptTransferTarget::accept_asset_ownership_transfer(did, auth_id),
+                        AuthorizationData::PortfolioCustody(_) =>
+                            T::AcceptTransferTarget::accept_portfolio_custody(did, auth_id),
                         AuthorizationData::AddMultiSigSigner =>
                             T::AddSignerMultiSigTarget::accept_multisig_signer(Signatory::from(did), auth_id),
                         AuthorizationData::JoinIdentity(_) =>
@@ -959,6 +1554,8 @@ decl_module! {
                                     T::AcceptTransferTarget::accept_ticker_transfer(did, auth_id),
                                 AuthorizationData::TransferAssetOwnership(_) =>
                                     T::AcceptTransferTarget::accept_asset_ownership_transfer(did, auth_id),
+                                AuthorizationData::PortfolioCustody(_) =>
+                                    T::AcceptTransferTarget::accept_portfolio_custody(did, auth_id),
                                 AuthorizationData::AddMultiSigSigner =>
                                     T::AddSignerMultiSigTarget::accept_multisig_signer(Signatory::from(did), auth_id),
                                 AuthorizationData::JoinIdentity(_) =>
@@ -1185,10 +1782,364 @@ decl_error! {
         SigningKeysContainMasterKey,
         /// Couldn't charge fee for the transaction
         FailedToChargeFee,
+        /// The name of the custom claim type is empty or too long.
+        InvalidCustomClaimTypeName,
+        /// A custom claim type with the same name is already registered.
+        CustomClaimTypeAlreadyExists,
+        /// The custom claim type is not registered.
+        CustomClaimTypeDoesNotExist,
+        /// No more custom claim types can be registered.
+        CustomClaimTypeIdOverflow,
+        /// The identity is already registered as a claim issuer.
+        ClaimIssuerAlreadyRegistered,
+        /// The identity is not registered as a claim issuer.
+        ClaimIssuerNotRegistered,
+        /// The claim issuer is suspended.
+        ClaimIssuerSuspended,
+        /// A field of the claim issuer metadata is too long.
+        ClaimIssuerMetadataTooLong,
+        /// The account cannot bond the claim issuer deposit.
+        InsufficientClaimIssuerDeposit,
+        /// There is no change in the state.
+        NoChange,
+        /// The signing key would exceed the POLYX it may spend per period.
+        PolyxSpendingLimitExceeded,
+        /// The signing key would exceed the amount of the asset it may transfer per period.
+        AssetSpendingLimitExceeded,
+        /// The signing key would exceed the number of transactions it may make per day.
+        DailyTransactionLimitExceeded,
+        /// The guardians of a recovery configuration are repeated, too many, include the identity
+        /// itself, or cannot reach the threshold.
+        InvalidRecoveryConfig,
+        /// The identity has no recovery configuration.
+        RecoveryConfigNotFound,
+        /// The caller is not a guardian of the identity.
+        NotRecoveryGuardian,
+        /// A recovery of the master key of the identity, or to the given key, is already in
+        /// progress.
+        RecoveryInProgress,
+        /// There is no recovery of the master key of the identity, or to the given key, in
+        /// progress.
+        NoActiveRecovery,
+        /// The guardian has already approved the recovery.
+        RecoveryAlreadyApproved,
+        /// Not enough guardians have approved the recovery.
+        RecoveryNotApproved,
+        /// The delay of the recovery has not passed yet.
+        RecoveryDelayNotElapsed,
+        /// A rotation of the master key of the identity is already pending.
+        MasterKeyRotationPending,
+        /// There is no pending rotation of the master key of the identity.
+        NoPendingMasterKeyRotation,
+        /// The identity holds assets or tickers.
+        IdentityHasAssetHoldings,
+        /// The identity or its master key has authorizations given or received.
+        IdentityHasOpenAuthorizations,
     }
 }
 
 impl<T: Trait> Module<T> {
+    /// Returns the guardian of `did` on behalf of which `key` acts, and the recovery
+    /// configuration of `did`. The key is either a guardian or a key of a guardian identity.
+    fn recovery_guardian(
+        did: IdentityId,
+        key: &AccountKey,
+    ) -> StdResult<(Signatory, RecoveryConfig), Error<T>> {
+        let config = Self::recovery_config(did).ok_or(Error::<T>::RecoveryConfigNotFound)?;
+        let guardian = Some(Signatory::from(*key))
+            .filter(|guardian| config.guardians.contains(guardian))
+            .or_else(|| {
+                Self::get_identity(key)
+                    .map(Signatory::from)
+                    .filter(|guardian| config.guardians.contains(guardian))
+            })
+            .ok_or(Error::<T>::NotRecoveryGuardian)?;
+        Ok((guardian, config))
+    }
+
+    /// Returns `true` if `signer` is a guardian of `did`.
+    pub fn is_recovery_guardian(did: IdentityId, signer: &Signatory) -> bool {
+        Self::recovery_config(did).map_or(false, |config| config.guardians.contains(signer))
+    }
+
+    /// Returns `true` if a recovery of the master key of `did` is in progress.
+    pub fn has_active_recovery(did: IdentityId) -> bool {
+        <ActiveRecoveries<T>>::iter_prefix(did).next().is_some()
+    }
+
+    /// Stores `recovery` of `did`, marking when it reaches the threshold of `config` and
+    /// clearing that mark when it falls below the threshold. A recovery without approvals is
+    /// removed.
+    fn unsafe_update_recovery(
+        did: IdentityId,
+        config: &RecoveryConfig,
+        mut recovery: ActiveRecovery,
+    ) {
+        if recovery.approvals.is_empty() {
+            <ActiveRecoveries<T>>::remove(did, &recovery.new_master_key);
+            return;
+        }
+        if recovery.approvals.len() < config.threshold as usize {
+            recovery.approved_at = None;
+        } else if recovery.approved_at.is_none() {
+            recovery.approved_at =
+                Some(<system::Module<T>>::block_number().saturated_into::<BlockNumber>());
+        }
+        <ActiveRecoveries<T>>::insert(did, recovery.new_master_key, recovery);
+    }
+
+    /// Withdraws the approval of `guardian` from the recoveries of `did` in progress. Each
+    /// recovery has at least one approval, so there are at most as many as guardians.
+    fn unsafe_withdraw_recovery_approval(
+        did: IdentityId,
+        config: &RecoveryConfig,
+        guardian: &Signatory,
+    ) {
+        let approved = <ActiveRecoveries<T>>::iter_prefix(did)
+            .filter(|recovery| recovery.approvals.contains(guardian))
+            .collect::<Vec<_>>();
+        for mut recovery in approved {
+            recovery.approvals.retain(|approval| approval != guardian);
+            Self::unsafe_update_recovery(did, config, recovery);
+        }
+    }
+
+    /// Ensures that each field of `metadata` is within `MAX_CLAIM_ISSUER_METADATA_LEN`.
+    fn ensure_valid_claim_issuer_metadata(metadata: &ClaimIssuerMetadata) -> DispatchResult {
+        ensure!(
+            metadata.name.len() <= MAX_CLAIM_ISSUER_METADATA_LEN
+                && metadata.website.len() <= MAX_CLAIM_ISSUER_METADATA_LEN
+                && metadata.jurisdiction.0.len() <= MAX_CLAIM_ISSUER_METADATA_LEN,
+            Error::<T>::ClaimIssuerMetadataTooLong
+        );
+        Ok(())
+    }
+
+    /// Returns the identity and spending limit of `key`, unless it is a master key or it has no
+    /// spending limit.
+    fn spending_limit_of(
+        key: &AccountKey,
+    ) -> Option<(IdentityId, SpendingLimit<T::Balance, T::Moment>)> {
+        let did = Self::get_identity(key)?;
+        if Self::is_master_key(did, key) {
+            return None;
+        }
+        Self::spending_limit(did, key).map(|limit| (did, limit))
+    }
+
+    /// Returns the usage of `key` at `did` after spending `polyx` and `asset`, and after a
+    /// transaction if `transaction` is set. Periods which are over are restarted.
+    fn spending_usage_after(
+        did: IdentityId,
+        key: &AccountKey,
+        limit: &SpendingLimit<T::Balance, T::Moment>,
+        polyx: T::Balance,
+        asset: Option<(Ticker, T::Balance)>,
+        transaction: bool,
+    ) -> SpendingUsage<T::Balance, T::Moment> {
+        let now = <pallet_timestamp::Module<T>>::get();
+        let mut usage = Self::spending_usage(did, key);
+        if now >= usage.period_start.saturating_add(limit.period) {
+            usage.period_start = now;
+            usage.polyx = Zero::zero();
+            usage.assets.clear();
+        }
+        let day = predicate::MILLISECONDS_PER_DAY.saturated_into::<T::Moment>();
+        if now >= usage.day_start.saturating_add(day) {
+            usage.day_start = now;
+            usage.transactions = 0;
+        }
+
+        usage.polyx = usage.polyx.saturating_add(polyx);
+        if let Some((ticker, amount)) = asset {
+            // Only the assets with a limit are tracked.
+            if limit
+                .max_assets
+                .iter()
+                .any(|(limited, _)| *limited == ticker)
+            {
+                match usage.assets.iter_mut().find(|(spent, _)| *spent == ticker) {
+                    Some((_, spent)) => *spent = spent.saturating_add(amount),
+                    None => usage.assets.push((ticker, amount)),
+                }
+            }
+        }
+        if transaction {
+            usage.transactions = usage.transactions.saturating_add(1);
+        }
+        usage
+    }
+
+    /// Returns the limit of `limit` which `usage` exceeds, if any.
+    fn exceeded_spending_limit(
+        limit: &SpendingLimit<T::Balance, T::Moment>,
+        usage: &SpendingUsage<T::Balance, T::Moment>,
+    ) -> Option<SpendingLimitKind> {
+        if limit.max_polyx.map_or(false, |max| usage.polyx > max) {
+            return Some(SpendingLimitKind::Polyx);
+        }
+        let exceeded_asset = limit.max_assets.iter().find(|(ticker, max)| {
+            usage
+                .assets
+                .iter()
+                .any(|(spent_ticker, spent)| spent_ticker == ticker && spent > max)
+        });
+        if let Some((ticker, _)) = exceeded_asset {
+            return Some(SpendingLimitKind::Asset(*ticker));
+        }
+        if limit
+            .max_daily_transactions
+            .map_or(false, |max| usage.transactions > max)
+        {
+            return Some(SpendingLimitKind::DailyTransactions);
+        }
+        None
+    }
+
+    /// Checks `key` against its spending limit and returns its usage after the spending, or
+    /// `None` if it has no spending limit. A breach is reported with an event.
+    fn check_spending(
+        key: &AccountKey,
+        polyx: T::Balance,
+        asset: Option<(Ticker, T::Balance)>,
+        transaction: bool,
+    ) -> StdResult<Option<(IdentityId, SpendingUsage<T::Balance, T::Moment>)>, DispatchError> {
+        if let Some((did, limit)) = Self::spending_limit_of(key) {
+            let usage = Self::spending_usage_after(did, key, &limit, polyx, asset, transaction);
+            if let Some(kind) = Self::exceeded_spending_limit(&limit, &usage) {
+                Self::deposit_event(RawEvent::SpendingLimitExceeded(did, *key, kind));
+                let error = match kind {
+                    SpendingLimitKind::Polyx => Error::<T>::PolyxSpendingLimitExceeded,
+                    SpendingLimitKind::Asset(_) => Error::<T>::AssetSpendingLimitExceeded,
+                    SpendingLimitKind::DailyTransactions => {
+                        Error::<T>::DailyTransactionLimitExceeded
+                    }
+                };
+                return Err(error.into());
+            }
+            return Ok(Some((did, usage)));
+        }
+        Ok(None)
+    }
+
+    /// Checks that the spending limit of `key` allows it to spend `polyx` and `asset`.
+    pub fn ensure_spending_allowed(
+        key: &AccountKey,
+        polyx: T::Balance,
+        asset: Option<(Ticker, T::Balance)>,
+    ) -> DispatchResult {
+        Self::check_spending(key, polyx, asset, false).map(|_| ())
+    }
+
+    /// Adds `polyx` and `asset` to what `key` has spent under its spending limit.
+    pub fn record_spending(
+        key: &AccountKey,
+        polyx: T::Balance,
+        asset: Option<(Ticker, T::Balance)>,
+    ) {
+        if let Some((did, limit)) = Self::spending_limit_of(key) {
+            let usage = Self::spending_usage_after(did, key, &limit, polyx, asset, false);
+            <SpendingUsages<T>>::insert(did, key, usage);
+        }
+    }
+
+    /// Counts a transaction of `key` paying `fee` against its spending limit.
+    pub fn charge_transaction_spending(key: &AccountKey, fee: T::Balance) -> DispatchResult {
+        if let Some((did, usage)) = Self::check_spending(key, fee, None, true)? {
+            <SpendingUsages<T>>::insert(did, key, usage);
+        }
+        Ok(())
+    }
+
+    /// Revokes the claims added or updated by `issuer` from `since`. An earlier revocation is kept.
+    fn unsafe_revoke_claims_since(issuer: IdentityId, since: T::Moment) {
+        let since_u64 = since.saturated_into::<u64>();
+        let revoked_since = Self::claims_revoked_since(issuer)
+            .map_or(since_u64, |revoked_since| revoked_since.min(since_u64));
+        <ClaimsRevokedSince>::insert(issuer, revoked_since);
+        Self::deposit_event(RawEvent::ClaimIssuerClaimsRevoked(
+            issuer,
+            revoked_since.saturated_into::<T::Moment>(),
+        ));
+    }
+
+    /// It returns false if the issuer of `id_claim` is suspended, or if `id_claim` was added or
+    /// updated after the claims of its issuer were revoked.
+    fn is_claim_issuer_valid_for(id_claim: &IdentityClaim) -> bool {
+        !Self::is_claim_issuer_suspended(id_claim.claim_issuer)
+            && Self::claims_revoked_since(id_claim.claim_issuer)
+                .map_or(true, |since| id_claim.last_update_date < since)
+    }
+
+    /// It returns false if `claim_type` is a custom claim type which is not registered.
+    pub fn is_claim_type_registered(claim_type: ClaimType) -> bool {
+        match claim_type {
+            ClaimType::Custom(id) => <CustomClaimTypes>::contains_key(id),
+            _ => true,
+        }
+    }
+
+    /// Runs a batch of the migration to the next storage version, and bumps the storage version
+    /// once the migration is done.
+    fn migrate_storage() {
+        let mut cursor = Self::migration_cursor();
+        let done = match Self::storage_version() {
+            0 => migration::run_migration_batch(
+                &mut cursor,
+                &[Self::migrate_claim_scopes, Self::unstage_claims],
+            ),
+            _ => migration::run_migration_batch(&mut cursor, &[Self::migrate_did_records]),
+        };
+        if done {
+            <StorageVersion>::mutate(|version| *version += 1);
+            <MigrationCursor>::kill();
+        } else {
+            <MigrationCursor>::put(cursor);
+        }
+    }
+
+    /// Migrates the claims stored when `Scope` was an `IdentityId` to `Scope::Identity`.
+    /// Claims without scope are encoded in the same way, so they are kept as they are. Migrated
+    /// claims are staged, and moved to their new keys by `unstage_claims`.
+    fn migrate_claim_scopes(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        let prefix = migration::storage_prefix(b"identity", b"Claims");
+        let staging = migration::storage_prefix(b"identity", b"ClaimsMigration");
+        let (entries, next) = migration::raw_entries_page::<IdentityClaimV1>(&prefix, after, limit);
+        for (raw_key, old_claim) in entries {
+            let id_claim = IdentityClaim::from(old_claim);
+            let scope = id_claim.claim.as_scope().cloned();
+            if scope.is_none() {
+                continue;
+            }
+            // Raw key: prefix ++ blake2_128(1st key) ++ 1st key ++ blake2_128(2nd key) ++ 2nd key.
+            let pk = match raw_key
+                .get(prefix.len() + 16..)
+                .and_then(|mut encoded| Claim1stKey::decode(&mut encoded).ok())
+            {
+                Some(pk) => pk,
+                None => continue,
+            };
+            let sk = Claim2ndKey {
+                issuer: id_claim.claim_issuer,
+                scope,
+            };
+            frame_support::storage::unhashed::kill(&raw_key);
+            let new_key = <Claims>::hashed_key_for(&pk, &sk);
+            migration::stage_entry(&staging, &prefix, &new_key, &id_claim);
+        }
+        next
+    }
+
+    /// Moves the claims staged by `migrate_claim_scopes` to their new keys.
+    fn unstage_claims(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        migration::unstage_entries(
+            &migration::storage_prefix(b"identity", b"ClaimsMigration"),
+            &migration::storage_prefix(b"identity", b"Claims"),
+            after,
+            limit,
+        )
+    }
+
     /// Accepts an auth to join an identity as a signer
     pub fn join_identity(signer: Signatory, auth_id: u64) -> DispatchResult {
         ensure!(
@@ -1420,6 +2371,12 @@ impl<T: Trait> Module<T> {
         if let AuthorizationData::RotateMasterKey(rotation_for_did) =
             rotation_auth.authorization_data
         {
+            ensure!(
+                Self::did_records(&rotation_for_did)
+                    .pending_master_key_rotation
+                    .is_none(),
+                Error::<T>::MasterKeyRotationPending
+            );
             // Ensure the request was made by the owner of master key
             match rotation_auth.authorized_by {
                 Signatory::AccountKey(key) => {
@@ -1430,7 +2387,19 @@ impl<T: Trait> Module<T> {
             };
             // consume owner's authorization
             Self::consume_auth(rotation_auth.authorized_by, signer, rotation_auth_id)?;
-            Self::unsafe_master_key_rotation(sender_key, rotation_for_did, optional_cdd_auth_id)
+
+            let delay = Self::master_key_rotation_delay(rotation_for_did);
+            if delay == 0 {
+                Self::unsafe_master_key_rotation(sender_key, rotation_for_did, optional_cdd_auth_id)
+            } else {
+                Self::consume_master_key_rotation_attestation(
+                    sender_key,
+                    rotation_for_did,
+                    optional_cdd_auth_id,
+                )?;
+                Self::unsafe_schedule_master_key_rotation(rotation_for_did, sender_key, delay);
+                Ok(())
+            }
         } else {
             Err(Error::<T>::UnknownAuthorization.into())
         }
@@ -1441,6 +2410,22 @@ impl<T: Trait> Module<T> {
         sender_key: AccountKey,
         rotation_for_did: IdentityId,
         optional_cdd_auth_id: Option<u64>,
+    ) -> DispatchResult {
+        Self::consume_master_key_rotation_attestation(
+            sender_key,
+            rotation_for_did,
+            optional_cdd_auth_id,
+        )?;
+        Self::unsafe_replace_master_key(rotation_for_did, sender_key);
+        Ok(())
+    }
+
+    /// Consumes the authorization of a CDD service provider attesting the rotation of the
+    /// master key of `rotation_for_did` to `sender_key`, if such attestations are required.
+    fn consume_master_key_rotation_attestation(
+        sender_key: AccountKey,
+        rotation_for_did: IdentityId,
+        optional_cdd_auth_id: Option<u64>,
     ) -> DispatchResult {
         // Aceept authorization from CDD service provider
         if Self::cdd_auth_for_master_key_rotation() {
@@ -1480,20 +2465,85 @@ impl<T: Trait> Module<T> {
                 _ => return Err(Error::<T>::UnknownAuthorization.into()),
             }
         }
+        Ok(())
+    }
 
-        // Replace master key of the owner that initiated key rotation
-        let old_master_key = Self::did_records(&rotation_for_did).master_key;
-        <DidRecords>::mutate(&rotation_for_did, |record| {
-            Self::unlink_key_to_did(&(*record).master_key, rotation_for_did);
-            (*record).master_key = sender_key;
+    /// Replaces the master key of `did` with `new_master_key`, dropping any pending rotation.
+    fn unsafe_replace_master_key(did: IdentityId, new_master_key: AccountKey) {
+        let old_master_key = Self::did_records(&did).master_key;
+        <DidRecords>::mutate(&did, |record| {
+            Self::unlink_key_to_did(&(*record).master_key, did);
+            (*record).master_key = new_master_key;
+            (*record).pending_master_key_rotation = None;
         });
 
         Self::deposit_event(RawEvent::MasterKeyUpdated(
-            rotation_for_did,
+            did,
             old_master_key,
-            sender_key,
+            new_master_key,
         ));
-        Ok(())
+    }
+
+    /// Schedules the rotation of the master key of `did` to `new_master_key` in `delay` blocks.
+    fn unsafe_schedule_master_key_rotation(
+        did: IdentityId,
+        new_master_key: AccountKey,
+        delay: BlockNumber,
+    ) {
+        let enact_at = <system::Module<T>>::block_number()
+            .saturated_into::<BlockNumber>()
+            .saturating_add(delay);
+        <DidRecords>::mutate(&did, |record| {
+            (*record).pending_master_key_rotation = Some(PendingMasterKeyRotation {
+                new_master_key,
+                enact_at,
+            });
+        });
+        <ScheduledMasterKeyRotations>::mutate(enact_at, |dids| dids.push(did));
+
+        Self::deposit_event(RawEvent::MasterKeyRotationScheduled(
+            did,
+            new_master_key,
+            enact_at,
+        ));
+    }
+
+    /// Enacts the master key rotations scheduled for `block_number`. Cancelled rotations are
+    /// no longer pending in their identities, so they are skipped.
+    fn enact_master_key_rotations(block_number: BlockNumber) {
+        for did in <ScheduledMasterKeyRotations>::take(block_number) {
+            match Self::did_records(&did).pending_master_key_rotation {
+                Some(rotation) if rotation.enact_at == block_number => {
+                    Self::unsafe_replace_master_key(did, rotation.new_master_key)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Enacts the reductions of master key rotation delays scheduled for `block_number`.
+    /// Replaced reductions are no longer pending, so they are skipped.
+    fn enact_master_key_rotation_delays(block_number: BlockNumber) {
+        for did in <ScheduledMasterKeyRotationDelays>::take(block_number) {
+            match Self::pending_master_key_rotation_delay(did) {
+                Some(pending) if pending.enact_at == block_number => {
+                    <PendingMasterKeyRotationDelays>::remove(did);
+                    <MasterKeyRotationDelay>::insert(did, pending.delay);
+                    Self::deposit_event(RawEvent::MasterKeyRotationDelaySet(did, pending.delay));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Migrates the identities stored before master key rotations could be delayed.
+    fn migrate_did_records(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        let prefix = migration::storage_prefix(b"identity", b"DidRecords");
+        let (entries, next) = migration::raw_entries_page::<IdentityV1>(&prefix, after, limit);
+        for (raw_key, old_record) in entries {
+            frame_support::storage::unhashed::put(&raw_key, &DidRecord::from(old_record));
+        }
+        next
     }
 
     /// Updates permissions of signing items.
@@ -1571,7 +2621,7 @@ impl<T: Trait> Module<T> {
                     {
                         // It retruns true if all requested permission are in this signing item.
                         return permissions.iter().all(|required_permission| {
-                            signing_item.has_permission(*required_permission)
+                            signing_item.has_permission(required_permission.clone())
                         });
                     }
                 }
@@ -1582,6 +2632,20 @@ impl<T: Trait> Module<T> {
         }
     }
 
+    /// Checks if the scoped permissions of `key` allow it to sign a call with `scope` on behalf
+    /// of `did`. The master key is never restricted.
+    pub fn is_key_permitted(did: IdentityId, key: &AccountKey, scope: &DispatchScope) -> bool {
+        let record = <DidRecords>::get(did);
+        if record.master_key == *key {
+            return true;
+        }
+        record
+            .signing_items
+            .iter()
+            .find(|si| si.signer == *key)
+            .map_or(false, |si| si.is_permitted(scope))
+    }
+
     /// Use `did` as reference.
     pub fn is_master_key(did: IdentityId, key: &AccountKey) -> bool {
         key == &<DidRecords>::get(did).master_key
@@ -1599,7 +2663,8 @@ impl<T: Trait> Module<T> {
 
     /// It fetches an specific `claim_type` claim type for target identity `id`, which was issued
     /// by `issuer`.
-    /// It only returns non-expired claims.
+    /// It only returns non-expired claims whose issuer is not suspended and which were not revoked
+    /// by `revoke_claims_since`.
     pub fn fetch_claim(
         id: IdentityId,
         claim_type: ClaimType,
@@ -1610,7 +2675,9 @@ impl<T: Trait> Module<T> {
 
         Self::fetch_base_claim_with_issuer(id, claim_type, issuer, scope)
             .into_iter()
-            .filter(|c| Self::is_identity_claim_not_expired_at(c, now))
+            .filter(|c| {
+                Self::is_identity_claim_not_expired_at(c, now) && Self::is_claim_issuer_valid_for(c)
+            })
             .next()
     }
 
@@ -1666,6 +2733,8 @@ impl<T: Trait> Module<T> {
     ///   * Issuer is an active CDD provider, or
     ///   * Issuer is an inactive CDD provider but claim was updated/created before that it was
     ///   deactivated.
+    /// * Its issuer is not suspended and the claim was updated/created before the claims of its
+    /// issuer were revoked.
     fn is_identity_cdd_claim_valid(
         id_claim: &IdentityClaim,
         exp_with_leeway: T::Moment,
@@ -1678,6 +2747,7 @@ impl<T: Trait> Module<T> {
             SystematicIssuers::CDDProvider.as_id(),
         ];
         Self::is_identity_claim_not_expired_at(id_claim, exp_with_leeway)
+            && Self::is_claim_issuer_valid_for(id_claim)
             && (active_cdds.contains(&id_claim.claim_issuer)
                 || systematic_cdds.contains(&id_claim.claim_issuer)
                 || inactive_not_expired_cdds
@@ -1832,7 +2902,7 @@ impl<T: Trait> Module<T> {
         // Making sure there's no pre-existing entry for the DID
         // This should never happen but just being defensive here
         ensure!(
-            !<DidRecords>::contains_key(did),
+            !<DidRecords>::contains_key(did) && !Self::is_identity_closed(did),
             Error::<T>::DidAlreadyExists
         );
         <DidRecords>::insert(did, DidRecord::default());
@@ -1883,7 +2953,7 @@ impl<T: Trait> Module<T> {
         // 1.3. Make sure there's no pre-existing entry for the DID
         // This should never happen but just being defensive here
         ensure!(
-            !<DidRecords>::contains_key(did),
+            !<DidRecords>::contains_key(did) && !Self::is_identity_closed(did),
             Error::<T>::DidAlreadyExists
         );
 
@@ -1940,7 +3010,7 @@ impl<T: Trait> Module<T> {
         let claim_type = claim.claim_type();
         let scope = claim.as_scope().cloned();
         let last_update_date = <pallet_timestamp::Module<T>>::get().saturated_into::<u64>();
-        let issuance_date = Self::fetch_claim(target, claim_type, issuer, scope)
+        let issuance_date = Self::fetch_claim(target, claim_type, issuer, scope.clone())
             .map_or(last_update_date, |id_claim| id_claim.issuance_date);
 
         let expiry = expiry.into_iter().map(|m| m.saturated_into::<u64>()).next();
@@ -1954,6 +3024,13 @@ impl<T: Trait> Module<T> {
             claim,
         };
 
+        if let ClaimType::Custom(id) = claim_type {
+            <CustomClaimTypesOf>::mutate(target, |ids| {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            });
+        }
         <Claims>::insert(&pk, &sk, id_claim.clone());
         Self::deposit_event(RawEvent::ClaimAdded(target, id_claim));
     }
@@ -1982,6 +3059,36 @@ impl<T: Trait> Module<T> {
         <DidRecords>::contains_key(did)
     }
 
+    /// Revokes all claims issued to `target`.
+    fn unsafe_revoke_claims_to(target: IdentityId) {
+        let custom_claim_types = <CustomClaimTypesOf>::take(target)
+            .into_iter()
+            .map(ClaimType::Custom);
+        let claim_types = [
+            ClaimType::Accredited,
+            ClaimType::Affiliate,
+            ClaimType::BuyLockup,
+            ClaimType::SellLockup,
+            ClaimType::CustomerDueDiligence,
+            ClaimType::KnowYourCustomer,
+            ClaimType::Jurisdiction,
+            ClaimType::Whitelisted,
+            ClaimType::BlackListed,
+            ClaimType::NoType,
+        ]
+        .iter()
+        .copied()
+        .chain(custom_claim_types);
+
+        for claim_type in claim_types {
+            let id_claims = Self::fetch_base_claims(target, claim_type).collect::<Vec<_>>();
+            for id_claim in id_claims {
+                let scope = id_claim.claim.as_scope().cloned();
+                Self::unsafe_revoke_claim(target, claim_type, id_claim.claim_issuer, scope);
+            }
+        }
+    }
+
     /// It removes a claim from `target` which was issued by `issuer` without any security check.
     fn unsafe_revoke_claim(
         target: IdentityId,
@@ -2095,6 +3202,16 @@ impl<T: Trait> IdentityTrait for Module<T> {
         Self::is_master_key(did, &key)
     }
 
+    /// Checks the POLYX spending limit of a signing key.
+    fn ensure_polyx_spending_allowed(key: &AccountKey, amount: PolymeshBalance) -> DispatchResult {
+        Self::ensure_spending_allowed(key, amount.saturated_into::<T::Balance>(), None)
+    }
+
+    /// Records POLYX spent by a signing key.
+    fn record_polyx_spending(key: &AccountKey, amount: PolymeshBalance) {
+        Self::record_spending(key, amount.saturated_into::<T::Balance>(), None)
+    }
+
     /// Checks if the signer is authorized and has certain permissions.
     fn is_signer_authorized_with_permissions(
         did: IdentityId,
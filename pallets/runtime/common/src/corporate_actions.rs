@@ -0,0 +1,955 @@
+// This file is part of the Polymesh distribution (https://github.com/PolymathNetwork/Polymesh).
+// Copyright (c) 2020 Polymath
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! # Corporate Actions Module
+//!
+//! The Corporate Actions module provides a way for token owners to announce and process corporate
+//! actions on their assets.
+//!
+//! ## Overview
+//!
+//! The Corporate Actions module provides functions for:
+//!
+//! - Initiating and cancelling corporate actions
+//! - Claiming the entitlement of a holder
+//! - Applying a split or a merger to every holder
+//! - Reclaiming the unclaimed funds of a cash distribution
+//!
+//! ### Terminology
+//!
+//! - **Cash distribution:** Pays an amount of POLYX, of a simple token or of another asset pro
+//! rata to the holdings at the ex-date.
+//! - **Stock dividend:** Issues new shares of the asset, a ratio of the shares held at the ex-date.
+//! - **Split:** Scales the holdings at the ex-date by a ratio. It is a forward split if the ratio
+//! is greater than one and a reverse split otherwise.
+//! - **Merger:** Converts the holdings at the ex-date into shares of another asset of the same
+//! owner at a given ratio.
+//! - **Ex-date:** The holdings of each identity are captured by an asset checkpoint created at the
+//! first block after the ex-date. Tokens bought after the ex-date carry no entitlement.
+//! - **Record date:** Entitlements can be claimed from the record date, which cannot be before the
+//! ex-date.
+//! - **Targets:** The identities which are entitled to the action, either only the listed ones or
+//! everyone except the listed ones.
+//! - **Withholding tax:** The portion of a cash payout which is withheld from a holder and paid to
+//! the tax receiver of the ticker set in the dividend module. The rate of a holder is its specific
+//! rate in the action, or else its specific or jurisdiction rate in the dividend module, or else
+//! the default rate of the action.
+//!
+//! Cash distributions in POLYX or in a simple token are reserved from the token owner when the
+//! action is initiated. Cash distributions in an asset are transferred from the default portfolio
+//! of the token owner when each holder claims them.
+//!
+//! Cash distributions and stock dividends are claimed by each holder. Splits and mergers are
+//! mandatory: the token owner applies them to every holder of the asset, in batches, and they
+//! never expire. The holders whose entitlement cannot be applied are recorded so that the owner
+//! can retry them.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `initiate` - Initiates a new corporate action.
+//! - `cancel` - Cancels a corporate action before its ex-date.
+//! - `claim` - Processes the entitlement of the caller in a cash distribution or a stock dividend.
+//! - `apply_reorganisation` - Applies a split or a merger to the next batch of holders.
+//! - `retry_entitlement` - Applies a split or a merger to a holder for which it failed.
+//! - `reclaim` - Returns the remaining funds of an expired cash distribution to the token owner.
+//!
+//! ### Public Functions
+//!
+//! - `withholding_tax_of` - Returns the withholding tax rate of an identity in an action.
+
+use crate::{dividend, simple_token};
+
+use pallet_asset as asset;
+use pallet_balances as balances;
+use pallet_identity as identity;
+use polymesh_common_utilities::{
+    balances::Trait as BalancesTrait, constants::ERC1400_TRANSFER_SUCCESS, CommonTrait, Context,
+};
+use polymesh_primitives::{
+    traits::IdentityCurrency, AccountKey, IdentityId, PortfolioId, PosRatio, Signatory, Ticker,
+};
+
+use codec::{Decode, Encode};
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
+    traits::Get, weights::SimpleDispatchInfo,
+};
+use frame_system::{self as system, ensure_signed};
+use sp_runtime::{
+    traits::{
+        CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, SaturatedConversion, Saturating, Zero,
+    },
+    DispatchError, Permill,
+};
+use sp_std::{convert::TryFrom, prelude::*};
+
+/// The module's configuration trait.
+pub trait Trait:
+    asset::Trait
+    + BalancesTrait
+    + dividend::Trait
+    + simple_token::Trait
+    + frame_system::Trait
+    + pallet_timestamp::Trait
+{
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+}
+
+/// The currency of a cash distribution.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DistributionCurrency {
+    /// The native currency, paid to the identity balance of holders.
+    Polyx,
+    /// A token created by the simple token module.
+    SimpleToken(Ticker),
+    /// An asset created by the asset module, paid to the default portfolio of holders.
+    Asset(Ticker),
+}
+
+/// The kind of a corporate action.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum CorporateActionKind<Balance> {
+    /// Pays the given amount of a currency pro rata to the holdings at the ex-date.
+    CashDistribution(DistributionCurrency, Balance),
+    /// Issues new shares, at the given ratio of the shares held at the ex-date.
+    StockDividend(PosRatio),
+    /// Scales the shares held at the ex-date by the given ratio.
+    Split(PosRatio),
+    /// Converts the shares held at the ex-date into shares of the given asset at the given ratio.
+    Merger(Ticker, PosRatio),
+}
+
+impl<Balance> CorporateActionKind<Balance> {
+    /// Returns `true` for splits and mergers, which are applied to every holder by the owner.
+    pub fn is_reorganisation(&self) -> bool {
+        match self {
+            Self::Split(_) | Self::Merger(..) => true,
+            Self::CashDistribution(..) | Self::StockDividend(_) => false,
+        }
+    }
+}
+
+/// How the identities of `TargetIdentities` are treated.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TargetTreatment {
+    /// Only the listed identities are entitled.
+    Include,
+    /// Every identity except the listed ones is entitled.
+    Exclude,
+}
+
+impl Default for TargetTreatment {
+    fn default() -> Self {
+        Self::Exclude
+    }
+}
+
+/// The identities entitled to a corporate action. By default, everyone is entitled.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+pub struct TargetIdentities {
+    /// The listed identities.
+    pub identities: Vec<IdentityId>,
+    /// How the listed identities are treated.
+    pub treatment: TargetTreatment,
+}
+
+impl TargetIdentities {
+    /// Returns `true` if `did` is entitled.
+    pub fn targets(&self, did: &IdentityId) -> bool {
+        let listed = self.identities.contains(did);
+        match self.treatment {
+            TargetTreatment::Include => listed,
+            TargetTreatment::Exclude => !listed,
+        }
+    }
+}
+
+/// Details about a corporate action.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct CorporateAction<Balance, Moment> {
+    /// What the action does.
+    pub kind: CorporateActionKind<Balance>,
+    /// Timestamp of the initiation of the action.
+    pub decl_date: Moment,
+    /// Holdings are captured at the first block after this timestamp.
+    pub ex_date: Moment,
+    /// Entitlements can be claimed from this timestamp.
+    pub record_date: Moment,
+    /// Entitlements cannot be claimed after this timestamp, and the remaining funds of a cash
+    /// distribution can be reclaimed.
+    pub expires_at: Option<Moment>,
+    /// The checkpoint which captured the holdings at the ex-date, once it has been created.
+    pub checkpoint_id: Option<u64>,
+    /// The identities entitled to the action.
+    pub targets: TargetIdentities,
+    /// Withholding tax rate of the identities which do not have a specific one.
+    pub default_withholding_tax: Permill,
+    /// Specific withholding tax rates.
+    pub withholding_tax: Vec<(IdentityId, Permill)>,
+    /// Funds of a cash distribution which have been neither paid nor reclaimed yet.
+    pub remaining: Balance,
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as CorporateActions {
+        /// Corporate actions of a ticker; (ticker, action id) -> action.
+        /// Note: action IDs are 0-indexed.
+        Actions get(fn actions): double_map hasher(blake2_128_concat) Ticker, hasher(twox_64_concat) u32 => Option<CorporateAction<T::Balance, T::Moment>>;
+        /// How many actions were initiated for a ticker so far; ticker -> count.
+        ActionCount get(fn action_count): map hasher(blake2_128_concat) Ticker => u32;
+        /// Actions to check at a block whose ex-date checkpoint has not been created yet. An action
+        /// is indexed at a block no later than its ex-date, and indexed again at a later block if
+        /// its ex-date has not passed yet.
+        /// (block number) -> (ticker, action id)
+        PendingCheckpointsAt get(fn pending_checkpoints_at): map hasher(twox_64_concat) T::BlockNumber => Vec<(Ticker, u32)>;
+        /// Number of actions of a ticker whose ex-date checkpoint has not been created yet;
+        /// ticker -> count.
+        PendingActionCount get(fn pending_action_count): map hasher(blake2_128_concat) Ticker => u32;
+        /// Whether the entitlement of a holder was processed; (ticker, action id) -> DID -> processed.
+        HolderProcessed get(fn holder_processed): double_map hasher(blake2_128_concat) (Ticker, u32), hasher(blake2_128_concat) IdentityId => bool;
+        /// Amount withheld from the payout of a holder; (ticker, action id) -> DID -> amount.
+        WithheldTax get(fn withheld_tax): double_map hasher(blake2_128_concat) (Ticker, u32), hasher(blake2_128_concat) IdentityId => T::Balance;
+        /// Index in the holder list of the ticker of the next holder to process by
+        /// `apply_reorganisation`; (ticker, action id) -> index.
+        ApplyCursor get(fn apply_cursor): map hasher(blake2_128_concat) (Ticker, u32) => u64;
+        /// Holders to which a split or a merger could not be applied;
+        /// (ticker, action id) -> DID -> failed.
+        FailedEntitlements get(fn failed_entitlement): double_map hasher(blake2_128_concat) (Ticker, u32), hasher(blake2_128_concat) IdentityId => bool;
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        Balance = <T as CommonTrait>::Balance,
+        Moment = <T as pallet_timestamp::Trait>::Moment,
+    {
+        /// caller DID, ticker, action id, action
+        CorporateActionInitiated(IdentityId, Ticker, u32, CorporateAction<Balance, Moment>),
+        /// caller DID, ticker, action id
+        CorporateActionCancelled(IdentityId, Ticker, u32),
+        /// token owner DID, ticker, action id, checkpoint id
+        ExDateCheckpointCreated(IdentityId, Ticker, u32, u64),
+        /// caller DID, ticker, action id, holder DID, holdings at the ex-date,
+        /// amount paid, issued or redeemed, amount withheld
+        EntitlementProcessed(
+            IdentityId,
+            Ticker,
+            u32,
+            IdentityId,
+            Balance,
+            Balance,
+            Balance,
+        ),
+        /// caller DID, ticker, action id, amount
+        RemainingReclaimed(IdentityId, Ticker, u32, Balance),
+        /// caller DID, ticker, action id, number of processed holders, new cursor
+        ReorganisationApplied(IdentityId, Ticker, u32, u32, u64),
+        /// caller DID, ticker, action id, holder DID
+        EntitlementFailed(IdentityId, Ticker, u32, IdentityId),
+    }
+);
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// The sender must be a signing key for the DID.
+        SenderMustBeSigningKeyForDid,
+        /// The user is not authorized.
+        Unauthorized,
+        /// The corporate action does not exist.
+        NoSuchAction,
+        /// The ex-date cannot be after the record date.
+        ExDateAfterRecordDate,
+        /// The expiry date must be after the record date.
+        ExpiryBeforeRecordDate,
+        /// A ratio cannot have a zero numerator or denominator.
+        InvalidRatio,
+        /// The amount of a cash distribution is zero.
+        ZeroAmount,
+        /// A merger cannot convert an asset into itself.
+        MergerIntoSameAsset,
+        /// The token owner does not have enough funds for the cash distribution.
+        InsufficientFunds,
+        /// The holdings at the ex-date have already been captured.
+        AlreadyCaptured,
+        /// The entitlements cannot be claimed before the record date.
+        BeforeRecordDate,
+        /// The action has expired.
+        Expired,
+        /// The action has not expired yet.
+        NotExpired,
+        /// The identity is not entitled to the action.
+        NotTargeted,
+        /// The entitlement has already been processed.
+        AlreadyProcessed,
+        /// The identity did not hold any share at the ex-date.
+        NoHoldings,
+        /// The payout transfer is not allowed by the rules of the distributed asset.
+        PayoutTransferNotAllowed,
+        /// The action is not a cash distribution.
+        NotACashDistribution,
+        /// An overflow while calculating an entitlement.
+        Overflow,
+        /// An overflow in the action count.
+        ActionCountOverflow,
+        /// Splits and mergers are applied by the token owner.
+        AppliedByOwner,
+        /// The action is neither a split nor a merger.
+        NotAReorganisation,
+        /// Every holder of the asset has already been processed.
+        NoHoldersLeft,
+        /// The entitlement of the holder has not failed.
+        NotFailed,
+        /// The ticker already has `MAX_PENDING_ACTIONS` actions whose holdings are not captured.
+        TooManyPendingActions,
+    }
+}
+
+type Identity<T> = identity::Module<T>;
+
+/// Maximum number of holders processed by one call to `apply_reorganisation`.
+pub const MAX_APPLIED_HOLDERS: u32 = 100;
+
+/// Maximum number of actions of a ticker whose holdings have not been captured yet.
+pub const MAX_PENDING_ACTIONS: u32 = 10;
+
+/// Maximum number of actions indexed at a block to capture their holdings.
+pub const MAX_PENDING_CHECKPOINTS_PER_BLOCK: usize = 50;
+
+decl_module! {
+    /// The module declaration.
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
+        fn deposit_event() = default;
+
+        /// Captures the holdings of the actions whose ex-date has passed.
+        fn on_initialize(block_number: T::BlockNumber) {
+            Self::create_due_checkpoints(block_number);
+        }
+
+        /// Initiates a new corporate action. The funds of a cash distribution in POLYX or in a
+        /// simple token are reserved from the caller.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the token owner.
+        /// * `ticker` Ticker of the token.
+        /// * `kind` What the action does.
+        /// * `ex_date` Holdings are captured at the first block after this timestamp.
+        /// * `record_date` Entitlements can be claimed from this timestamp.
+        /// * `expires_at` Optional timestamp after which entitlements cannot be claimed. Splits and
+        /// mergers never expire.
+        /// * `targets` The identities entitled to the action.
+        /// * `default_withholding_tax` Withholding tax rate of cash payouts.
+        /// * `withholding_tax` Specific withholding tax rates of some identities.
+        ///
+        /// A ticker has at most `MAX_PENDING_ACTIONS` actions whose holdings are not captured yet.
+        #[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+        pub fn initiate(
+            origin,
+            ticker: Ticker,
+            kind: CorporateActionKind<T::Balance>,
+            ex_date: T::Moment,
+            record_date: T::Moment,
+            expires_at: Option<T::Moment>,
+            targets: TargetIdentities,
+            default_withholding_tax: Permill,
+            withholding_tax: Vec<(IdentityId, Permill)>
+        ) -> DispatchResult {
+            let did = Self::ensure_signer_did(origin)?;
+            ensure!(<asset::Module<T>>::_is_owner(&ticker, did), Error::<T>::Unauthorized);
+            ensure!(ex_date <= record_date, Error::<T>::ExDateAfterRecordDate);
+            if let Some(expires_at) = expires_at {
+                ensure!(expires_at > record_date, Error::<T>::ExpiryBeforeRecordDate);
+            }
+            let pending_actions = Self::pending_action_count(ticker);
+            ensure!(pending_actions < MAX_PENDING_ACTIONS, Error::<T>::TooManyPendingActions);
+
+            let mut remaining = Zero::zero();
+            match &kind {
+                CorporateActionKind::CashDistribution(currency, amount) => {
+                    ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+                    Self::ensure_sufficient_funds(did, currency, *amount)?;
+                    remaining = *amount;
+                }
+                CorporateActionKind::StockDividend(ratio) | CorporateActionKind::Split(ratio) => {
+                    Self::ensure_valid_ratio(ratio)?;
+                }
+                CorporateActionKind::Merger(to_ticker, ratio) => {
+                    Self::ensure_valid_ratio(ratio)?;
+                    ensure!(*to_ticker != ticker, Error::<T>::MergerIntoSameAsset);
+                    ensure!(<asset::Module<T>>::_is_owner(to_ticker, did), Error::<T>::Unauthorized);
+                }
+            }
+
+            let action_id = Self::action_count(ticker);
+            let next_action_id = action_id.checked_add(1).ok_or(Error::<T>::ActionCountOverflow)?;
+
+            if let CorporateActionKind::CashDistribution(currency, amount) = &kind {
+                Self::reserve_funds(did, currency, *amount)?;
+            }
+
+            let action = CorporateAction {
+                kind,
+                decl_date: <pallet_timestamp::Module<T>>::get(),
+                ex_date,
+                record_date,
+                expires_at,
+                checkpoint_id: None,
+                targets,
+                default_withholding_tax,
+                withholding_tax,
+                remaining,
+            };
+            <ActionCount>::insert(ticker, next_action_id);
+            <Actions<T>>::insert(ticker, action_id, action.clone());
+            <PendingActionCount>::insert(ticker, pending_actions + 1);
+            Self::deposit_event(RawEvent::CorporateActionInitiated(did, ticker, action_id, action));
+
+            // The ex-date may have already passed.
+            Self::check_pending_action(ticker, action_id);
+            Ok(())
+        }
+
+        /// Cancels a corporate action whose holdings have not been captured yet. The reserved
+        /// funds of a cash distribution are returned to the token owner.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the token owner.
+        /// * `ticker` Ticker of the token.
+        /// * `action_id` Id of the action.
+        #[weight = SimpleDispatchInfo::FixedNormal(300_000)]
+        pub fn cancel(origin, ticker: Ticker, action_id: u32) -> DispatchResult {
+            let did = Self::ensure_signer_did(origin)?;
+            ensure!(<asset::Module<T>>::_is_owner(&ticker, did), Error::<T>::Unauthorized);
+            let action = Self::actions(ticker, action_id).ok_or(Error::<T>::NoSuchAction)?;
+            ensure!(action.checkpoint_id.is_none(), Error::<T>::AlreadyCaptured);
+
+            if let CorporateActionKind::CashDistribution(currency, _) = &action.kind {
+                Self::release_funds(did, currency, action.remaining)?;
+            }
+            // The index entry of the action is skipped once the action is gone.
+            <Actions<T>>::remove(ticker, action_id);
+            <PendingActionCount>::mutate(ticker, |count| *count = count.saturating_sub(1));
+
+            Self::deposit_event(RawEvent::CorporateActionCancelled(did, ticker, action_id));
+            Ok(())
+        }
+
+        /// Processes the entitlement of the caller: pays its share of a cash distribution or
+        /// issues the new shares of a stock dividend.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of a holder at the ex-date.
+        /// * `ticker` Ticker of the token.
+        /// * `action_id` Id of the action.
+        #[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+        pub fn claim(origin, ticker: Ticker, action_id: u32) -> DispatchResult {
+            let sender = ensure_signed(origin.clone())?;
+            let did = Self::ensure_signer_did(origin)?;
+            let action = Self::actions(ticker, action_id).ok_or(Error::<T>::NoSuchAction)?;
+            ensure!(!action.kind.is_reorganisation(), Error::<T>::AppliedByOwner);
+            Self::process_entitlement(sender, did, ticker, action_id, did)
+        }
+
+        /// Applies a split or a merger to the next `max_holders` holders in the holder list of
+        /// the ticker, starting from the cursor of the action, and advances the cursor.
+        /// Holders which are not targeted, already processed or held nothing at the ex-date are
+        /// skipped. A holder whose entitlement fails is recorded and skipped.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the token owner.
+        /// * `ticker` Ticker of the token.
+        /// * `action_id` Id of the action.
+        /// * `max_holders` Maximum number of holders to process, capped at `MAX_APPLIED_HOLDERS`.
+        #[weight = SimpleDispatchInfo::FixedNormal(5_000_000)]
+        pub fn apply_reorganisation(origin, ticker: Ticker, action_id: u32, max_holders: u32) -> DispatchResult {
+            let sender = ensure_signed(origin.clone())?;
+            let did = Self::ensure_signer_did(origin)?;
+            ensure!(<asset::Module<T>>::_is_owner(&ticker, did), Error::<T>::Unauthorized);
+            let action = Self::actions(ticker, action_id).ok_or(Error::<T>::NoSuchAction)?;
+            ensure!(action.kind.is_reorganisation(), Error::<T>::NotAReorganisation);
+            let checkpoint_id = action.checkpoint_id.ok_or(Error::<T>::BeforeRecordDate)?;
+            ensure!(
+                <pallet_timestamp::Module<T>>::get() >= action.record_date,
+                Error::<T>::BeforeRecordDate
+            );
+
+            let cursor = Self::apply_cursor((ticker, action_id));
+            let end = <asset::Module<T>>::holder_count(&ticker)
+                .min(cursor + u64::from(max_holders.min(MAX_APPLIED_HOLDERS)));
+            ensure!(cursor < end, Error::<T>::NoHoldersLeft);
+
+            let mut processed = 0u32;
+            for index in cursor..end {
+                let holder = <asset::Module<T>>::holder_at(&ticker, index);
+                if !action.targets.targets(&holder)
+                    || Self::holder_processed((ticker, action_id), holder)
+                    || <asset::Module<T>>::get_balance_at(ticker, holder, checkpoint_id).is_zero()
+                {
+                    continue;
+                }
+                match Self::process_entitlement(sender.clone(), did, ticker, action_id, holder) {
+                    Ok(()) => processed += 1,
+                    Err(e) => {
+                        sp_runtime::print(e);
+                        <FailedEntitlements>::insert((ticker, action_id), holder, true);
+                        Self::deposit_event(RawEvent::EntitlementFailed(did, ticker, action_id, holder));
+                    }
+                }
+            }
+            <ApplyCursor>::insert((ticker, action_id), end);
+
+            Self::deposit_event(RawEvent::ReorganisationApplied(did, ticker, action_id, processed, end));
+            Ok(())
+        }
+
+        /// Applies a split or a merger to a holder for which `apply_reorganisation` failed.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the token owner.
+        /// * `ticker` Ticker of the token.
+        /// * `action_id` Id of the action.
+        /// * `holder` DID of the holder.
+        #[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+        pub fn retry_entitlement(origin, ticker: Ticker, action_id: u32, holder: IdentityId) -> DispatchResult {
+            let sender = ensure_signed(origin.clone())?;
+            let did = Self::ensure_signer_did(origin)?;
+            ensure!(<asset::Module<T>>::_is_owner(&ticker, did), Error::<T>::Unauthorized);
+            ensure!(Self::failed_entitlement((ticker, action_id), holder), Error::<T>::NotFailed);
+
+            Self::process_entitlement(sender, did, ticker, action_id, holder)?;
+            <FailedEntitlements>::remove((ticker, action_id), holder);
+            Ok(())
+        }
+
+        /// Returns the remaining funds of an expired cash distribution to the token owner.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the token owner.
+        /// * `ticker` Ticker of the token.
+        /// * `action_id` Id of the action.
+        #[weight = SimpleDispatchInfo::FixedNormal(300_000)]
+        pub fn reclaim(origin, ticker: Ticker, action_id: u32) -> DispatchResult {
+            let did = Self::ensure_signer_did(origin)?;
+            ensure!(<asset::Module<T>>::_is_owner(&ticker, did), Error::<T>::Unauthorized);
+            let mut action = Self::actions(ticker, action_id).ok_or(Error::<T>::NoSuchAction)?;
+            let currency = match action.kind {
+                CorporateActionKind::CashDistribution(currency, _) => currency,
+                _ => return Err(Error::<T>::NotACashDistribution.into()),
+            };
+            let now = <pallet_timestamp::Module<T>>::get();
+            ensure!(action.expires_at.map_or(false, |expiry| now > expiry), Error::<T>::NotExpired);
+
+            let amount = action.remaining;
+            Self::release_funds(did, &currency, amount)?;
+            action.remaining = Zero::zero();
+            <Actions<T>>::insert(ticker, action_id, action);
+
+            Self::deposit_event(RawEvent::RemainingReclaimed(did, ticker, action_id, amount));
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    fn ensure_signer_did(origin: T::Origin) -> Result<IdentityId, DispatchError> {
+        let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+        let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+        let sender = Signatory::AccountKey(sender_key);
+
+        // Check that sender is allowed to act on behalf of `did`
+        ensure!(
+            <identity::Module<T>>::is_signer_authorized(did, &sender),
+            Error::<T>::SenderMustBeSigningKeyForDid
+        );
+        Ok(did)
+    }
+
+    fn ensure_valid_ratio(ratio: &PosRatio) -> DispatchResult {
+        ensure!(ratio.0 > 0 && ratio.1 > 0, Error::<T>::InvalidRatio);
+        Ok(())
+    }
+
+    /// Returns the withholding tax rate of `did` in `action` of `ticker`.
+    pub fn withholding_tax_of(
+        ticker: &Ticker,
+        action: &CorporateAction<T::Balance, T::Moment>,
+        did: &IdentityId,
+    ) -> Permill {
+        action
+            .withholding_tax
+            .iter()
+            .find(|(id, _)| id == did)
+            .map(|(_, rate)| *rate)
+            .or_else(|| <dividend::Module<T>>::withholding_tax_override(ticker, *did))
+            .unwrap_or(action.default_withholding_tax)
+    }
+
+    /// Checks the pending actions indexed at `block_number`.
+    fn create_due_checkpoints(block_number: T::BlockNumber) {
+        for (ticker, action_id) in <PendingCheckpointsAt<T>>::take(block_number) {
+            Self::check_pending_action(ticker, action_id);
+        }
+    }
+
+    /// Creates the checkpoint of a pending action if its ex-date has passed, or indexes the
+    /// action again otherwise.
+    fn check_pending_action(ticker: Ticker, action_id: u32) {
+        let mut action = match Self::actions(ticker, action_id) {
+            Some(action) if action.checkpoint_id.is_none() => action,
+            _ => return,
+        };
+        let now = <pallet_timestamp::Module<T>>::get();
+        if action.ex_date > now {
+            Self::index_pending_action(ticker, action_id, action.ex_date);
+            return;
+        }
+        if let Err(e) = <asset::Module<T>>::_create_checkpoint(&ticker) {
+            sp_runtime::print(e);
+            Self::index_pending_action(ticker, action_id, action.ex_date);
+            return;
+        }
+        let checkpoint_id = <asset::Module<T>>::total_checkpoints_of(&ticker);
+        action.checkpoint_id = Some(checkpoint_id);
+        <Actions<T>>::insert(ticker, action_id, action);
+        <PendingActionCount>::mutate(ticker, |count| *count = count.saturating_sub(1));
+        let owner = <asset::Module<T>>::token_details(&ticker).owner_did;
+        Self::deposit_event(RawEvent::ExDateCheckpointCreated(
+            owner,
+            ticker,
+            action_id,
+            checkpoint_id,
+        ));
+    }
+
+    /// Indexes a pending action whose ex-date is `ex_date` at a later block, in the same way as
+    /// the checkpoint schedules of the asset module. Blocks which already have
+    /// `MAX_PENDING_CHECKPOINTS_PER_BLOCK` actions are skipped.
+    fn index_pending_action(ticker: Ticker, action_id: u32, ex_date: T::Moment) {
+        let now = <pallet_timestamp::Module<T>>::get();
+        let expected_block_time = <T as pallet_timestamp::Trait>::MinimumPeriod::get()
+            .saturating_mul(2u32.into())
+            .max(1u32.into());
+        let blocks: u32 =
+            (ex_date.saturating_sub(now) / expected_block_time / 2u32.into()).saturated_into();
+        let mut block_number =
+            <system::Module<T>>::block_number() + T::BlockNumber::from(blocks.max(1));
+        while Self::pending_checkpoints_at(block_number).len() >= MAX_PENDING_CHECKPOINTS_PER_BLOCK
+        {
+            block_number += One::one();
+        }
+        <PendingCheckpointsAt<T>>::mutate(block_number, |pending| {
+            pending.push((ticker, action_id))
+        });
+    }
+
+    /// Processes the entitlement of `holder` in an action, on behalf of `caller_did`.
+    /// Every check is made before the first write, so that an error leaves no partial change.
+    fn process_entitlement(
+        caller: T::AccountId,
+        caller_did: IdentityId,
+        ticker: Ticker,
+        action_id: u32,
+        holder: IdentityId,
+    ) -> DispatchResult {
+        let mut action = Self::actions(ticker, action_id).ok_or(Error::<T>::NoSuchAction)?;
+        let now = <pallet_timestamp::Module<T>>::get();
+        ensure!(now >= action.record_date, Error::<T>::BeforeRecordDate);
+        let checkpoint_id = action.checkpoint_id.ok_or(Error::<T>::BeforeRecordDate)?;
+        // Splits and mergers are mandatory, so they never expire.
+        if !action.kind.is_reorganisation() {
+            if let Some(expires_at) = action.expires_at {
+                ensure!(now <= expires_at, Error::<T>::Expired);
+            }
+        }
+        ensure!(action.targets.targets(&holder), Error::<T>::NotTargeted);
+        ensure!(
+            !Self::holder_processed((ticker, action_id), holder),
+            Error::<T>::AlreadyProcessed
+        );
+        let holdings = <asset::Module<T>>::get_balance_at(ticker, holder, checkpoint_id);
+        ensure!(!holdings.is_zero(), Error::<T>::NoHoldings);
+
+        let owner = <asset::Module<T>>::token_details(&ticker).owner_did;
+        let mut withheld = Zero::zero();
+        let amount = match action.kind.clone() {
+            CorporateActionKind::CashDistribution(currency, total) => {
+                let supply = <asset::Module<T>>::total_supply_at((ticker, checkpoint_id));
+                let share = total
+                    .checked_mul(&holdings)
+                    .and_then(|v| v.checked_div(&supply))
+                    .ok_or(Error::<T>::Overflow)?;
+                withheld = Self::withholding_tax_of(&ticker, &action, &holder) * share;
+                let payout = share - withheld;
+                action.remaining = action
+                    .remaining
+                    .checked_sub(&share)
+                    .ok_or(Error::<T>::InsufficientFunds)?;
+                let tax_receiver = <dividend::Module<T>>::withholding_tax_receiver_of(&ticker);
+                // A failed payment does not revert the other one, so both payments are validated
+                // before any of them is made.
+                Self::ensure_can_pay(
+                    &caller,
+                    owner,
+                    &currency,
+                    &[(holder, payout), (tax_receiver, withheld)],
+                )?;
+                Self::pay(&caller, caller_did, owner, holder, &currency, payout)?;
+                if !withheld.is_zero() {
+                    Self::pay(
+                        &caller,
+                        caller_did,
+                        owner,
+                        tax_receiver,
+                        &currency,
+                        withheld,
+                    )?;
+                    <WithheldTax<T>>::insert((ticker, action_id), holder, withheld);
+                }
+                payout
+            }
+            CorporateActionKind::StockDividend(ratio) => {
+                let issued = Self::scale(&ticker, holdings, &ratio)?;
+                Self::mint(&ticker, caller, holder, issued)?;
+                issued
+            }
+            CorporateActionKind::Split(ratio) => {
+                let new_holdings = Self::scale(&ticker, holdings, &ratio)?;
+                if new_holdings > holdings {
+                    let issued = new_holdings - holdings;
+                    Self::mint(&ticker, caller, holder, issued)?;
+                    issued
+                } else {
+                    // Shares sold after the ex-date cannot be redeemed from the holder.
+                    let redeemed = (holdings - new_holdings)
+                        .min(<asset::Module<T>>::balance(&ticker, &holder));
+                    Self::redeem(&ticker, holder, redeemed)?;
+                    redeemed
+                }
+            }
+            CorporateActionKind::Merger(to_ticker, ratio) => {
+                // Shares sold after the ex-date cannot be redeemed from the holder, so they are
+                // not converted either.
+                let redeemed = holdings.min(<asset::Module<T>>::balance(&ticker, &holder));
+                let issued = Self::scale(&to_ticker, redeemed, &ratio)?;
+                if !issued.is_zero() {
+                    <asset::Module<T>>::_ensure_can_mint(
+                        &to_ticker,
+                        caller.clone(),
+                        holder,
+                        issued,
+                    )?;
+                }
+                Self::redeem(&ticker, holder, redeemed)?;
+                Self::mint(&to_ticker, caller, holder, issued)?;
+                issued
+            }
+        };
+
+        <HolderProcessed>::insert((ticker, action_id), holder, true);
+        <Actions<T>>::insert(ticker, action_id, action);
+        Self::deposit_event(RawEvent::EntitlementProcessed(
+            caller_did, ticker, action_id, holder, holdings, amount, withheld,
+        ));
+        Ok(())
+    }
+
+    /// Returns `value * ratio`, rounded down to the granularity of `ticker`.
+    fn scale(
+        ticker: &Ticker,
+        value: T::Balance,
+        ratio: &PosRatio,
+    ) -> Result<T::Balance, DispatchError> {
+        value
+            .checked_mul(&T::Balance::from(ratio.0))
+            .and_then(|v| v.checked_div(&T::Balance::from(ratio.1)))
+            .map(|v| <asset::Module<T>>::round_to_granularity(ticker, v))
+            .ok_or_else(|| Error::<T>::Overflow.into())
+    }
+
+    /// Issues `value` new shares of `ticker` to `holder`, if any.
+    fn mint(
+        ticker: &Ticker,
+        caller: T::AccountId,
+        holder: IdentityId,
+        value: T::Balance,
+    ) -> DispatchResult {
+        if value.is_zero() {
+            return Ok(());
+        }
+        <asset::Module<T>>::_mint(ticker, caller, holder, value, None)
+    }
+
+    /// Redeems `value` shares of `ticker` from `holder`, if any.
+    fn redeem(ticker: &Ticker, holder: IdentityId, value: T::Balance) -> DispatchResult {
+        if value.is_zero() {
+            return Ok(());
+        }
+        <asset::Module<T>>::_forced_redeem(ticker, holder, value)
+    }
+
+    fn ensure_sufficient_funds(
+        did: IdentityId,
+        currency: &DistributionCurrency,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        let funds = match currency {
+            DistributionCurrency::Polyx => <balances::Module<T>>::identity_balance(did),
+            DistributionCurrency::SimpleToken(payout_ticker) => {
+                <simple_token::Module<T>>::balance_of((*payout_ticker, did))
+            }
+            DistributionCurrency::Asset(payout_ticker) => <asset::Module<T>>::portfolio_balance(
+                &PortfolioId::default_portfolio(did),
+                payout_ticker,
+            ),
+        };
+        ensure!(funds >= amount, Error::<T>::InsufficientFunds);
+        Ok(())
+    }
+
+    /// Reserves the funds of a cash distribution from the token owner.
+    /// Distributions in an asset are not reserved.
+    fn reserve_funds(
+        did: IdentityId,
+        currency: &DistributionCurrency,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        match currency {
+            DistributionCurrency::Polyx => {
+                let _ = <balances::Module<T> as IdentityCurrency<_>>::withdraw_identity_balance(
+                    &did, amount,
+                )?;
+            }
+            DistributionCurrency::SimpleToken(payout_ticker) => {
+                <simple_token::BalanceOf<T>>::mutate((*payout_ticker, did), |balance| {
+                    *balance = balance.saturating_sub(amount)
+                });
+            }
+            DistributionCurrency::Asset(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Returns reserved funds of a cash distribution to the token owner.
+    fn release_funds(
+        did: IdentityId,
+        currency: &DistributionCurrency,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        match currency {
+            DistributionCurrency::Polyx => {
+                let _ =
+                    <balances::Module<T> as IdentityCurrency<_>>::deposit_into_existing_identity(
+                        &did, amount,
+                    )?;
+            }
+            DistributionCurrency::SimpleToken(payout_ticker) => {
+                let balance = <simple_token::Module<T>>::balance_of((*payout_ticker, did))
+                    .checked_add(&amount)
+                    .ok_or(Error::<T>::Overflow)?;
+                <simple_token::BalanceOf<T>>::insert((*payout_ticker, did), balance);
+            }
+            DistributionCurrency::Asset(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Ensures that the token owner can pay every amount of `payees` of a cash distribution.
+    fn ensure_can_pay(
+        caller: &T::AccountId,
+        owner: IdentityId,
+        currency: &DistributionCurrency,
+        payees: &[(IdentityId, T::Balance)],
+    ) -> DispatchResult {
+        let mut total = T::Balance::zero();
+        for (payee, amount) in payees.iter().filter(|(_, amount)| !amount.is_zero()) {
+            let balance = match currency {
+                DistributionCurrency::Polyx => <balances::Module<T>>::identity_balance(payee),
+                DistributionCurrency::SimpleToken(payout_ticker) => {
+                    <simple_token::Module<T>>::balance_of((*payout_ticker, *payee))
+                }
+                // The funds of the owner stay in its default portfolio.
+                DistributionCurrency::Asset(_) if *payee == owner => continue,
+                DistributionCurrency::Asset(payout_ticker) => {
+                    ensure!(
+                        <asset::Module<T>>::unsafe_can_transfer(
+                            caller.clone(),
+                            *payout_ticker,
+                            Some(owner),
+                            Some(*payee),
+                            *amount
+                        ) == Ok(ERC1400_TRANSFER_SUCCESS),
+                        Error::<T>::PayoutTransferNotAllowed
+                    );
+                    total = total.checked_add(amount).ok_or(Error::<T>::Overflow)?;
+                    <asset::Module<T>>::balance(payout_ticker, payee)
+                }
+            };
+            balance.checked_add(amount).ok_or(Error::<T>::Overflow)?;
+        }
+        if let DistributionCurrency::Asset(_) = currency {
+            Self::ensure_sufficient_funds(owner, currency, total)?;
+        }
+        Ok(())
+    }
+
+    /// Pays `amount` of a cash distribution to `holder`.
+    fn pay(
+        caller: &T::AccountId,
+        caller_did: IdentityId,
+        owner: IdentityId,
+        holder: IdentityId,
+        currency: &DistributionCurrency,
+        amount: T::Balance,
+    ) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        match currency {
+            DistributionCurrency::Polyx => {
+                let _ =
+                    <balances::Module<T> as IdentityCurrency<_>>::deposit_into_existing_identity(
+                        &holder, amount,
+                    )?;
+            }
+            DistributionCurrency::SimpleToken(payout_ticker) => {
+                let balance = <simple_token::Module<T>>::balance_of((*payout_ticker, holder))
+                    .checked_add(&amount)
+                    .ok_or(Error::<T>::Overflow)?;
+                <simple_token::BalanceOf<T>>::insert((*payout_ticker, holder), balance);
+            }
+            // The funds of the owner stay in its default portfolio.
+            DistributionCurrency::Asset(_) if holder == owner => {}
+            DistributionCurrency::Asset(payout_ticker) => {
+                ensure!(
+                    <asset::Module<T>>::unsafe_can_transfer(
+                        caller.clone(),
+                        *payout_ticker,
+                        Some(owner),
+                        Some(holder),
+                        amount
+                    ) == Ok(ERC1400_TRANSFER_SUCCESS),
+                    Error::<T>::PayoutTransferNotAllowed
+                );
+                <asset::Module<T>>::_transfer(caller_did, payout_ticker, owner, holder, amount)?;
+            }
+        }
+        Ok(())
+    }
+}
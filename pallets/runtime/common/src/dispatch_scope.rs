@@ -0,0 +1,602 @@
+// This file is part of the Polymesh distribution (https://github.com/PolymathNetwork/Polymesh).
+// Copyright (c) 2020 Polymath
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! # Dispatch scopes
+//!
+//! Describes what a call touches for the signing key permission check that runs before
+//! dispatch. Every call gets the name of its pallet and dispatchable. Calls of the asset
+//! related pallets, the balances pallet and the identity pallet also list the tickers they
+//! operate on, the portfolios they move funds out of or manage and the identities on the other
+//! side of the call. Calls which refer to stored state, like the affirmation of a settlement
+//! instruction or the acceptance of an authorization, take them from storage. Accounts on the
+//! other side of a call are mapped to their identities; an account without one can't be
+//! allowed by a counterparty permission.
+//!
+//! The matches on the calls of a pallet list every call, so that a new call doesn't compile
+//! until its scope is described here.
+//!
+//! Pallet names are given by the runtime. They are the names of the modules in
+//! `construct_runtime!` in snake case, e.g. `asset`, `compliance_manager` or `corporate_actions`.
+
+use crate::{corporate_actions, dividend, exemption, settlement, simple_token, sto_capped, voting};
+
+use pallet_asset as asset;
+use pallet_balances as balances;
+use pallet_compliance_manager as compliance_manager;
+use pallet_identity as identity;
+use pallet_statistics as statistics;
+use polymesh_primitives::{
+    AccountKey, AuthorizationData, DispatchScope, IdentityId, PortfolioId, Signatory,
+};
+
+use codec::Encode;
+use core::convert::TryFrom;
+use frame_support::dispatch::GetCallName;
+use sp_runtime::traits::StaticLookup;
+use sp_std::prelude::*;
+
+/// The scope of a call of a pallet that carries no tickers, portfolios or counterparties.
+pub fn named<C: GetCallName>(pallet: &[u8], call: &C) -> DispatchScope {
+    DispatchScope::new(pallet, call.get_call_name().as_bytes())
+}
+
+/// Finishes `scope` for calls made by `did`, which is not a counterparty of itself.
+fn finish(did: IdentityId, mut scope: DispatchScope) -> DispatchScope {
+    scope.tickers.sort();
+    scope.tickers.dedup();
+    scope.portfolios.sort();
+    scope.portfolios.dedup();
+    scope.counterparties.retain(|cp| *cp != did);
+    scope.counterparties.sort();
+    scope.counterparties.dedup();
+    scope
+}
+
+/// Adds the identity of `signer` to the counterparties of `scope`.
+fn push_signatory<T: identity::Trait>(scope: &mut DispatchScope, signer: &Signatory) {
+    match <identity::Module<T>>::get_identity_of_signatory(signer) {
+        Some(did) => scope.counterparties.push(did),
+        None => scope.unresolved_counterparties = true,
+    }
+}
+
+/// Adds the identity of `account` to the counterparties of `scope`.
+fn push_account<T: identity::Trait>(scope: &mut DispatchScope, account: &T::AccountId) {
+    match AccountKey::try_from(account.encode()) {
+        Ok(key) => push_signatory::<T>(scope, &Signatory::from(key)),
+        Err(_) => scope.unresolved_counterparties = true,
+    }
+}
+
+/// Adds the identity of the account `source` looks up to the counterparties of `scope`.
+fn push_lookup<T: balances::Trait + identity::Trait>(
+    scope: &mut DispatchScope,
+    source: &<T::Lookup as StaticLookup>::Source,
+) {
+    match T::Lookup::lookup(source.clone()) {
+        Ok(account) => push_account::<T>(scope, &account),
+        Err(_) => scope.unresolved_counterparties = true,
+    }
+}
+
+/// Adds the ticker, portfolio or identity `data` refers to.
+fn push_authorization_data(scope: &mut DispatchScope, data: &AuthorizationData) {
+    match data {
+        AuthorizationData::TransferTicker(ticker)
+        | AuthorizationData::TransferAssetOwnership(ticker)
+        | AuthorizationData::Custom(ticker) => scope.tickers.push(*ticker),
+        AuthorizationData::PortfolioCustody(portfolio) => scope.portfolios.push(*portfolio),
+        AuthorizationData::AttestMasterKeyRotation(did)
+        | AuthorizationData::RotateMasterKey(did)
+        | AuthorizationData::JoinIdentity(did) => scope.counterparties.push(*did),
+        AuthorizationData::AddMultiSigSigner | AuthorizationData::NoData => {}
+    }
+}
+
+/// Adds what the authorization `auth_id` given to `did` refers to and the identity which gave
+/// it.
+fn push_authorization<T: identity::Trait>(
+    scope: &mut DispatchScope,
+    did: IdentityId,
+    auth_id: u64,
+) {
+    if let Some(auth) = <identity::Module<T>>::get_non_expired_auth(&Signatory::from(did), &auth_id)
+    {
+        push_authorization_data(scope, &auth.authorization_data);
+        push_signatory::<T>(scope, &auth.authorized_by);
+    }
+}
+
+/// The scope of a call of the asset pallet `pallet` made by `did`.
+pub fn asset<T: asset::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &asset::Call<T>,
+) -> DispatchScope {
+    use asset::Call;
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::accept_ticker_transfer(auth_id)
+        | Call::accept_asset_ownership_transfer(auth_id)
+        | Call::accept_portfolio_custody(auth_id) => {
+            push_authorization::<T>(&mut scope, did, *auth_id)
+        }
+        Call::register_ticker(ticker)
+        | Call::create_asset(_, ticker, ..)
+        | Call::freeze(ticker)
+        | Call::unfreeze(ticker)
+        | Call::rename_asset(ticker, ..)
+        | Call::create_checkpoint(ticker)
+        | Call::create_checkpoint_schedule(ticker, ..)
+        | Call::remove_checkpoint_schedule(ticker, ..)
+        | Call::make_divisible(ticker)
+        | Call::is_issuable(ticker)
+        | Call::add_documents(ticker, ..)
+        | Call::remove_documents(ticker, ..)
+        | Call::update_documents(ticker, ..)
+        | Call::set_funding_round(ticker, ..)
+        | Call::update_identifiers(ticker, ..)
+        | Call::add_extension(ticker, ..)
+        | Call::archive_extension(ticker, ..)
+        | Call::unarchive_extension(ticker, ..) => scope.tickers.push(*ticker),
+        Call::transfer(ticker, to, ..) | Call::transfer_with_data(ticker, to, ..) => {
+            scope.tickers.push(*ticker);
+            scope.portfolios.push(PortfolioId::default_portfolio(did));
+            scope.counterparties.push(*to);
+        }
+        Call::issue(ticker, to, ..) | Call::approve(ticker, to, ..) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.push(*to);
+        }
+        Call::batch_issue(ticker, investors, ..) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.extend(investors.iter().copied());
+        }
+        Call::redeem(ticker, ..) => {
+            scope.tickers.push(*ticker);
+            scope.portfolios.push(PortfolioId::default_portfolio(did));
+        }
+        Call::redeem_from(ticker, from, ..) | Call::controller_redeem(ticker, from, ..) => {
+            scope.tickers.push(*ticker);
+            scope.portfolios.push(PortfolioId::default_portfolio(*from));
+            scope.counterparties.push(*from);
+        }
+        Call::transfer_from(ticker, from, to, ..)
+        | Call::transfer_from_with_data(ticker, from, to, ..)
+        | Call::controller_transfer(ticker, from, to, ..) => {
+            scope.tickers.push(*ticker);
+            scope.portfolios.push(PortfolioId::default_portfolio(*from));
+            scope.counterparties.extend([*from, *to].iter().copied());
+        }
+        Call::delete_portfolio(num) | Call::rename_portfolio(num, ..) => scope
+            .portfolios
+            .push(PortfolioId::user_portfolio(did, *num)),
+        Call::move_portfolio_funds(from, _, items) => {
+            scope
+                .tickers
+                .extend(items.iter().map(|(ticker, _)| *ticker));
+            scope.portfolios.push(*from);
+        }
+        Call::portfolio_transfer(ticker, from, to, ..) => {
+            scope.tickers.push(*ticker);
+            scope.portfolios.push(*from);
+            scope.counterparties.push(to.did);
+        }
+        Call::quit_portfolio_custody(portfolio) => scope.portfolios.push(*portfolio),
+        Call::create_portfolio(..) | Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the compliance manager pallet `pallet` made by `did`.
+pub fn compliance_manager<T: compliance_manager::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &compliance_manager::Call<T>,
+) -> DispatchScope {
+    use compliance_manager::Call;
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::add_active_rule(ticker, ..)
+        | Call::remove_active_rule(ticker, ..)
+        | Call::reset_active_rules(ticker)
+        | Call::pause_asset_rules(ticker)
+        | Call::resume_asset_rules(ticker)
+        | Call::change_asset_rule(ticker, ..)
+        | Call::change_asset_rule_batch(ticker, ..)
+        | Call::add_transfer_restriction(ticker, ..)
+        | Call::remove_transfer_restriction(ticker, ..) => scope.tickers.push(*ticker),
+        Call::add_default_trusted_claim_issuer(ticker, other)
+        | Call::remove_default_trusted_claim_issuer(ticker, other)
+        | Call::modify_ownership_exemption(ticker, other, ..)
+        | Call::set_trusted_issuer_scope(ticker, other, ..)
+        | Call::subscribe_rule_template(ticker, other, ..)
+        | Call::unsubscribe_rule_template(ticker, other, ..) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.push(*other);
+        }
+        Call::add_default_trusted_claim_issuers_batch(ticker, issuers)
+        | Call::remove_default_trusted_claim_issuers_batch(ticker, issuers) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.extend(issuers.iter().copied());
+        }
+        Call::set_rule_template(..) | Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the statistics pallet `pallet` made by `did`.
+pub fn statistics<T: statistics::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &statistics::Call<T>,
+) -> DispatchScope {
+    use statistics::Call;
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::track_claim(ticker, ..)
+        | Call::untrack_claim(ticker, ..)
+        | Call::clear_holder_claims(ticker, ..) => scope.tickers.push(*ticker),
+        Call::refresh_holder_stats(ticker, holders) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.extend(holders.iter().copied());
+        }
+        Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the dividend pallet `pallet` made by `did`.
+pub fn dividend<T: dividend::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &dividend::Call<T>,
+) -> DispatchScope {
+    use dividend::Call;
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::new(_, ticker, _, _, payout_ticker, ..) => scope
+            .tickers
+            .extend([*ticker, *payout_ticker].iter().copied()),
+        Call::cancel(ticker, ..)
+        | Call::claim(ticker, ..)
+        | Call::push_payouts(ticker, ..)
+        | Call::claim_unclaimed(ticker, ..)
+        | Call::set_default_withholding_tax(ticker, ..)
+        | Call::set_jurisdiction_withholding_tax(ticker, ..) => scope.tickers.push(*ticker),
+        Call::set_did_withholding_tax(ticker, holder, ..) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.push(*holder);
+        }
+        Call::set_withholding_tax_receiver(ticker, receiver) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.extend(receiver.iter().copied());
+        }
+        Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the exemption pallet `pallet` made by `did`.
+pub fn exemption<T: exemption::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &exemption::Call<T>,
+) -> DispatchScope {
+    use exemption::Call;
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::modify_exemption_list(ticker, _, holder, ..) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.push(*holder);
+        }
+        Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the settlement pallet `pallet` made by `did`.
+pub fn settlement<T: settlement::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &settlement::Call<T>,
+) -> DispatchScope {
+    use settlement::Call;
+
+    let mut scope = named(pallet, call);
+    let stored_legs;
+    let legs = match call {
+        Call::set_venue_filtering(ticker, ..)
+        | Call::allow_venues(ticker, ..)
+        | Call::disallow_venues(ticker, ..) => {
+            scope.tickers.push(*ticker);
+            &[][..]
+        }
+        Call::add_instruction(_, _, legs) => &legs[..],
+        Call::add_instruction_with_receipt(receipt, ..) => &receipt.legs[..],
+        // Affirmations and rejections take the legs of the stored instruction.
+        Call::affirm_instruction(instruction_id) | Call::reject_instruction(instruction_id) => {
+            stored_legs = <settlement::Module<T>>::instruction_legs(instruction_id);
+            &stored_legs[..]
+        }
+        Call::add_venue_signers(_, signers) | Call::remove_venue_signers(_, signers) => {
+            for signer in signers {
+                push_signatory::<T>(&mut scope, signer);
+            }
+            &[][..]
+        }
+        Call::create_venue(..) | Call::__PhantomItem(..) => &[][..],
+    };
+    for leg in legs {
+        match leg.asset {
+            settlement::LegAsset::Asset(ticker) => {
+                scope.tickers.push(ticker);
+                scope
+                    .portfolios
+                    .push(PortfolioId::default_portfolio(leg.from));
+            }
+            settlement::LegAsset::SimpleToken(ticker) => scope.tickers.push(ticker),
+            settlement::LegAsset::Polyx => {}
+        }
+        scope
+            .counterparties
+            .extend([leg.from, leg.to].iter().copied());
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the corporate actions pallet `pallet` made by `did`.
+pub fn corporate_actions<T: corporate_actions::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &corporate_actions::Call<T>,
+) -> DispatchScope {
+    use corporate_actions::{Call, CorporateActionKind, DistributionCurrency};
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::initiate(ticker, kind, ..) => {
+            scope.tickers.push(*ticker);
+            match kind {
+                CorporateActionKind::CashDistribution(DistributionCurrency::Asset(other), _)
+                | CorporateActionKind::CashDistribution(
+                    DistributionCurrency::SimpleToken(other),
+                    _,
+                )
+                | CorporateActionKind::Merger(other, _) => scope.tickers.push(*other),
+                CorporateActionKind::CashDistribution(DistributionCurrency::Polyx, _)
+                | CorporateActionKind::StockDividend(..)
+                | CorporateActionKind::Split(..) => {}
+            }
+        }
+        Call::cancel(ticker, ..)
+        | Call::claim(ticker, ..)
+        | Call::apply_reorganisation(ticker, ..)
+        | Call::reclaim(ticker, ..) => scope.tickers.push(*ticker),
+        Call::retry_entitlement(ticker, _, holder) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.push(*holder);
+        }
+        Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the capped STO pallet `pallet` made by `did`.
+pub fn sto_capped<T: sto_capped::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &sto_capped::Call<T>,
+) -> DispatchScope {
+    use sto_capped::Call;
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::launch_sto(ticker, beneficiary, _, _, _, _, simple_token_ticker) => {
+            scope
+                .tickers
+                .extend([*ticker, *simple_token_ticker].iter().copied());
+            scope.counterparties.push(*beneficiary);
+        }
+        Call::modify_allowed_tokens(ticker, _, simple_token_ticker, ..)
+        | Call::buy_tokens_by_simple_token(ticker, _, _, simple_token_ticker) => scope
+            .tickers
+            .extend([*ticker, *simple_token_ticker].iter().copied()),
+        Call::buy_tokens(ticker, ..)
+        | Call::pause_sto(ticker, ..)
+        | Call::unpause_sto(ticker, ..) => scope.tickers.push(*ticker),
+        Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the voting pallet `pallet` made by `did`.
+pub fn voting<T: voting::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &voting::Call<T>,
+) -> DispatchScope {
+    use voting::Call;
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::add_ballot(ticker, ..) | Call::vote(ticker, ..) | Call::cancel_ballot(ticker, ..) => {
+            scope.tickers.push(*ticker)
+        }
+        Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the simple token pallet `pallet` made by `did`.
+pub fn simple_token<T: simple_token::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &simple_token::Call<T>,
+) -> DispatchScope {
+    use simple_token::Call;
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::create_token(ticker, ..) => scope.tickers.push(*ticker),
+        Call::approve(ticker, counterparty, ..) | Call::transfer(ticker, counterparty, ..) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.push(*counterparty);
+        }
+        Call::transfer_from(ticker, from, to, ..) => {
+            scope.tickers.push(*ticker);
+            scope.counterparties.extend([*from, *to].iter().copied());
+        }
+        Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the balances pallet `pallet` made by `did`.
+pub fn balances<T: balances::Trait + identity::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &balances::Call<T>,
+) -> DispatchScope {
+    use balances::Call;
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::transfer(dest, ..) | Call::transfer_with_memo(dest, ..) => {
+            push_lookup::<T>(&mut scope, dest)
+        }
+        Call::top_up_identity_balance(target, ..) | Call::reclaim_identity_balance(target, ..) => {
+            scope.counterparties.push(*target)
+        }
+        // The BRR account has no identity.
+        Call::top_up_brr_balance(..) => scope.unresolved_counterparties = true,
+        Call::force_transfer(source, dest, ..) => {
+            push_lookup::<T>(&mut scope, source);
+            push_lookup::<T>(&mut scope, dest);
+        }
+        Call::set_balance(who, ..) => push_lookup::<T>(&mut scope, who),
+        Call::change_charge_did_flag(..)
+        | Call::burn_account_balance(..)
+        | Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
+
+/// The scope of a call of the identity pallet `pallet` made by `did`.
+pub fn identity<T: identity::Trait>(
+    pallet: &[u8],
+    did: IdentityId,
+    call: &identity::Call<T>,
+) -> DispatchScope {
+    use identity::Call;
+
+    let mut scope = named(pallet, call);
+    match call {
+        Call::cdd_register_did(account, ..)
+        | Call::close_identity(account)
+        | Call::get_cdd_of(account) => push_account::<T>(&mut scope, account),
+        Call::invalidate_cdd_claims(target, ..)
+        | Call::cancel_master_key_rotation(target)
+        | Call::initiate_recovery(target, ..)
+        | Call::approve_recovery(target, ..)
+        | Call::finalize_recovery(target, ..)
+        | Call::add_claim(target, ..)
+        | Call::revoke_claim(target, ..)
+        | Call::forwarded_call(target, ..)
+        | Call::suspend_claim_issuer(target, ..)
+        | Call::revoke_issuer_claims_since(target, ..)
+        | Call::clear_claims_revocation(target)
+        | Call::slash_claim_issuer(target, ..) => scope.counterparties.push(*target),
+        Call::add_claims_batch(claims) => scope
+            .counterparties
+            .extend(claims.iter().map(|item| item.target)),
+        Call::revoke_claims_batch(claims) => scope
+            .counterparties
+            .extend(claims.iter().map(|item| item.target)),
+        Call::remove_signing_items(signers) => {
+            for signer in signers {
+                push_signatory::<T>(&mut scope, signer);
+            }
+        }
+        Call::set_recovery_config(config) => {
+            for guardian in config.iter().flat_map(|config| config.guardians.iter()) {
+                push_signatory::<T>(&mut scope, guardian);
+            }
+        }
+        Call::set_permission_to_signer(signer, ..)
+        | Call::revoke_offchain_authorization(signer, ..)
+        | Call::remove_authorization(signer, ..) => push_signatory::<T>(&mut scope, signer),
+        Call::add_authorization(target, data, ..)
+        | Call::add_authorization_as_key(target, data, ..) => {
+            push_signatory::<T>(&mut scope, target);
+            push_authorization_data(&mut scope, data);
+        }
+        Call::batch_add_authorization(auths) => {
+            for (target, data, _) in auths {
+                push_signatory::<T>(&mut scope, target);
+                push_authorization_data(&mut scope, data);
+            }
+        }
+        Call::batch_remove_authorization(auth_identifiers) => {
+            for auth_identifier in auth_identifiers {
+                push_signatory::<T>(&mut scope, &auth_identifier.0);
+            }
+        }
+        Call::accept_master_key(auth_id, cdd_auth_id) => {
+            push_authorization::<T>(&mut scope, did, *auth_id);
+            if let Some(cdd_auth_id) = cdd_auth_id {
+                push_authorization::<T>(&mut scope, did, *cdd_auth_id);
+            }
+        }
+        Call::join_identity_as_identity(auth_id) | Call::accept_authorization(auth_id) => {
+            push_authorization::<T>(&mut scope, did, *auth_id)
+        }
+        Call::batch_accept_authorization(auth_ids) => {
+            for auth_id in auth_ids {
+                push_authorization::<T>(&mut scope, did, *auth_id);
+            }
+        }
+        Call::add_signing_items_with_authorization(_, additional_keys) => {
+            for key in additional_keys {
+                push_signatory::<T>(&mut scope, &key.signing_item.signer);
+            }
+        }
+        // Calls which only touch the caller's own identity or keys, or which are made by keys
+        // without an identity.
+        Call::register_did(..)
+        | Call::set_master_key(..)
+        | Call::change_cdd_requirement_for_mk_rotation(..)
+        | Call::set_master_key_rotation_delay(..)
+        | Call::cancel_recovery(..)
+        | Call::join_identity_as_key(..)
+        | Call::register_custom_claim_type(..)
+        | Call::register_claim_issuer(..)
+        | Call::update_claim_issuer(..)
+        | Call::deregister_claim_issuer(..)
+        | Call::revoke_claims_since(..)
+        | Call::set_claim_issuer_deposit(..)
+        | Call::set_spending_limit(..)
+        | Call::freeze_signing_keys(..)
+        | Call::unfreeze_signing_keys(..)
+        | Call::get_my_did(..)
+        | Call::__PhantomItem(..) => {}
+    }
+    finish(did, scope)
+}
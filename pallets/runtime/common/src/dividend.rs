@@ -25,6 +25,7 @@
 //! - Termination existing dividends
 //! - claiming dividends
 //! - Claiming back unclaimed dividends
+//! - Configuring the withholding tax of dividend payouts
 //!
 //! ### Terminology
 //!
@@ -32,6 +33,11 @@
 //! - **Dividend maturity date:** It is the date after which dividends can be claimed by tokenholders
 //! - **Dividend expiry date:** Tokenholders can claim dividends before this date.
 //! After this date, issuer can reclaim the remaining dividend.
+//! - **Withholding tax:** The portion of a payout which is withheld from a tokenholder and paid to
+//! the tax receiver of the ticker, which is the token owner unless another identity is set. The rate
+//! of a tokenholder is, in order of precedence, its specific rate, the rate of its jurisdiction or
+//! the default rate of the ticker. Jurisdictions are taken from the `Jurisdiction` claims issued by
+//! the trusted claim issuers of the ticker, scoped to the ticker.
 //!
 //! ## Interface
 //!
@@ -40,15 +46,24 @@
 //! - `new` - Creates a new dividend
 //! - `cancel` - Cancels an existing dividend
 //! - `claim` - Allows tokenholders to claim/collect their fair share of the dividend
+//! - `push_payouts` - Pays out the shares of a batch of tokenholders on their behalf
 //! - `claim_unclaimed` - Allows token issuer to claim unclaimed dividend
+//! - `set_default_withholding_tax` - Sets the default withholding tax rate of a ticker
+//! - `set_did_withholding_tax` - Sets or removes the withholding tax rate of an identity
+//! - `set_jurisdiction_withholding_tax` - Sets or removes the withholding tax rate of a jurisdiction
+//! - `set_withholding_tax_receiver` - Sets or removes the receiver of the withheld taxes
 //!
 //! ### Public Functions
 //!
 //! - `get_dividend` - Returns details about a dividend
+//! - `withholding_tax_of` - Returns the withholding tax rate of a tokenholder
+//! - `withholding_tax_override` - Returns the specific or jurisdiction rate of a tokenholder
+//! - `withholding_tax_receiver_of` - Returns the receiver of the withheld taxes of a ticker
 
 use crate::simple_token;
 
 use pallet_asset as asset;
+use pallet_compliance_manager as compliance_manager;
 use pallet_identity as identity;
 use polymesh_common_utilities::{
     balances::Trait as BalancesTrait,
@@ -56,7 +71,9 @@ use polymesh_common_utilities::{
     protocol_fee::{ChargeProtocolFee, ProtocolOp},
     CommonTrait, Context,
 };
-use polymesh_primitives::{AccountKey, IdentityId, Signatory, Ticker};
+use polymesh_primitives::{
+    AccountKey, Claim, ClaimType, IdentityId, JurisdictionName, Scope, Signatory, Ticker,
+};
 
 use codec::Encode;
 use frame_support::{
@@ -64,12 +81,20 @@ use frame_support::{
     weights::SimpleDispatchInfo,
 };
 use frame_system::{self as system, ensure_signed};
-use sp_runtime::traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Zero};
+use sp_runtime::{
+    traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Zero},
+    DispatchError, Permill,
+};
 use sp_std::{convert::TryFrom, prelude::*};
 
 /// The module's configuration trait.
 pub trait Trait:
-    asset::Trait + BalancesTrait + simple_token::Trait + frame_system::Trait + pallet_timestamp::Trait
+    asset::Trait
+    + BalancesTrait
+    + compliance_manager::Trait
+    + simple_token::Trait
+    + frame_system::Trait
+    + pallet_timestamp::Trait
 {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 }
@@ -104,11 +129,30 @@ decl_storage! {
         /// Payout flags, decide whether a user already was paid their dividend
         /// (DID, ticker, dividend_id) -> whether they got their payout
         UserPayoutCompleted get(fn payout_completed): map hasher(blake2_128_concat) (IdentityId, Ticker, u32) => bool;
+        /// Index in the holder list of the ticker of the next holder to process by `push_payouts`
+        /// (ticker, dividend_id) -> index
+        PayoutCursor get(fn payout_cursor): map hasher(blake2_128_concat) (Ticker, u32) => u64;
+        /// Withholding tax rate of the tokenholders without a more specific rate; ticker -> rate
+        DefaultWithholdingTax get(fn default_withholding_tax): map hasher(blake2_128_concat) Ticker => Permill;
+        /// Withholding tax rates of specific tokenholders; ticker -> DID -> rate
+        DidWithholdingTax get(fn did_withholding_tax): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) IdentityId => Option<Permill>;
+        /// Withholding tax rates of jurisdictions; ticker -> jurisdiction -> rate
+        JurisdictionWithholdingTax get(fn jurisdiction_withholding_tax): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) JurisdictionName => Option<Permill>;
+        /// Receiver of the withheld taxes if it is not the token owner; ticker -> DID
+        WithholdingTaxReceiver get(fn withholding_tax_receiver): map hasher(blake2_128_concat) Ticker => Option<IdentityId>;
+        /// Amount withheld from the payout of a tokenholder; (ticker, dividend ID) -> DID -> amount
+        WithheldTax get(fn withheld_tax): double_map hasher(blake2_128_concat) (Ticker, u32), hasher(blake2_128_concat) IdentityId => T::Balance;
+        /// Tokenholders whose pushed payout failed and who have not claimed it since
+        /// (ticker, dividend ID) -> DID -> failed
+        FailedPayouts get(fn failed_payout): double_map hasher(blake2_128_concat) (Ticker, u32), hasher(blake2_128_concat) IdentityId => bool;
     }
 }
 
 type Identity<T> = identity::Module<T>;
 
+/// Maximum number of holders processed by one call to `push_payouts`.
+pub const MAX_PUSHED_PAYOUTS: u32 = 100;
+
 // The module's dispatchable functions.
 decl_module! {
     /// The module declaration.
@@ -120,6 +164,8 @@ decl_module! {
         fn deposit_event() = default;
 
         /// Creates a new dividend entry without payout. Token must have at least one checkpoint.
+        /// If `checkpoint_schedule` is given, the dividend uses the latest checkpoint created by
+        /// that checkpoint schedule of the token, and `checkpoint_id` is ignored.
         #[weight = SimpleDispatchInfo::FixedNormal(400_000)]
         pub fn new(origin,
             amount: T::Balance,
@@ -127,7 +173,8 @@ decl_module! {
             matures_at: T::Moment,
             expires_at: T::Moment,
             payout_ticker: Ticker,
-            checkpoint_id: u64
+            checkpoint_id: u64,
+            checkpoint_schedule: Option<u64>
         ) -> DispatchResult {
             let sender_key = AccountKey::try_from( ensure_signed(origin)?.encode())?;
             let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
@@ -145,8 +192,13 @@ decl_module! {
             let balance = <simple_token::BalanceOf<T>>::get((payout_ticker, did));
             ensure!(balance >= amount, Error::<T>::InsufficientFunds);
 
-            // Unpack the checkpoint ID, use the latest or create a new one, in that order
-            let checkpoint_id = if checkpoint_id > 0 {
+            let now = <pallet_timestamp::Module<T>>::get();
+            // Unpack the checkpoint ID, use the one of the schedule, the given one, the latest or
+            // create a new one, in that order
+            let checkpoint_id = if let Some(schedule_id) = checkpoint_schedule {
+                <asset::Module<T>>::schedule_checkpoint_at(&ticker, schedule_id, now)
+                    .ok_or(Error::<T>::NoSuchCheckpoint)?
+            } else if checkpoint_id > 0 {
                 checkpoint_id
             } else {
                 let count = <asset::TotalCheckpoints>::get(&ticker);
@@ -163,7 +215,6 @@ decl_module! {
                 Error::<T>::NoSuchCheckpoint
             );
 
-            let now = <pallet_timestamp::Module<T>>::get();
             let zero_ts = Zero::zero(); // A 0 timestamp
             // Check maturity/expiration dates
             match (&matures_at, &expires_at) {
@@ -272,57 +323,63 @@ decl_module! {
 
             // Look dividend entry up
             let dividend = Self::get_dividend(&ticker, dividend_id).ok_or(Error::<T>::NoSuchDividend)?;
+            Self::ensure_payable(&ticker, &dividend)?;
 
-            let balance_at_checkpoint =
-                <asset::Module<T>>::get_balance_at(ticker, did, dividend.checkpoint_id);
-
-            // Check if the owner hadn't yanked the remaining amount out
-            ensure!(!dividend.remaining_claimed, Error::<T>::RemainingFundsAlreadyClaimed);
-
-            let now = <pallet_timestamp::Module<T>>::get();
-
-            // Check if the current time is within maturity/expiration bounds
-            if let Some(start) = dividend.matures_at.as_ref() {
-                ensure!(now > *start, Error::<T>::CannotPayBeforeMaturity);
-            }
-
-            if let Some(end) = dividend.expires_at.as_ref() {
-                ensure!(*end > now, Error::<T>::CannotPayAfterExpiration);
-            }
-
-            // Compute the share
-            ensure!(<asset::Tokens<T>>::contains_key(&ticker), Error::<T>::NoSuchToken);
-            let supply_at_checkpoint = <asset::CheckpointTotalSupply<T>>::get((ticker, dividend.checkpoint_id));
-
-            let balance_amount_product = balance_at_checkpoint
-                .checked_mul(&dividend.amount)
-                .ok_or(Error::<T>::BalanceAmountProductOverflowed)?;
+            Self::pay_out(&ticker, dividend_id, &dividend, did)
+        }
 
-            let share = balance_amount_product
-                .checked_div(&supply_at_checkpoint)
-                .ok_or(Error::<T>::BalanceAmountProductSupplyDivisionFailed)?;
+        /// Pays out the shares of the next `max_holders` tokenholders in the holder list of the
+        /// ticker, starting from the payout cursor of the dividend, and advances the cursor.
+        /// Tokenholders who were already paid or who held nothing at the checkpoint are skipped.
+        /// A tokenholder whose payout fails is recorded in `FailedPayouts` and skipped, and it can
+        /// still claim its payout later. It can be called by anyone, so that the whole holder list
+        /// is paid across many calls.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the caller.
+        /// * `ticker` Ticker of the token.
+        /// * `dividend_id` ID of the dividend.
+        /// * `max_holders` Maximum number of holders to process, capped at `MAX_PUSHED_PAYOUTS`.
+        #[weight = SimpleDispatchInfo::FixedNormal(5_000_000)]
+        pub fn push_payouts(origin, ticker: Ticker, dividend_id: u32, max_holders: u32) -> DispatchResult {
+            let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+            let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+            let sender = Signatory::AccountKey(sender_key);
 
-            // Adjust the paid_out amount
-            <Dividends<T>>::mutate((ticker, dividend_id), |entry| -> DispatchResult {
-                entry.amount_left = entry.amount_left.checked_sub(&share)
-                    .ok_or(Error::<T>::CouldNotIncreaseAmount)?;
-                Ok(())
-            })?;
+            // Check that sender is allowed to act on behalf of `did`
+            ensure!(
+                <identity::Module<T>>::is_signer_authorized(did, &sender),
+                Error::<T>::SenderMustBeSigningKeyForDid
+            );
 
-            // Perform the payout in designated tokens
-            <simple_token::BalanceOf<T>>::mutate(
-                (dividend.payout_currency, did),
-                |balance| -> DispatchResult {
-                    *balance = balance.checked_add(&share).ok_or(Error::<T>::CouldNotAddShare)?;
-                    Ok(())
+            let dividend = Self::get_dividend(&ticker, dividend_id).ok_or(Error::<T>::NoSuchDividend)?;
+            Self::ensure_payable(&ticker, &dividend)?;
+
+            let cursor = Self::payout_cursor((ticker, dividend_id));
+            let end = <asset::Module<T>>::holder_count(&ticker)
+                .min(cursor + u64::from(max_holders.min(MAX_PUSHED_PAYOUTS)));
+            ensure!(cursor < end, Error::<T>::NoHoldersLeft);
+
+            let mut paid = 0u32;
+            for index in cursor..end {
+                let holder = <asset::Module<T>>::holder_at(&ticker, index);
+                if <UserPayoutCompleted>::get((holder, ticker, dividend_id))
+                    || <asset::Module<T>>::get_balance_at(ticker, holder, dividend.checkpoint_id).is_zero()
+                {
+                    continue;
                 }
-            )?;
-
-            // Create payout entry
-            <UserPayoutCompleted>::insert((did, ticker, dividend_id), true);
+                match Self::pay_out(&ticker, dividend_id, &dividend, holder) {
+                    Ok(()) => paid += 1,
+                    Err(e) => {
+                        sp_runtime::print(e);
+                        <FailedPayouts>::insert((ticker, dividend_id), holder, true);
+                        Self::deposit_event(RawEvent::DividendPayoutFailed(did, ticker, dividend_id, holder));
+                    }
+                }
+            }
+            <PayoutCursor>::insert((ticker, dividend_id), end);
 
-            // Dispatch event
-            Self::deposit_event(RawEvent::DividendPaidOutToUser(did, ticker, dividend_id, share));
+            Self::deposit_event(RawEvent::DividendPayoutsPushed(did, ticker, dividend_id, paid, end));
             Ok(())
         }
 
@@ -368,6 +425,68 @@ decl_module! {
 
             Ok(())
         }
+
+        /// Sets the withholding tax rate of the tokenholders of a ticker which have neither a
+        /// specific nor a jurisdiction rate.
+        #[weight = SimpleDispatchInfo::FixedNormal(150_000)]
+        pub fn set_default_withholding_tax(origin, ticker: Ticker, rate: Permill) -> DispatchResult {
+            let did = Self::ensure_owner(origin, &ticker)?;
+            <DefaultWithholdingTax>::insert(ticker, rate);
+            Self::deposit_event(RawEvent::DefaultWithholdingTaxChanged(did, ticker, rate));
+            Ok(())
+        }
+
+        /// Sets the withholding tax rate of a tokenholder, or removes it if `rate` is `None`.
+        #[weight = SimpleDispatchInfo::FixedNormal(150_000)]
+        pub fn set_did_withholding_tax(
+            origin,
+            ticker: Ticker,
+            holder: IdentityId,
+            rate: Option<Permill>
+        ) -> DispatchResult {
+            let did = Self::ensure_owner(origin, &ticker)?;
+            match rate {
+                Some(rate) => <DidWithholdingTax>::insert(ticker, holder, rate),
+                None => <DidWithholdingTax>::remove(ticker, holder),
+            }
+            Self::deposit_event(RawEvent::DidWithholdingTaxChanged(did, ticker, holder, rate));
+            Ok(())
+        }
+
+        /// Sets the withholding tax rate of the tokenholders of a jurisdiction, or removes it if
+        /// `rate` is `None`.
+        #[weight = SimpleDispatchInfo::FixedNormal(150_000)]
+        pub fn set_jurisdiction_withholding_tax(
+            origin,
+            ticker: Ticker,
+            jurisdiction: JurisdictionName,
+            rate: Option<Permill>
+        ) -> DispatchResult {
+            let did = Self::ensure_owner(origin, &ticker)?;
+            match rate {
+                Some(rate) => <JurisdictionWithholdingTax>::insert(ticker, &jurisdiction, rate),
+                None => <JurisdictionWithholdingTax>::remove(ticker, &jurisdiction),
+            }
+            Self::deposit_event(RawEvent::JurisdictionWithholdingTaxChanged(did, ticker, jurisdiction, rate));
+            Ok(())
+        }
+
+        /// Sets the identity which receives the withheld taxes of a ticker. The token owner
+        /// receives them if `receiver` is `None`.
+        #[weight = SimpleDispatchInfo::FixedNormal(150_000)]
+        pub fn set_withholding_tax_receiver(
+            origin,
+            ticker: Ticker,
+            receiver: Option<IdentityId>
+        ) -> DispatchResult {
+            let did = Self::ensure_owner(origin, &ticker)?;
+            match receiver {
+                Some(receiver) => <WithholdingTaxReceiver>::insert(ticker, receiver),
+                None => <WithholdingTaxReceiver>::remove(ticker),
+            }
+            Self::deposit_event(RawEvent::WithholdingTaxReceiverChanged(did, ticker, receiver));
+            Ok(())
+        }
     }
 }
 
@@ -387,6 +506,30 @@ decl_event!(
 
         /// Unclaimed dividend was claimed back (ticker, dividend ID, amount)
         DividendRemainingClaimed(IdentityId, Ticker, u32, Balance),
+
+        /// Payouts were pushed to a batch of holders
+        /// (caller DID, ticker, dividend ID, number of holders paid, new payout cursor)
+        DividendPayoutsPushed(IdentityId, Ticker, u32, u32, u64),
+
+        /// A pushed payout failed and the tokenholder was skipped
+        /// (caller DID, ticker, dividend ID, holder)
+        DividendPayoutFailed(IdentityId, Ticker, u32, IdentityId),
+
+        /// Tax was withheld from a payout (who, ticker, dividend ID, tax receiver, amount)
+        DividendTaxWithheld(IdentityId, Ticker, u32, IdentityId, Balance),
+
+        /// The default withholding tax rate of a ticker was changed (caller DID, ticker, rate)
+        DefaultWithholdingTaxChanged(IdentityId, Ticker, Permill),
+
+        /// The withholding tax rate of a tokenholder was changed (caller DID, ticker, holder, rate)
+        DidWithholdingTaxChanged(IdentityId, Ticker, IdentityId, Option<Permill>),
+
+        /// The withholding tax rate of a jurisdiction was changed
+        /// (caller DID, ticker, jurisdiction, rate)
+        JurisdictionWithholdingTaxChanged(IdentityId, Ticker, JurisdictionName, Option<Permill>),
+
+        /// The receiver of the withheld taxes was changed (caller DID, ticker, receiver)
+        WithholdingTaxReceiverChanged(IdentityId, Ticker, Option<IdentityId>),
     }
 );
 
@@ -432,10 +575,182 @@ decl_error! {
         CouldNotIncreaseAmount,
         /// Could not add the share to sender's balance.
         CouldNotAddShare,
+        /// Every holder of the ticker has already been processed.
+        NoHoldersLeft,
     }
 }
 
 impl<T: Trait> Module<T> {
+    /// Ensures that `origin` is a signing key of the owner of `ticker`. Returns the DID of the owner.
+    fn ensure_owner(origin: T::Origin, ticker: &Ticker) -> Result<IdentityId, DispatchError> {
+        let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+        let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+        let sender = Signatory::AccountKey(sender_key);
+
+        // Check that sender is allowed to act on behalf of `did`
+        ensure!(
+            <identity::Module<T>>::is_signer_authorized(did, &sender),
+            Error::<T>::SenderMustBeSigningKeyForDid
+        );
+        // Check that sender owns the asset token
+        ensure!(
+            <asset::Module<T>>::_is_owner(ticker, did),
+            Error::<T>::NotAnOwner
+        );
+        Ok(did)
+    }
+
+    /// Returns the withholding tax rate of `did` on the payouts of `ticker`.
+    pub fn withholding_tax_of(ticker: &Ticker, did: IdentityId) -> Permill {
+        Self::withholding_tax_override(ticker, did)
+            .unwrap_or_else(|| Self::default_withholding_tax(ticker))
+    }
+
+    /// Returns the specific withholding tax rate of `did`, or else the rate of its jurisdiction,
+    /// if any of them is set.
+    pub fn withholding_tax_override(ticker: &Ticker, did: IdentityId) -> Option<Permill> {
+        if let Some(rate) = Self::did_withholding_tax(ticker, did) {
+            return Some(rate);
+        }
+        let scope = <identity::Module<T>>::get_token_did(ticker)
+            .ok()
+            .map(Scope::Identity);
+        <compliance_manager::Module<T>>::trusted_claim_issuers_for(ticker, ClaimType::Jurisdiction)
+            .into_iter()
+            .filter_map(|issuer| {
+                <identity::Module<T>>::fetch_claim(
+                    did,
+                    ClaimType::Jurisdiction,
+                    issuer,
+                    scope.clone(),
+                )
+            })
+            .find_map(|id_claim| match id_claim.claim {
+                Claim::Jurisdiction(jurisdiction, _) => {
+                    Self::jurisdiction_withholding_tax(ticker, jurisdiction)
+                }
+                _ => None,
+            })
+    }
+
+    /// Returns the identity which receives the withheld taxes of `ticker`.
+    pub fn withholding_tax_receiver_of(ticker: &Ticker) -> IdentityId {
+        Self::withholding_tax_receiver(ticker)
+            .unwrap_or_else(|| <asset::Module<T>>::token_details(ticker).owner_did)
+    }
+
+    /// Checks that the shares of `dividend` can be paid out now.
+    fn ensure_payable(
+        ticker: &Ticker,
+        dividend: &Dividend<T::Balance, T::Moment>,
+    ) -> DispatchResult {
+        // Check if the owner hadn't yanked the remaining amount out
+        ensure!(
+            !dividend.remaining_claimed,
+            Error::<T>::RemainingFundsAlreadyClaimed
+        );
+
+        let now = <pallet_timestamp::Module<T>>::get();
+
+        // Check if the current time is within maturity/expiration bounds
+        if let Some(start) = dividend.matures_at.as_ref() {
+            ensure!(now > *start, Error::<T>::CannotPayBeforeMaturity);
+        }
+
+        if let Some(end) = dividend.expires_at.as_ref() {
+            ensure!(*end > now, Error::<T>::CannotPayAfterExpiration);
+        }
+
+        ensure!(
+            <asset::Tokens<T>>::contains_key(ticker),
+            Error::<T>::NoSuchToken
+        );
+        Ok(())
+    }
+
+    /// Pays out the share of `did` in a dividend, withholding its tax.
+    fn pay_out(
+        ticker: &Ticker,
+        dividend_id: u32,
+        dividend: &Dividend<T::Balance, T::Moment>,
+        did: IdentityId,
+    ) -> DispatchResult {
+        let ticker = *ticker;
+        let balance_at_checkpoint =
+            <asset::Module<T>>::get_balance_at(ticker, did, dividend.checkpoint_id);
+
+        // Compute the share
+        let supply_at_checkpoint =
+            <asset::CheckpointTotalSupply<T>>::get((ticker, dividend.checkpoint_id));
+
+        let balance_amount_product = balance_at_checkpoint
+            .checked_mul(&dividend.amount)
+            .ok_or(Error::<T>::BalanceAmountProductOverflowed)?;
+
+        let share = balance_amount_product
+            .checked_div(&supply_at_checkpoint)
+            .ok_or(Error::<T>::BalanceAmountProductSupplyDivisionFailed)?;
+
+        // The amount left is read from the storage since `dividend` may predate earlier payouts.
+        let amount_left = Self::dividends((ticker, dividend_id))
+            .amount_left
+            .checked_sub(&share)
+            .ok_or(Error::<T>::CouldNotIncreaseAmount)?;
+
+        // Split the share between the tokenholder and the tax receiver
+        let tax = Self::withholding_tax_of(&ticker, did) * share;
+        let payout = share - tax;
+        let tax_receiver = Self::withholding_tax_receiver_of(&ticker);
+
+        // Every balance is computed before any of them is written, so that a failed payout leaves
+        // no partial change.
+        let holder_balance = <simple_token::Module<T>>::balance_of((dividend.payout_currency, did))
+            .checked_add(&payout)
+            .ok_or(Error::<T>::CouldNotAddShare)?;
+        let receiver_balance = if tax_receiver == did {
+            holder_balance.checked_add(&tax)
+        } else {
+            <simple_token::Module<T>>::balance_of((dividend.payout_currency, tax_receiver))
+                .checked_add(&tax)
+        }
+        .ok_or(Error::<T>::CouldNotAddShare)?;
+
+        // Perform the payout in designated tokens
+        <Dividends<T>>::mutate((ticker, dividend_id), |entry| {
+            entry.amount_left = amount_left
+        });
+        <simple_token::BalanceOf<T>>::insert((dividend.payout_currency, did), holder_balance);
+        if !tax.is_zero() {
+            <simple_token::BalanceOf<T>>::insert(
+                (dividend.payout_currency, tax_receiver),
+                receiver_balance,
+            );
+            <WithheldTax<T>>::insert((ticker, dividend_id), did, tax);
+        }
+
+        // Create payout entry
+        <UserPayoutCompleted>::insert((did, ticker, dividend_id), true);
+        <FailedPayouts>::remove((ticker, dividend_id), did);
+
+        // Dispatch events
+        Self::deposit_event(RawEvent::DividendPaidOutToUser(
+            did,
+            ticker,
+            dividend_id,
+            payout,
+        ));
+        if !tax.is_zero() {
+            Self::deposit_event(RawEvent::DividendTaxWithheld(
+                did,
+                ticker,
+                dividend_id,
+                tax_receiver,
+                tax,
+            ));
+        }
+        Ok(())
+    }
+
     /// A helper method for dividend creation. Returns dividend ID
     /// #[inline]
     fn add_dividend_entry(
@@ -18,7 +18,7 @@ use polymesh_common_utilities::{
     asset::Trait as AssetTrait, balances::Trait as BalancesTrait,
     exemption::Trait as ExemptionTrait, identity::Trait as IdentityTrait, Context,
 };
-use polymesh_primitives::{AccountKey, IdentityId, Signatory, Ticker};
+use polymesh_primitives::{AccountKey, ClaimType, IdentityId, Signatory, Ticker};
 
 use codec::Encode;
 use frame_support::{
@@ -35,11 +35,17 @@ pub trait Trait: frame_system::Trait + BalancesTrait + IdentityTrait {
     type Asset: AssetTrait<Self::Balance, Self::AccountId>;
 }
 
+/// Maximum number of exemptions per asset.
+pub const MAX_EXEMPTIONS: usize = 50;
+
 // This module's storage items.
 decl_storage! {
     trait Store for Module<T: Trait> as exemption {
-        // Mapping -> ExemptionList[ticker][TM][DID] = true/false
-        ExemptionList get(fn exemption_list): map hasher(blake2_128_concat) (Ticker, u16, IdentityId) => bool;
+        /// Exemptions from the compliance rules of an asset (Ticker -> (DID, claim type) -> exempted).
+        /// A `None` claim type exempts the identity from all the rules of the asset.
+        Exemptions get(fn is_exempted_from): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) (IdentityId, Option<ClaimType>) => bool;
+        /// List of exemptions of an asset (Ticker -> Array of (DID, claim type)).
+        ExemptionList get(fn exemption_list): map hasher(blake2_128_concat) Ticker => Vec<(IdentityId, Option<ClaimType>)>;
     }
 }
 
@@ -50,7 +56,9 @@ decl_error! {
         /// The sender is not a token owner.
         NotAnOwner,
         /// No change in the state.
-        NoChange
+        NoChange,
+        /// The maximum number of exemptions has been reached.
+        TooManyExemptions
     }
 }
 
@@ -64,8 +72,18 @@ decl_module! {
         // this is needed only if you are using events in your module
         fn deposit_event() = default;
 
+        /// Exempts `asset_holder_did` from the compliance rules of `ticker` which only check claims
+        /// of `claim_type`, or from all the rules of `ticker` if `claim_type` is `None`. Only the
+        /// owner of the asset can change its exemptions.
+        ///
+        /// # Arguments
+        /// * origin - Signing key of the asset owner.
+        /// * ticker - Symbol of the asset.
+        /// * claim_type - Claim type of the exempted rules, or `None` for all the rules.
+        /// * asset_holder_did - Identity whose exemption changes.
+        /// * exempted - New exemption status of `asset_holder_did`.
         #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
-        fn modify_exemption_list(origin, ticker: Ticker, _tm: u16, asset_holder_did: IdentityId, exempted: bool) -> DispatchResult {
+        pub fn modify_exemption_list(origin, ticker: Ticker, claim_type: Option<ClaimType>, asset_holder_did: IdentityId, exempted: bool) -> DispatchResult {
             let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
             let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
             let sender = Signatory::AccountKey(sender_key);
@@ -77,12 +95,20 @@ decl_module! {
             );
 
             ensure!(Self::is_owner(&ticker, did), Error::<T>::NotAnOwner);
-            let ticker_asset_holder_did = (ticker, _tm, asset_holder_did);
-            let is_exempted = Self::exemption_list(&ticker_asset_holder_did);
-            ensure!(is_exempted != exempted, Error::<T>::NoChange);
-
-            <ExemptionList>::insert(&ticker_asset_holder_did, exempted);
-            Self::deposit_event(Event::ExemptionListModified(did, ticker, _tm, asset_holder_did, exempted));
+            let exemption = (asset_holder_did, claim_type);
+            ensure!(Self::is_exempted_from(&ticker, &exemption) != exempted, Error::<T>::NoChange);
+
+            let mut exemptions = Self::exemption_list(&ticker);
+            if exempted {
+                ensure!(exemptions.len() < MAX_EXEMPTIONS, Error::<T>::TooManyExemptions);
+                exemptions.push(exemption);
+                <Exemptions>::insert(&ticker, &exemption, true);
+            } else {
+                exemptions.retain(|e| *e != exemption);
+                <Exemptions>::remove(&ticker, &exemption);
+            }
+            <ExemptionList>::insert(&ticker, exemptions);
+            Self::deposit_event(Event::ExemptionListModified(did, ticker, claim_type, asset_holder_did, exempted));
 
             Ok(())
         }
@@ -91,7 +117,9 @@ decl_module! {
 
 decl_event!(
     pub enum Event {
-        ExemptionListModified(IdentityId, Ticker, u16, IdentityId, bool),
+        /// Emitted when the exemption of an identity changes.
+        /// (caller DID, ticker, claim type, asset holder DID, exempted)
+        ExemptionListModified(IdentityId, Ticker, Option<ClaimType>, IdentityId, bool),
     }
 );
 
@@ -102,8 +130,8 @@ impl<T: Trait> Module<T> {
 }
 
 impl<T: Trait> ExemptionTrait for Module<T> {
-    fn is_exempted(ticker: &Ticker, tm: u16, did: IdentityId) -> bool {
-        Self::exemption_list((*ticker, tm, did))
+    fn is_exempted(ticker: &Ticker, claim_type: Option<ClaimType>, did: IdentityId) -> bool {
+        Self::is_exempted_from(ticker, (did, claim_type))
     }
 }
 
@@ -20,9 +20,12 @@
 pub mod bridge;
 pub mod cdd_check;
 pub mod contracts_wrapper;
+pub mod corporate_actions;
+pub mod dispatch_scope;
 pub mod dividend;
 pub mod exemption;
 pub mod impls;
+pub mod settlement;
 pub mod simple_token;
 pub mod sto_capped;
 pub mod voting;
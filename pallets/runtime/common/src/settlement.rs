@@ -0,0 +1,1027 @@
+// This file is part of the Polymesh distribution (https://github.com/PolymathNetwork/Polymesh).
+// Copyright (c) 2020 Polymath
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! # Settlement Module
+//!
+//! The Settlement module provides atomic delivery-versus-payment settlement of multi-leg
+//! instructions.
+//!
+//! ## Overview
+//!
+//! The Settlement module provides functions for:
+//!
+//! - Creating venues and managing their signers
+//! - Restricting the venues which can move an asset
+//! - Adding instructions to a venue, either directly or from a receipt signed off-chain by a venue
+//! signer
+//! - Affirming or rejecting instructions
+//! - Executing instructions atomically, either once every party has affirmed or at a given block
+//!
+//! ### Terminology
+//!
+//! - **Venue:** An identity which creates instructions, e.g. an exchange or an STO.
+//! - **Venue signer:** A signatory authorized by the venue creator to add instructions to the
+//! venue, either on-chain or by signing an instruction receipt off-chain.
+//! - **Venue filtering:** When an asset owner enables it, only the venues allowed by the owner can
+//! add instructions with legs of that asset.
+//! - **Instruction:** A set of legs which are settled together or not at all.
+//! - **Leg:** A movement of an amount of an asset, a simple token or POLYX from one identity to
+//! another one. Assets are moved between default portfolios and POLYX between identity balances.
+//! - **Party:** Any identity which sends or receives a leg of an instruction. Every party has to
+//! affirm an instruction before it can be executed.
+//!
+//! Every leg is validated before any of them is executed. Asset legs are checked against the
+//! compliance manager and the smart extensions of the asset. If any leg is invalid, the instruction
+//! fails and the ERC1400 status code of each invalid leg is recorded.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `create_venue` - Creates a new venue.
+//! - `add_venue_signers` - Authorizes signatories to add instructions to a venue.
+//! - `remove_venue_signers` - Revokes the authorization of venue signers.
+//! - `set_venue_filtering` - Enables or disables venue filtering for an asset.
+//! - `allow_venues` - Allows venues to add instructions with legs of an asset.
+//! - `disallow_venues` - Disallows venues to add instructions with legs of an asset.
+//! - `add_instruction` - Adds a new instruction to a venue.
+//! - `add_instruction_with_receipt` - Adds a new instruction from a receipt signed by a venue
+//! signer.
+//! - `affirm_instruction` - Affirms an instruction as one of its parties.
+//! - `reject_instruction` - Rejects an instruction as one of its parties.
+//!
+//! ### Public Functions
+//!
+//! - `account_id` - Returns the account used by the module to call smart extensions.
+
+use crate::simple_token;
+
+use pallet_asset as asset;
+use pallet_balances as balances;
+use pallet_identity as identity;
+use polymesh_common_utilities::{
+    balances::Trait as BalancesTrait,
+    constants::{
+        ERC1400_FUNDS_LOCKED, ERC1400_INSUFFICIENT_BALANCE, ERC1400_TRANSFER_FAILURE,
+        ERC1400_TRANSFER_SUCCESS, SETTLEMENT_MODULE_ID,
+    },
+    identity::TargetIdAuthorization,
+    CommonTrait, Context,
+};
+use polymesh_primitives::{
+    traits::IdentityCurrency, AccountKey, IdentityId, PortfolioId, Signatory, Ticker,
+};
+
+use codec::{Decode, Encode};
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage,
+    dispatch::DispatchResult,
+    ensure,
+    weights::{DispatchClass, FunctionOf, SimpleDispatchInfo, Weight},
+};
+use frame_system::{self as system, ensure_signed};
+use sp_core::{
+    sr25519::{Public, Signature},
+    H512,
+};
+use sp_runtime::{
+    traits::{AccountIdConversion, Saturating, Verify, Zero},
+    AnySignature, DispatchError,
+};
+use sp_std::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    convert::TryFrom,
+    prelude::*,
+};
+
+/// Maximum number of legs of an instruction.
+pub const MAX_LEGS: usize = 10;
+
+/// Maximum number of instructions which can be scheduled for the same block.
+pub const MAX_SCHEDULED_INSTRUCTIONS: usize = 50;
+
+/// Weight of validating and settling a single leg, including the compliance checks and the smart
+/// extensions of its asset.
+const LEG_EXECUTION_WEIGHT: Weight = 250_000;
+
+/// Returns the weight of adding an instruction of `legs` legs. Instructions settled on a block are
+/// executed by `on_initialize`, so their execution is paid upfront when they are added.
+fn add_instruction_weight<BlockNumber>(
+    settlement_type: &SettlementType<BlockNumber>,
+    legs: usize,
+) -> Weight {
+    let legs = u32::try_from(legs).unwrap_or(u32::max_value());
+    let execution = match settlement_type {
+        SettlementType::SettleOnAffirmation => 0,
+        SettlementType::SettleOnBlock(_) => LEG_EXECUTION_WEIGHT.saturating_mul(legs),
+    };
+    100_000u32
+        .saturating_add(50_000u32.saturating_mul(legs))
+        .saturating_add(execution)
+}
+
+/// The module's configuration trait.
+pub trait Trait:
+    asset::Trait + BalancesTrait + simple_token::Trait + frame_system::Trait + pallet_timestamp::Trait
+{
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+}
+
+/// Type of a venue. It is only used as a hint for the UI.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum VenueType {
+    /// Default type.
+    Other,
+    /// Used for distributions of assets.
+    Distribution,
+    /// Used for security token offerings.
+    Sto,
+    /// Used for exchanges.
+    Exchange,
+}
+
+impl Default for VenueType {
+    fn default() -> Self {
+        Self::Other
+    }
+}
+
+/// Free-form details of a venue.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+pub struct VenueDetails(pub Vec<u8>);
+
+impl<T: AsRef<[u8]>> From<T> for VenueDetails {
+    fn from(s: T) -> Self {
+        VenueDetails(s.as_ref().to_vec())
+    }
+}
+
+/// Details about a venue.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Venue {
+    /// Identity of the venue creator.
+    pub creator: IdentityId,
+    /// Free-form details of the venue.
+    pub details: VenueDetails,
+    /// Type of the venue.
+    pub venue_type: VenueType,
+}
+
+/// The asset moved by a leg.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum LegAsset {
+    /// An asset created by the asset module.
+    Asset(Ticker),
+    /// A token created by the simple token module.
+    SimpleToken(Ticker),
+    /// The native currency.
+    Polyx,
+}
+
+/// A single movement of value inside an instruction.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct Leg<Balance> {
+    /// Identity of the sender.
+    pub from: IdentityId,
+    /// Identity of the receiver.
+    pub to: IdentityId,
+    /// The moved asset.
+    pub asset: LegAsset,
+    /// The moved amount.
+    pub amount: Balance,
+}
+
+/// Defines when an instruction is executed.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SettlementType<BlockNumber> {
+    /// Executed as soon as every party has affirmed it.
+    SettleOnAffirmation,
+    /// Executed at the given block, provided that every party has affirmed it by then.
+    SettleOnBlock(BlockNumber),
+}
+
+impl<BlockNumber> Default for SettlementType<BlockNumber> {
+    fn default() -> Self {
+        Self::SettleOnAffirmation
+    }
+}
+
+/// Status of an instruction.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InstructionStatus {
+    /// The instruction does not exist.
+    Unknown,
+    /// The instruction is waiting for affirmations or for its settlement block.
+    Pending,
+    /// Every leg of the instruction has been settled.
+    Executed,
+    /// At least one leg was invalid at execution time, or some party did not affirm the
+    /// instruction before its settlement block. No leg has been settled.
+    Failed,
+    /// A party rejected the instruction.
+    Rejected,
+}
+
+impl Default for InstructionStatus {
+    fn default() -> Self {
+        Self::Unknown
+    }
+}
+
+/// Details about an instruction.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Instruction<Moment, BlockNumber> {
+    /// Unique instruction id.
+    pub instruction_id: u64,
+    /// Id of the venue which created the instruction.
+    pub venue_id: u64,
+    /// Status of the instruction.
+    pub status: InstructionStatus,
+    /// When the instruction is executed.
+    pub settlement_type: SettlementType<BlockNumber>,
+    /// Timestamp of the instruction creation.
+    pub created_at: Option<Moment>,
+}
+
+/// An instruction signed off-chain by a venue signer, which anyone can submit on-chain.
+///
+/// `authorization` targets the venue creator. Its nonce is chosen by the signer and each nonce can
+/// be used by a signer only once. See `Settlement::receipts_used`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct InstructionReceipt<Moment, BlockNumber, Balance> {
+    /// Authorization for the venue creator, whose nonce prevents the receipt from being replayed.
+    pub authorization: TargetIdAuthorization<Moment>,
+    /// Id of the venue.
+    pub venue_id: u64,
+    /// When the instruction is executed.
+    pub settlement_type: SettlementType<BlockNumber>,
+    /// Legs of the instruction.
+    pub legs: Vec<Leg<Balance>>,
+}
+
+/// Status of the affirmation of an instruction by a party.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AffirmationStatus {
+    /// The identity is not a party of the instruction.
+    Unknown,
+    /// The party has not responded yet.
+    Pending,
+    /// The party affirmed the instruction.
+    Affirmed,
+    /// The party rejected the instruction.
+    Rejected,
+}
+
+impl Default for AffirmationStatus {
+    fn default() -> Self {
+        Self::Unknown
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Settlement {
+        /// Details of a venue; venue id -> venue.
+        VenueInfo get(fn venue_info): map hasher(twox_64_concat) u64 => Option<Venue>;
+        /// Venues created by an identity; DID -> venue ids.
+        UserVenues get(fn user_venues): map hasher(blake2_128_concat) IdentityId => Vec<u64>;
+        /// Number of venues created so far. It is also the id of the next venue.
+        VenueCounter get(fn venue_counter): u64;
+        /// Signers authorized by the creator of a venue; (venue id, signer) -> authorized.
+        VenueSigners get(fn venue_signers): double_map hasher(twox_64_concat) u64, hasher(blake2_128_concat) Signatory => bool;
+        /// Whether only allowed venues can move an asset; ticker -> enabled.
+        VenueFiltering get(fn venue_filtering): map hasher(blake2_128_concat) Ticker => bool;
+        /// Venues allowed by the asset owner; (ticker, venue id) -> allowed.
+        VenueAllowList get(fn venue_allow_list): double_map hasher(blake2_128_concat) Ticker, hasher(twox_64_concat) u64 => bool;
+        /// Details of an instruction; instruction id -> instruction.
+        InstructionDetails get(fn instruction_details): map hasher(twox_64_concat) u64 => Instruction<T::Moment, T::BlockNumber>;
+        /// Legs of an instruction; instruction id -> legs. The id of a leg is its index.
+        InstructionLegs get(fn instruction_legs): map hasher(twox_64_concat) u64 => Vec<Leg<T::Balance>>;
+        /// Affirmation status of a party; (instruction id, DID) -> status.
+        AffirmsReceived get(fn affirms_received): double_map hasher(twox_64_concat) u64, hasher(blake2_128_concat) IdentityId => AffirmationStatus;
+        /// Number of parties which have not affirmed an instruction yet; instruction id -> count.
+        InstructionAffirmsPending get(fn instruction_affirms_pending): map hasher(twox_64_concat) u64 => u64;
+        /// Signing key which affirmed an instruction for a party. What the party sends is charged
+        /// against the spending limit of the key when the instruction executes;
+        /// (instruction id, DID) -> key.
+        AffirmingKeys get(fn affirming_key): double_map hasher(twox_64_concat) u64, hasher(blake2_128_concat) IdentityId => Option<AccountKey>;
+        /// ERC1400 status codes of the invalid legs of a failed instruction;
+        /// instruction id -> [(leg id, status code)].
+        InstructionFailureReasons get(fn instruction_failure_reasons): map hasher(twox_64_concat) u64 => Vec<(u64, u8)>;
+        /// Number of instructions created so far. It is also the id of the next instruction.
+        InstructionCounter get(fn instruction_counter): u64;
+        /// Instructions to be executed at a block; block number -> instruction ids.
+        ScheduledInstructions get(fn scheduled_instructions): map hasher(twox_64_concat) T::BlockNumber => Vec<u64>;
+        /// Receipt nonces already used by a signer; (signer, nonce) -> used.
+        ReceiptsUsed get(fn receipts_used): double_map hasher(blake2_128_concat) Signatory, hasher(twox_64_concat) u64 => bool;
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        Balance = <T as CommonTrait>::Balance,
+        BlockNumber = <T as frame_system::Trait>::BlockNumber,
+    {
+        /// caller DID, venue id, details, type
+        VenueCreated(IdentityId, u64, VenueDetails, VenueType),
+        /// caller DID, venue id, signers
+        VenueSignersAdded(IdentityId, u64, Vec<Signatory>),
+        /// caller DID, venue id, signers
+        VenueSignersRemoved(IdentityId, u64, Vec<Signatory>),
+        /// caller DID, ticker, enabled
+        VenueFilteringChanged(IdentityId, Ticker, bool),
+        /// caller DID, ticker, venue ids
+        VenuesAllowed(IdentityId, Ticker, Vec<u64>),
+        /// caller DID, ticker, venue ids
+        VenuesDisallowed(IdentityId, Ticker, Vec<u64>),
+        /// caller DID, venue id, instruction id, settlement type, legs
+        InstructionCreated(
+            IdentityId,
+            u64,
+            u64,
+            SettlementType<BlockNumber>,
+            Vec<Leg<Balance>>,
+        ),
+        /// caller DID, instruction id
+        InstructionAffirmed(IdentityId, u64),
+        /// caller DID, instruction id
+        InstructionRejected(IdentityId, u64),
+        /// caller DID, instruction id
+        InstructionExecuted(IdentityId, u64),
+        /// caller DID, instruction id, [(leg id, ERC1400 status code)]
+        InstructionFailed(IdentityId, u64, Vec<(u64, u8)>),
+        /// caller DID, instruction id
+        InstructionUnaffirmed(IdentityId, u64),
+    }
+);
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// The sender must be a signing key for the DID.
+        SenderMustBeSigningKeyForDid,
+        /// The venue does not exist.
+        InvalidVenue,
+        /// The caller is not allowed to perform this operation.
+        Unauthorized,
+        /// An instruction must have at least one leg.
+        NoLegs,
+        /// An instruction cannot have more than `MAX_LEGS` legs.
+        TooManyLegs,
+        /// The settlement block already has `MAX_SCHEDULED_INSTRUCTIONS` instructions.
+        TooManyScheduledInstructions,
+        /// The sender and the receiver of a leg are the same identity.
+        SameSenderReceiver,
+        /// The amount of a leg is zero.
+        ZeroAmount,
+        /// The settlement block is not in the future.
+        SettleOnPastBlock,
+        /// The instruction is not pending.
+        InstructionNotPending,
+        /// The caller is not a party of the instruction.
+        NotAParty,
+        /// The caller has already responded to the instruction.
+        AlreadyResponded,
+        /// An overflow in the venue or instruction counter.
+        CounterOverflow,
+        /// The venue is not allowed to move the asset of a leg.
+        UnauthorizedVenue,
+        /// The signer of a receipt is not a venue signer.
+        UnauthorizedSigner,
+        /// The authorization of a receipt does not target the venue creator.
+        InvalidReceiptAuthorization,
+        /// The signer has already used the nonce of the receipt.
+        ReceiptAlreadyUsed,
+        /// The receipt has expired.
+        ReceiptExpired,
+        /// The signer revoked the authorization of the receipt.
+        ReceiptRevoked,
+        /// The signature of the receipt is invalid.
+        InvalidSignature,
+    }
+}
+
+type Identity<T> = identity::Module<T>;
+
+decl_module! {
+    /// The module declaration.
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
+        fn deposit_event() = default;
+
+        /// Executes the instructions scheduled for this block.
+        fn on_initialize(block_number: T::BlockNumber) {
+            Self::execute_scheduled_instructions(block_number);
+        }
+
+        /// Creates a new venue owned by the caller.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the venue creator.
+        /// * `details` Free-form details of the venue.
+        /// * `venue_type` Type of the venue.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn create_venue(origin, details: VenueDetails, venue_type: VenueType) -> DispatchResult {
+            let (did, _) = Self::ensure_signer_did(origin)?;
+
+            let venue_id = Self::venue_counter();
+            let next_venue_id = venue_id.checked_add(1).ok_or(Error::<T>::CounterOverflow)?;
+
+            <VenueCounter>::put(next_venue_id);
+            <VenueInfo>::insert(venue_id, Venue {
+                creator: did,
+                details: details.clone(),
+                venue_type,
+            });
+            <UserVenues>::mutate(did, |venues| venues.push(venue_id));
+
+            Self::deposit_event(RawEvent::VenueCreated(did, venue_id, details, venue_type));
+            Ok(())
+        }
+
+        /// Authorizes signatories to add instructions to a venue.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the venue creator.
+        /// * `venue_id` Id of the venue.
+        /// * `signers` Signatories to authorize.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn add_venue_signers(origin, venue_id: u64, signers: Vec<Signatory>) -> DispatchResult {
+            let (did, _) = Self::ensure_signer_did(origin)?;
+            Self::ensure_venue_creator(venue_id, did)?;
+
+            for signer in &signers {
+                <VenueSigners>::insert(venue_id, signer, true);
+            }
+
+            Self::deposit_event(RawEvent::VenueSignersAdded(did, venue_id, signers));
+            Ok(())
+        }
+
+        /// Revokes the authorization of venue signers.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the venue creator.
+        /// * `venue_id` Id of the venue.
+        /// * `signers` Signatories to remove.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn remove_venue_signers(origin, venue_id: u64, signers: Vec<Signatory>) -> DispatchResult {
+            let (did, _) = Self::ensure_signer_did(origin)?;
+            Self::ensure_venue_creator(venue_id, did)?;
+
+            for signer in &signers {
+                <VenueSigners>::remove(venue_id, signer);
+            }
+
+            Self::deposit_event(RawEvent::VenueSignersRemoved(did, venue_id, signers));
+            Ok(())
+        }
+
+        /// Enables or disables venue filtering for an asset. While it is enabled, only the venues
+        /// allowed by the asset owner can add instructions with legs of the asset.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the token owner.
+        /// * `ticker` Ticker of the token.
+        /// * `enabled` Whether venue filtering is enabled.
+        #[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+        pub fn set_venue_filtering(origin, ticker: Ticker, enabled: bool) -> DispatchResult {
+            let (did, _) = Self::ensure_signer_did(origin)?;
+            ensure!(<asset::Module<T>>::_is_owner(&ticker, did), Error::<T>::Unauthorized);
+
+            <VenueFiltering>::insert(ticker, enabled);
+
+            Self::deposit_event(RawEvent::VenueFilteringChanged(did, ticker, enabled));
+            Ok(())
+        }
+
+        /// Allows venues to add instructions with legs of an asset.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the token owner.
+        /// * `ticker` Ticker of the token.
+        /// * `venues` Ids of the venues.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn allow_venues(origin, ticker: Ticker, venues: Vec<u64>) -> DispatchResult {
+            let (did, _) = Self::ensure_signer_did(origin)?;
+            ensure!(<asset::Module<T>>::_is_owner(&ticker, did), Error::<T>::Unauthorized);
+
+            for venue_id in &venues {
+                <VenueAllowList>::insert(ticker, venue_id, true);
+            }
+
+            Self::deposit_event(RawEvent::VenuesAllowed(did, ticker, venues));
+            Ok(())
+        }
+
+        /// Disallows venues to add instructions with legs of an asset. Instructions which have
+        /// already been added are not affected.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the token owner.
+        /// * `ticker` Ticker of the token.
+        /// * `venues` Ids of the venues.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn disallow_venues(origin, ticker: Ticker, venues: Vec<u64>) -> DispatchResult {
+            let (did, _) = Self::ensure_signer_did(origin)?;
+            ensure!(<asset::Module<T>>::_is_owner(&ticker, did), Error::<T>::Unauthorized);
+
+            for venue_id in &venues {
+                <VenueAllowList>::remove(ticker, venue_id);
+            }
+
+            Self::deposit_event(RawEvent::VenuesDisallowed(did, ticker, venues));
+            Ok(())
+        }
+
+        /// Adds a new instruction to a venue. Every sender and receiver of a leg has to affirm the
+        /// instruction before it is executed.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of the venue creator or of a venue signer.
+        /// * `venue_id` Id of the venue.
+        /// * `settlement_type` When the instruction is executed.
+        /// * `legs` Legs of the instruction, at most `MAX_LEGS`.
+        #[weight = FunctionOf(
+            |(_, settlement_type, legs): (&u64, &SettlementType<T::BlockNumber>, &Vec<Leg<T::Balance>>)| {
+                add_instruction_weight(settlement_type, legs.len())
+            },
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn add_instruction(
+            origin,
+            venue_id: u64,
+            settlement_type: SettlementType<T::BlockNumber>,
+            legs: Vec<Leg<T::Balance>>
+        ) -> DispatchResult {
+            let (did, sender_key) = Self::ensure_signer_did(origin)?;
+
+            ensure!(
+                Self::is_venue_signer(venue_id, &Signatory::Identity(did))
+                    || Self::is_venue_signer(venue_id, &Signatory::AccountKey(sender_key)),
+                Error::<T>::Unauthorized
+            );
+
+            Self::base_add_instruction(did, venue_id, settlement_type, legs)
+        }
+
+        /// Adds a new instruction from a receipt signed off-chain by a venue signer.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of any identity, usually a party of the instruction.
+        /// * `receipt` The signed instruction.
+        /// * `signer` Venue signer who signed the receipt. If it is an identity, the receipt has
+        /// to be signed by its master key.
+        /// * `signature` Signature of the encoded receipt.
+        ///
+        /// # Errors
+        /// * `ReceiptExpired` if the receipt authorization has expired.
+        /// * `InvalidReceiptAuthorization` if the authorization does not target the venue creator.
+        /// * `ReceiptAlreadyUsed` if the signer has already used the nonce of the receipt.
+        /// * `ReceiptRevoked` if the signer revoked the authorization through
+        /// `Identity::revoke_offchain_authorization`.
+        #[weight = FunctionOf(
+            |(receipt, _, _): (&InstructionReceipt<T::Moment, T::BlockNumber, T::Balance>, &Signatory, &H512)| {
+                add_instruction_weight(&receipt.settlement_type, receipt.legs.len())
+                    .saturating_add(100_000)
+            },
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn add_instruction_with_receipt(
+            origin,
+            receipt: InstructionReceipt<T::Moment, T::BlockNumber, T::Balance>,
+            signer: Signatory,
+            signature: H512
+        ) -> DispatchResult {
+            let (did, _) = Self::ensure_signer_did(origin)?;
+
+            let venue = Self::venue_info(receipt.venue_id).ok_or(Error::<T>::InvalidVenue)?;
+            ensure!(Self::is_venue_signer(receipt.venue_id, &signer), Error::<T>::UnauthorizedSigner);
+
+            let authorization = &receipt.authorization;
+            ensure!(
+                <pallet_timestamp::Module<T>>::get() < authorization.expires_at,
+                Error::<T>::ReceiptExpired
+            );
+            ensure!(authorization.target_id == venue.creator, Error::<T>::InvalidReceiptAuthorization);
+            ensure!(
+                !Self::receipts_used(&signer, authorization.nonce),
+                Error::<T>::ReceiptAlreadyUsed
+            );
+            ensure!(
+                !<identity::Module<T>>::is_offchain_authorization_revoked((signer, authorization.clone())),
+                Error::<T>::ReceiptRevoked
+            );
+            Self::ensure_valid_signature(&signer, &receipt.encode(), signature)?;
+
+            let nonce = receipt.authorization.nonce;
+            Self::base_add_instruction(did, receipt.venue_id, receipt.settlement_type, receipt.legs)?;
+            <ReceiptsUsed>::insert(&signer, nonce, true);
+            Ok(())
+        }
+
+        /// Affirms a pending instruction. If the instruction settles on affirmation and this is
+        /// the last missing affirmation, the instruction is executed.
+        /// The POLYX and the assets which the identity of the caller sends in the instruction are
+        /// checked against the spending limit of its signing key, and charged to it when the
+        /// instruction executes.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of a party of the instruction.
+        /// * `instruction_id` Id of the instruction.
+        ///
+        /// # Weight
+        /// The last affirmation may execute the instruction, so it is charged for `MAX_LEGS` legs.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000 + LEG_EXECUTION_WEIGHT * MAX_LEGS as u32)]
+        pub fn affirm_instruction(origin, instruction_id: u64) -> DispatchResult {
+            let (did, sender_key) = Self::ensure_signer_did(origin)?;
+
+            let details = Self::instruction_details(instruction_id);
+            ensure!(details.status == InstructionStatus::Pending, Error::<T>::InstructionNotPending);
+            Self::ensure_pending_party(instruction_id, did)?;
+            // The affirming key authorizes everything its identity sends in the instruction.
+            let (polyx, assets) = Self::sent_by(did, &Self::instruction_legs(instruction_id));
+            <identity::Module<T>>::ensure_spending_allowed(&sender_key, polyx, None)?;
+            for (ticker, amount) in &assets {
+                <identity::Module<T>>::ensure_spending_allowed(&sender_key, Zero::zero(), Some((*ticker, *amount)))?;
+            }
+
+            <AffirmingKeys>::insert(instruction_id, did, sender_key);
+            <AffirmsReceived>::insert(instruction_id, did, AffirmationStatus::Affirmed);
+            let affirms_pending = Self::instruction_affirms_pending(instruction_id).saturating_sub(1);
+            <InstructionAffirmsPending>::insert(instruction_id, affirms_pending);
+            Self::deposit_event(RawEvent::InstructionAffirmed(did, instruction_id));
+
+            if affirms_pending == 0 && details.settlement_type == SettlementType::SettleOnAffirmation {
+                Self::execute_instruction(did, instruction_id)?;
+            }
+            Ok(())
+        }
+
+        /// Rejects a pending instruction. A rejected instruction is never executed.
+        ///
+        /// # Arguments
+        /// * `origin` Signing key of a party of the instruction.
+        /// * `instruction_id` Id of the instruction.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn reject_instruction(origin, instruction_id: u64) -> DispatchResult {
+            let (did, _) = Self::ensure_signer_did(origin)?;
+
+            ensure!(
+                Self::instruction_details(instruction_id).status == InstructionStatus::Pending,
+                Error::<T>::InstructionNotPending
+            );
+            ensure!(
+                Self::affirms_received(instruction_id, did) != AffirmationStatus::Unknown,
+                Error::<T>::NotAParty
+            );
+
+            <AffirmsReceived>::insert(instruction_id, did, AffirmationStatus::Rejected);
+            <InstructionDetails<T>>::mutate(instruction_id, |details| {
+                details.status = InstructionStatus::Rejected
+            });
+            <AffirmingKeys>::remove_prefix(instruction_id);
+
+            Self::deposit_event(RawEvent::InstructionRejected(did, instruction_id));
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Returns the account used by the module to call the smart extensions of an asset.
+    pub fn account_id() -> T::AccountId {
+        SETTLEMENT_MODULE_ID.into_account()
+    }
+
+    /// Returns the DID of the caller and its signing key.
+    fn ensure_signer_did(origin: T::Origin) -> Result<(IdentityId, AccountKey), DispatchError> {
+        let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+        let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+
+        // Check that sender is allowed to act on behalf of `did`
+        ensure!(
+            <identity::Module<T>>::is_signer_authorized(did, &Signatory::AccountKey(sender_key)),
+            Error::<T>::SenderMustBeSigningKeyForDid
+        );
+        Ok((did, sender_key))
+    }
+
+    fn ensure_venue_creator(venue_id: u64, did: IdentityId) -> DispatchResult {
+        let venue = Self::venue_info(venue_id).ok_or(Error::<T>::InvalidVenue)?;
+        ensure!(venue.creator == did, Error::<T>::Unauthorized);
+        Ok(())
+    }
+
+    /// Returns `true` if `signer` can add instructions to the venue. The identity of the venue
+    /// creator is always a venue signer.
+    pub fn is_venue_signer(venue_id: u64, signer: &Signatory) -> bool {
+        match Self::venue_info(venue_id) {
+            Some(venue) => {
+                *signer == Signatory::Identity(venue.creator)
+                    || Self::venue_signers(venue_id, signer)
+            }
+            None => false,
+        }
+    }
+
+    /// Verifies that `signature` of `data` was generated by `signer`, or by the master key of
+    /// `signer` if it is an identity.
+    fn ensure_valid_signature(signer: &Signatory, data: &[u8], signature: H512) -> DispatchResult {
+        let public = match signer {
+            Signatory::AccountKey(key) => Public::try_from(key.as_slice()).ok(),
+            Signatory::Identity(id) => {
+                let master_key = <identity::Module<T>>::did_records(id).master_key;
+                Public::try_from(master_key.as_slice()).ok()
+            }
+        }
+        .ok_or(Error::<T>::InvalidSignature)?;
+        ensure!(
+            AnySignature::from(Signature::from_h512(signature)).verify(data, &public),
+            Error::<T>::InvalidSignature
+        );
+        Ok(())
+    }
+
+    /// Validates and stores a new instruction of `venue_id`. The caller has to check that
+    /// the instruction was submitted by a venue signer.
+    fn base_add_instruction(
+        did: IdentityId,
+        venue_id: u64,
+        settlement_type: SettlementType<T::BlockNumber>,
+        legs: Vec<Leg<T::Balance>>,
+    ) -> DispatchResult {
+        ensure!(!legs.is_empty(), Error::<T>::NoLegs);
+        ensure!(legs.len() <= MAX_LEGS, Error::<T>::TooManyLegs);
+        for leg in &legs {
+            ensure!(leg.from != leg.to, Error::<T>::SameSenderReceiver);
+            ensure!(!leg.amount.is_zero(), Error::<T>::ZeroAmount);
+            if let LegAsset::Asset(ticker) = leg.asset {
+                ensure!(
+                    !Self::venue_filtering(ticker) || Self::venue_allow_list(ticker, venue_id),
+                    Error::<T>::UnauthorizedVenue
+                );
+            }
+        }
+        if let SettlementType::SettleOnBlock(block_number) = settlement_type {
+            ensure!(
+                block_number > <system::Module<T>>::block_number(),
+                Error::<T>::SettleOnPastBlock
+            );
+            ensure!(
+                Self::scheduled_instructions(block_number).len() < MAX_SCHEDULED_INSTRUCTIONS,
+                Error::<T>::TooManyScheduledInstructions
+            );
+        }
+
+        let instruction_id = Self::instruction_counter();
+        let next_instruction_id = instruction_id
+            .checked_add(1)
+            .ok_or(Error::<T>::CounterOverflow)?;
+
+        let parties = legs
+            .iter()
+            .flat_map(|leg| vec![leg.from, leg.to])
+            .collect::<BTreeSet<_>>();
+        for party in &parties {
+            <AffirmsReceived>::insert(instruction_id, party, AffirmationStatus::Pending);
+        }
+        <InstructionAffirmsPending>::insert(instruction_id, parties.len() as u64);
+        <InstructionCounter>::put(next_instruction_id);
+        <InstructionDetails<T>>::insert(
+            instruction_id,
+            Instruction {
+                instruction_id,
+                venue_id,
+                status: InstructionStatus::Pending,
+                settlement_type,
+                created_at: Some(<pallet_timestamp::Module<T>>::get()),
+            },
+        );
+        <InstructionLegs<T>>::insert(instruction_id, legs.clone());
+        if let SettlementType::SettleOnBlock(block_number) = settlement_type {
+            <ScheduledInstructions<T>>::mutate(block_number, |ids| ids.push(instruction_id));
+        }
+
+        Self::deposit_event(RawEvent::InstructionCreated(
+            did,
+            venue_id,
+            instruction_id,
+            settlement_type,
+            legs,
+        ));
+        Ok(())
+    }
+
+    /// Returns the POLYX and the amount of each asset which `did` sends in `legs`.
+    fn sent_by(
+        did: IdentityId,
+        legs: &[Leg<T::Balance>],
+    ) -> (T::Balance, BTreeMap<Ticker, T::Balance>) {
+        let mut polyx = T::Balance::zero();
+        let mut assets = BTreeMap::new();
+        for leg in legs.iter().filter(|leg| leg.from == did) {
+            match leg.asset {
+                LegAsset::Asset(ticker) => {
+                    let amount = assets.entry(ticker).or_insert_with(T::Balance::zero);
+                    *amount = amount.saturating_add(leg.amount);
+                }
+                LegAsset::Polyx => polyx = polyx.saturating_add(leg.amount),
+                LegAsset::SimpleToken(_) => {}
+            }
+        }
+        (polyx, assets)
+    }
+
+    /// Charges what each sender of an executed instruction sends against the spending limit of
+    /// the key which affirmed the instruction for it.
+    fn record_affirmed_spending(instruction_id: u64, legs: &[Leg<T::Balance>]) {
+        let senders = legs.iter().map(|leg| leg.from).collect::<BTreeSet<_>>();
+        for did in senders {
+            if let Some(key) = Self::affirming_key(instruction_id, did) {
+                let (polyx, assets) = Self::sent_by(did, legs);
+                <identity::Module<T>>::record_spending(&key, polyx, None);
+                for (ticker, amount) in assets {
+                    <identity::Module<T>>::record_spending(
+                        &key,
+                        Zero::zero(),
+                        Some((ticker, amount)),
+                    );
+                }
+            }
+        }
+        <AffirmingKeys>::remove_prefix(instruction_id);
+    }
+
+    fn ensure_pending_party(instruction_id: u64, did: IdentityId) -> DispatchResult {
+        match Self::affirms_received(instruction_id, did) {
+            AffirmationStatus::Pending => Ok(()),
+            AffirmationStatus::Unknown => Err(Error::<T>::NotAParty.into()),
+            _ => Err(Error::<T>::AlreadyResponded.into()),
+        }
+    }
+
+    /// Executes, or fails, every pending instruction scheduled for `block_number`.
+    fn execute_scheduled_instructions(block_number: T::BlockNumber) {
+        for instruction_id in <ScheduledInstructions<T>>::take(block_number) {
+            let details = Self::instruction_details(instruction_id);
+            if details.status != InstructionStatus::Pending {
+                continue;
+            }
+            let venue_creator = Self::venue_info(details.venue_id)
+                .map(|venue| venue.creator)
+                .unwrap_or_default();
+            if Self::instruction_affirms_pending(instruction_id) == 0 {
+                if let Err(e) = Self::execute_instruction(venue_creator, instruction_id) {
+                    sp_runtime::print(e);
+                }
+            } else {
+                <InstructionDetails<T>>::mutate(instruction_id, |details| {
+                    details.status = InstructionStatus::Failed
+                });
+                <AffirmingKeys>::remove_prefix(instruction_id);
+                Self::deposit_event(RawEvent::InstructionUnaffirmed(
+                    venue_creator,
+                    instruction_id,
+                ));
+            }
+        }
+    }
+
+    /// Validates every leg of an instruction and settles all of them if they are valid.
+    /// Otherwise, the instruction fails and no leg is settled.
+    fn execute_instruction(caller_did: IdentityId, instruction_id: u64) -> DispatchResult {
+        let legs = Self::instruction_legs(instruction_id);
+        let failures = Self::invalid_legs(&legs);
+        if !failures.is_empty() {
+            <InstructionDetails<T>>::mutate(instruction_id, |details| {
+                details.status = InstructionStatus::Failed
+            });
+            <InstructionFailureReasons>::insert(instruction_id, failures.clone());
+            <AffirmingKeys>::remove_prefix(instruction_id);
+            Self::deposit_event(RawEvent::InstructionFailed(
+                caller_did,
+                instruction_id,
+                failures,
+            ));
+            return Ok(());
+        }
+
+        // Every leg has been validated against the cumulative spending of its sender, so no leg
+        // can fail from here on and the instruction is settled as a whole.
+        for leg in &legs {
+            Self::settle_leg(caller_did, leg)?;
+        }
+        Self::record_affirmed_spending(instruction_id, &legs);
+        <InstructionDetails<T>>::mutate(instruction_id, |details| {
+            details.status = InstructionStatus::Executed
+        });
+        Self::deposit_event(RawEvent::InstructionExecuted(caller_did, instruction_id));
+        Ok(())
+    }
+
+    /// Returns the ids and the ERC1400 status codes of the legs which cannot be settled.
+    /// The balance of a sender is checked against the sum of everything it sends in the
+    /// instruction, not only against the amount of a single leg.
+    fn invalid_legs(legs: &[Leg<T::Balance>]) -> Vec<(u64, u8)> {
+        let extension_caller = Self::account_id();
+        let mut spent = BTreeMap::<(IdentityId, LegAsset), T::Balance>::new();
+        let mut failures = Vec::new();
+        for (leg_id, leg) in legs.iter().enumerate() {
+            let total_spent = spent
+                .entry((leg.from, leg.asset))
+                .or_insert_with(Zero::zero);
+            *total_spent = total_spent.saturating_add(leg.amount);
+            let status = Self::leg_status(leg, *total_spent, extension_caller.clone());
+            if status != ERC1400_TRANSFER_SUCCESS {
+                failures.push((leg_id as u64, status));
+            }
+        }
+        failures
+    }
+
+    /// Returns the ERC1400 status code of a single leg, given that its sender sends `total_spent`
+    /// of the same asset up to and including this leg.
+    fn leg_status(
+        leg: &Leg<T::Balance>,
+        total_spent: T::Balance,
+        extension_caller: T::AccountId,
+    ) -> u8 {
+        match leg.asset {
+            LegAsset::Asset(ticker) => {
+                let from_portfolio = PortfolioId::default_portfolio(leg.from);
+                if <asset::Module<T>>::custodian_of(&from_portfolio) != leg.from {
+                    return ERC1400_FUNDS_LOCKED;
+                }
+                if <asset::Module<T>>::portfolio_balance(&from_portfolio, &ticker) < total_spent {
+                    return ERC1400_INSUFFICIENT_BALANCE;
+                }
+                <asset::Module<T>>::unsafe_can_transfer(
+                    extension_caller,
+                    ticker,
+                    Some(leg.from),
+                    Some(leg.to),
+                    leg.amount,
+                )
+                .unwrap_or(ERC1400_TRANSFER_FAILURE)
+            }
+            LegAsset::SimpleToken(ticker) => {
+                if <simple_token::Module<T>>::balance_of((ticker, leg.from)) < total_spent {
+                    ERC1400_INSUFFICIENT_BALANCE
+                } else {
+                    ERC1400_TRANSFER_SUCCESS
+                }
+            }
+            LegAsset::Polyx => {
+                if <balances::Module<T>>::identity_balance(leg.from) < total_spent {
+                    ERC1400_INSUFFICIENT_BALANCE
+                } else {
+                    ERC1400_TRANSFER_SUCCESS
+                }
+            }
+        }
+    }
+
+    fn settle_leg(caller_did: IdentityId, leg: &Leg<T::Balance>) -> DispatchResult {
+        match leg.asset {
+            LegAsset::Asset(ticker) => {
+                <asset::Module<T>>::_transfer(caller_did, &ticker, leg.from, leg.to, leg.amount)
+            }
+            LegAsset::SimpleToken(ticker) => {
+                <simple_token::BalanceOf<T>>::mutate((ticker, leg.from), |balance| {
+                    *balance = balance.saturating_sub(leg.amount)
+                });
+                <simple_token::BalanceOf<T>>::mutate((ticker, leg.to), |balance| {
+                    *balance = balance.saturating_add(leg.amount)
+                });
+                Ok(())
+            }
+            LegAsset::Polyx => {
+                // The negative and the positive imbalances cancel each other out.
+                let _ = <balances::Module<T> as IdentityCurrency<_>>::withdraw_identity_balance(
+                    &leg.from, leg.amount,
+                )?;
+                let _ =
+                    <balances::Module<T> as IdentityCurrency<_>>::deposit_into_existing_identity(
+                        &leg.to, leg.amount,
+                    )?;
+                Ok(())
+            }
+        }
+    }
+}
@@ -60,6 +60,7 @@ use frame_support::{
     weights::SimpleDispatchInfo,
 };
 use frame_system::{self as system, ensure_signed};
+use sp_runtime::SaturatedConversion;
 use sp_std::{convert::TryFrom, prelude::*, vec};
 
 /// The module's configuration trait.
@@ -100,6 +101,10 @@ pub struct Ballot<V> {
     /// The user's historic balance at this checkpoint is used as maximum vote weight
     pub checkpoint_id: u64,
 
+    /// If set, the checkpoint is instead the latest one created by this checkpoint schedule of
+    /// the token at or before `voting_start`
+    pub checkpoint_schedule: Option<u64>,
+
     /// Timestamp at which voting should start
     pub voting_start: V,
 
@@ -185,7 +190,7 @@ decl_module! {
             ensure!(ballot_details.voting_end > ballot_details.voting_start, Error::<T>::InvalidDate);
             ensure!(!ballot_details.motions.is_empty(), Error::<T>::NoMotions);
 
-            // NB: Checkpoint ID is not verified here to allow creating ballots that will become active in future.
+            // NB: Checkpoint ID or schedule is not verified here to allow creating ballots that will become active in future.
             // Voting will only be allowed on checkpoints that exist.
 
             let mut total_choices:usize = 0usize;
@@ -240,9 +245,20 @@ decl_module! {
             ensure!(ballot.voting_end > now, Error::<T>::AlreadyEnded);
 
             // Ensure validity of checkpoint
-            ensure!(<asset::TotalCheckpoints>::contains_key(&ticker), Error::<T>::NoCheckpoints);
-            let count = <asset::TotalCheckpoints>::get(&ticker);
-            ensure!(ballot.checkpoint_id <= count, Error::<T>::NoCheckpoints);
+            let checkpoint_id = match ballot.checkpoint_schedule {
+                Some(schedule_id) => T::Asset::schedule_checkpoint_at(
+                    &ticker,
+                    schedule_id,
+                    ballot.voting_start.saturated_into(),
+                )
+                .ok_or(Error::<T>::NoCheckpoints)?,
+                None => {
+                    ensure!(<asset::TotalCheckpoints>::contains_key(&ticker), Error::<T>::NoCheckpoints);
+                    let count = <asset::TotalCheckpoints>::get(&ticker);
+                    ensure!(ballot.checkpoint_id <= count, Error::<T>::NoCheckpoints);
+                    ballot.checkpoint_id
+                }
+            };
 
             // Ensure vote is valid
             if let Ok(votes_len) = u64::try_from(votes.len()) {
@@ -255,7 +271,7 @@ decl_module! {
             for vote in &votes {
                 total_votes += *vote;
             }
-            ensure!(total_votes <= T::Asset::get_balance_at(&ticker, did, ballot.checkpoint_id), Error::<T>::InsufficientBalance);
+            ensure!(total_votes <= T::Asset::get_balance_at(&ticker, did, checkpoint_id), Error::<T>::InsufficientBalance);
 
             // This avoids cloning the variables to make the same tupple again and again
             let ticker_ballot_name_did = (ticker, ballot_name.clone(), did);
@@ -6,27 +6,26 @@ use super::{
     ExtBuilder,
 };
 
-use pallet_asset::{
-    self as asset, AssetType, FundingRoundName, IdentifierType, SecurityToken, SignData,
-};
+use pallet_asset::{self as asset, AssetType, FundingRoundName, IdentifierType, SecurityToken};
 use pallet_balances as balances;
 use pallet_compliance_manager as compliance_manager;
 use pallet_identity as identity;
+use pallet_statistics as statistics;
 use polymesh_common_utilities::{constants::*, traits::balances::Memo, Context};
 use polymesh_primitives::{
-    AccountKey, AuthorizationData, Document, IdentityId, LinkData, Signatory, SmartExtension,
-    SmartExtensionType, Ticker,
+    AccountKey, AuthorizationData, Claim, Document, IdentityId, LinkData, PortfolioId,
+    PortfolioKind, PortfolioName, RuleType, Scope, Signatory, SmartExtension, SmartExtensionType,
+    Ticker,
 };
 
 use chrono::prelude::Utc;
-use codec::Encode;
 use frame_support::{
     assert_err, assert_noop, assert_ok, traits::Currency, StorageDoubleMap, StorageMap,
 };
 use hex_literal::hex;
 use ink_primitives::hash as FunctionSelectorHasher;
 use rand::Rng;
-use sp_runtime::AnySignature;
+use sp_runtime::{traits::OnInitialize, AnySignature};
 use std::{
     convert::{TryFrom, TryInto},
     mem,
@@ -37,7 +36,9 @@ type Identity = identity::Module<TestStorage>;
 type Balances = balances::Module<TestStorage>;
 type Asset = asset::Module<TestStorage>;
 type Timestamp = pallet_timestamp::Module<TestStorage>;
+type System = frame_system::Module<TestStorage>;
 type ComplianceManager = compliance_manager::Module<TestStorage>;
+type Statistic = statistics::Module<TestStorage>;
 type AssetError = asset::Error<TestStorage>;
 type OffChainSignature = AnySignature;
 type Origin = <TestStorage as frame_system::Trait>::Origin;
@@ -235,7 +236,7 @@ fn valid_transfers_pass() {
 }
 
 #[test]
-fn valid_custodian_allowance() {
+fn portfolio_moves_and_transfers() {
     ExtBuilder::default().build().execute_with(|| {
         let (owner_signed, owner_did) = make_account(AccountKeyring::Dave.public()).unwrap();
 
@@ -254,10 +255,9 @@ fn valid_custodian_allowance() {
         let ticker = Ticker::try_from(token.name.as_slice()).unwrap();
 
         let (investor1_signed, investor1_did) = make_account(AccountKeyring::Bob.public()).unwrap();
-        let (_, investor2_did) = make_account(AccountKeyring::Charlie.public()).unwrap();
-        let (custodian_signed, custodian_did) = make_account(AccountKeyring::Eve.public()).unwrap();
+        let (investor2_signed, investor2_did) =
+            make_account(AccountKeyring::Charlie.public()).unwrap();
 
-        // Issuance is successful
         assert_ok!(Asset::create_asset(
             owner_signed.clone(),
             token.name.clone(),
@@ -269,11 +269,6 @@ fn valid_custodian_allowance() {
             None
         ));
 
-        assert_eq!(
-            Asset::balance(&ticker, &token.owner_did),
-            token.total_supply
-        );
-
         // Allow all transfers
         assert_ok!(ComplianceManager::add_active_rule(
             owner_signed.clone(),
@@ -306,141 +301,218 @@ fn valid_custodian_allowance() {
         assert_eq!(Asset::issued_in_funding_round((ticker, no_such_round)), 0);
         assert_eq!(Asset::balance(&ticker, &investor1_did), num_tokens1);
 
-        // Failed to add custodian because of insufficient balance
+        // Create portfolios. Names must be unique per DID.
+        let collateral: PortfolioName = b"Collateral".into();
+        assert_ok!(Asset::create_portfolio(
+            investor1_signed.clone(),
+            collateral.clone()
+        ));
         assert_noop!(
-            Asset::increase_custody_allowance(
+            Asset::create_portfolio(investor1_signed.clone(), collateral.clone()),
+            AssetError::PortfolioNameAlreadyInUse
+        );
+        assert_ok!(Asset::create_portfolio(
+            investor2_signed.clone(),
+            collateral.clone()
+        ));
+        assert_eq!(Asset::portfolios(investor1_did, 1), collateral);
+        assert_ok!(Asset::rename_portfolio(
+            investor1_signed.clone(),
+            1,
+            b"Treasury".into()
+        ));
+        assert_eq!(
+            Asset::portfolios(investor1_did, 1),
+            PortfolioName::from(b"Treasury")
+        );
+
+        let investor1_default = PortfolioId::default_portfolio(investor1_did);
+        let investor1_user = PortfolioId::user_portfolio(investor1_did, 1);
+        let investor2_default = PortfolioId::default_portfolio(investor2_did);
+        let investor2_user = PortfolioId::user_portfolio(investor2_did, 1);
+
+        // Move funds into the user portfolio.
+        assert_noop!(
+            Asset::move_portfolio_funds(
                 investor1_signed.clone(),
-                ticker,
-                custodian_did,
-                250_00_00 as u128
+                investor1_default,
+                PortfolioKind::User(2),
+                vec![(ticker, 10)]
             ),
-            AssetError::InsufficientBalance
+            AssetError::PortfolioDoesNotExist
         );
-
-        // Failed to add/increase the custodian allowance because of Invalid custodian did
-        let custodian_did_not_register = IdentityId::from(5u128);
         assert_noop!(
-            Asset::increase_custody_allowance(
+            Asset::move_portfolio_funds(
                 investor1_signed.clone(),
-                ticker,
-                custodian_did_not_register,
-                50_00_00 as u128
+                investor1_default,
+                PortfolioKind::User(1),
+                vec![(ticker, 250_00_00 as u128)]
             ),
-            AssetError::InvalidCustodianDid
+            AssetError::InsufficientPortfolioBalance
         );
-
-        // Add custodian
-        assert_ok!(Asset::increase_custody_allowance(
+        // Items of the same ticker are validated against their total.
+        assert_noop!(
+            Asset::move_portfolio_funds(
+                investor1_signed.clone(),
+                investor1_default,
+                PortfolioKind::User(1),
+                vec![(ticker, 150_00_00 as u128), (ticker, 150_00_00 as u128)]
+            ),
+            AssetError::InsufficientPortfolioBalance
+        );
+        assert_ok!(Asset::move_portfolio_funds(
             investor1_signed.clone(),
-            ticker,
-            custodian_did,
-            50_00_00 as u128
+            investor1_default,
+            PortfolioKind::User(1),
+            vec![(ticker, 150_00_00 as u128)]
         ));
-
         assert_eq!(
-            Asset::custodian_allowance((ticker, investor1_did, custodian_did)),
-            50_00_00 as u128
+            Asset::user_portfolios_holding(&ticker, &investor1_did),
+            vec![1]
         );
-
         assert_eq!(
-            Asset::total_custody_allowance((ticker, investor1_did)),
+            Asset::portfolio_balance(&investor1_default, &ticker),
             50_00_00 as u128
         );
+        assert_eq!(
+            Asset::portfolio_balance(&investor1_user, &ticker),
+            150_00_00 as u128
+        );
+        // The identity level balance is unchanged.
+        assert_eq!(Asset::balance(&ticker, &investor1_did), 200_00_00 as u128);
 
-        // Transfer the token upto the limit
+        // A plain transfer only spends the default portfolio.
+        assert_noop!(
+            Asset::transfer(
+                investor1_signed.clone(),
+                ticker,
+                investor2_did,
+                60_00_00 as u128
+            ),
+            AssetError::InsufficientPortfolioBalance
+        );
+        assert_ok!(Asset::create_checkpoint(owner_signed.clone(), ticker));
         assert_ok!(Asset::transfer(
             investor1_signed.clone(),
             ticker,
             investor2_did,
-            140_00_00 as u128
+            50_00_00 as u128
         ));
 
-        assert_eq!(Asset::balance(&ticker, &investor2_did), 140_00_00 as u128);
-
-        // Try to Transfer the tokens beyond the limit
+        // Transfer from a user portfolio into a user portfolio of another DID.
         assert_noop!(
-            Asset::transfer(
-                investor1_signed.clone(),
+            Asset::portfolio_transfer(
+                investor2_signed.clone(),
                 ticker,
-                investor2_did,
-                50_00_00 as u128
+                investor1_user,
+                investor2_user,
+                10_00_00 as u128
             ),
-            AssetError::InsufficientBalance
+            AssetError::UnauthorizedCustodian
         );
-
-        // Should fail to transfer the token by the custodian because of insufficient allowance
         assert_noop!(
-            Asset::transfer_by_custodian(
-                custodian_signed.clone(),
+            Asset::portfolio_transfer(
+                investor1_signed.clone(),
                 ticker,
-                investor1_did,
-                investor2_did,
-                55_00_00 as u128
+                investor1_user,
+                investor1_default,
+                10_00_00 as u128
             ),
-            AssetError::InsufficientAllowance
+            AssetError::PortfolioTransferToSelf
+        );
+        assert_ok!(Asset::portfolio_transfer(
+            investor1_signed.clone(),
+            ticker,
+            investor1_user,
+            investor2_user,
+            100_00_00 as u128
+        ));
+        assert_eq!(
+            Asset::portfolio_balance(&investor1_user, &ticker),
+            50_00_00 as u128
         );
+        assert_eq!(
+            Asset::portfolio_balance(&investor2_user, &ticker),
+            100_00_00 as u128
+        );
+        assert_eq!(
+            Asset::portfolio_balance(&investor2_default, &ticker),
+            50_00_00 as u128
+        );
+        assert_eq!(Asset::balance(&ticker, &investor1_did), 50_00_00 as u128);
+        assert_eq!(Asset::balance(&ticker, &investor2_did), 150_00_00 as u128);
 
-        // Successfully transfer by the custodian
-        assert_ok!(Asset::transfer_by_custodian(
-            custodian_signed.clone(),
+        // Checkpoints keep tracking the identity level balances.
+        assert_eq!(
+            Asset::get_balance_at(ticker, investor1_did, 1),
+            200_00_00 as u128
+        );
+        assert_eq!(Asset::get_balance_at(ticker, investor2_did, 1), 0);
+
+        // Only empty portfolios can be deleted.
+        assert_noop!(
+            Asset::delete_portfolio(investor1_signed.clone(), 1),
+            AssetError::PortfolioNotEmpty
+        );
+        assert_ok!(Asset::move_portfolio_funds(
+            investor1_signed.clone(),
+            investor1_user,
+            PortfolioKind::Default,
+            vec![(ticker, 50_00_00 as u128)]
+        ));
+        assert_ok!(Asset::delete_portfolio(investor1_signed.clone(), 1));
+        assert!(!<asset::Portfolios>::contains_key(investor1_did, 1));
+
+        // A forced transfer also spends the user portfolios of the holder.
+        assert_ok!(Asset::controller_transfer(
+            owner_signed.clone(),
             ticker,
-            investor1_did,
             investor2_did,
-            45_00_00 as u128
+            investor1_did,
+            120_00_00 as u128,
+            vec![],
+            vec![]
         ));
+        assert_eq!(Asset::portfolio_balance(&investor2_default, &ticker), 0);
+        assert_eq!(
+            Asset::portfolio_balance(&investor2_user, &ticker),
+            30_00_00 as u128
+        );
+        assert_eq!(Asset::balance(&ticker, &investor2_did), 30_00_00 as u128);
     });
 }
 
 #[test]
-fn valid_custodian_allowance_of() {
+fn portfolio_custody() {
     ExtBuilder::default().build().execute_with(|| {
         let (owner_signed, owner_did) = make_account(AccountKeyring::Dave.public()).unwrap();
 
         let now = Utc::now();
         Timestamp::set_timestamp(now.timestamp() as u64);
 
-        // Expected token entry
-        let token = SecurityToken {
-            name: vec![0x01].into(),
-            owner_did,
-            total_supply: 1_000_000,
-            divisible: true,
-            asset_type: AssetType::default(),
-            ..Default::default()
-        };
-        let ticker = Ticker::try_from(token.name.as_slice()).unwrap();
+        let token_name = b"COOL";
+        let ticker = Ticker::try_from(&token_name[..]).unwrap();
 
         let (investor1_signed, investor1_did) = make_account(AccountKeyring::Bob.public()).unwrap();
-        let (investor2_signed, investor2_did) =
-            make_account(AccountKeyring::Charlie.public()).unwrap();
+        let (_, investor2_did) = make_account(AccountKeyring::Charlie.public()).unwrap();
         let (custodian_signed, custodian_did) = make_account(AccountKeyring::Eve.public()).unwrap();
 
-        // Issuance is successful
         assert_ok!(Asset::create_asset(
             owner_signed.clone(),
-            token.name.clone(),
+            token_name.into(),
             ticker,
-            token.total_supply,
+            1_000_000,
             true,
-            token.asset_type.clone(),
+            AssetType::default(),
             vec![],
             None
         ));
-
-        assert_eq!(
-            Asset::balance(&ticker, &token.owner_did),
-            token.total_supply
-        );
-
-        // Allow all transfers
         assert_ok!(ComplianceManager::add_active_rule(
             owner_signed.clone(),
             ticker,
             vec![],
             vec![]
         ));
-
-        // Mint some tokens to investor1
         assert_ok!(Asset::issue(
             owner_signed.clone(),
             ticker,
@@ -449,111 +521,96 @@ fn valid_custodian_allowance_of() {
             vec![0x0]
         ));
 
-        assert_eq!(Asset::balance(&ticker, &investor1_did), 200_00_00 as u128);
-
-        let msg = SignData {
-            custodian_did,
-            holder_did: investor1_did,
-            ticker,
-            value: 50_00_00 as u128,
-            nonce: 1,
-        };
-
-        let investor1_key = AccountKeyring::Bob;
-
-        // Add custodian
-        assert_ok!(Asset::increase_custody_allowance_of(
-            investor2_signed.clone(),
-            ticker,
-            investor1_did,
-            AccountKeyring::Bob.public(),
-            custodian_did,
-            50_00_00 as u128,
-            1,
-            OffChainSignature::from(investor1_key.sign(&msg.encode()))
+        assert_ok!(Asset::create_portfolio(
+            investor1_signed.clone(),
+            b"Omnibus".into()
         ));
+        let omnibus = PortfolioId::user_portfolio(investor1_did, 1);
+        assert_ok!(Asset::move_portfolio_funds(
+            investor1_signed.clone(),
+            PortfolioId::default_portfolio(investor1_did),
+            PortfolioKind::User(1),
+            vec![(ticker, 50_00_00 as u128)]
+        ));
+        assert_eq!(Asset::custodian_of(&omnibus), investor1_did);
 
-        assert_eq!(
-            Asset::custodian_allowance((ticker, investor1_did, custodian_did)),
-            50_00_00 as u128
+        // Only the custodian of the portfolio can hand over the custody.
+        let invalid_auth_id = Identity::add_auth(
+            Signatory::from(investor2_did),
+            Signatory::from(custodian_did),
+            AuthorizationData::PortfolioCustody(omnibus),
+            None,
+        );
+        assert_err!(
+            Asset::accept_portfolio_custody(custodian_signed.clone(), invalid_auth_id),
+            "Illegal use of Authorization"
         );
 
-        assert_eq!(
-            Asset::total_custody_allowance((ticker, investor1_did)),
-            50_00_00 as u128
+        let auth_id = Identity::add_auth(
+            Signatory::from(investor1_did),
+            Signatory::from(custodian_did),
+            AuthorizationData::PortfolioCustody(omnibus),
+            None,
         );
+        assert_ok!(Asset::accept_portfolio_custody(
+            custodian_signed.clone(),
+            auth_id
+        ));
+        assert_eq!(Asset::custodian_of(&omnibus), custodian_did);
 
-        // use the same signature with the same nonce should fail
+        // The owner can no longer move or transfer the tokens under custody.
         assert_noop!(
-            Asset::increase_custody_allowance_of(
-                investor2_signed.clone(),
-                ticker,
-                investor1_did,
-                AccountKeyring::Bob.public(),
-                custodian_did,
-                50_00_00 as u128,
-                1,
-                OffChainSignature::from(investor1_key.sign(&msg.encode()))
+            Asset::move_portfolio_funds(
+                investor1_signed.clone(),
+                omnibus,
+                PortfolioKind::Default,
+                vec![(ticker, 10_00_00 as u128)]
             ),
-            AssetError::SignatureAlreadyUsed
+            AssetError::UnauthorizedCustodian
         );
-
-        // use the same signature with the different nonce should fail
         assert_noop!(
-            Asset::increase_custody_allowance_of(
-                investor2_signed.clone(),
-                ticker,
-                investor1_did,
-                AccountKeyring::Bob.public(),
-                custodian_did,
-                50_00_00 as u128,
-                3,
-                OffChainSignature::from(investor1_key.sign(&msg.encode()))
-            ),
-            AssetError::InvalidSignature
+            Asset::delete_portfolio(investor1_signed.clone(), 1),
+            AssetError::UnauthorizedCustodian
         );
-
-        // Transfer the token upto the limit
+        // But the tokens of the default portfolio are still available.
         assert_ok!(Asset::transfer(
             investor1_signed.clone(),
             ticker,
             investor2_did,
-            140_00_00 as u128
+            150_00_00 as u128
         ));
 
-        assert_eq!(Asset::balance(&ticker, &investor2_did), 140_00_00 as u128);
-
-        // Try to Transfer the tokens beyond the limit
-        assert_noop!(
-            Asset::transfer(
-                investor1_signed.clone(),
-                ticker,
-                investor2_did,
-                50_00_00 as u128
-            ),
-            AssetError::InsufficientBalance
-        );
-
-        // Should fail to transfer the token by the custodian because of insufficient allowance
+        // The custodian transfers the tokens on behalf of the holder.
         assert_noop!(
-            Asset::transfer_by_custodian(
+            Asset::portfolio_transfer(
                 custodian_signed.clone(),
                 ticker,
-                investor1_did,
-                investor2_did,
+                omnibus,
+                PortfolioId::default_portfolio(investor2_did),
                 55_00_00 as u128
             ),
-            AssetError::InsufficientAllowance
+            AssetError::InsufficientPortfolioBalance
         );
-
-        // Successfully transfer by the custodian
-        assert_ok!(Asset::transfer_by_custodian(
+        assert_ok!(Asset::portfolio_transfer(
             custodian_signed.clone(),
             ticker,
-            investor1_did,
-            investor2_did,
+            omnibus,
+            PortfolioId::default_portfolio(investor2_did),
             45_00_00 as u128
         ));
+        assert_eq!(Asset::balance(&ticker, &investor1_did), 5_00_00 as u128);
+        assert_eq!(Asset::balance(&ticker, &investor2_did), 195_00_00 as u128);
+
+        // The custodian gives the custody back to the owner.
+        assert_ok!(Asset::quit_portfolio_custody(
+            custodian_signed.clone(),
+            omnibus
+        ));
+        assert_eq!(Asset::custodian_of(&omnibus), investor1_did);
+        assert_noop!(
+            Asset::quit_portfolio_custody(custodian_signed.clone(), omnibus),
+            AssetError::UnauthorizedCustodian
+        );
     });
 }
 
@@ -1850,8 +1907,6 @@ fn test_can_transfer_rpc() {
         .execute_with(|| {
             let (alice_signed, alice_did) = make_account(AccountKeyring::Alice.public()).unwrap();
             let (bob_signed, bob_did) = make_account(AccountKeyring::Bob.public()).unwrap();
-            let (custodian_signed, custodian_did) =
-                make_account(AccountKeyring::Charlie.public()).unwrap();
             let token_name = b"COOL";
             let ticker = Ticker::try_from(&token_name[..]).unwrap();
             assert_ok!(Asset::create_asset(
@@ -1897,13 +1952,18 @@ fn test_can_transfer_rpc() {
                 ERC1400_INSUFFICIENT_BALANCE
             );
 
-            // Case 3: When custody allowance is provided and amount of transfer is more than free balance
-            // 3.1: Add custody provider
-            assert_ok!(Asset::increase_custody_allowance(
+            // Case 3: When tokens are held in a user portfolio and amount of transfer is more than
+            // the balance of the default portfolio
+            // 3.1: Move tokens into a user portfolio
+            assert_ok!(Asset::create_portfolio(
                 alice_signed.clone(),
-                ticker,
-                custodian_did,
-                900 * currency::ONE_UNIT
+                b"Treasury".into()
+            ));
+            assert_ok!(Asset::move_portfolio_funds(
+                alice_signed.clone(),
+                PortfolioId::default_portfolio(alice_did),
+                PortfolioKind::User(1),
+                vec![(ticker, 900 * currency::ONE_UNIT)]
             ));
 
             // 3.2: Execute can_transfer
@@ -1986,3 +2046,334 @@ fn test_can_transfer_rpc() {
             );
         })
 }
+
+#[test]
+fn checkpoint_schedules() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(checkpoint_schedules_we);
+}
+
+fn checkpoint_schedules_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let _alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let _bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+
+    // Blocks are produced every 10 ms, slower than the 6 ms expected by the test runtime.
+    System::set_block_number(100);
+    Timestamp::set_timestamp(1_000);
+    let advance_to = |timestamp: u64| {
+        while Timestamp::get() < timestamp {
+            let block_number = System::block_number() + 1;
+            System::set_block_number(block_number);
+            Timestamp::set_timestamp(block_number * 10);
+            Asset::on_initialize(block_number);
+        }
+    };
+    assert_ok!(Asset::create_checkpoint(alice.clone(), ticker));
+
+    assert_err!(
+        Asset::create_checkpoint_schedule(bob, ticker, 2_000, 1_000, 2),
+        AssetError::Unauthorized
+    );
+    assert_err!(
+        Asset::create_checkpoint_schedule(alice.clone(), ticker, 2_000, 0, 2),
+        AssetError::InvalidCheckpointPeriod
+    );
+    assert_ok!(Asset::create_checkpoint_schedule(
+        alice.clone(),
+        ticker,
+        2_000,
+        1_000,
+        2
+    ));
+
+    // Checkpoints are created in the first block after each due time.
+    advance_to(1_500);
+    assert_eq!(Asset::total_checkpoints_of(&ticker), 1);
+    advance_to(2_000);
+    assert_eq!(Asset::total_checkpoints_of(&ticker), 2);
+    advance_to(2_500);
+    assert_eq!(Asset::total_checkpoints_of(&ticker), 2);
+    advance_to(3_100);
+    assert_eq!(Asset::total_checkpoints_of(&ticker), 3);
+
+    // The schedule ends after its last checkpoint.
+    advance_to(4_000);
+    assert_eq!(Asset::total_checkpoints_of(&ticker), 3);
+    assert_eq!(Asset::schedule_checkpoints((ticker, 0)), vec![2, 3]);
+    assert_eq!(Asset::checkpoint_schedules(&ticker, 0), None);
+
+    assert_eq!(Asset::checkpoint_at(&ticker, 999), None);
+    assert_eq!(Asset::checkpoint_at(&ticker, 1_000), Some(1));
+    assert_eq!(Asset::checkpoint_at(&ticker, 2_999), Some(2));
+    assert_eq!(Asset::checkpoint_at(&ticker, 10_000), Some(3));
+    assert_eq!(Asset::schedule_checkpoint_at(&ticker, 0, 1_500), None);
+    assert_eq!(Asset::schedule_checkpoint_at(&ticker, 0, 2_500), Some(2));
+
+    // A schedule starting in the past creates a single checkpoint to catch up.
+    assert_ok!(Asset::create_checkpoint_schedule(
+        alice.clone(),
+        ticker,
+        1_000,
+        1_000,
+        0
+    ));
+    advance_to(4_500);
+    assert_eq!(Asset::total_checkpoints_of(&ticker), 4);
+    assert_eq!(
+        Asset::checkpoint_schedules(&ticker, 1).unwrap().next_at,
+        5_000
+    );
+    advance_to(5_000);
+    assert_eq!(Asset::total_checkpoints_of(&ticker), 5);
+
+    // The number of schedules of a token is limited.
+    for _ in 1..asset::MAX_CHECKPOINT_SCHEDULES {
+        assert_ok!(Asset::create_checkpoint_schedule(
+            alice.clone(),
+            ticker,
+            10_000,
+            1_000,
+            0
+        ));
+    }
+    assert_err!(
+        Asset::create_checkpoint_schedule(alice.clone(), ticker, 10_000, 1_000, 0),
+        AssetError::TooManyCheckpointSchedules
+    );
+    assert_ok!(Asset::remove_checkpoint_schedule(alice.clone(), ticker, 1));
+    assert_ok!(Asset::create_checkpoint_schedule(
+        alice, ticker, 10_000, 1_000, 0
+    ));
+}
+
+#[test]
+fn checkpoint_schedules_per_block() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(checkpoint_schedules_per_block_we);
+}
+
+fn checkpoint_schedules_per_block_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let _alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    System::set_block_number(1);
+    Timestamp::set_timestamp(1_000);
+
+    // Schedules due at the same time are indexed at the same block until it is full.
+    let schedules_per_block = asset::MAX_SCHEDULED_CHECKPOINTS_PER_BLOCK;
+    let tickers = schedules_per_block / asset::MAX_CHECKPOINT_SCHEDULES + 1;
+    for i in 0..tickers {
+        let name = format!("ACME{}", i).into_bytes();
+        let ticker = Ticker::try_from(name.as_slice()).unwrap();
+        assert_ok!(Asset::create_asset(
+            alice.clone(),
+            name.into(),
+            ticker,
+            1_000_000,
+            true,
+            AssetType::default(),
+            vec![],
+            None
+        ));
+        for _ in 0..asset::MAX_CHECKPOINT_SCHEDULES {
+            assert_ok!(Asset::create_checkpoint_schedule(
+                alice.clone(),
+                ticker,
+                10_000,
+                1_000,
+                0
+            ));
+        }
+    }
+    // The test runtime expects a block every 6 ms, and schedules are indexed at half the
+    // expected number of blocks.
+    let block_number = 1 + 9_000 / 6 / 2;
+    assert_eq!(
+        Asset::scheduled_checkpoints_at(block_number).len(),
+        schedules_per_block
+    );
+    assert_eq!(
+        Asset::scheduled_checkpoints_at(block_number + 1).len(),
+        tickers * asset::MAX_CHECKPOINT_SCHEDULES - schedules_per_block
+    );
+}
+
+#[test]
+fn checkpoint_investor_count_and_holders() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(checkpoint_investor_count_and_holders_we);
+}
+
+fn checkpoint_investor_count_and_holders_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let charlie_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![]
+    ));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 100));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, charlie_did, 200));
+    assert_ok!(Asset::create_checkpoint(alice.clone(), ticker));
+    let investor_count = Statistic::investor_count_per_asset(&ticker);
+    assert_eq!(
+        Statistic::investor_count_at_checkpoint((ticker, 1)),
+        investor_count
+    );
+
+    // Bob leaves the cap table.
+    assert_ok!(Asset::transfer(bob, ticker, charlie_did, 100));
+    assert_ok!(Asset::create_checkpoint(alice, ticker));
+    assert_eq!(
+        Statistic::investor_count_at_checkpoint((ticker, 2)),
+        investor_count - 1
+    );
+    assert_eq!(
+        Statistic::investor_count_at_checkpoint((ticker, 1)),
+        investor_count
+    );
+
+    // Holders at the first checkpoint, two per page.
+    assert_eq!(
+        Asset::holders(&ticker, Some(1), 0, 2),
+        (vec![(alice_did, 999_700), (bob_did, 100)], Some(2))
+    );
+    assert_eq!(
+        Asset::holders(&ticker, Some(1), 2, 2),
+        (vec![(charlie_did, 200)], None)
+    );
+
+    // Holders without a balance are skipped.
+    assert_eq!(
+        Asset::holders(&ticker, None, 0, 10),
+        (vec![(alice_did, 999_700), (charlie_did, 300)], None)
+    );
+}
+
+#[test]
+fn transfer_report() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(transfer_report_we);
+}
+
+fn transfer_report_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_default_trusted_claim_issuer(
+        alice.clone(),
+        ticker,
+        dave_did
+    ));
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![RuleType::IsPresent(Claim::Accredited(scope.clone())).into()]
+    ));
+
+    let report = |amount| {
+        let report = Asset::transfer_report(
+            AccountKeyring::Alice.public(),
+            ticker,
+            Some(alice_did),
+            Some(bob_did),
+            amount,
+        );
+        assert_eq!(
+            report.status,
+            Asset::unsafe_can_transfer(
+                AccountKeyring::Alice.public(),
+                ticker,
+                Some(alice_did),
+                Some(bob_did),
+                amount
+            )
+            .unwrap()
+        );
+        report
+    };
+
+    // Bob is not accredited.
+    let first_report = report(100);
+    assert_eq!(first_report.status, COMPLIANCE_MANAGER_FAILURE);
+    assert!(first_report.granularity);
+    assert_eq!(first_report.sender_balance, Some(1_000_000));
+    assert!(first_report.sufficient_balance);
+    assert_eq!(first_report.sender_cdd, Some(true));
+    assert_eq!(first_report.receiver_cdd, Some(true));
+    assert!(!first_report.frozen);
+    assert!(first_report.compliance.restrictions_satisfied);
+    assert_eq!(first_report.compliance.rules.len(), 1);
+    let rule_report = &first_report.compliance.rules[0];
+    assert!(rule_report.sender_rules.is_empty());
+    assert!(!rule_report.result);
+    assert!(!rule_report.receiver_rules[0].result);
+    assert_eq!(rule_report.receiver_rules[0].issuers, vec![dave_did]);
+    assert!(rule_report.receiver_rules[0].claims.is_empty());
+    assert!(first_report.extensions.is_empty());
+
+    assert_ok!(Identity::add_claim(
+        dave,
+        bob_did,
+        Claim::Accredited(scope),
+        None
+    ));
+    let second_report = report(100);
+    assert_eq!(second_report.status, ERC1400_TRANSFER_SUCCESS);
+    let receiver_report = &second_report.compliance.rules[0].receiver_rules[0];
+    assert!(receiver_report.result);
+    assert_eq!(receiver_report.claims.len(), 1);
+    assert_eq!(receiver_report.claims[0].claim_issuer, dave_did);
+
+    // Every check is reported, even after a failed one.
+    let third_report = report(2_000_000);
+    assert_eq!(third_report.status, ERC1400_INSUFFICIENT_BALANCE);
+    assert!(!third_report.sufficient_balance);
+    assert!(third_report.compliance.result);
+}
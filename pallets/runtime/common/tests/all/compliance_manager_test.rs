@@ -1,18 +1,27 @@
 use super::{
+    ext_builder::PROTOCOL_OP_BASE_FEE,
     storage::{make_account, register_keyring_account, TestStorage},
     ExtBuilder,
 };
 
 use pallet_asset::{self as asset, AssetName, AssetType, Error as AssetError, SecurityToken};
 use pallet_balances as balances;
-use pallet_compliance_manager::{self as compliance_manager, AssetTransferRule, Error as CMError};
+use pallet_compliance_manager::{
+    self as compliance_manager, AssetTransferRule, Error as CMError, RuleTemplateName,
+    TransferRestriction, TrustedIssuerScope,
+};
 use pallet_group::{self as group};
-use pallet_identity::{self as identity, BatchAddClaimItem};
+use pallet_identity::{self as identity, BatchAddClaimItem, Error as IdentityError};
+use pallet_statistics as statistics;
 use polymesh_common_utilities::Context;
-use polymesh_primitives::{Claim, IdentityId, Rule, RuleType, Scope, Ticker};
+use polymesh_primitives::{
+    predicate, Claim, ClaimType, CustomClaimTypeId, IdentityId, Rule, RuleType, Scope, Ticker,
+};
+use polymesh_runtime_common::exemption::{self, Error as ExemptionError};
 
 use chrono::prelude::Utc;
 use frame_support::{assert_err, assert_ok, traits::Currency};
+use sp_runtime::Permill;
 use test_client::AccountKeyring;
 
 use sp_std::{convert::TryFrom, prelude::*};
@@ -23,6 +32,8 @@ type Timestamp = pallet_timestamp::Module<TestStorage>;
 type Asset = asset::Module<TestStorage>;
 type ComplianceManager = compliance_manager::Module<TestStorage>;
 type CDDGroup = group::Module<TestStorage, group::Instance2>;
+type Statistic = statistics::Module<TestStorage>;
+type Exemption = exemption::Module<TestStorage>;
 type Moment = u64;
 type Origin = <TestStorage as frame_system::Trait>::Origin;
 
@@ -122,7 +133,7 @@ fn should_add_and_verify_asset_rule_we() {
 
     let receiver_rule2 = Rule {
         issuers: vec![claim_issuer_did],
-        rule_type: RuleType::IsPresent(Claim::Accredited(token_owner_did)),
+        rule_type: RuleType::IsPresent(Claim::Accredited(token_owner_did.into())),
     };
 
     assert_ok!(ComplianceManager::add_active_rule(
@@ -135,7 +146,7 @@ fn should_add_and_verify_asset_rule_we() {
     assert_ok!(Identity::add_claim(
         claim_issuer_signed.clone(),
         token_owner_did,
-        Claim::Accredited(claim_issuer_did),
+        Claim::Accredited(claim_issuer_did.into()),
         None,
     ));
 
@@ -153,7 +164,7 @@ fn should_add_and_verify_asset_rule_we() {
     assert_ok!(Identity::add_claim(
         claim_issuer_signed.clone(),
         token_owner_did,
-        Claim::Accredited(token_owner_did),
+        Claim::Accredited(token_owner_did.into()),
         None,
     ));
 
@@ -720,17 +731,17 @@ fn jurisdiction_asset_rules_we() {
         None
     ));
     // 2. Set up rules for Asset transfer.
-    let scope = Scope::from(0);
+    let scope = Scope::Identity(IdentityId::from(0));
     let receiver_rules = vec![
         Rule {
             rule_type: RuleType::IsAnyOf(vec![
-                Claim::Jurisdiction(b"Canada".into(), scope),
-                Claim::Jurisdiction(b"Spain".into(), scope),
+                Claim::Jurisdiction(b"Canada".into(), scope.clone()),
+                Claim::Jurisdiction(b"Spain".into(), scope.clone()),
             ]),
             issuers: vec![cdd_id],
         },
         Rule {
-            rule_type: RuleType::IsAbsent(Claim::BlackListed(scope)),
+            rule_type: RuleType::IsAbsent(Claim::BlackListed(scope.clone())),
             issuers: vec![token_owner_id],
         },
     ];
@@ -750,7 +761,7 @@ fn jurisdiction_asset_rules_we() {
     assert_ok!(Identity::add_claim(
         cdd_signed.clone(),
         user_id,
-        Claim::Jurisdiction(b"Canada".into(), scope),
+        Claim::Jurisdiction(b"Canada".into(), scope.clone()),
         None
     ));
     assert_ok!(Asset::transfer(
@@ -763,7 +774,7 @@ fn jurisdiction_asset_rules_we() {
     assert_ok!(Identity::add_claim(
         token_owner_signed.clone(),
         user_id,
-        Claim::BlackListed(scope),
+        Claim::BlackListed(scope.clone()),
         None,
     ));
     assert_err!(
@@ -789,9 +800,9 @@ fn scope_asset_rules_we() {
     let ticker = make_ticker_env(owner, vec![0x01].into());
 
     // 2. Set up rules for Asset transfer.
-    let scope = Identity::get_token_did(&ticker).unwrap();
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
     let receiver_rules = vec![Rule {
-        rule_type: RuleType::IsPresent(Claim::Affiliate(scope)),
+        rule_type: RuleType::IsPresent(Claim::Affiliate(scope.clone())),
         issuers: vec![cdd_id],
     }];
     assert_ok!(ComplianceManager::add_active_rule(
@@ -810,7 +821,7 @@ fn scope_asset_rules_we() {
     assert_ok!(Identity::add_claim(
         cdd_signed.clone(),
         user_id,
-        Claim::Affiliate(scope),
+        Claim::Affiliate(scope.clone()),
         None
     ));
     assert_ok!(Asset::transfer(owner_signed.clone(), ticker, user_id, 100));
@@ -832,13 +843,13 @@ fn cm_test_case_9_we() {
     // 1. Create a token.
     let ticker = make_ticker_env(AccountKeyring::Alice, vec![0x01].into());
     // 2. Set up rules for Asset transfer.
-    let scope = Identity::get_token_did(&ticker).unwrap();
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
     let receiver_rules = vec![Rule {
         rule_type: RuleType::IsAnyOf(vec![
-            Claim::KnowYourCustomer(scope),
-            Claim::Affiliate(scope),
-            Claim::Accredited(scope),
-            Claim::Whitelisted(scope),
+            Claim::KnowYourCustomer(scope.clone()),
+            Claim::Affiliate(scope.clone()),
+            Claim::Accredited(scope.clone()),
+            Claim::Whitelisted(scope.clone()),
         ]),
         issuers: vec![issuer_id],
     }];
@@ -858,7 +869,7 @@ fn cm_test_case_9_we() {
     assert_ok!(Identity::add_claim(
         issuer.clone(),
         charlie,
-        Claim::KnowYourCustomer(scope),
+        Claim::KnowYourCustomer(scope.clone()),
         None
     ));
     assert_ok!(Asset::transfer(owner.clone(), ticker, charlie, 100));
@@ -867,7 +878,7 @@ fn cm_test_case_9_we() {
     assert_ok!(Identity::add_claim(
         issuer.clone(),
         dave,
-        Claim::Affiliate(scope),
+        Claim::Affiliate(scope.clone()),
         None
     ));
     assert_ok!(Asset::transfer(owner.clone(), ticker, dave, 100));
@@ -876,7 +887,7 @@ fn cm_test_case_9_we() {
     assert_ok!(Identity::add_claim(
         issuer.clone(),
         eve,
-        Claim::Whitelisted(scope),
+        Claim::Whitelisted(scope.clone()),
         None
     ));
     assert_ok!(Asset::transfer(owner.clone(), ticker, eve, 100));
@@ -899,21 +910,21 @@ fn cm_test_case_11_we() {
     // 1. Create a token.
     let ticker = make_ticker_env(AccountKeyring::Alice, vec![0x01].into());
     // 2. Set up rules for Asset transfer.
-    let scope = Identity::get_token_did(&ticker).unwrap();
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
     let receiver_rules = vec![
         Rule {
             rule_type: RuleType::IsAnyOf(vec![
-                Claim::KnowYourCustomer(scope),
-                Claim::Affiliate(scope),
-                Claim::Accredited(scope),
-                Claim::Whitelisted(scope),
+                Claim::KnowYourCustomer(scope.clone()),
+                Claim::Affiliate(scope.clone()),
+                Claim::Accredited(scope.clone()),
+                Claim::Whitelisted(scope.clone()),
             ]),
             issuers: vec![issuer_id],
         },
         Rule {
             rule_type: RuleType::IsNoneOf(vec![
-                Claim::Jurisdiction(b"USA".into(), scope),
-                Claim::Jurisdiction(b"North Kore".into(), scope),
+                Claim::Jurisdiction(b"USA".into(), scope.clone()),
+                Claim::Jurisdiction(b"North Kore".into(), scope.clone()),
             ]),
             issuers: vec![issuer_id],
         },
@@ -934,7 +945,7 @@ fn cm_test_case_11_we() {
     assert_ok!(Identity::add_claim(
         issuer.clone(),
         charlie,
-        Claim::KnowYourCustomer(scope),
+        Claim::KnowYourCustomer(scope.clone()),
         None
     ));
     assert_ok!(Asset::transfer(owner.clone(), ticker, charlie, 100));
@@ -943,13 +954,13 @@ fn cm_test_case_11_we() {
     assert_ok!(Identity::add_claim(
         issuer.clone(),
         dave,
-        Claim::Affiliate(scope),
+        Claim::Affiliate(scope.clone()),
         None
     ));
     assert_ok!(Identity::add_claim(
         issuer.clone(),
         dave,
-        Claim::Jurisdiction(b"USA".into(), scope),
+        Claim::Jurisdiction(b"USA".into(), scope.clone()),
         None
     ));
     assert_err!(
@@ -961,13 +972,13 @@ fn cm_test_case_11_we() {
     assert_ok!(Identity::add_claim(
         issuer.clone(),
         eve,
-        Claim::Whitelisted(scope),
+        Claim::Whitelisted(scope.clone()),
         None
     ));
     assert_ok!(Identity::add_claim(
         issuer.clone(),
         eve,
-        Claim::Jurisdiction(b"UK".into(), scope),
+        Claim::Jurisdiction(b"UK".into(), scope.clone()),
         None
     ));
     assert_ok!(Asset::transfer(owner.clone(), ticker, eve, 100));
@@ -990,24 +1001,24 @@ fn cm_test_case_13_we() {
     // 1. Create a token.
     let ticker = make_ticker_env(AccountKeyring::Alice, vec![0x01].into());
     // 2. Set up rules for Asset transfer.
-    let scope = Identity::get_token_did(&ticker).unwrap();
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
     let receiver_rules = vec![
         Rule {
-            rule_type: RuleType::IsPresent(Claim::KnowYourCustomer(scope)),
+            rule_type: RuleType::IsPresent(Claim::KnowYourCustomer(scope.clone())),
             issuers: vec![issuer_id],
         },
         Rule {
             rule_type: RuleType::IsAnyOf(vec![
-                Claim::Affiliate(scope),
-                Claim::Accredited(scope),
-                Claim::Whitelisted(scope),
+                Claim::Affiliate(scope.clone()),
+                Claim::Accredited(scope.clone()),
+                Claim::Whitelisted(scope.clone()),
             ]),
             issuers: vec![issuer_id],
         },
         Rule {
             rule_type: RuleType::IsNoneOf(vec![
-                Claim::Jurisdiction(b"USA".into(), scope),
-                Claim::Jurisdiction(b"North Kore".into(), scope),
+                Claim::Jurisdiction(b"USA".into(), scope.clone()),
+                Claim::Jurisdiction(b"North Kore".into(), scope.clone()),
             ]),
             issuers: vec![issuer_id],
         },
@@ -1029,7 +1040,7 @@ fn cm_test_case_13_we() {
     assert_ok!(Identity::add_claim(
         issuer.clone(),
         charlie,
-        Claim::KnowYourCustomer(scope),
+        Claim::KnowYourCustomer(scope.clone()),
         None
     ));
     assert_err!(
@@ -1041,17 +1052,17 @@ fn cm_test_case_13_we() {
     let dave_claims = vec![
         BatchAddClaimItem::<Moment> {
             target: dave,
-            claim: Claim::Whitelisted(scope),
+            claim: Claim::Whitelisted(scope.clone()),
             expiry: None,
         },
         BatchAddClaimItem::<Moment> {
             target: dave,
-            claim: Claim::KnowYourCustomer(scope),
+            claim: Claim::KnowYourCustomer(scope.clone()),
             expiry: None,
         },
         BatchAddClaimItem::<Moment> {
             target: dave,
-            claim: Claim::Jurisdiction(b"USA".into(), scope),
+            claim: Claim::Jurisdiction(b"USA".into(), scope.clone()),
             expiry: None,
         },
     ];
@@ -1066,17 +1077,17 @@ fn cm_test_case_13_we() {
     let eve_claims = vec![
         BatchAddClaimItem::<Moment> {
             target: eve,
-            claim: Claim::Whitelisted(scope),
+            claim: Claim::Whitelisted(scope.clone()),
             expiry: None,
         },
         BatchAddClaimItem::<Moment> {
             target: eve,
-            claim: Claim::KnowYourCustomer(scope),
+            claim: Claim::KnowYourCustomer(scope.clone()),
             expiry: None,
         },
         BatchAddClaimItem::<Moment> {
             target: eve,
-            claim: Claim::Jurisdiction(b"UK".into(), scope),
+            claim: Claim::Jurisdiction(b"UK".into(), scope.clone()),
             expiry: None,
         },
     ];
@@ -1084,3 +1095,844 @@ fn cm_test_case_13_we() {
     assert_ok!(Identity::add_claims_batch(issuer.clone(), eve_claims));
     assert_ok!(Asset::transfer(owner.clone(), ticker, eve, 100));
 }
+
+#[test]
+fn transfer_restrictions() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(transfer_restrictions_we);
+}
+
+fn transfer_restrictions_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let _alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let charlie_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+    let eve_did = register_keyring_account(AccountKeyring::Eve).unwrap();
+    let ferdie_did = register_keyring_account(AccountKeyring::Ferdie).unwrap();
+
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![]
+    ));
+
+    // Maximum investor count.
+    let max_investors = TransferRestriction::MaxInvestorCount(2);
+    assert_err!(
+        ComplianceManager::add_transfer_restriction(bob.clone(), ticker, max_investors.clone()),
+        CMError::<TestStorage>::Unauthorized
+    );
+    assert_ok!(ComplianceManager::add_transfer_restriction(
+        alice.clone(),
+        ticker,
+        max_investors.clone()
+    ));
+    assert_err!(
+        ComplianceManager::add_transfer_restriction(alice.clone(), ticker, max_investors.clone()),
+        CMError::<TestStorage>::DuplicateTransferRestriction
+    );
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 100));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, charlie_did, 100));
+    assert_err!(
+        Asset::transfer(alice.clone(), ticker, eve_did, 100),
+        AssetError::<TestStorage>::InvalidTransfer
+    );
+    // Bob leaves, so Eve can take his place.
+    assert_ok!(Asset::transfer(bob.clone(), ticker, eve_did, 100));
+    assert_ok!(ComplianceManager::remove_transfer_restriction(
+        alice.clone(),
+        ticker,
+        max_investors
+    ));
+
+    // Maximum ownership with exemptions.
+    assert_ok!(ComplianceManager::add_transfer_restriction(
+        alice.clone(),
+        ticker,
+        TransferRestriction::MaxInvestorOwnership(Permill::from_percent(10), false)
+    ));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, charlie_did, 99_900));
+    assert_err!(
+        Asset::transfer(alice.clone(), ticker, charlie_did, 1),
+        AssetError::<TestStorage>::InvalidTransfer
+    );
+    assert_ok!(ComplianceManager::modify_ownership_exemption(
+        alice.clone(),
+        ticker,
+        charlie_did,
+        true
+    ));
+    assert_err!(
+        ComplianceManager::modify_ownership_exemption(alice.clone(), ticker, charlie_did, true),
+        CMError::<TestStorage>::NoChange
+    );
+    assert_ok!(Asset::transfer(alice.clone(), ticker, charlie_did, 1));
+
+    // Maximum investor count per claim.
+    assert_ok!(ComplianceManager::add_default_trusted_claim_issuer(
+        alice.clone(),
+        ticker,
+        dave_did
+    ));
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
+    for did in vec![bob_did, ferdie_did] {
+        assert_ok!(Identity::add_claim(
+            dave.clone(),
+            did,
+            Claim::Accredited(scope.clone()),
+            None
+        ));
+    }
+    assert_ok!(Statistic::track_claim(
+        alice.clone(),
+        ticker,
+        ClaimType::Accredited,
+        scope.clone()
+    ));
+    assert_ok!(ComplianceManager::add_transfer_restriction(
+        alice.clone(),
+        ticker,
+        TransferRestriction::MaxClaimInvestorCount(ClaimType::Accredited, scope.clone(), 1)
+    ));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 100));
+    assert_err!(
+        Asset::transfer(alice.clone(), ticker, ferdie_did, 100),
+        AssetError::<TestStorage>::InvalidTransfer
+    );
+    assert_ok!(Asset::transfer(alice, ticker, dave_did, 100));
+}
+
+#[test]
+fn nested_rules() {
+    ExtBuilder::default().build().execute_with(nested_rules_we);
+}
+
+fn nested_rules_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let _alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let charlie_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+    let eve_did = register_keyring_account(AccountKeyring::Eve).unwrap();
+
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_default_trusted_claim_issuer(
+        alice.clone(),
+        ticker,
+        dave_did
+    ));
+
+    // Receivers must be (Accredited AND KYC) OR Affiliate.
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
+    let receiver_rule: Rule = RuleType::Any(vec![
+        RuleType::All(vec![
+            RuleType::IsPresent(Claim::Accredited(scope.clone())).into(),
+            RuleType::IsPresent(Claim::KnowYourCustomer(scope.clone())).into(),
+        ])
+        .into(),
+        RuleType::IsPresent(Claim::Affiliate(scope.clone())).into(),
+    ])
+    .into();
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![receiver_rule]
+    ));
+
+    let add_claim = |target, claim| {
+        assert_ok!(Identity::add_claim(dave.clone(), target, claim, None));
+    };
+    add_claim(bob_did, Claim::Accredited(scope.clone()));
+    add_claim(bob_did, Claim::KnowYourCustomer(scope.clone()));
+    add_claim(charlie_did, Claim::Affiliate(scope.clone()));
+    add_claim(eve_did, Claim::Accredited(scope.clone()));
+
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 100));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, charlie_did, 100));
+    assert_err!(
+        Asset::transfer(alice.clone(), ticker, eve_did, 100),
+        AssetError::<TestStorage>::InvalidTransfer
+    );
+
+    // Rule trees are limited in depth and size.
+    let mut deep_rule: Rule = RuleType::IsPresent(Claim::Affiliate(scope.clone())).into();
+    for _ in 0..compliance_manager::MAX_RULE_DEPTH {
+        deep_rule = RuleType::Not(Box::new(deep_rule)).into();
+    }
+    assert_err!(
+        ComplianceManager::add_active_rule(alice.clone(), ticker, vec![deep_rule], vec![]),
+        CMError::<TestStorage>::RuleTooDeep
+    );
+    let large_rules = (0..=compliance_manager::MAX_RULE_SIZE)
+        .map(|_| RuleType::IsPresent(Claim::Affiliate(scope.clone())).into())
+        .collect::<Vec<Rule>>();
+    assert_err!(
+        ComplianceManager::add_active_rule(alice, ticker, large_rules, vec![]),
+        CMError::<TestStorage>::TooManyRules
+    );
+}
+
+#[test]
+fn value_rules() {
+    ExtBuilder::default().build().execute_with(value_rules_we);
+}
+
+fn value_rules_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let _alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let charlie_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_default_trusted_claim_issuer(
+        alice.clone(),
+        ticker,
+        dave_did
+    ));
+
+    // Receivers must be in US or CA, accredited for a week, not locked up and hold less than
+    // 1000 tokens after the transfer.
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
+    let receiver_rules: Vec<Rule> = vec![
+        RuleType::IsJurisdictionIn(scope.clone(), vec![b"US".into(), b"CA".into()]).into(),
+        RuleType::IsIssuedDaysAgo(Claim::Accredited(scope.clone()), 7).into(),
+        RuleType::IsNotLockedUp(Claim::BuyLockup(scope.clone())).into(),
+        RuleType::IsBalanceBelow(1_000).into(),
+    ];
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        receiver_rules
+    ));
+
+    let day = predicate::MILLISECONDS_PER_DAY;
+    Timestamp::set_timestamp(day);
+    let add_claim = |target, claim, expiry| {
+        assert_ok!(Identity::add_claim(dave.clone(), target, claim, expiry));
+    };
+    add_claim(
+        bob_did,
+        Claim::Jurisdiction(b"ca".into(), scope.clone()),
+        None,
+    );
+    add_claim(bob_did, Claim::Accredited(scope.clone()), None);
+    add_claim(bob_did, Claim::BuyLockup(scope.clone()), Some(11 * day));
+    add_claim(
+        charlie_did,
+        Claim::Jurisdiction(b"FR".into(), scope.clone()),
+        None,
+    );
+    add_claim(charlie_did, Claim::Accredited(scope.clone()), None);
+
+    let assert_invalid_transfer = |to, amount| {
+        assert_err!(
+            Asset::transfer(alice.clone(), ticker, to, amount),
+            AssetError::<TestStorage>::InvalidTransfer
+        );
+    };
+
+    // Accreditation is too recent.
+    assert_invalid_transfer(bob_did, 500);
+
+    // Bob is still locked up.
+    Timestamp::set_timestamp(9 * day);
+    assert_invalid_transfer(bob_did, 500);
+
+    Timestamp::set_timestamp(12 * day);
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 500));
+
+    // Bob would reach the balance threshold.
+    assert_invalid_transfer(bob_did, 500);
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 499));
+
+    // Charlie is out of the allowed jurisdictions.
+    assert_invalid_transfer(charlie_did, 500);
+}
+
+#[test]
+fn trusted_issuer_scopes() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(trusted_issuer_scopes_we);
+}
+
+fn trusted_issuer_scopes_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let _alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+    let eve = Origin::signed(AccountKeyring::Eve.public());
+    let eve_did = register_keyring_account(AccountKeyring::Eve).unwrap();
+
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![
+            RuleType::IsPresent(Claim::KnowYourCustomer(scope.clone())).into(),
+            RuleType::IsPresent(Claim::Accredited(scope.clone())).into(),
+        ]
+    ));
+    assert_ok!(ComplianceManager::add_default_trusted_claim_issuer(
+        alice.clone(),
+        ticker,
+        dave_did
+    ));
+    assert_ok!(Identity::add_claim(
+        dave.clone(),
+        bob_did,
+        Claim::KnowYourCustomer(scope.clone()),
+        None
+    ));
+    assert_ok!(Identity::add_claim(
+        dave,
+        bob_did,
+        Claim::Accredited(scope.clone()),
+        None
+    ));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 100));
+
+    // Only KYC claims are trusted from Dave.
+    let kyc_only = TrustedIssuerScope {
+        claim_types: vec![ClaimType::KnowYourCustomer],
+        ..Default::default()
+    };
+    assert_err!(
+        ComplianceManager::set_trusted_issuer_scope(bob, ticker, dave_did, kyc_only.clone()),
+        CMError::<TestStorage>::Unauthorized
+    );
+    assert_ok!(ComplianceManager::set_trusted_issuer_scope(
+        alice.clone(),
+        ticker,
+        dave_did,
+        kyc_only
+    ));
+    assert_err!(
+        Asset::transfer(alice.clone(), ticker, bob_did, 100),
+        AssetError::<TestStorage>::InvalidTransfer
+    );
+
+    // Accreditation is trusted from Eve until the moment 1000.
+    let invalid_window = TrustedIssuerScope {
+        claim_types: vec![ClaimType::Accredited],
+        valid_from: Some(1_000),
+        valid_until: Some(1_000),
+    };
+    assert_err!(
+        ComplianceManager::set_trusted_issuer_scope(alice.clone(), ticker, eve_did, invalid_window),
+        CMError::<TestStorage>::InvalidValidityWindow
+    );
+    let accredited_until_1000 = TrustedIssuerScope {
+        claim_types: vec![ClaimType::Accredited],
+        valid_from: None,
+        valid_until: Some(1_000),
+    };
+    assert_ok!(ComplianceManager::set_trusted_issuer_scope(
+        alice.clone(),
+        ticker,
+        eve_did,
+        accredited_until_1000
+    ));
+    assert_ok!(ComplianceManager::add_default_trusted_claim_issuer(
+        alice.clone(),
+        ticker,
+        eve_did
+    ));
+    assert_ok!(Identity::add_claim(
+        eve,
+        bob_did,
+        Claim::Accredited(scope.clone()),
+        None
+    ));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 100));
+
+    Timestamp::set_timestamp(1_000);
+    assert_err!(
+        Asset::transfer(alice.clone(), ticker, bob_did, 100),
+        AssetError::<TestStorage>::InvalidTransfer
+    );
+
+    // The default scope lifts the restrictions on Dave.
+    assert_ok!(ComplianceManager::set_trusted_issuer_scope(
+        alice.clone(),
+        ticker,
+        dave_did,
+        TrustedIssuerScope::default()
+    ));
+    assert_eq!(
+        ComplianceManager::trusted_issuer_scope(&ticker, &dave_did),
+        TrustedIssuerScope::default()
+    );
+    assert_ok!(Asset::transfer(alice, ticker, bob_did, 100));
+}
+
+#[test]
+fn rule_templates() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(rule_templates_we);
+}
+
+fn rule_templates_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let _alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let charlie_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+
+    let create_asset = |name: &[u8]| {
+        let ticker = Ticker::try_from(name).unwrap();
+        assert_ok!(Asset::create_asset(
+            alice.clone(),
+            name.into(),
+            ticker,
+            1_000_000,
+            true,
+            AssetType::default(),
+            vec![],
+            None
+        ));
+        ticker
+    };
+    let acme = create_asset(b"ACME");
+    let beta = create_asset(b"BETA");
+
+    // Dave publishes a template whose claims are scoped to his own identity.
+    let name = RuleTemplateName::from(b"RegD");
+    let receiver_rule = |claim| Rule {
+        rule_type: RuleType::IsPresent(claim),
+        issuers: vec![dave_did],
+    };
+    assert_err!(
+        ComplianceManager::set_rule_template(dave.clone(), name.clone(), vec![(vec![], vec![])]),
+        CMError::<TestStorage>::EmptyTemplateRule
+    );
+    assert_ok!(ComplianceManager::set_rule_template(
+        dave.clone(),
+        name.clone(),
+        vec![(
+            vec![],
+            vec![receiver_rule(Claim::Accredited(dave_did.into()))]
+        )]
+    ));
+    assert_eq!(
+        ComplianceManager::rule_template_version((dave_did, name.clone())),
+        1
+    );
+    assert_ok!(Identity::add_claim(
+        dave.clone(),
+        bob_did,
+        Claim::Accredited(dave_did.into()),
+        None
+    ));
+    assert_ok!(Identity::add_claim(
+        dave.clone(),
+        charlie_did,
+        Claim::Affiliate(dave_did.into()),
+        None
+    ));
+
+    // ACME follows the template, BETA pins its first version.
+    assert_err!(
+        ComplianceManager::subscribe_rule_template(
+            alice.clone(),
+            acme,
+            dave_did,
+            b"RegS".into(),
+            None
+        ),
+        CMError::<TestStorage>::NoSuchRuleTemplate
+    );
+    assert_err!(
+        ComplianceManager::subscribe_rule_template(
+            alice.clone(),
+            beta,
+            dave_did,
+            name.clone(),
+            Some(2)
+        ),
+        CMError::<TestStorage>::NoSuchRuleTemplateVersion
+    );
+    assert_ok!(ComplianceManager::subscribe_rule_template(
+        alice.clone(),
+        acme,
+        dave_did,
+        name.clone(),
+        None
+    ));
+    assert_ok!(ComplianceManager::subscribe_rule_template(
+        alice.clone(),
+        beta,
+        dave_did,
+        name.clone(),
+        Some(1)
+    ));
+
+    let assert_transfers = |ticker, bob_allowed: bool, charlie_allowed: bool| {
+        for (did, allowed) in vec![(bob_did, bob_allowed), (charlie_did, charlie_allowed)] {
+            let result = Asset::transfer(alice.clone(), ticker, did, 100);
+            if allowed {
+                assert_ok!(result);
+            } else {
+                assert_err!(result, AssetError::<TestStorage>::InvalidTransfer);
+            }
+        }
+    };
+    assert_transfers(acme, true, false);
+    assert_transfers(beta, true, false);
+
+    // Updates of the template apply only to ACME.
+    assert_ok!(ComplianceManager::set_rule_template(
+        dave,
+        name.clone(),
+        vec![(
+            vec![],
+            vec![receiver_rule(Claim::Affiliate(dave_did.into()))]
+        )]
+    ));
+    assert_transfers(acme, false, true);
+    assert_transfers(beta, true, false);
+
+    // Template rules are merged with the rules of the ticker.
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        acme,
+        vec![],
+        vec![receiver_rule(Claim::Accredited(dave_did.into()))]
+    ));
+    assert_transfers(acme, true, true);
+
+    assert_ok!(ComplianceManager::unsubscribe_rule_template(
+        alice.clone(),
+        acme,
+        dave_did,
+        name.clone()
+    ));
+    assert_err!(
+        ComplianceManager::unsubscribe_rule_template(alice.clone(), acme, dave_did, name),
+        CMError::<TestStorage>::NotSubscribedToRuleTemplate
+    );
+    assert_transfers(acme, true, false);
+}
+
+#[test]
+fn custom_claim_types() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(custom_claim_types_we);
+}
+
+fn custom_claim_types_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let _alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    let scope = Scope::from(ticker);
+
+    // Dave registers a new claim type and pays its protocol fee.
+    let dave_balance = Balances::free_balance(&AccountKeyring::Dave.public());
+    assert_ok!(Identity::register_custom_claim_type(
+        dave.clone(),
+        b"QualifiedPurchaser".to_vec()
+    ));
+    assert_eq!(
+        Balances::free_balance(&AccountKeyring::Dave.public()),
+        dave_balance - PROTOCOL_OP_BASE_FEE
+    );
+    let qualified_purchaser = CustomClaimTypeId(0);
+    assert_eq!(
+        Identity::custom_claim_type_id(b"QualifiedPurchaser".to_vec()),
+        Some(qualified_purchaser)
+    );
+    assert_err!(
+        Identity::register_custom_claim_type(dave.clone(), b"QualifiedPurchaser".to_vec()),
+        IdentityError::<TestStorage>::CustomClaimTypeAlreadyExists
+    );
+    assert_err!(
+        Identity::register_custom_claim_type(dave.clone(), vec![]),
+        IdentityError::<TestStorage>::InvalidCustomClaimTypeName
+    );
+
+    // Only registered claim types can be issued and referenced by rules.
+    let unregistered = CustomClaimTypeId(1);
+    assert_err!(
+        Identity::add_claim(
+            dave.clone(),
+            bob_did,
+            Claim::Custom(unregistered, scope.clone()),
+            None
+        ),
+        IdentityError::<TestStorage>::CustomClaimTypeDoesNotExist
+    );
+    let receiver_rule = |claim| Rule {
+        rule_type: RuleType::Not(Box::new(RuleType::IsAbsent(claim).into())),
+        issuers: vec![dave_did],
+    };
+    assert_err!(
+        ComplianceManager::add_active_rule(
+            alice.clone(),
+            ticker,
+            vec![],
+            vec![receiver_rule(Claim::Custom(unregistered, scope.clone()))]
+        ),
+        CMError::<TestStorage>::CustomClaimTypeDoesNotExist
+    );
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![Rule {
+            rule_type: RuleType::IsPresent(Claim::Custom(qualified_purchaser, scope.clone())),
+            issuers: vec![dave_did],
+        }]
+    ));
+
+    assert_err!(
+        Asset::transfer(alice.clone(), ticker, bob_did, 100),
+        AssetError::<TestStorage>::InvalidTransfer
+    );
+    assert_ok!(Identity::add_claim(
+        dave.clone(),
+        bob_did,
+        Claim::Custom(qualified_purchaser, scope),
+        None
+    ));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 100));
+}
+
+#[test]
+fn exemptions() {
+    ExtBuilder::default().build().execute_with(exemptions_we);
+}
+
+fn exemptions_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let charlie_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    let scope = Scope::from(ticker);
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![
+            Rule {
+                rule_type: RuleType::IsPresent(Claim::KnowYourCustomer(scope.clone())),
+                issuers: vec![dave_did],
+            },
+            Rule {
+                rule_type: RuleType::IsPresent(Claim::Accredited(scope.clone())),
+                issuers: vec![dave_did],
+            },
+        ]
+    ));
+    assert_ok!(Identity::add_claim(
+        dave.clone(),
+        bob_did,
+        Claim::KnowYourCustomer(scope.clone()),
+        None
+    ));
+    let assert_invalid_transfer = |to| {
+        assert_err!(
+            Asset::transfer(alice.clone(), ticker, to, 100),
+            AssetError::<TestStorage>::InvalidTransfer
+        );
+    };
+    assert_invalid_transfer(bob_did);
+    assert_invalid_transfer(charlie_did);
+
+    // Only the owner of the asset can exempt identities.
+    assert_err!(
+        Exemption::modify_exemption_list(
+            bob.clone(),
+            ticker,
+            Some(ClaimType::Accredited),
+            bob_did,
+            true
+        ),
+        ExemptionError::<TestStorage>::NotAnOwner
+    );
+
+    // Bob is exempted from the accreditation rule, and Charlie from all the rules.
+    assert_ok!(Exemption::modify_exemption_list(
+        alice.clone(),
+        ticker,
+        Some(ClaimType::Accredited),
+        bob_did,
+        true
+    ));
+    assert_err!(
+        Exemption::modify_exemption_list(
+            alice.clone(),
+            ticker,
+            Some(ClaimType::Accredited),
+            bob_did,
+            true
+        ),
+        ExemptionError::<TestStorage>::NoChange
+    );
+    assert_ok!(Exemption::modify_exemption_list(
+        alice.clone(),
+        ticker,
+        None,
+        charlie_did,
+        true
+    ));
+    assert_eq!(
+        Exemption::exemption_list(ticker),
+        vec![(bob_did, Some(ClaimType::Accredited)), (charlie_did, None)]
+    );
+    let report = Asset::transfer_report(
+        AccountKeyring::Alice.public(),
+        ticker,
+        Some(alice_did),
+        Some(bob_did),
+        100,
+    );
+    let receiver_rules = &report.compliance.rules[0].receiver_rules;
+    assert!(report.compliance.result);
+    assert_eq!(
+        receiver_rules
+            .iter()
+            .map(|rule_report| (rule_report.result, rule_report.exempted))
+            .collect::<Vec<_>>(),
+        vec![(true, false), (true, true)]
+    );
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 100));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, charlie_did, 100));
+
+    // The exemption of Bob is lifted.
+    assert_ok!(Exemption::modify_exemption_list(
+        alice.clone(),
+        ticker,
+        Some(ClaimType::Accredited),
+        bob_did,
+        false
+    ));
+    assert_eq!(Exemption::exemption_list(ticker), vec![(charlie_did, None)]);
+    assert_invalid_transfer(bob_did);
+
+    // A rule is exempted only if every claim type it checks is exempted, and rules which check no
+    // claim are never exempted.
+    assert_ok!(ComplianceManager::reset_active_rules(alice.clone(), ticker));
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![
+            Rule {
+                rule_type: RuleType::IsAnyOf(vec![
+                    Claim::Accredited(scope.clone()),
+                    Claim::Affiliate(scope)
+                ]),
+                issuers: vec![dave_did],
+            },
+            RuleType::IsBalanceBelow(150).into(),
+        ]
+    ));
+    assert_ok!(Exemption::modify_exemption_list(
+        alice.clone(),
+        ticker,
+        Some(ClaimType::Accredited),
+        bob_did,
+        true
+    ));
+    assert_err!(
+        Asset::transfer(alice.clone(), ticker, bob_did, 10),
+        AssetError::<TestStorage>::InvalidTransfer
+    );
+    assert_ok!(Exemption::modify_exemption_list(
+        alice.clone(),
+        ticker,
+        Some(ClaimType::Affiliate),
+        bob_did,
+        true
+    ));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 10));
+    assert_invalid_transfer(charlie_did);
+    assert_ok!(Asset::transfer(alice, ticker, charlie_did, 10));
+}
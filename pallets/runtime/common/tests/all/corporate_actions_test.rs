@@ -0,0 +1,448 @@
+use super::{
+    storage::{register_keyring_account, TestStorage},
+    ExtBuilder,
+};
+
+use pallet_asset::{self as asset, AssetType};
+use pallet_balances as balances;
+use pallet_compliance_manager as compliance_manager;
+use polymesh_primitives::{IdentityId, PosRatio, Ticker};
+use polymesh_runtime_common::corporate_actions::{
+    self, CorporateActionKind, DistributionCurrency, TargetIdentities, TargetTreatment,
+};
+
+use frame_support::{assert_err, assert_ok};
+use sp_runtime::{traits::OnInitialize, Permill};
+use std::convert::TryFrom;
+use test_client::AccountKeyring;
+
+type Asset = asset::Module<TestStorage>;
+type Balances = balances::Module<TestStorage>;
+type ComplianceManager = compliance_manager::Module<TestStorage>;
+type CorporateActions = corporate_actions::Module<TestStorage>;
+type Error = corporate_actions::Error<TestStorage>;
+type Origin = <TestStorage as frame_system::Trait>::Origin;
+type System = frame_system::Module<TestStorage>;
+type Timestamp = pallet_timestamp::Module<TestStorage>;
+
+/// Creates an asset owned by `owner` which allows every transfer.
+fn create_asset(owner: AccountKeyring, name: &[u8]) -> Ticker {
+    let ticker = Ticker::try_from(name).unwrap();
+    assert_ok!(Asset::create_asset(
+        Origin::signed(owner.public()),
+        name.into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_active_rule(
+        Origin::signed(owner.public()),
+        ticker,
+        vec![],
+        vec![]
+    ));
+    ticker
+}
+
+/// Initiates an action of `owner` on `ticker` which targets everyone and captures the holdings
+/// at `ex_date`.
+fn initiate(
+    owner: AccountKeyring,
+    ticker: Ticker,
+    kind: CorporateActionKind<u128>,
+    ex_date: u64,
+    expires_at: Option<u64>,
+    withholding_tax: Vec<(IdentityId, Permill)>,
+) -> u32 {
+    let action_id = CorporateActions::action_count(ticker);
+    assert_ok!(CorporateActions::initiate(
+        Origin::signed(owner.public()),
+        ticker,
+        kind,
+        ex_date,
+        ex_date,
+        expires_at,
+        TargetIdentities::default(),
+        Permill::zero(),
+        withholding_tax
+    ));
+    action_id
+}
+
+#[test]
+fn cash_distribution_with_withholding_tax() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(cash_distribution_with_withholding_tax_we);
+}
+
+fn cash_distribution_with_withholding_tax_we() {
+    let alice = AccountKeyring::Alice;
+    let alice_did = register_keyring_account(alice).unwrap();
+    let bob = AccountKeyring::Bob;
+    let bob_did = register_keyring_account(bob).unwrap();
+    Timestamp::set_timestamp(1_000);
+
+    let ticker = create_asset(alice, b"ACME");
+    assert_ok!(Asset::transfer(
+        Origin::signed(alice.public()),
+        ticker,
+        bob_did,
+        250_000
+    ));
+    assert_ok!(Balances::top_up_identity_balance(
+        Origin::signed(alice.public()),
+        alice_did,
+        10_000
+    ));
+
+    let action_id = initiate(
+        alice,
+        ticker,
+        CorporateActionKind::CashDistribution(DistributionCurrency::Polyx, 1_000),
+        1_000,
+        Some(2_000),
+        vec![(bob_did, Permill::from_percent(10))],
+    );
+    // The funds are reserved and the holdings are captured right away.
+    assert_eq!(Balances::identity_balance(alice_did), 9_000);
+    let action = CorporateActions::actions(ticker, action_id).unwrap();
+    assert_eq!(action.checkpoint_id, Some(1));
+
+    // Tokens bought after the ex-date carry no entitlement.
+    assert_ok!(Asset::transfer(
+        Origin::signed(alice.public()),
+        ticker,
+        bob_did,
+        250_000
+    ));
+
+    assert_ok!(CorporateActions::claim(
+        Origin::signed(bob.public()),
+        ticker,
+        action_id
+    ));
+    // The withheld tax is paid to the owner, the default tax receiver.
+    assert_eq!(Balances::identity_balance(bob_did), 225);
+    assert_eq!(Balances::identity_balance(alice_did), 9_025);
+    assert_eq!(
+        CorporateActions::withheld_tax((ticker, action_id), bob_did),
+        25
+    );
+    assert_err!(
+        CorporateActions::claim(Origin::signed(bob.public()), ticker, action_id),
+        Error::AlreadyProcessed
+    );
+
+    assert_err!(
+        CorporateActions::reclaim(Origin::signed(alice.public()), ticker, action_id),
+        Error::NotExpired
+    );
+    Timestamp::set_timestamp(2_001);
+    assert_err!(
+        CorporateActions::claim(Origin::signed(alice.public()), ticker, action_id),
+        Error::Expired
+    );
+
+    // The unclaimed funds return to the owner.
+    assert_ok!(CorporateActions::reclaim(
+        Origin::signed(alice.public()),
+        ticker,
+        action_id
+    ));
+    assert_eq!(Balances::identity_balance(alice_did), 9_775);
+    assert_eq!(
+        CorporateActions::actions(ticker, action_id)
+            .unwrap()
+            .remaining,
+        0
+    );
+}
+
+#[test]
+fn splits_and_mergers() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(splits_and_mergers_we);
+}
+
+fn splits_and_mergers_we() {
+    let alice = AccountKeyring::Alice;
+    let alice_did = register_keyring_account(alice).unwrap();
+    let bob = AccountKeyring::Bob;
+    let bob_did = register_keyring_account(bob).unwrap();
+    Timestamp::set_timestamp(1_000);
+
+    let ticker = create_asset(alice, b"ACME");
+    let new_ticker = create_asset(alice, b"NEWCO");
+    assert_ok!(Asset::transfer(
+        Origin::signed(alice.public()),
+        ticker,
+        bob_did,
+        100_000
+    ));
+    let apply = |action_id| {
+        assert_ok!(CorporateActions::apply_reorganisation(
+            Origin::signed(alice.public()),
+            ticker,
+            action_id,
+            10
+        ));
+    };
+
+    // Forward split. It is applied by the owner to every holder.
+    let split_id = initiate(
+        alice,
+        ticker,
+        CorporateActionKind::Split(PosRatio(3, 1)),
+        1_000,
+        None,
+        vec![],
+    );
+    assert_err!(
+        CorporateActions::claim(Origin::signed(bob.public()), ticker, split_id),
+        Error::AppliedByOwner
+    );
+    assert_err!(
+        CorporateActions::apply_reorganisation(Origin::signed(bob.public()), ticker, split_id, 10),
+        Error::Unauthorized
+    );
+    apply(split_id);
+    assert_eq!(Asset::balance(&ticker, &alice_did), 2_700_000);
+    assert_eq!(Asset::balance(&ticker, &bob_did), 300_000);
+    assert_err!(
+        CorporateActions::apply_reorganisation(
+            Origin::signed(alice.public()),
+            ticker,
+            split_id,
+            10
+        ),
+        Error::NoHoldersLeft
+    );
+
+    // Reverse split.
+    let reverse_split_id = initiate(
+        alice,
+        ticker,
+        CorporateActionKind::Split(PosRatio(1, 2)),
+        1_000,
+        None,
+        vec![],
+    );
+    apply(reverse_split_id);
+    assert_eq!(Asset::balance(&ticker, &alice_did), 1_350_000);
+    assert_eq!(Asset::balance(&ticker, &bob_did), 150_000);
+
+    // Merger into another asset of the same owner. Shares sold after the ex-date are neither
+    // redeemed from the seller nor converted for it.
+    let merger_id = initiate(
+        alice,
+        ticker,
+        CorporateActionKind::Merger(new_ticker, PosRatio(2, 3)),
+        1_000,
+        None,
+        vec![],
+    );
+    assert_ok!(Asset::transfer(
+        Origin::signed(bob.public()),
+        ticker,
+        alice_did,
+        50_000
+    ));
+    apply(merger_id);
+    assert_eq!(Asset::balance(&ticker, &alice_did), 50_000);
+    assert_eq!(Asset::balance(&new_ticker, &alice_did), 1_900_000);
+    assert_eq!(Asset::balance(&ticker, &bob_did), 0);
+    assert_eq!(Asset::balance(&new_ticker, &bob_did), 66_666);
+    assert_eq!(Asset::token_details(&ticker).total_supply, 50_000);
+    assert_eq!(Asset::token_details(&new_ticker).total_supply, 1_966_666);
+}
+
+#[test]
+fn ex_date_and_cancellation() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(ex_date_and_cancellation_we);
+}
+
+fn ex_date_and_cancellation_we() {
+    let alice = AccountKeyring::Alice;
+    let alice_did = register_keyring_account(alice).unwrap();
+    let bob = AccountKeyring::Bob;
+    let bob_did = register_keyring_account(bob).unwrap();
+    // Blocks are produced every 10 ms, slower than the 6 ms expected by the test runtime.
+    System::set_block_number(100);
+    Timestamp::set_timestamp(1_000);
+    let advance_to = |timestamp: u64| {
+        while Timestamp::get() < timestamp {
+            let block_number = System::block_number() + 1;
+            System::set_block_number(block_number);
+            Timestamp::set_timestamp(block_number * 10);
+            CorporateActions::on_initialize(block_number);
+        }
+    };
+
+    let ticker = create_asset(alice, b"ACME");
+    assert_ok!(Asset::transfer(
+        Origin::signed(alice.public()),
+        ticker,
+        bob_did,
+        100_000
+    ));
+    assert_ok!(Balances::top_up_identity_balance(
+        Origin::signed(alice.public()),
+        alice_did,
+        1_000
+    ));
+
+    assert_err!(
+        CorporateActions::initiate(
+            Origin::signed(bob.public()),
+            ticker,
+            CorporateActionKind::StockDividend(PosRatio(1, 10)),
+            2_000,
+            2_000,
+            None,
+            TargetIdentities::default(),
+            Permill::zero(),
+            vec![]
+        ),
+        Error::Unauthorized
+    );
+    assert_err!(
+        CorporateActions::initiate(
+            Origin::signed(alice.public()),
+            ticker,
+            CorporateActionKind::CashDistribution(DistributionCurrency::Polyx, 5_000),
+            2_000,
+            2_000,
+            None,
+            TargetIdentities::default(),
+            Permill::zero(),
+            vec![]
+        ),
+        Error::InsufficientFunds
+    );
+
+    // A distribution cancelled before its ex-date refunds the owner.
+    let cancelled_id = initiate(
+        alice,
+        ticker,
+        CorporateActionKind::CashDistribution(DistributionCurrency::Polyx, 1_000),
+        2_000,
+        None,
+        vec![],
+    );
+    assert_eq!(Balances::identity_balance(alice_did), 0);
+    assert_err!(
+        CorporateActions::claim(Origin::signed(bob.public()), ticker, cancelled_id),
+        Error::BeforeRecordDate
+    );
+    assert_ok!(CorporateActions::cancel(
+        Origin::signed(alice.public()),
+        ticker,
+        cancelled_id
+    ));
+    assert_eq!(Balances::identity_balance(alice_did), 1_000);
+    assert_eq!(CorporateActions::actions(ticker, cancelled_id), None);
+
+    // Only the targeted identities are entitled.
+    let action_id = CorporateActions::action_count(ticker);
+    assert_ok!(CorporateActions::initiate(
+        Origin::signed(alice.public()),
+        ticker,
+        CorporateActionKind::StockDividend(PosRatio(1, 10)),
+        2_000,
+        2_000,
+        None,
+        TargetIdentities {
+            identities: vec![alice_did],
+            treatment: TargetTreatment::Include,
+        },
+        Permill::zero(),
+        vec![]
+    ));
+
+    assert_eq!(CorporateActions::pending_action_count(ticker), 1);
+
+    // The holdings are captured in the first block after the ex-date.
+    advance_to(1_990);
+    assert_eq!(
+        CorporateActions::actions(ticker, action_id)
+            .unwrap()
+            .checkpoint_id,
+        None
+    );
+    advance_to(2_000);
+    assert_eq!(
+        CorporateActions::actions(ticker, action_id)
+            .unwrap()
+            .checkpoint_id,
+        Some(1)
+    );
+    assert_eq!(CorporateActions::pending_action_count(ticker), 0);
+    assert_err!(
+        CorporateActions::cancel(Origin::signed(alice.public()), ticker, action_id),
+        Error::AlreadyCaptured
+    );
+    assert_err!(
+        CorporateActions::claim(Origin::signed(bob.public()), ticker, action_id),
+        Error::NotTargeted
+    );
+    assert_ok!(CorporateActions::claim(
+        Origin::signed(alice.public()),
+        ticker,
+        action_id
+    ));
+    assert_eq!(Asset::balance(&ticker, &alice_did), 990_000);
+}
+
+#[test]
+fn pending_actions_limit() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(pending_actions_limit_we);
+}
+
+fn pending_actions_limit_we() {
+    let alice = AccountKeyring::Alice;
+    let _alice_did = register_keyring_account(alice).unwrap();
+    Timestamp::set_timestamp(1_000);
+    let ticker = create_asset(alice, b"ACME");
+    let stock_dividend = CorporateActionKind::StockDividend(PosRatio(1, 10));
+
+    // A ticker has a limited number of actions whose holdings are not captured yet.
+    let action_ids = (0..corporate_actions::MAX_PENDING_ACTIONS)
+        .map(|_| initiate(alice, ticker, stock_dividend.clone(), 5_000, None, vec![]))
+        .collect::<Vec<_>>();
+    assert_err!(
+        CorporateActions::initiate(
+            Origin::signed(alice.public()),
+            ticker,
+            stock_dividend.clone(),
+            5_000,
+            5_000,
+            None,
+            TargetIdentities::default(),
+            Permill::zero(),
+            vec![]
+        ),
+        Error::TooManyPendingActions
+    );
+
+    // Cancelled actions and actions whose ex-date has passed do not count.
+    assert_ok!(CorporateActions::cancel(
+        Origin::signed(alice.public()),
+        ticker,
+        action_ids[0]
+    ));
+    initiate(alice, ticker, stock_dividend.clone(), 1_000, None, vec![]);
+    initiate(alice, ticker, stock_dividend, 5_000, None, vec![]);
+    assert_eq!(
+        CorporateActions::pending_action_count(ticker),
+        corporate_actions::MAX_PENDING_ACTIONS
+    );
+}
@@ -1,10 +1,10 @@
 use super::{
-    storage::{make_account, TestStorage},
+    storage::{make_account, register_keyring_account, TestStorage},
     ExtBuilder,
 };
 
 use polymesh_common_utilities::traits::CommonTrait;
-use polymesh_primitives::Ticker;
+use polymesh_primitives::{Claim, Scope, Ticker};
 use polymesh_runtime_common::{
     dividend::{self, Dividend},
     simple_token::{self, SimpleTokenRecord},
@@ -13,9 +13,11 @@ use polymesh_runtime_common::{
 use pallet_asset::{self as asset, AssetType, SecurityToken};
 use pallet_balances as balances;
 use pallet_compliance_manager as compliance_manager;
+use pallet_identity as identity;
 
-use frame_support::{assert_ok, traits::Currency};
+use frame_support::{assert_err, assert_ok, traits::Currency};
 use frame_system::ensure_signed;
+use sp_runtime::Permill;
 
 use chrono::{prelude::*, Duration};
 use lazy_static::lazy_static;
@@ -48,6 +50,8 @@ type Balances = balances::Module<TestStorage>;
 type Asset = asset::Module<TestStorage>;
 type ComplianceManager = compliance_manager::Module<TestStorage>;
 type SimpleToken = simple_token::Module<TestStorage>;
+type Identity = identity::Module<TestStorage>;
+type Origin = <TestStorage as frame_system::Trait>::Origin;
 
 #[test]
 fn correct_dividend_must_work() {
@@ -164,7 +168,8 @@ fn correct_dividend_must_work() {
             dividend.matures_at.clone().unwrap(),
             dividend.expires_at.clone().unwrap(),
             dividend.payout_currency.clone(),
-            dividend.checkpoint_id
+            dividend.checkpoint_id,
+            None
         ));
 
         // Compare created dividend with the expected structure
@@ -189,3 +194,233 @@ fn correct_dividend_must_work() {
         assert_eq!(current_entry.amount_left, current_entry.amount - share);
     });
 }
+
+#[test]
+fn withholding_tax_must_work() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(withholding_tax_must_work_we);
+}
+
+fn withholding_tax_must_work_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let charlie = Origin::signed(AccountKeyring::Charlie.public());
+    let charlie_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+    let eve_did = register_keyring_account(AccountKeyring::Eve).unwrap();
+
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![]
+    ));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 100_000));
+    assert_ok!(Asset::transfer(alice.clone(), ticker, charlie_did, 100_000));
+    let payout_ticker = Ticker::try_from(&b"USD"[..]).unwrap();
+    assert_ok!(SimpleToken::create_token(
+        alice.clone(),
+        payout_ticker,
+        1_000_000
+    ));
+
+    // Only the token owner configures the withholding tax.
+    assert_err!(
+        DividendModule::set_default_withholding_tax(bob.clone(), ticker, Permill::from_percent(10)),
+        dividend::Error::<TestStorage>::NotAnOwner
+    );
+    assert_ok!(DividendModule::set_default_withholding_tax(
+        alice.clone(),
+        ticker,
+        Permill::from_percent(10)
+    ));
+    assert_ok!(DividendModule::set_did_withholding_tax(
+        alice.clone(),
+        ticker,
+        bob_did,
+        Some(Permill::from_percent(20))
+    ));
+    assert_ok!(DividendModule::set_jurisdiction_withholding_tax(
+        alice.clone(),
+        ticker,
+        b"Canada".into(),
+        Some(Permill::from_percent(5))
+    ));
+    assert_ok!(DividendModule::set_withholding_tax_receiver(
+        alice.clone(),
+        ticker,
+        Some(eve_did)
+    ));
+
+    // The jurisdiction of Charlie is attested by a trusted claim issuer of the ticker.
+    assert_ok!(ComplianceManager::add_default_trusted_claim_issuer(
+        alice.clone(),
+        ticker,
+        dave_did
+    ));
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
+    assert_ok!(Identity::add_claim(
+        dave,
+        charlie_did,
+        Claim::Jurisdiction(b"Canada".into(), scope.clone()),
+        None
+    ));
+    assert_eq!(
+        DividendModule::withholding_tax_of(&ticker, bob_did),
+        Permill::from_percent(20)
+    );
+    assert_eq!(
+        DividendModule::withholding_tax_of(&ticker, charlie_did),
+        Permill::from_percent(5)
+    );
+    assert_eq!(
+        DividendModule::withholding_tax_of(&ticker, alice_did),
+        Permill::from_percent(10)
+    );
+
+    assert_ok!(Asset::create_checkpoint(alice.clone(), ticker));
+    assert_ok!(DividendModule::new(
+        alice.clone(),
+        100_000,
+        ticker,
+        0,
+        0,
+        payout_ticker,
+        1,
+        None
+    ));
+
+    assert_ok!(DividendModule::claim(bob, ticker, 0));
+    assert_ok!(DividendModule::claim(charlie, ticker, 0));
+    assert_ok!(DividendModule::claim(alice, ticker, 0));
+    assert_eq!(SimpleToken::balance_of((payout_ticker, bob_did)), 8_000);
+    assert_eq!(SimpleToken::balance_of((payout_ticker, charlie_did)), 9_500);
+    assert_eq!(SimpleToken::balance_of((payout_ticker, alice_did)), 972_000);
+    assert_eq!(SimpleToken::balance_of((payout_ticker, eve_did)), 10_500);
+    assert_eq!(DividendModule::withheld_tax((ticker, 0), bob_did), 2_000);
+    assert_eq!(DividendModule::withheld_tax((ticker, 0), charlie_did), 500);
+    assert_eq!(DividendModule::withheld_tax((ticker, 0), alice_did), 8_000);
+    assert_eq!(
+        DividendModule::get_dividend(&ticker, 0)
+            .unwrap()
+            .amount_left,
+        0
+    );
+}
+
+#[test]
+fn push_payouts_must_work() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(push_payouts_must_work_we);
+}
+
+fn push_payouts_must_work_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let charlie = Origin::signed(AccountKeyring::Charlie.public());
+    let charlie_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+    let eve = Origin::signed(AccountKeyring::Eve.public());
+    let _eve_did = register_keyring_account(AccountKeyring::Eve).unwrap();
+
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![]
+    ));
+    for did in &[bob_did, charlie_did, dave_did] {
+        assert_ok!(Asset::transfer(alice.clone(), ticker, *did, 100_000));
+    }
+    assert_eq!(Asset::holder_count(&ticker), 4);
+
+    let payout_ticker = Ticker::try_from(&b"USD"[..]).unwrap();
+    assert_ok!(SimpleToken::create_token(
+        alice.clone(),
+        payout_ticker,
+        1_000_000
+    ));
+    assert_ok!(Asset::create_checkpoint(alice.clone(), ticker));
+    assert_ok!(DividendModule::new(
+        alice.clone(),
+        100_000,
+        ticker,
+        0,
+        0,
+        payout_ticker,
+        1,
+        None
+    ));
+
+    // Charlie claims its share before the payouts are pushed.
+    assert_ok!(DividendModule::claim(charlie, ticker, 0));
+    // The payout of Dave cannot be credited.
+    <simple_token::BalanceOf<TestStorage>>::insert((payout_ticker, dave_did), u128::max_value());
+
+    // Anyone can push the payouts, batch by batch.
+    assert_ok!(DividendModule::push_payouts(eve.clone(), ticker, 0, 2));
+    assert_eq!(DividendModule::payout_cursor((ticker, 0)), 2);
+    assert_eq!(SimpleToken::balance_of((payout_ticker, alice_did)), 970_000);
+    assert_eq!(SimpleToken::balance_of((payout_ticker, bob_did)), 10_000);
+    assert_eq!(SimpleToken::balance_of((payout_ticker, dave_did)), 0);
+
+    // A failed payout is recorded and skipped, and the cursor advances past it.
+    assert_ok!(DividendModule::push_payouts(eve.clone(), ticker, 0, 10));
+    assert_eq!(DividendModule::payout_cursor((ticker, 0)), 4);
+    assert_eq!(
+        SimpleToken::balance_of((payout_ticker, charlie_did)),
+        10_000
+    );
+    assert!(DividendModule::failed_payout((ticker, 0), dave_did));
+    assert_eq!(
+        DividendModule::get_dividend(&ticker, 0)
+            .unwrap()
+            .amount_left,
+        10_000
+    );
+
+    // Dave can still claim the payout once it can be credited.
+    <simple_token::BalanceOf<TestStorage>>::insert((payout_ticker, dave_did), 0);
+    assert_ok!(DividendModule::claim(dave, ticker, 0));
+    assert_eq!(SimpleToken::balance_of((payout_ticker, dave_did)), 10_000);
+    assert!(!DividendModule::failed_payout((ticker, 0), dave_did));
+    assert_eq!(
+        DividendModule::get_dividend(&ticker, 0)
+            .unwrap()
+            .amount_left,
+        0
+    );
+    assert_err!(
+        DividendModule::push_payouts(eve, ticker, 0, 10),
+        dividend::Error::<TestStorage>::NoHoldersLeft
+    );
+}
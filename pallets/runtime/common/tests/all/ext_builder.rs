@@ -51,6 +51,7 @@ impl Default for MockProtocolBaseFees {
             ProtocolOp::IdentityAddSigningItem,
             ProtocolOp::PipsPropose,
             ProtocolOp::VotingAddBallot,
+            ProtocolOp::IdentityRegisterCustomClaimType,
         ];
         let fees = ops
             .into_iter()
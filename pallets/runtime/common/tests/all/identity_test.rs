@@ -2,7 +2,7 @@ use super::{
     ext_builder::PROTOCOL_OP_BASE_FEE,
     storage::{
         add_signing_item, authorizations_to, get_identity_id, register_keyring_account,
-        register_keyring_account_with_balance, GovernanceCommittee, TestStorage,
+        register_keyring_account_with_balance, EventTest, GovernanceCommittee, TestStorage,
     },
     ExtBuilder,
 };
@@ -10,32 +10,51 @@ use super::{
 use polymesh_common_utilities::{
     traits::{
         group::GroupTrait,
-        identity::{SigningItemWithAuth, TargetIdAuthorization, Trait as IdentityTrait},
+        identity::{
+            RawEvent as IdentityRawEvent, SigningItemWithAuth, TargetIdAuthorization,
+            Trait as IdentityTrait,
+        },
     },
     SystematicIssuers,
 };
 use polymesh_primitives::{
-    AccountKey, AuthorizationData, AuthorizationError, Claim, ClaimType, IdentityClaim, IdentityId,
-    LinkData, Permission, Scope, Signatory, SigningItem, Ticker, TransactionError,
+    migration::{self, ClaimV1, IdentityClaimV1, IdentityV1},
+    AccountKey, AuthorizationData, AuthorizationError, Claim, ClaimType, Identity as DidRecord,
+    IdentityClaim, IdentityId, LinkData, PendingMasterKeyRotation, PendingMasterKeyRotationDelay,
+    Permission, PortfolioId, PortfolioKind, PortfolioName, RecoveryConfig, Scope, Signatory,
+    SigningItem, SpendingLimit, SpendingLimitKind, Ticker, TransactionError,
 };
+use polymesh_runtime_common::settlement::{self, Leg, LegAsset, SettlementType, VenueType};
 use polymesh_runtime_develop::{fee_details::CddHandler, runtime::Call};
 
+use pallet_asset::{self as asset, AssetName, AssetType};
 use pallet_balances as balances;
-use pallet_identity::{self as identity, BatchAddClaimItem, BatchRevokeClaimItem, Error};
+use pallet_identity::{
+    self as identity, BatchAddClaimItem, BatchRevokeClaimItem, Claim1stKey, ClaimIssuerMetadata,
+    Error,
+};
 use pallet_transaction_payment::CddAndFeeDetails;
 
 use codec::Encode;
-use frame_support::{assert_err, assert_ok, traits::Currency, StorageDoubleMap};
+use frame_support::{
+    assert_err, assert_ok,
+    storage::unhashed,
+    traits::{Currency, ReservableCurrency},
+    Blake2_128Concat, StorageDoubleMap, StorageHasher, StorageMap, StorageValue, Twox64Concat,
+};
 use sp_core::H512;
-use sp_runtime::transaction_validity::InvalidTransaction;
+use sp_runtime::{traits::OnInitialize, transaction_validity::InvalidTransaction, DispatchError};
 use test_client::AccountKeyring;
 
 use std::convert::{From, TryFrom};
 
 type Balances = balances::Module<TestStorage>;
+type Settlement = settlement::Module<TestStorage>;
 // type BalancesCall = <balances::Module<TestStorage> as BTrait>::Call;
 
 type Identity = identity::Module<TestStorage>;
+type Asset = asset::Module<TestStorage>;
+type ComplianceManager = pallet_compliance_manager::Module<TestStorage>;
 type System = frame_system::Module<TestStorage>;
 type Timestamp = pallet_timestamp::Module<TestStorage>;
 
@@ -83,7 +102,7 @@ fn add_claims_batch() {
     let cdd_claim_issuer = AccountKeyring::Eve.public();
     let cdd_claim_did = get_identity_id(AccountKeyring::Eve).unwrap();
 
-    let scope = Scope::from(0);
+    let scope = Scope::Identity(IdentityId::from(0));
 
     let claim_records = vec![
         BatchAddClaimItem {
@@ -93,7 +112,7 @@ fn add_claims_batch() {
         },
         BatchAddClaimItem {
             target: alice_did,
-            claim: Claim::Affiliate(scope),
+            claim: Claim::Affiliate(scope.clone()),
             expiry: None,
         },
     ];
@@ -117,14 +136,19 @@ fn add_claims_batch() {
     )
     .unwrap();
 
-    let claim2 =
-        Identity::fetch_claim(alice_did, ClaimType::Affiliate, cdd_claim_did, Some(scope)).unwrap();
+    let claim2 = Identity::fetch_claim(
+        alice_did,
+        ClaimType::Affiliate,
+        cdd_claim_did,
+        Some(scope.clone()),
+    )
+    .unwrap();
 
     assert_eq!(claim1.expiry, None);
     assert_eq!(claim2.expiry, None);
 
     assert_eq!(claim1.claim, Claim::CustomerDueDiligence);
-    assert_eq!(claim2.claim, Claim::Affiliate(scope));
+    assert_eq!(claim2.claim, Claim::Affiliate(scope.clone()));
 }
 
 /// TODO Add `Signatory::Identity(..)` test.
@@ -164,6 +188,83 @@ fn only_master_or_signing_keys_can_authenticate_as_an_identity() {
     });
 }
 
+#[test]
+fn claim_scopes() {
+    ExtBuilder::default().build().execute_with(claim_scopes_we);
+}
+
+fn claim_scopes_we() {
+    let alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+
+    // Claims stored when the scope was an identity are migrated to `Scope::Identity`.
+    let did_scope = IdentityId::from(7);
+    let old_claim = IdentityClaimV1 {
+        claim_issuer: alice_did,
+        issuance_date: 0,
+        last_update_date: 0,
+        expiry: None,
+        claim: ClaimV1::Affiliate(did_scope),
+    };
+    let first_key = Claim1stKey {
+        target: bob_did,
+        claim_type: ClaimType::Affiliate,
+    };
+    let old_second_key = (alice_did, Some(did_scope));
+    let raw_key = [
+        migration::storage_prefix(b"identity", b"Claims"),
+        Blake2_128Concat::hash(&first_key.encode()),
+        Blake2_128Concat::hash(&old_second_key.encode()),
+    ]
+    .concat();
+    unhashed::put(&raw_key, &old_claim);
+    identity::StorageVersion::put(0u32);
+
+    // The migration runs in batches over several blocks.
+    Identity::on_initialize(1);
+    assert_eq!(Identity::storage_version(), 0);
+    for block_number in 2..10 {
+        Identity::on_initialize(block_number);
+    }
+    assert_eq!(Identity::storage_version(), 2);
+    assert!(!unhashed::exists(&raw_key));
+    assert_eq!(
+        Identity::fetch_claim(
+            bob_did,
+            ClaimType::Affiliate,
+            alice_did,
+            Some(Scope::Identity(did_scope))
+        ),
+        Some(IdentityClaim::from(old_claim))
+    );
+
+    // Claims can be scoped to a ticker or to an opaque custom value.
+    let ticker_scope = Scope::from(Ticker::try_from(&b"ACME"[..]).unwrap());
+    let custom_scope = Scope::Custom(b"RegD".to_vec());
+    assert_ok!(Identity::add_claim(
+        alice.clone(),
+        bob_did,
+        Claim::Accredited(ticker_scope.clone()),
+        None
+    ));
+    let fetch_accredited =
+        |scope| Identity::fetch_claim(bob_did, ClaimType::Accredited, alice_did, Some(scope));
+    assert_eq!(
+        fetch_accredited(ticker_scope.clone()).map(|id_claim| id_claim.claim),
+        Some(Claim::Accredited(ticker_scope.clone()))
+    );
+    assert!(fetch_accredited(custom_scope.clone()).is_none());
+    assert_ok!(Identity::add_claim(
+        alice.clone(),
+        bob_did,
+        Claim::Accredited(custom_scope.clone()),
+        None
+    ));
+    assert!(fetch_accredited(custom_scope).is_some());
+    assert!(fetch_accredited(ticker_scope).is_some());
+}
+
 #[test]
 fn revoking_claims() {
     ExtBuilder::default().build().execute_with(|| {
@@ -172,32 +273,32 @@ fn revoking_claims() {
         let _issuer = Origin::signed(AccountKeyring::Bob.public());
         let claim_issuer_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
         let claim_issuer = Origin::signed(AccountKeyring::Charlie.public());
-        let scope = Scope::from(0);
+        let scope = Scope::Identity(IdentityId::from(0));
 
         assert_ok!(Identity::add_claim(
             claim_issuer.clone(),
             claim_issuer_did,
-            Claim::Accredited(scope),
+            Claim::Accredited(scope.clone()),
             Some(100u64),
         ));
         assert!(Identity::fetch_claim(
             claim_issuer_did,
             ClaimType::Accredited,
             claim_issuer_did,
-            Some(scope)
+            Some(scope.clone())
         )
         .is_some());
 
         assert_ok!(Identity::revoke_claim(
             claim_issuer.clone(),
             claim_issuer_did,
-            Claim::Accredited(scope),
+            Claim::Accredited(scope.clone()),
         ));
         assert!(Identity::fetch_claim(
             claim_issuer_did,
             ClaimType::Accredited,
             claim_issuer_did,
-            Some(scope)
+            Some(scope.clone())
         )
         .is_none());
     });
@@ -211,12 +312,12 @@ fn revoking_batch_claims() {
         let _issuer = Origin::signed(AccountKeyring::Bob.public());
         let claim_issuer_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
         let claim_issuer = Origin::signed(AccountKeyring::Charlie.public());
-        let scope = Scope::from(0);
+        let scope = Scope::Identity(IdentityId::from(0));
 
         assert_ok!(Identity::add_claim(
             claim_issuer.clone(),
             claim_issuer_did,
-            Claim::Accredited(scope),
+            Claim::Accredited(scope.clone()),
             Some(100u64),
         ));
 
@@ -230,7 +331,7 @@ fn revoking_batch_claims() {
             claim_issuer_did,
             ClaimType::Accredited,
             claim_issuer_did,
-            Some(scope)
+            Some(scope.clone())
         )
         .is_some());
 
@@ -243,7 +344,7 @@ fn revoking_batch_claims() {
             claim_issuer_did,
             ClaimType::Accredited,
             claim_issuer_did,
-            Some(scope),
+            Some(scope.clone()),
         )
         .is_some());
 
@@ -252,7 +353,7 @@ fn revoking_batch_claims() {
             vec![
                 BatchRevokeClaimItem {
                     target: claim_issuer_did,
-                    claim: Claim::Accredited(scope),
+                    claim: Claim::Accredited(scope.clone()),
                 },
                 BatchRevokeClaimItem {
                     target: claim_issuer_did,
@@ -264,7 +365,7 @@ fn revoking_batch_claims() {
             claim_issuer_did,
             ClaimType::Accredited,
             claim_issuer_did,
-            Some(scope)
+            Some(scope.clone())
         )
         .is_none());
 
@@ -277,7 +378,7 @@ fn revoking_batch_claims() {
             claim_issuer_did,
             ClaimType::Accredited,
             claim_issuer_did,
-            Some(scope),
+            Some(scope.clone()),
         )
         .is_none());
     });
@@ -544,6 +645,384 @@ fn frozen_signing_keys_cdd_verification_test_we() {
     assert_eq!(Balances::free_balance(charlie), 3_059);
 }
 
+#[test]
+fn scoped_signing_key_permissions() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(&scoped_signing_key_permissions_we);
+}
+
+fn scoped_signing_key_permissions_we() {
+    let alice = AccountKeyring::Alice.public();
+    let alice_id = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let charlie_id = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave_id = register_keyring_account(AccountKeyring::Dave).unwrap();
+    let bob_key = AccountKey::from(AccountKeyring::Bob.public().0);
+    let acme = Ticker::try_from(&b"ACME"[..]).unwrap();
+    let emca = Ticker::try_from(&b"EMCA"[..]).unwrap();
+    add_signing_item(alice_id, Signatory::from(bob_key));
+
+    let payer = |call: &Call, key: AccountKey| {
+        let payer = CddHandler::get_valid_payer(call, &Signatory::from(key));
+        CddHandler::clear_context();
+        payer
+    };
+    let transfer = |ticker, to| Call::Asset(asset::Call::transfer(ticker, to, 10));
+    let denied = InvalidTransaction::Custom(TransactionError::MissingPermissions as u8);
+
+    // Without scoped permissions, Bob can sign anything.
+    assert_ok!(payer(&transfer(emca, dave_id), bob_key));
+    assert_ok!(payer(&Call::Asset(asset::Call::freeze(emca)), bob_key));
+
+    // Restrict Bob to transfers and issuance of ACME to Charlie.
+    assert_ok!(Identity::set_permission_to_signer(
+        Origin::signed(alice),
+        Signatory::from(bob_key),
+        vec![
+            Permission::Extrinsic(b"asset".to_vec(), b"issue".to_vec()),
+            Permission::Extrinsic(b"asset".to_vec(), b"transfer".to_vec()),
+            Permission::Ticker(acme),
+            Permission::Counterparty(charlie_id),
+        ]
+    ));
+    assert_ok!(payer(&transfer(acme, charlie_id), bob_key));
+    assert_ok!(payer(
+        &Call::Asset(asset::Call::issue(acme, charlie_id, 10, vec![])),
+        bob_key
+    ));
+    assert_err!(payer(&transfer(emca, charlie_id), bob_key), denied);
+    assert_err!(payer(&transfer(acme, dave_id), bob_key), denied);
+    assert_err!(
+        payer(&Call::Asset(asset::Call::freeze(acme)), bob_key),
+        denied
+    );
+    assert_err!(
+        payer(
+            &Call::Identity(identity::Call::freeze_signing_keys()),
+            bob_key
+        ),
+        denied
+    );
+
+    // Forwarded proposals are checked with the scope of the target identity.
+    assert_ok!(Identity::set_permission_to_signer(
+        Origin::signed(alice),
+        Signatory::from(bob_key),
+        vec![
+            Permission::Extrinsic(b"identity".to_vec(), b"forwarded_call".to_vec()),
+            Permission::Extrinsic(b"asset".to_vec(), b"transfer".to_vec()),
+            Permission::Ticker(acme),
+            Permission::Counterparty(charlie_id),
+        ]
+    ));
+    let forwarded = |call| Call::Identity(identity::Call::forwarded_call(dave_id, Box::new(call)));
+    assert_ok!(payer(&forwarded(transfer(acme, charlie_id)), bob_key));
+    assert_err!(
+        payer(&forwarded(transfer(emca, charlie_id)), bob_key),
+        denied
+    );
+    assert_err!(
+        payer(&forwarded(Call::Asset(asset::Call::freeze(acme))), bob_key),
+        denied
+    );
+    assert_err!(
+        payer(&forwarded(forwarded(transfer(emca, charlie_id))), bob_key),
+        denied
+    );
+
+    // Affirmations are checked with the legs of the stored instruction.
+    let charlie = Origin::signed(AccountKeyring::Charlie.public());
+    let venue_id = Settlement::venue_counter();
+    assert_ok!(Settlement::create_venue(
+        charlie.clone(),
+        b"Venue".into(),
+        VenueType::Exchange
+    ));
+    let add_instruction = |ticker| {
+        let instruction_id = Settlement::instruction_counter();
+        let leg = Leg {
+            from: alice_id,
+            to: charlie_id,
+            asset: LegAsset::Asset(ticker),
+            amount: 10,
+        };
+        assert_ok!(Settlement::add_instruction(
+            charlie.clone(),
+            venue_id,
+            SettlementType::SettleOnAffirmation,
+            vec![leg]
+        ));
+        instruction_id
+    };
+    let acme_instruction = add_instruction(acme);
+    let emca_instruction = add_instruction(emca);
+    assert_ok!(Identity::set_permission_to_signer(
+        Origin::signed(alice),
+        Signatory::from(bob_key),
+        vec![
+            Permission::Pallet(b"settlement".to_vec()),
+            Permission::Ticker(acme),
+        ]
+    ));
+    let affirm =
+        |instruction_id| Call::Settlement(settlement::Call::affirm_instruction(instruction_id));
+    assert_ok!(payer(&affirm(acme_instruction), bob_key));
+    assert_err!(payer(&affirm(emca_instruction), bob_key), denied);
+
+    // PolyX transfers and identity calls are checked with the identities they refer to.
+    assert_ok!(Identity::set_permission_to_signer(
+        Origin::signed(alice),
+        Signatory::from(bob_key),
+        vec![
+            Permission::Pallet(b"balances".to_vec()),
+            Permission::Pallet(b"identity".to_vec()),
+            Permission::Counterparty(charlie_id),
+        ]
+    ));
+    let polyx_transfer = |to: AccountKeyring| {
+        Call::Balances(balances::Call::transfer(to.to_account_id().into(), 10))
+    };
+    assert_ok!(payer(&polyx_transfer(AccountKeyring::Charlie), bob_key));
+    assert_err!(
+        payer(&polyx_transfer(AccountKeyring::Dave), bob_key),
+        denied
+    );
+    // Ferdie has no identity, so no counterparty permission allows a transfer to that account.
+    assert_err!(
+        payer(&polyx_transfer(AccountKeyring::Ferdie), bob_key),
+        denied
+    );
+    let top_up = |did| Call::Balances(balances::Call::top_up_identity_balance(did, 10));
+    assert_ok!(payer(&top_up(charlie_id), bob_key));
+    assert_err!(payer(&top_up(dave_id), bob_key), denied);
+    let authorize = |did| {
+        Call::Identity(identity::Call::add_authorization(
+            Signatory::from(did),
+            AuthorizationData::NoData,
+            None,
+        ))
+    };
+    assert_ok!(payer(&authorize(charlie_id), bob_key));
+    assert_err!(payer(&authorize(dave_id), bob_key), denied);
+
+    // The master key is never restricted.
+    let alice_key = AccountKey::from(alice.0);
+    assert_ok!(payer(&transfer(emca, dave_id), alice_key));
+    assert_ok!(payer(&Call::Asset(asset::Call::freeze(emca)), alice_key));
+
+    // `Full` lifts every restriction.
+    assert_ok!(Identity::set_permission_to_signer(
+        Origin::signed(alice),
+        Signatory::from(bob_key),
+        vec![Permission::Ticker(acme), Permission::Full]
+    ));
+    assert_ok!(payer(&transfer(emca, dave_id), bob_key));
+}
+
+#[test]
+fn spending_limits() {
+    ExtBuilder::default()
+        .monied(true)
+        .build()
+        .execute_with(&spending_limits_we);
+}
+
+fn spending_limits_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let alice_id = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let charlie_id = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let bob_key = AccountKey::from(AccountKeyring::Bob.public().0);
+    let charlie = AccountKeyring::Charlie.public();
+    let acme = Ticker::try_from(&b"ACME"[..]).unwrap();
+    add_signing_item(alice_id, Signatory::from(bob_key));
+    assert_ok!(Balances::transfer_with_memo(
+        alice.clone(),
+        AccountKeyring::Bob.public(),
+        10_000,
+        None
+    ));
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        AssetName::from(b"ACME"),
+        acme,
+        1_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        acme,
+        vec![],
+        vec![]
+    ));
+
+    // Only signing keys can be limited.
+    let limit = SpendingLimit {
+        period: 1_000,
+        max_polyx: Some(1_500),
+        max_assets: vec![(acme, 100)],
+        max_daily_transactions: Some(2),
+    };
+    let alice_key = AccountKey::from(AccountKeyring::Alice.public().0);
+    assert_err!(
+        Identity::set_spending_limit(alice.clone(), alice_key, Some(limit.clone())),
+        Error::<TestStorage>::InvalidSender
+    );
+    assert_err!(
+        Identity::set_spending_limit(bob.clone(), bob_key, None),
+        Error::<TestStorage>::KeyNotAllowed
+    );
+    assert_ok!(Identity::set_spending_limit(
+        alice.clone(),
+        bob_key,
+        Some(limit)
+    ));
+    System::set_block_number(1);
+    Timestamp::set_timestamp(1);
+
+    // POLYX transfers are limited per period.
+    assert_ok!(Balances::transfer(bob.clone(), charlie, 1_000));
+    assert_err!(
+        Balances::transfer(bob.clone(), charlie, 1_000),
+        Error::<TestStorage>::PolyxSpendingLimitExceeded
+    );
+    assert!(System::events().iter().any(|record| record.event
+        == EventTest::identity(IdentityRawEvent::SpendingLimitExceeded(
+            alice_id,
+            bob_key,
+            SpendingLimitKind::Polyx
+        ))));
+    Timestamp::set_timestamp(1_001);
+    assert_ok!(Balances::transfer(bob.clone(), charlie, 1_000));
+
+    // Asset transfers are limited per ticker and period.
+    assert_ok!(Asset::transfer(bob.clone(), acme, charlie_id, 60));
+    assert_err!(
+        Asset::transfer(bob.clone(), acme, charlie_id, 60),
+        Error::<TestStorage>::AssetSpendingLimitExceeded
+    );
+    assert_eq!(Asset::balance(&acme, &charlie_id), 60);
+    // The master key is not limited.
+    assert_ok!(Asset::transfer(alice.clone(), acme, charlie_id, 60));
+    // Every asset transfer path shares the limit.
+    let limited = Error::<TestStorage>::AssetSpendingLimitExceeded;
+    assert_err!(
+        Asset::transfer_with_data(bob.clone(), acme, charlie_id, 60, vec![]),
+        limited
+    );
+    let alice_default = PortfolioId::default_portfolio(alice_id);
+    assert_err!(
+        Asset::portfolio_transfer(
+            bob.clone(),
+            acme,
+            alice_default,
+            PortfolioId::default_portfolio(charlie_id),
+            60
+        ),
+        limited
+    );
+    assert_ok!(Asset::create_portfolio(
+        alice.clone(),
+        PortfolioName::from(b"Savings")
+    ));
+    assert_err!(
+        Asset::move_portfolio_funds(
+            bob.clone(),
+            alice_default,
+            PortfolioKind::User(1),
+            vec![(acme, 30), (acme, 30)]
+        ),
+        limited
+    );
+    assert_eq!(Asset::portfolio_balance(&alice_default, &acme), 880);
+
+    // Settlement legs sent by the identity of the affirming key are limited too.
+    assert_ok!(Balances::top_up_identity_balance(
+        alice.clone(),
+        alice_id,
+        1_000
+    ));
+    let charlie_origin = Origin::signed(charlie);
+    let venue_id = Settlement::venue_counter();
+    assert_ok!(Settlement::create_venue(
+        charlie_origin.clone(),
+        b"Venue".into(),
+        VenueType::Exchange
+    ));
+    let add_instruction = |asset, amount| {
+        let instruction_id = Settlement::instruction_counter();
+        let leg = Leg {
+            from: alice_id,
+            to: charlie_id,
+            asset,
+            amount,
+        };
+        assert_ok!(Settlement::add_instruction(
+            charlie_origin.clone(),
+            venue_id,
+            SettlementType::SettleOnAffirmation,
+            vec![leg]
+        ));
+        instruction_id
+    };
+    let polyx_instruction = add_instruction(LegAsset::Polyx, 600);
+    assert_err!(
+        Settlement::affirm_instruction(bob.clone(), polyx_instruction),
+        Error::<TestStorage>::PolyxSpendingLimitExceeded
+    );
+    // Spending is only charged when the instruction executes, so a rejected instruction
+    // doesn't use up the limit.
+    let rejected_instruction = add_instruction(LegAsset::Polyx, 500);
+    assert_ok!(Settlement::affirm_instruction(
+        bob.clone(),
+        rejected_instruction
+    ));
+    assert_ok!(Settlement::reject_instruction(
+        charlie_origin.clone(),
+        rejected_instruction
+    ));
+    let polyx_instruction = add_instruction(LegAsset::Polyx, 500);
+    assert_ok!(Settlement::affirm_instruction(
+        bob.clone(),
+        polyx_instruction
+    ));
+    assert_eq!(
+        Settlement::affirming_key(polyx_instruction, alice_id),
+        Some(bob_key)
+    );
+    assert_ok!(Settlement::affirm_instruction(
+        charlie_origin.clone(),
+        polyx_instruction
+    ));
+    assert_eq!(Settlement::affirming_key(polyx_instruction, alice_id), None);
+    assert_err!(
+        Balances::transfer(bob.clone(), charlie, 1),
+        Error::<TestStorage>::PolyxSpendingLimitExceeded
+    );
+    let acme_instruction = add_instruction(LegAsset::Asset(acme), 60);
+    assert_err!(
+        Settlement::affirm_instruction(bob.clone(), acme_instruction),
+        limited
+    );
+
+    // Transactions paying fees are limited per day.
+    let bob_signer = Signatory::from(bob_key);
+    assert_ok!(CddHandler::charge_spending_limit(&bob_signer, 0));
+    assert_ok!(CddHandler::charge_spending_limit(&bob_signer, 0));
+    assert_err!(
+        CddHandler::charge_spending_limit(&bob_signer, 0),
+        InvalidTransaction::Custom(TransactionError::SpendingLimitExceeded as u8)
+    );
+
+    // Removing the limit lifts it.
+    assert_ok!(Identity::set_spending_limit(alice, bob_key, None));
+    assert_ok!(CddHandler::charge_spending_limit(&bob_signer, 0));
+    assert_ok!(Asset::transfer(bob, acme, charlie_id, 60));
+}
+
 #[test]
 fn enforce_uniqueness_keys_in_identity_tests() {
     ExtBuilder::default()
@@ -973,6 +1452,518 @@ fn changing_master_key_with_cdd_auth_we() {
     );
 }
 
+#[test]
+fn delayed_master_key_rotation() {
+    ExtBuilder::default()
+        .monied(true)
+        .build()
+        .execute_with(&delayed_master_key_rotation_we);
+}
+
+fn delayed_master_key_rotation_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let alice_key = AccountKey::from(AccountKeyring::Alice.public().0);
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let bob_key = AccountKey::from(AccountKeyring::Bob.public().0);
+    let charlie = Origin::signed(AccountKeyring::Charlie.public());
+    let charlie_key = AccountKey::from(AccountKeyring::Charlie.public().0);
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_key = AccountKey::from(AccountKeyring::Dave.public().0);
+    add_signing_item(alice_did, Signatory::from(charlie_key));
+    add_signing_item(alice_did, Signatory::from(dave_key));
+    assert_ok!(Identity::set_permission_to_signer(
+        alice.clone(),
+        Signatory::from(dave_key),
+        vec![Permission::Full]
+    ));
+    System::set_block_number(1);
+
+    // Only the master key sets the delay.
+    assert_err!(
+        Identity::set_master_key_rotation_delay(charlie.clone(), 10),
+        Error::<TestStorage>::KeyNotAllowed
+    );
+    assert_ok!(Identity::set_master_key_rotation_delay(alice.clone(), 10));
+    assert_eq!(Identity::master_key_rotation_delay(alice_did), 10);
+
+    // Accepted rotations are pending until the delay expires.
+    let rotate = || {
+        let auth_id = Identity::add_auth(
+            Signatory::from(alice_key),
+            Signatory::from(bob_key),
+            AuthorizationData::RotateMasterKey(alice_did),
+            None,
+        );
+        Identity::accept_master_key(bob.clone(), auth_id, None)
+    };
+    assert_ok!(rotate());
+    let pending = PendingMasterKeyRotation {
+        new_master_key: bob_key,
+        enact_at: 11,
+    };
+    assert_eq!(Identity::did_records(alice_did).master_key, alice_key);
+    assert_eq!(
+        Identity::did_records(alice_did).pending_master_key_rotation,
+        Some(pending)
+    );
+    assert_eq!(
+        Identity::scheduled_master_key_rotations(11),
+        vec![alice_did]
+    );
+    assert!(System::events().iter().any(|record| record.event
+        == EventTest::identity(IdentityRawEvent::MasterKeyRotationScheduled(
+            alice_did, bob_key, 11
+        ))));
+    assert_err!(rotate(), Error::<TestStorage>::MasterKeyRotationPending);
+
+    // Signers without full permissions cannot cancel the rotation.
+    assert_err!(
+        Identity::cancel_master_key_rotation(charlie.clone(), alice_did),
+        Error::<TestStorage>::KeyNotAllowed
+    );
+    assert_ok!(Identity::cancel_master_key_rotation(
+        dave.clone(),
+        alice_did
+    ));
+    assert_eq!(
+        Identity::did_records(alice_did).pending_master_key_rotation,
+        None
+    );
+    assert!(Identity::scheduled_master_key_rotations(11).is_empty());
+
+    // Setting the master key directly waits for the delay too.
+    let ferdie_key = AccountKey::from(AccountKeyring::Ferdie.public().0);
+    assert_ok!(Identity::set_master_key(alice.clone(), ferdie_key));
+    assert_eq!(Identity::did_records(alice_did).master_key, alice_key);
+    assert_eq!(
+        Identity::did_records(alice_did).pending_master_key_rotation,
+        Some(PendingMasterKeyRotation {
+            new_master_key: ferdie_key,
+            enact_at: 11,
+        })
+    );
+    assert_err!(
+        Identity::set_master_key(alice.clone(), ferdie_key),
+        Error::<TestStorage>::MasterKeyRotationPending
+    );
+    assert_ok!(Identity::cancel_master_key_rotation(
+        alice.clone(),
+        alice_did
+    ));
+
+    // The rotation is enacted at the start of its block.
+    assert_ok!(rotate());
+    Identity::on_initialize(10);
+    assert_eq!(Identity::did_records(alice_did).master_key, alice_key);
+    System::set_block_number(11);
+    Identity::on_initialize(11);
+    assert_eq!(Identity::did_records(alice_did).master_key, bob_key);
+    assert_eq!(
+        Identity::did_records(alice_did).pending_master_key_rotation,
+        None
+    );
+    assert!(System::events().iter().any(|record| record.event
+        == EventTest::identity(IdentityRawEvent::MasterKeyUpdated(
+            alice_did, alice_key, bob_key
+        ))));
+    assert_err!(
+        Identity::cancel_master_key_rotation(dave.clone(), alice_did),
+        Error::<TestStorage>::NoPendingMasterKeyRotation
+    );
+
+    // Identities stored without pending rotations are migrated.
+    let old_did = IdentityId::from(9);
+    let old_record = IdentityV1 {
+        roles: vec![],
+        master_key: charlie_key,
+        signing_items: vec![SigningItem::from(dave_key)],
+    };
+    let raw_key = [
+        migration::storage_prefix(b"identity", b"DidRecords"),
+        Twox64Concat::hash(&old_did.encode()),
+    ]
+    .concat();
+    unhashed::put(&raw_key, &old_record);
+    identity::StorageVersion::put(1u32);
+
+    Identity::on_initialize(12);
+    assert_eq!(Identity::storage_version(), 2);
+    assert_eq!(Identity::did_records(old_did), DidRecord::from(old_record));
+}
+
+#[test]
+fn master_key_rotation_delay_reduction() {
+    ExtBuilder::default()
+        .monied(true)
+        .build()
+        .execute_with(&master_key_rotation_delay_reduction_we);
+}
+
+fn master_key_rotation_delay_reduction_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob_key = AccountKey::from(AccountKeyring::Bob.public().0);
+    System::set_block_number(1);
+
+    // Increases of the delay apply immediately.
+    assert_ok!(Identity::set_master_key_rotation_delay(alice.clone(), 10));
+    assert_eq!(Identity::master_key_rotation_delay(alice_did), 10);
+
+    // Reductions wait for the current delay, which still applies to rotations meanwhile.
+    assert_ok!(Identity::set_master_key_rotation_delay(alice.clone(), 0));
+    assert_eq!(Identity::master_key_rotation_delay(alice_did), 10);
+    assert_eq!(
+        Identity::pending_master_key_rotation_delay(alice_did),
+        Some(PendingMasterKeyRotationDelay {
+            delay: 0,
+            enact_at: 11,
+        })
+    );
+    assert!(System::events().iter().any(|record| record.event
+        == EventTest::identity(IdentityRawEvent::MasterKeyRotationDelayReductionScheduled(
+            alice_did, 0, 11
+        ))));
+    assert_ok!(Identity::set_master_key(alice.clone(), bob_key));
+    assert_eq!(
+        Identity::did_records(alice_did)
+            .pending_master_key_rotation
+            .map(|rotation| rotation.enact_at),
+        Some(11)
+    );
+
+    // A new delay replaces the pending reduction.
+    assert_ok!(Identity::set_master_key_rotation_delay(alice.clone(), 20));
+    assert_eq!(Identity::master_key_rotation_delay(alice_did), 20);
+    assert_eq!(Identity::pending_master_key_rotation_delay(alice_did), None);
+    assert!(Identity::scheduled_master_key_rotation_delays(11).is_empty());
+    assert_ok!(Identity::set_master_key_rotation_delay(alice.clone(), 5));
+
+    // The reduction is enacted at the start of its block.
+    System::set_block_number(11);
+    Identity::on_initialize(11);
+    assert_eq!(Identity::did_records(alice_did).master_key, bob_key);
+    assert_eq!(Identity::master_key_rotation_delay(alice_did), 20);
+    System::set_block_number(21);
+    Identity::on_initialize(21);
+    assert_eq!(Identity::master_key_rotation_delay(alice_did), 5);
+    assert_eq!(Identity::pending_master_key_rotation_delay(alice_did), None);
+}
+
+#[test]
+fn master_key_recovery() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(&master_key_recovery_we);
+}
+
+fn master_key_recovery_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let alice_id = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let alice_key = AccountKey::from(AccountKeyring::Alice.public().0);
+    let charlie = Origin::signed(AccountKeyring::Charlie.public());
+    let charlie_id = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_key = AccountKey::from(AccountKeyring::Dave.public().0);
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let bob_key = AccountKey::from(AccountKeyring::Bob.public().0);
+    let ferdie = Origin::signed(AccountKeyring::Ferdie.public());
+    let ferdie_key = AccountKey::from(AccountKeyring::Ferdie.public().0);
+    System::set_block_number(10);
+
+    // Guardians must be unique, exclude the identity itself and reach the threshold.
+    let guardians = vec![Signatory::from(charlie_id), Signatory::from(dave_key)];
+    let config = |guardians: Vec<Signatory>, threshold| RecoveryConfig {
+        guardians,
+        threshold,
+        delay: 100,
+    };
+    assert_err!(
+        Identity::set_recovery_config(alice.clone(), Some(config(guardians.clone(), 3))),
+        Error::<TestStorage>::InvalidRecoveryConfig
+    );
+    assert_err!(
+        Identity::set_recovery_config(
+            alice.clone(),
+            Some(config(vec![Signatory::from(alice_id)], 1))
+        ),
+        Error::<TestStorage>::InvalidRecoveryConfig
+    );
+    assert_ok!(Identity::set_recovery_config(
+        alice.clone(),
+        Some(config(guardians.clone(), 2))
+    ));
+
+    // Only guardians can start a recovery, and guardian keys without identity pay through the
+    // recovered identity.
+    assert_err!(
+        Identity::initiate_recovery(bob.clone(), alice_id, ferdie_key),
+        Error::<TestStorage>::NotRecoveryGuardian
+    );
+    let initiate = Call::Identity(identity::Call::initiate_recovery(alice_id, ferdie_key));
+    assert_eq!(
+        CddHandler::get_valid_payer(&initiate, &Signatory::from(dave_key)),
+        Ok(Some(Signatory::from(alice_id)))
+    );
+    assert_eq!(
+        CddHandler::get_valid_payer(&initiate, &Signatory::from(bob_key)),
+        Err(InvalidTransaction::Custom(TransactionError::MissingIdentity as u8).into())
+    );
+    assert_err!(
+        Identity::initiate_recovery(charlie.clone(), alice_id, alice_key),
+        Error::<TestStorage>::AlreadyLinked
+    );
+    assert_ok!(Identity::initiate_recovery(
+        charlie.clone(),
+        alice_id,
+        ferdie_key
+    ));
+    assert_err!(
+        Identity::finalize_recovery(ferdie.clone(), alice_id, None),
+        Error::<TestStorage>::RecoveryNotApproved
+    );
+
+    // The master key cancels the recovery.
+    assert_ok!(Identity::cancel_recovery(alice.clone()));
+    assert_eq!(Identity::active_recovery(alice_id, ferdie_key), None);
+    assert_err!(
+        Identity::approve_recovery(dave.clone(), alice_id, ferdie_key),
+        Error::<TestStorage>::NoActiveRecovery
+    );
+
+    // Approvals are counted per proposed key, so a guardian proposing another key does not
+    // block a recovery.
+    let approved_at = |key: AccountKey| {
+        Identity::active_recovery(alice_id, key).and_then(|recovery| recovery.approved_at)
+    };
+    assert_ok!(Identity::initiate_recovery(
+        charlie.clone(),
+        alice_id,
+        bob_key
+    ));
+    assert_ok!(Identity::initiate_recovery(
+        dave.clone(),
+        alice_id,
+        ferdie_key
+    ));
+    assert_err!(
+        Identity::initiate_recovery(charlie.clone(), alice_id, ferdie_key),
+        Error::<TestStorage>::RecoveryInProgress
+    );
+    assert_err!(
+        Identity::approve_recovery(dave.clone(), alice_id, ferdie_key),
+        Error::<TestStorage>::RecoveryAlreadyApproved
+    );
+    assert_err!(
+        Identity::approve_recovery(dave.clone(), alice_id, dave_key),
+        Error::<TestStorage>::NoActiveRecovery
+    );
+    assert_eq!(approved_at(ferdie_key), None);
+
+    // Approving a key withdraws the approval of the guardian for any other key.
+    assert_ok!(Identity::approve_recovery(
+        charlie.clone(),
+        alice_id,
+        ferdie_key
+    ));
+    assert_eq!(Identity::active_recovery(alice_id, bob_key), None);
+    assert_eq!(approved_at(ferdie_key), Some(10));
+    assert_ok!(Identity::initiate_recovery(dave.clone(), alice_id, bob_key));
+    assert_eq!(approved_at(ferdie_key), None);
+    assert_ok!(Identity::approve_recovery(
+        dave.clone(),
+        alice_id,
+        ferdie_key
+    ));
+    assert_eq!(Identity::active_recovery(alice_id, bob_key), None);
+    assert_eq!(approved_at(ferdie_key), Some(10));
+    assert_err!(
+        Identity::set_recovery_config(alice.clone(), None),
+        Error::<TestStorage>::RecoveryInProgress
+    );
+
+    // Only the new master key finalizes the recovery, once the delay has passed. A longer
+    // master key rotation delay applies to the recovery too.
+    assert_ok!(Identity::set_master_key_rotation_delay(alice.clone(), 200));
+    let finalize = Call::Identity(identity::Call::finalize_recovery(alice_id, None));
+    assert_eq!(
+        CddHandler::get_valid_payer(&finalize, &Signatory::from(ferdie_key)),
+        Ok(Some(Signatory::from(alice_id)))
+    );
+    assert_eq!(
+        CddHandler::get_valid_payer(&finalize, &Signatory::from(bob_key)),
+        Err(InvalidTransaction::Custom(TransactionError::InvalidAuthorization as u8).into())
+    );
+    assert_err!(
+        Identity::finalize_recovery(bob.clone(), alice_id, None),
+        Error::<TestStorage>::NoActiveRecovery
+    );
+    System::set_block_number(60);
+    assert_err!(
+        Identity::finalize_recovery(ferdie.clone(), alice_id, None),
+        Error::<TestStorage>::RecoveryDelayNotElapsed
+    );
+    System::set_block_number(110);
+    assert_err!(
+        Identity::finalize_recovery(ferdie.clone(), alice_id, None),
+        Error::<TestStorage>::RecoveryDelayNotElapsed
+    );
+    System::set_block_number(210);
+    assert_ok!(Identity::finalize_recovery(ferdie.clone(), alice_id, None));
+
+    assert_eq!(Identity::did_records(alice_id).master_key, ferdie_key);
+    assert_eq!(Identity::get_identity(&ferdie_key), Some(alice_id));
+    assert_eq!(Identity::get_identity(&alice_key), None);
+    assert!(!Identity::has_active_recovery(alice_id));
+}
+
+#[test]
+fn closing_identity() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(&closing_identity_we);
+}
+
+fn closing_identity_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let alice_key = AccountKey::from(AccountKeyring::Alice.public().0);
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let bob_key = AccountKey::from(AccountKeyring::Bob.public().0);
+    let charlie = Origin::signed(AccountKeyring::Charlie.public());
+    let charlie_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave_key = AccountKey::from(AccountKeyring::Dave.public().0);
+    let ferdie = AccountKeyring::Ferdie.public();
+    let acme = Ticker::try_from(&b"ACME"[..]).unwrap();
+    add_signing_item(alice_did, Signatory::from(bob_key));
+    assert_ok!(Balances::top_up_identity_balance(
+        alice.clone(),
+        alice_did,
+        1_000
+    ));
+    assert_ok!(Identity::add_claim(
+        charlie.clone(),
+        alice_did,
+        Claim::Accredited(Scope::from(acme)),
+        None
+    ));
+    System::set_block_number(1);
+
+    // Only the master key closes the identity.
+    assert_err!(
+        Identity::close_identity(bob.clone(), ferdie),
+        Error::<TestStorage>::KeyNotAllowed
+    );
+
+    // The identity cannot hold assets.
+    assert_ok!(Asset::create_asset(
+        charlie.clone(),
+        AssetName::from(b"ACME"),
+        acme,
+        1_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_active_rule(
+        charlie.clone(),
+        acme,
+        vec![],
+        vec![]
+    ));
+    assert_eq!(Asset::owned_tickers(charlie_did), vec![acme]);
+    assert_ok!(Asset::transfer(charlie.clone(), acme, alice_did, 100));
+    assert!(Asset::is_ticker_held(alice_did, acme));
+    assert_err!(
+        Identity::close_identity(alice.clone(), ferdie),
+        Error::<TestStorage>::IdentityHasAssetHoldings
+    );
+    assert_ok!(Asset::transfer(alice.clone(), acme, charlie_did, 100));
+    assert!(!Asset::is_ticker_held(alice_did, acme));
+
+    // The identity cannot have open authorizations.
+    let auth_id = Identity::add_auth(
+        Signatory::from(alice_did),
+        Signatory::from(dave_key),
+        AuthorizationData::JoinIdentity(alice_did),
+        None,
+    );
+    assert_err!(
+        Identity::close_identity(alice.clone(), ferdie),
+        Error::<TestStorage>::IdentityHasOpenAuthorizations
+    );
+    assert_ok!(Identity::remove_authorization(
+        alice.clone(),
+        Signatory::from(dave_key),
+        auth_id
+    ));
+
+    // Neither can its signing keys, whether they received or gave the authorizations.
+    let auth_id = Identity::add_auth(
+        Signatory::from(charlie_did),
+        Signatory::from(bob_key),
+        AuthorizationData::NoData,
+        None,
+    );
+    assert_err!(
+        Identity::close_identity(alice.clone(), ferdie),
+        Error::<TestStorage>::IdentityHasOpenAuthorizations
+    );
+    assert_ok!(Identity::remove_authorization(
+        bob.clone(),
+        Signatory::from(bob_key),
+        auth_id
+    ));
+    let auth_id = Identity::add_auth(
+        Signatory::from(bob_key),
+        Signatory::from(dave_key),
+        AuthorizationData::NoData,
+        None,
+    );
+    assert_err!(
+        Identity::close_identity(alice.clone(), ferdie),
+        Error::<TestStorage>::IdentityHasOpenAuthorizations
+    );
+    assert_ok!(Identity::remove_authorization(
+        bob.clone(),
+        Signatory::from(dave_key),
+        auth_id
+    ));
+
+    // Nothing is closed if the beneficiary can't take the identity balance.
+    Balances::make_free_balance_be(&ferdie, u128::MAX);
+    assert_err!(
+        Identity::close_identity(alice.clone(), ferdie),
+        balances::Error::<TestStorage>::Overflow
+    );
+    assert_eq!(Balances::identity_balance(alice_did), 1_000);
+    assert_eq!(Identity::get_identity(&alice_key), Some(alice_did));
+    assert!(!Identity::is_identity_closed(alice_did));
+    Balances::make_free_balance_be(&ferdie, 0);
+
+    assert_ok!(Identity::close_identity(alice.clone(), ferdie));
+    assert!(System::events().iter().any(|record| record.event
+        == EventTest::identity(IdentityRawEvent::IdentityClosed(alice_did, ferdie, 1_000))));
+    assert_eq!(Balances::free_balance(&ferdie), 1_000);
+    assert_eq!(Balances::identity_balance(alice_did), 0);
+    assert!(Identity::is_identity_closed(alice_did));
+    assert!(!identity::DidRecords::contains_key(alice_did));
+    assert_eq!(Identity::get_identity(&alice_key), None);
+    assert_eq!(Identity::get_identity(&bob_key), None);
+    assert!(Identity::fetch_claim(
+        alice_did,
+        ClaimType::Accredited,
+        charlie_did,
+        Some(Scope::from(acme))
+    )
+    .is_none());
+
+    // The unlinked master key can create a new identity.
+    let new_alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    assert_ne!(new_alice_did, alice_did);
+}
+
 #[test]
 fn cdd_register_did_test() {
     ExtBuilder::default()
@@ -1198,6 +2189,173 @@ fn add_identity_signers() {
     });
 }
 
+#[test]
+fn claim_issuer_registry() {
+    ExtBuilder::default()
+        .cdd_providers(vec![AccountKeyring::Eve.public()])
+        .build()
+        .execute_with(claim_issuer_registry_we);
+}
+
+fn claim_issuer_registry_we() {
+    let root = Origin::system(frame_system::RawOrigin::Root);
+    let alice_acc = AccountKeyring::Alice.public();
+    let alice = Origin::signed(alice_acc);
+    let alice_did = register_keyring_account_with_balance(AccountKeyring::Alice, 10_000).unwrap();
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let eve_did = get_identity_id(AccountKeyring::Eve).unwrap();
+    let scope = Scope::Custom(b"ACME".to_vec());
+
+    // Alice registers as a claim issuer and bonds the deposit.
+    let metadata = ClaimIssuerMetadata {
+        name: b"Alice KYC".to_vec(),
+        website: b"https://alice.example".to_vec(),
+        jurisdiction: b"US".into(),
+    };
+    assert_ok!(Identity::set_claim_issuer_deposit(root.clone(), 1_000));
+    assert_ok!(Identity::register_claim_issuer(
+        alice.clone(),
+        metadata.clone()
+    ));
+    assert_err!(
+        Identity::register_claim_issuer(alice.clone(), metadata.clone()),
+        Error::<TestStorage>::ClaimIssuerAlreadyRegistered
+    );
+    assert_eq!(Balances::reserved_balance(alice_acc), 1_000);
+    assert_eq!(
+        Identity::claim_issuer(alice_did).map(|claim_issuer| claim_issuer.metadata),
+        Some(metadata)
+    );
+
+    Timestamp::set_timestamp(1);
+    let add_accredited = || {
+        Identity::add_claim(
+            alice.clone(),
+            bob_did,
+            Claim::Accredited(scope.clone()),
+            None,
+        )
+    };
+    let fetch_accredited = || {
+        Identity::fetch_claim(
+            bob_did,
+            ClaimType::Accredited,
+            alice_did,
+            Some(scope.clone()),
+        )
+    };
+    assert_ok!(add_accredited());
+    assert!(fetch_accredited().is_some());
+
+    // Claims of a suspended issuer are not valid until it is reinstated.
+    assert_ok!(Identity::suspend_claim_issuer(
+        root.clone(),
+        alice_did,
+        true
+    ));
+    assert!(fetch_accredited().is_none());
+    assert_err!(add_accredited(), Error::<TestStorage>::ClaimIssuerSuspended);
+    assert_err!(
+        Identity::deregister_claim_issuer(alice.clone()),
+        Error::<TestStorage>::ClaimIssuerSuspended
+    );
+    assert_ok!(Identity::suspend_claim_issuer(
+        root.clone(),
+        alice_did,
+        false
+    ));
+    assert!(fetch_accredited().is_some());
+
+    // Claims added or updated from the revocation moment are not valid.
+    assert_ok!(Identity::revoke_claims_since(alice.clone(), 3));
+    assert!(fetch_accredited().is_some());
+    Timestamp::set_timestamp(3);
+    assert_ok!(add_accredited());
+    assert!(fetch_accredited().is_none());
+    assert_ok!(Identity::revoke_claims_since(alice.clone(), 5));
+    assert_eq!(Identity::claims_revoked_since(alice_did), Some(3));
+
+    // Only the governance can clear the revocation.
+    assert_err!(
+        Identity::clear_claims_revocation(alice.clone(), alice_did),
+        DispatchError::BadOrigin
+    );
+    assert_ok!(Identity::clear_claims_revocation(root.clone(), alice_did));
+    assert_eq!(Identity::claims_revoked_since(alice_did), None);
+    assert!(fetch_accredited().is_some());
+    assert_err!(
+        Identity::clear_claims_revocation(root.clone(), alice_did),
+        Error::<TestStorage>::NoChange
+    );
+
+    // Issuers which are not registered can be suspended too.
+    let eve = Origin::signed(AccountKeyring::Eve.public());
+    assert!(Identity::claim_issuer(eve_did).is_none());
+    assert!(Identity::has_valid_cdd(bob_did));
+    assert_ok!(Identity::suspend_claim_issuer(root.clone(), eve_did, true));
+    assert!(Identity::is_claim_issuer_suspended(eve_did));
+    assert!(!Identity::has_valid_cdd(bob_did));
+    assert_err!(
+        Identity::add_claim(eve.clone(), bob_did, Claim::CustomerDueDiligence, None),
+        Error::<TestStorage>::ClaimIssuerSuspended
+    );
+    assert_err!(
+        Identity::add_claims_batch(
+            eve.clone(),
+            vec![BatchAddClaimItem {
+                target: bob_did,
+                claim: Claim::CustomerDueDiligence,
+                expiry: None,
+            }]
+        ),
+        Error::<TestStorage>::ClaimIssuerSuspended
+    );
+    assert_err!(
+        Identity::cdd_register_did(eve, AccountKeyring::Dave.public(), None, vec![]),
+        Error::<TestStorage>::ClaimIssuerSuspended
+    );
+    assert_ok!(Identity::suspend_claim_issuer(root.clone(), eve_did, false));
+    assert!(!Identity::is_claim_issuer_suspended(eve_did));
+
+    // The governance revokes the CDD claims of Eve.
+    assert!(Identity::has_valid_cdd(bob_did));
+    assert_ok!(Identity::revoke_issuer_claims_since(
+        root.clone(),
+        eve_did,
+        0
+    ));
+    assert!(!Identity::has_valid_cdd(bob_did));
+
+    // The governance slashes part of the deposit, which is burned.
+    let total_issuance = Balances::total_issuance();
+    assert_err!(
+        Identity::slash_claim_issuer(alice.clone(), alice_did, 400),
+        DispatchError::BadOrigin
+    );
+    assert_ok!(Identity::slash_claim_issuer(root.clone(), alice_did, 400));
+    assert_eq!(Balances::reserved_balance(alice_acc), 600);
+    assert_eq!(Balances::total_issuance(), total_issuance - 400);
+    assert_eq!(
+        Identity::claim_issuer(alice_did).map(|claim_issuer| claim_issuer.deposit),
+        Some(600)
+    );
+    assert_err!(
+        Identity::slash_claim_issuer(root.clone(), bob_did, 400),
+        Error::<TestStorage>::ClaimIssuerNotRegistered
+    );
+
+    // Deregistration releases the rest of the deposit.
+    let free_balance = Balances::free_balance(alice_acc);
+    assert_ok!(Identity::deregister_claim_issuer(alice.clone()));
+    assert_eq!(Balances::reserved_balance(alice_acc), 0);
+    assert_eq!(Balances::free_balance(alice_acc), free_balance + 600);
+    assert!(Identity::claim_issuer(alice_did).is_none());
+    assert_err!(
+        Identity::slash_claim_issuer(root, alice_did, 400),
+        Error::<TestStorage>::ClaimIssuerNotRegistered
+    );
+}
+
 #[test]
 fn invalidate_cdd_claims() {
     ExtBuilder::default()
@@ -15,6 +15,7 @@ mod balances_test;
 mod bridge;
 mod committee_test;
 mod compliance_manager_test;
+mod corporate_actions_test;
 mod dividend_test;
 mod fee_details;
 mod group_test;
@@ -22,6 +23,7 @@ mod identity_test;
 mod multisig;
 mod pips_test;
 mod protocol_fee;
+mod settlement_test;
 mod simple_token_test;
 mod statistics_test;
 mod treasury_test;
@@ -0,0 +1,591 @@
+use super::{
+    storage::{register_keyring_account, TestStorage},
+    ExtBuilder,
+};
+
+use pallet_asset::{self as asset, AssetType};
+use pallet_balances as balances;
+use pallet_compliance_manager as compliance_manager;
+use pallet_identity as identity;
+use polymesh_common_utilities::{
+    constants::{COMPLIANCE_MANAGER_FAILURE, ERC1400_INSUFFICIENT_BALANCE},
+    traits::identity::TargetIdAuthorization,
+};
+use polymesh_primitives::{AccountKey, IdentityId, Signatory, Ticker};
+use polymesh_runtime_common::{
+    settlement::{
+        self, AffirmationStatus, InstructionReceipt, InstructionStatus, Leg, LegAsset,
+        SettlementType, VenueType,
+    },
+    simple_token,
+};
+
+use codec::Encode;
+use frame_support::{assert_err, assert_ok};
+use sp_core::H512;
+use sp_runtime::traits::OnInitialize;
+use std::convert::TryFrom;
+use test_client::AccountKeyring;
+
+type Asset = asset::Module<TestStorage>;
+type Balances = balances::Module<TestStorage>;
+type ComplianceManager = compliance_manager::Module<TestStorage>;
+type Identity = identity::Module<TestStorage>;
+type Settlement = settlement::Module<TestStorage>;
+type SimpleToken = simple_token::Module<TestStorage>;
+type Error = settlement::Error<TestStorage>;
+type Origin = <TestStorage as frame_system::Trait>::Origin;
+type System = frame_system::Module<TestStorage>;
+
+/// Creates an asset owned by `owner`. Transfers are allowed by an empty active rule if
+/// `allow_transfers` is set.
+fn create_asset(owner: AccountKeyring, name: &[u8], allow_transfers: bool) -> Ticker {
+    let ticker = Ticker::try_from(name).unwrap();
+    assert_ok!(Asset::create_asset(
+        Origin::signed(owner.public()),
+        name.into(),
+        ticker,
+        1_000_000,
+        true,
+        AssetType::default(),
+        vec![],
+        None
+    ));
+    if allow_transfers {
+        assert_ok!(ComplianceManager::add_active_rule(
+            Origin::signed(owner.public()),
+            ticker,
+            vec![],
+            vec![]
+        ));
+    }
+    ticker
+}
+
+fn create_venue(creator: AccountKeyring) -> u64 {
+    let venue_id = Settlement::venue_counter();
+    assert_ok!(Settlement::create_venue(
+        Origin::signed(creator.public()),
+        b"Venue".into(),
+        VenueType::Exchange
+    ));
+    venue_id
+}
+
+fn leg(from: IdentityId, to: IdentityId, asset: LegAsset, amount: u128) -> Leg<u128> {
+    Leg {
+        from,
+        to,
+        asset,
+        amount,
+    }
+}
+
+fn next_block() {
+    let block_number = System::block_number() + 1;
+    System::set_block_number(block_number);
+    Settlement::on_initialize(block_number);
+}
+
+#[test]
+fn settle_on_affirmation() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(settle_on_affirmation_we);
+}
+
+fn settle_on_affirmation_we() {
+    let alice = AccountKeyring::Alice;
+    let alice_did = register_keyring_account(alice).unwrap();
+    let bob = AccountKeyring::Bob;
+    let bob_did = register_keyring_account(bob).unwrap();
+    let charlie = AccountKeyring::Charlie;
+    let _charlie_did = register_keyring_account(charlie).unwrap();
+
+    let ticker = create_asset(alice, b"ACME", true);
+    let usd = Ticker::try_from(&b"USD"[..]).unwrap();
+    assert_ok!(SimpleToken::create_token(
+        Origin::signed(bob.public()),
+        usd,
+        1_000_000
+    ));
+    assert_ok!(Balances::top_up_identity_balance(
+        Origin::signed(bob.public()),
+        bob_did,
+        1_000
+    ));
+
+    let venue_id = create_venue(charlie);
+    let instruction_id = Settlement::instruction_counter();
+    assert_ok!(Settlement::add_instruction(
+        Origin::signed(charlie.public()),
+        venue_id,
+        SettlementType::SettleOnAffirmation,
+        vec![
+            leg(alice_did, bob_did, LegAsset::Asset(ticker), 100),
+            leg(bob_did, alice_did, LegAsset::SimpleToken(usd), 5_000),
+            leg(bob_did, alice_did, LegAsset::Polyx, 500),
+        ]
+    ));
+    assert_eq!(
+        Settlement::affirms_received(instruction_id, alice_did),
+        AffirmationStatus::Pending
+    );
+    assert_eq!(Settlement::instruction_affirms_pending(instruction_id), 2);
+
+    // The venue creator is not a party of the instruction.
+    assert_err!(
+        Settlement::affirm_instruction(Origin::signed(charlie.public()), instruction_id),
+        Error::NotAParty
+    );
+
+    assert_ok!(Settlement::affirm_instruction(
+        Origin::signed(alice.public()),
+        instruction_id
+    ));
+    assert_err!(
+        Settlement::affirm_instruction(Origin::signed(alice.public()), instruction_id),
+        Error::AlreadyResponded
+    );
+    assert_eq!(
+        Settlement::instruction_details(instruction_id).status,
+        InstructionStatus::Pending
+    );
+    assert_eq!(Asset::balance(&ticker, &alice_did), 1_000_000);
+
+    // The last affirmation executes every leg.
+    assert_ok!(Settlement::affirm_instruction(
+        Origin::signed(bob.public()),
+        instruction_id
+    ));
+    assert_eq!(
+        Settlement::instruction_details(instruction_id).status,
+        InstructionStatus::Executed
+    );
+    assert_eq!(Asset::balance(&ticker, &alice_did), 999_900);
+    assert_eq!(Asset::balance(&ticker, &bob_did), 100);
+    assert_eq!(SimpleToken::balance_of((usd, alice_did)), 5_000);
+    assert_eq!(SimpleToken::balance_of((usd, bob_did)), 995_000);
+    assert_eq!(Balances::identity_balance(alice_did), 500);
+    assert_eq!(Balances::identity_balance(bob_did), 500);
+}
+
+#[test]
+fn failed_instruction_reports_invalid_legs() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(failed_instruction_reports_invalid_legs_we);
+}
+
+fn failed_instruction_reports_invalid_legs_we() {
+    let alice = AccountKeyring::Alice;
+    let alice_did = register_keyring_account(alice).unwrap();
+    let bob = AccountKeyring::Bob;
+    let bob_did = register_keyring_account(bob).unwrap();
+
+    let ticker = create_asset(alice, b"ACME", true);
+    // Without any active rule, every transfer of this asset is rejected.
+    let restricted = create_asset(alice, b"RESTRICTED", false);
+
+    let venue_id = create_venue(alice);
+    let instruction_id = Settlement::instruction_counter();
+    assert_ok!(Settlement::add_instruction(
+        Origin::signed(alice.public()),
+        venue_id,
+        SettlementType::SettleOnAffirmation,
+        vec![
+            leg(alice_did, bob_did, LegAsset::Asset(ticker), 600_000),
+            leg(alice_did, bob_did, LegAsset::Asset(restricted), 10),
+            // Valid on its own, but Alice does not hold 1.2M ACME in total.
+            leg(alice_did, bob_did, LegAsset::Asset(ticker), 600_000),
+            leg(bob_did, alice_did, LegAsset::Polyx, 1),
+        ]
+    ));
+    assert_ok!(Settlement::affirm_instruction(
+        Origin::signed(alice.public()),
+        instruction_id
+    ));
+    assert_ok!(Settlement::affirm_instruction(
+        Origin::signed(bob.public()),
+        instruction_id
+    ));
+
+    assert_eq!(
+        Settlement::instruction_details(instruction_id).status,
+        InstructionStatus::Failed
+    );
+    assert_eq!(
+        Settlement::instruction_failure_reasons(instruction_id),
+        vec![
+            (1, COMPLIANCE_MANAGER_FAILURE),
+            (2, ERC1400_INSUFFICIENT_BALANCE),
+            (3, ERC1400_INSUFFICIENT_BALANCE),
+        ]
+    );
+    // No leg has been settled.
+    assert_eq!(Asset::balance(&ticker, &alice_did), 1_000_000);
+    assert_eq!(Asset::balance(&ticker, &bob_did), 0);
+}
+
+#[test]
+fn settle_on_block() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(settle_on_block_we);
+}
+
+fn settle_on_block_we() {
+    let alice = AccountKeyring::Alice;
+    let alice_did = register_keyring_account(alice).unwrap();
+    let bob = AccountKeyring::Bob;
+    let bob_did = register_keyring_account(bob).unwrap();
+
+    let ticker = create_asset(alice, b"ACME", true);
+    let venue_id = create_venue(alice);
+    let legs = vec![leg(alice_did, bob_did, LegAsset::Asset(ticker), 100)];
+
+    System::set_block_number(1);
+    assert_err!(
+        Settlement::add_instruction(
+            Origin::signed(alice.public()),
+            venue_id,
+            SettlementType::SettleOnBlock(1),
+            legs.clone()
+        ),
+        Error::SettleOnPastBlock
+    );
+
+    let affirmed_id = Settlement::instruction_counter();
+    assert_ok!(Settlement::add_instruction(
+        Origin::signed(alice.public()),
+        venue_id,
+        SettlementType::SettleOnBlock(3),
+        legs.clone()
+    ));
+    let unaffirmed_id = Settlement::instruction_counter();
+    assert_ok!(Settlement::add_instruction(
+        Origin::signed(alice.public()),
+        venue_id,
+        SettlementType::SettleOnBlock(3),
+        legs
+    ));
+    assert_eq!(
+        Settlement::scheduled_instructions(3),
+        vec![affirmed_id, unaffirmed_id]
+    );
+
+    for account in &[alice, bob] {
+        assert_ok!(Settlement::affirm_instruction(
+            Origin::signed(account.public()),
+            affirmed_id
+        ));
+    }
+    assert_ok!(Settlement::affirm_instruction(
+        Origin::signed(alice.public()),
+        unaffirmed_id
+    ));
+    // Fully affirmed instructions wait for their settlement block.
+    assert_eq!(
+        Settlement::instruction_details(affirmed_id).status,
+        InstructionStatus::Pending
+    );
+
+    next_block();
+    assert_eq!(Asset::balance(&ticker, &bob_did), 0);
+    next_block();
+    assert_eq!(
+        Settlement::instruction_details(affirmed_id).status,
+        InstructionStatus::Executed
+    );
+    assert_eq!(
+        Settlement::instruction_details(unaffirmed_id).status,
+        InstructionStatus::Failed
+    );
+    assert_eq!(Asset::balance(&ticker, &bob_did), 100);
+}
+
+#[test]
+fn reject_instruction() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(reject_instruction_we);
+}
+
+fn reject_instruction_we() {
+    let alice = AccountKeyring::Alice;
+    let alice_did = register_keyring_account(alice).unwrap();
+    let bob = AccountKeyring::Bob;
+    let bob_did = register_keyring_account(bob).unwrap();
+
+    let ticker = create_asset(alice, b"ACME", true);
+    let venue_id = create_venue(alice);
+
+    // Only the venue creator adds instructions, and every leg must be meaningful.
+    assert_err!(
+        Settlement::add_instruction(
+            Origin::signed(bob.public()),
+            venue_id,
+            SettlementType::SettleOnAffirmation,
+            vec![leg(alice_did, bob_did, LegAsset::Asset(ticker), 100)]
+        ),
+        Error::Unauthorized
+    );
+    assert_err!(
+        Settlement::add_instruction(
+            Origin::signed(alice.public()),
+            venue_id,
+            SettlementType::SettleOnAffirmation,
+            vec![]
+        ),
+        Error::NoLegs
+    );
+    assert_err!(
+        Settlement::add_instruction(
+            Origin::signed(alice.public()),
+            venue_id,
+            SettlementType::SettleOnAffirmation,
+            vec![leg(alice_did, bob_did, LegAsset::Asset(ticker), 100); settlement::MAX_LEGS + 1]
+        ),
+        Error::TooManyLegs
+    );
+    assert_err!(
+        Settlement::add_instruction(
+            Origin::signed(alice.public()),
+            venue_id,
+            SettlementType::SettleOnAffirmation,
+            vec![leg(alice_did, alice_did, LegAsset::Asset(ticker), 100)]
+        ),
+        Error::SameSenderReceiver
+    );
+
+    let instruction_id = Settlement::instruction_counter();
+    assert_ok!(Settlement::add_instruction(
+        Origin::signed(alice.public()),
+        venue_id,
+        SettlementType::SettleOnAffirmation,
+        vec![leg(alice_did, bob_did, LegAsset::Asset(ticker), 100)]
+    ));
+    assert_ok!(Settlement::affirm_instruction(
+        Origin::signed(alice.public()),
+        instruction_id
+    ));
+    assert_ok!(Settlement::reject_instruction(
+        Origin::signed(bob.public()),
+        instruction_id
+    ));
+    assert_eq!(
+        Settlement::instruction_details(instruction_id).status,
+        InstructionStatus::Rejected
+    );
+    assert_err!(
+        Settlement::affirm_instruction(Origin::signed(bob.public()), instruction_id),
+        Error::InstructionNotPending
+    );
+    assert_eq!(Asset::balance(&ticker, &bob_did), 0);
+}
+
+#[test]
+fn venue_signers_and_filtering() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(venue_signers_and_filtering_we);
+}
+
+fn venue_signers_and_filtering_we() {
+    let alice = AccountKeyring::Alice;
+    let alice_did = register_keyring_account(alice).unwrap();
+    let bob = AccountKeyring::Bob;
+    let bob_did = register_keyring_account(bob).unwrap();
+    let charlie = AccountKeyring::Charlie;
+    let _charlie_did = register_keyring_account(charlie).unwrap();
+    let dave = AccountKeyring::Dave;
+    let dave_did = register_keyring_account(dave).unwrap();
+
+    let ticker = create_asset(alice, b"ACME", true);
+    let venue_id = create_venue(charlie);
+    let legs = vec![leg(alice_did, bob_did, LegAsset::Asset(ticker), 100)];
+    let add_instruction = |account: AccountKeyring| {
+        Settlement::add_instruction(
+            Origin::signed(account.public()),
+            venue_id,
+            SettlementType::SettleOnAffirmation,
+            legs.clone(),
+        )
+    };
+
+    // Only the venue creator can manage its signers.
+    assert_err!(add_instruction(dave), Error::Unauthorized);
+    assert_err!(
+        Settlement::add_venue_signers(
+            Origin::signed(dave.public()),
+            venue_id,
+            vec![Signatory::Identity(dave_did)]
+        ),
+        Error::Unauthorized
+    );
+    assert_ok!(Settlement::add_venue_signers(
+        Origin::signed(charlie.public()),
+        venue_id,
+        vec![Signatory::Identity(dave_did)]
+    ));
+    assert!(Settlement::is_venue_signer(
+        venue_id,
+        &Signatory::Identity(dave_did)
+    ));
+    assert_ok!(add_instruction(dave));
+    assert_ok!(Settlement::remove_venue_signers(
+        Origin::signed(charlie.public()),
+        venue_id,
+        vec![Signatory::Identity(dave_did)]
+    ));
+    assert_err!(add_instruction(dave), Error::Unauthorized);
+
+    // Only the token owner can restrict the venues which move the token.
+    assert_err!(
+        Settlement::set_venue_filtering(Origin::signed(charlie.public()), ticker, true),
+        Error::Unauthorized
+    );
+    assert_ok!(Settlement::set_venue_filtering(
+        Origin::signed(alice.public()),
+        ticker,
+        true
+    ));
+    assert_err!(add_instruction(charlie), Error::UnauthorizedVenue);
+    assert_ok!(Settlement::allow_venues(
+        Origin::signed(alice.public()),
+        ticker,
+        vec![venue_id]
+    ));
+    assert_ok!(add_instruction(charlie));
+    assert_ok!(Settlement::disallow_venues(
+        Origin::signed(alice.public()),
+        ticker,
+        vec![venue_id]
+    ));
+    assert_err!(add_instruction(charlie), Error::UnauthorizedVenue);
+    assert_ok!(Settlement::set_venue_filtering(
+        Origin::signed(alice.public()),
+        ticker,
+        false
+    ));
+    assert_ok!(add_instruction(charlie));
+}
+
+#[test]
+fn add_instruction_with_receipt() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(add_instruction_with_receipt_we);
+}
+
+fn add_instruction_with_receipt_we() {
+    let alice = AccountKeyring::Alice;
+    let alice_did = register_keyring_account(alice).unwrap();
+    let bob = AccountKeyring::Bob;
+    let bob_did = register_keyring_account(bob).unwrap();
+    let charlie = AccountKeyring::Charlie;
+    let charlie_did = register_keyring_account(charlie).unwrap();
+    let dave = AccountKeyring::Dave;
+    let dave_signer = Signatory::AccountKey(AccountKey::from(dave.public().0));
+
+    let ticker = create_asset(alice, b"ACME", true);
+    let venue_id = create_venue(charlie);
+    assert_ok!(Settlement::add_venue_signers(
+        Origin::signed(charlie.public()),
+        venue_id,
+        vec![dave_signer]
+    ));
+
+    let offchain_nonce = Identity::offchain_authorization_nonce(charlie_did);
+    let receipt = InstructionReceipt {
+        authorization: TargetIdAuthorization {
+            target_id: charlie_did,
+            nonce: 7,
+            expires_at: 100,
+        },
+        venue_id,
+        settlement_type: SettlementType::SettleOnAffirmation,
+        legs: vec![leg(alice_did, bob_did, LegAsset::Asset(ticker), 100)],
+    };
+    let signature = H512::from(dave.sign(&receipt.encode()));
+
+    // The receipt has to be signed by a venue signer.
+    assert_err!(
+        Settlement::add_instruction_with_receipt(
+            Origin::signed(bob.public()),
+            receipt.clone(),
+            Signatory::AccountKey(AccountKey::from(AccountKeyring::Eve.public().0)),
+            signature
+        ),
+        Error::UnauthorizedSigner
+    );
+    // The signature covers the whole receipt.
+    let mut tampered_receipt = receipt.clone();
+    tampered_receipt.legs[0].amount = 1_000;
+    assert_err!(
+        Settlement::add_instruction_with_receipt(
+            Origin::signed(bob.public()),
+            tampered_receipt,
+            dave_signer,
+            signature
+        ),
+        Error::InvalidSignature
+    );
+
+    let instruction_id = Settlement::instruction_counter();
+    assert_ok!(Settlement::add_instruction_with_receipt(
+        Origin::signed(bob.public()),
+        receipt.clone(),
+        dave_signer,
+        signature
+    ));
+    assert_eq!(Settlement::instruction_legs(instruction_id), receipt.legs);
+    assert_eq!(
+        Settlement::instruction_details(instruction_id).status,
+        InstructionStatus::Pending
+    );
+
+    // The nonce has been used by the signer, so the receipt cannot be replayed. The off-chain
+    // authorizations of the venue creator are not affected.
+    assert!(Settlement::receipts_used(&dave_signer, 7));
+    assert_eq!(
+        Identity::offchain_authorization_nonce(charlie_did),
+        offchain_nonce
+    );
+    assert_err!(
+        Settlement::add_instruction_with_receipt(
+            Origin::signed(bob.public()),
+            receipt,
+            dave_signer,
+            signature
+        ),
+        Error::ReceiptAlreadyUsed
+    );
+
+    // A signer can revoke a receipt before it is submitted.
+    let receipt = InstructionReceipt {
+        authorization: TargetIdAuthorization {
+            target_id: charlie_did,
+            nonce: 8,
+            expires_at: 100,
+        },
+        venue_id,
+        settlement_type: SettlementType::SettleOnAffirmation,
+        legs: vec![leg(alice_did, bob_did, LegAsset::Asset(ticker), 100)],
+    };
+    let signature = H512::from(dave.sign(&receipt.encode()));
+    assert_ok!(Identity::revoke_offchain_authorization(
+        Origin::signed(dave.public()),
+        dave_signer,
+        receipt.authorization.clone()
+    ));
+    assert_err!(
+        Settlement::add_instruction_with_receipt(
+            Origin::signed(bob.public()),
+            receipt,
+            dave_signer,
+            signature
+        ),
+        Error::ReceiptRevoked
+    );
+}
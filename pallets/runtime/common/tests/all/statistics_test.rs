@@ -4,10 +4,11 @@ use super::{
 };
 use pallet_asset::{self as asset, IdentifierType, SecurityToken};
 use pallet_compliance_manager as compliance_manager;
+use pallet_identity as identity;
 use pallet_statistics as statistics;
-use polymesh_primitives::Ticker;
+use polymesh_primitives::{Claim, ClaimType, JurisdictionName, Scope, Ticker};
 
-use frame_support::assert_ok;
+use frame_support::{assert_err, assert_ok};
 use sp_std::convert::TryFrom;
 use test_client::AccountKeyring;
 
@@ -15,6 +16,9 @@ type Origin = <TestStorage as frame_system::Trait>::Origin;
 type Asset = asset::Module<TestStorage>;
 type Statistic = statistics::Module<TestStorage>;
 type ComplianceManager = compliance_manager::Module<TestStorage>;
+type Identity = identity::Module<TestStorage>;
+type Timestamp = pallet_timestamp::Module<TestStorage>;
+type Error = statistics::Error<TestStorage>;
 
 #[test]
 fn investor_count_per_asset() {
@@ -72,3 +76,166 @@ fn investor_count_per_asset_with_ext() {
     assert_ok!(Asset::transfer(bob_signed, ticker, charlie_did, 500));
     assert_eq!(Statistic::investor_count_per_asset(&ticker), 1);
 }
+
+#[test]
+fn claim_jurisdiction_and_holding_period_stats() {
+    ExtBuilder::default()
+        .build()
+        .execute_with(claim_jurisdiction_and_holding_period_stats_we);
+}
+
+fn claim_jurisdiction_and_holding_period_stats_we() {
+    let alice = Origin::signed(AccountKeyring::Alice.public());
+    let _alice_did = register_keyring_account(AccountKeyring::Alice).unwrap();
+    let bob = Origin::signed(AccountKeyring::Bob.public());
+    let bob_did = register_keyring_account(AccountKeyring::Bob).unwrap();
+    let charlie_did = register_keyring_account(AccountKeyring::Charlie).unwrap();
+    let dave = Origin::signed(AccountKeyring::Dave.public());
+    let dave_did = register_keyring_account(AccountKeyring::Dave).unwrap();
+
+    let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+    assert_ok!(Asset::create_asset(
+        alice.clone(),
+        b"ACME".into(),
+        ticker,
+        1_000_000,
+        true,
+        Default::default(),
+        vec![],
+        None
+    ));
+    assert_ok!(ComplianceManager::add_active_rule(
+        alice.clone(),
+        ticker,
+        vec![],
+        vec![]
+    ));
+    assert_ok!(ComplianceManager::add_default_trusted_claim_issuer(
+        alice.clone(),
+        ticker,
+        dave_did
+    ));
+
+    // Dave attests that Bob is an accredited investor from Canada.
+    let scope = Scope::Identity(Identity::get_token_did(&ticker).unwrap());
+    let canada = JurisdictionName::from(b"Canada");
+    assert_ok!(Identity::add_claim(
+        dave.clone(),
+        bob_did,
+        Claim::Accredited(scope.clone()),
+        None
+    ));
+    assert_ok!(Identity::add_claim(
+        dave.clone(),
+        bob_did,
+        Claim::Jurisdiction(canada.clone(), scope.clone()),
+        None
+    ));
+
+    assert_err!(
+        Statistic::track_claim(bob.clone(), ticker, ClaimType::Accredited, scope.clone()),
+        Error::Unauthorized
+    );
+    assert_ok!(Statistic::track_claim(
+        alice.clone(),
+        ticker,
+        ClaimType::Accredited,
+        scope.clone()
+    ));
+    assert_err!(
+        Statistic::track_claim(alice.clone(), ticker, ClaimType::Accredited, scope.clone()),
+        Error::ClaimAlreadyTracked
+    );
+
+    Timestamp::set_timestamp(1_000);
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 100));
+    assert_eq!(
+        Statistic::investor_count_per_claim(&ticker, (ClaimType::Accredited, scope.clone())),
+        1
+    );
+    assert_eq!(Statistic::balance_per_jurisdiction(&ticker, &canada), 100);
+    assert_eq!(Statistic::first_acquisition(&ticker, bob_did), Some(1_000));
+
+    // Further acquisitions keep the holding period.
+    Timestamp::set_timestamp(2_000);
+    assert_ok!(Asset::transfer(alice.clone(), ticker, bob_did, 50));
+    assert_eq!(Statistic::balance_per_jurisdiction(&ticker, &canada), 150);
+    assert_eq!(Statistic::first_acquisition(&ticker, bob_did), Some(1_000));
+
+    // Charlie is only counted once refreshed after getting the claim.
+    assert_ok!(Asset::transfer(alice.clone(), ticker, charlie_did, 10));
+    assert_ok!(Identity::add_claim(
+        dave,
+        charlie_did,
+        Claim::Accredited(scope.clone()),
+        None
+    ));
+    assert_eq!(
+        Statistic::investor_count_per_claim(&ticker, (ClaimType::Accredited, scope.clone())),
+        1
+    );
+    assert_ok!(Statistic::refresh_holder_stats(
+        bob.clone(),
+        ticker,
+        vec![charlie_did]
+    ));
+    assert_eq!(
+        Statistic::investor_count_per_claim(&ticker, (ClaimType::Accredited, scope.clone())),
+        2
+    );
+
+    // Bob leaves the cap table.
+    assert_ok!(Asset::transfer(bob, ticker, charlie_did, 150));
+    assert_eq!(
+        Statistic::investor_count_per_claim(&ticker, (ClaimType::Accredited, scope.clone())),
+        1
+    );
+    assert_eq!(Statistic::balance_per_jurisdiction(&ticker, &canada), 0);
+    assert_eq!(Statistic::first_acquisition(&ticker, bob_did), None);
+    assert_eq!(
+        Statistic::first_acquisition(&ticker, charlie_did),
+        Some(2_000)
+    );
+
+    assert_ok!(Statistic::untrack_claim(
+        alice.clone(),
+        ticker,
+        ClaimType::Accredited,
+        scope.clone()
+    ));
+    assert_eq!(
+        Statistic::investor_count_per_claim(&ticker, (ClaimType::Accredited, scope.clone())),
+        0
+    );
+    assert!(Statistic::holder_claims(&ticker, charlie_did).is_empty());
+    assert_eq!(
+        Statistic::untrack_cursor(&ticker, (ClaimType::Accredited, scope.clone())),
+        None
+    );
+    assert_err!(
+        Statistic::clear_holder_claims(
+            Origin::signed(AccountKeyring::Charlie.public()),
+            ticker,
+            ClaimType::Accredited,
+            scope.clone()
+        ),
+        Error::NothingToClear
+    );
+
+    // Tracking the claim again counts Charlie from scratch.
+    assert_ok!(Statistic::track_claim(
+        alice.clone(),
+        ticker,
+        ClaimType::Accredited,
+        scope.clone()
+    ));
+    assert_ok!(Statistic::refresh_holder_stats(
+        alice,
+        ticker,
+        vec![charlie_did]
+    ));
+    assert_eq!(
+        Statistic::investor_count_per_claim(&ticker, (ClaimType::Accredited, scope)),
+        1
+    );
+}
@@ -13,9 +13,12 @@ use polymesh_common_utilities::traits::{
     asset::AcceptTransfer, balances::AccountData, group::GroupTrait,
     identity::Trait as IdentityTrait, multisig::AddSignerMultiSig, CommonTrait,
 };
-use polymesh_primitives::{AccountKey, Authorization, AuthorizationData, IdentityId, Signatory};
+use polymesh_primitives::{
+    AccountKey, Authorization, AuthorizationData, Balance, IdentityId, Signatory,
+};
 use polymesh_runtime_common::{
-    bridge, cdd_check::CddChecker, dividend, exemption, simple_token, voting,
+    bridge, cdd_check::CddChecker, corporate_actions, dividend, exemption, settlement,
+    simple_token, voting,
 };
 
 use codec::Encode;
@@ -78,6 +81,7 @@ impl_outer_event! {
         pallet_contracts<T>,
         pallet_session,
         compliance_manager,
+        statistics,
         exemption,
         group Instance1<T>,
         group Instance2<T>,
@@ -87,6 +91,8 @@ impl_outer_event! {
         voting<T>,
         dividend<T>,
         simple_token<T>,
+        settlement<T>,
+        corporate_actions<T>,
         frame_system<T>,
         protocol_fee<T>,
         treasury<T>,
@@ -195,6 +201,9 @@ impl pallet_transaction_payment::CddAndFeeDetails<Call> for TestStorage {
         None
     }
     fn set_current_identity(_: &IdentityId) {}
+    fn charge_spending_limit(_: &Signatory, _: Balance) -> Result<(), InvalidTransaction> {
+        Ok(())
+    }
 }
 
 parameter_types! {
@@ -269,6 +278,7 @@ impl IdentityTrait for TestStorage {
     type Event = Event;
     type Proposal = Call;
     type AddSignerMultiSigTarget = TestStorage;
+    type AssetHoldingsTarget = asset::Module<TestStorage>;
     type CddServiceProviders = group::Module<TestStorage, group::Instance2>;
     type Balances = balances::Module<TestStorage>;
     type ChargeTxFeeTarget = TestStorage;
@@ -291,6 +301,9 @@ impl AcceptTransfer for TestStorage {
     fn accept_asset_ownership_transfer(_: IdentityId, _: u64) -> DispatchResult {
         Ok(())
     }
+    fn accept_portfolio_custody(_: IdentityId, _: u64) -> DispatchResult {
+        Ok(())
+    }
 }
 
 parameter_types! {
@@ -339,11 +352,17 @@ impl pallet_contracts::Trait for TestStorage {
     type BlockGasLimit = BlockGasLimit;
 }
 
-impl statistics::Trait for TestStorage {}
+impl statistics::Trait for TestStorage {
+    type Event = Event;
+    type Asset = asset::Module<TestStorage>;
+    type ComplianceManager = compliance_manager::Module<TestStorage>;
+}
 
 impl compliance_manager::Trait for TestStorage {
     type Event = Event;
     type Asset = asset::Module<TestStorage>;
+    type Statistics = statistics::Module<TestStorage>;
+    type Exemption = exemption::Module<TestStorage>;
 }
 
 impl protocol_fee::Trait for TestStorage {
@@ -447,6 +466,14 @@ impl dividend::Trait for TestStorage {
     type Event = Event;
 }
 
+impl settlement::Trait for TestStorage {
+    type Event = Event;
+}
+
+impl corporate_actions::Trait for TestStorage {
+    type Event = Event;
+}
+
 impl pips::Trait for TestStorage {
     type Currency = balances::Module<Self>;
     type CommitteeOrigin = frame_system::EnsureRoot<AccountId>;
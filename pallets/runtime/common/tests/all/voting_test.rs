@@ -66,6 +66,7 @@ fn add_ballot() {
 
         let ballot_details = Ballot {
             checkpoint_id: 1,
+            checkpoint_schedule: None,
             voting_start: now,
             voting_end: now + now,
             motions: vec![motion1.clone(), motion2.clone()],
@@ -83,6 +84,7 @@ fn add_ballot() {
 
         let expired_ballot_details = Ballot {
             checkpoint_id: 1,
+            checkpoint_schedule: None,
             voting_start: now,
             voting_end: 0,
             motions: vec![motion1.clone(), motion2.clone()],
@@ -100,6 +102,7 @@ fn add_ballot() {
 
         let invalid_date_ballot_details = Ballot {
             checkpoint_id: 1,
+            checkpoint_schedule: None,
             voting_start: now + now + now,
             voting_end: now + now,
             motions: vec![motion1.clone(), motion2.clone()],
@@ -117,6 +120,7 @@ fn add_ballot() {
 
         let empty_ballot_details = Ballot {
             checkpoint_id: 1,
+            checkpoint_schedule: None,
             voting_start: now,
             voting_end: now + now,
             motions: vec![],
@@ -140,6 +144,7 @@ fn add_ballot() {
 
         let no_choice_ballot_details = Ballot {
             checkpoint_id: 1,
+            checkpoint_schedule: None,
             voting_start: now,
             voting_end: now + now,
             motions: vec![motion1.clone(), motion2.clone(), empty_motion],
@@ -224,6 +229,7 @@ fn cancel_ballot() {
 
         let ballot_details = Ballot {
             checkpoint_id: 1,
+            checkpoint_schedule: None,
             voting_start: now,
             voting_end: now + now,
             motions: vec![motion1.clone(), motion2.clone()],
@@ -330,6 +336,7 @@ fn vote() {
 
         let ballot_details = Ballot {
             checkpoint_id: 2,
+            checkpoint_schedule: None,
             voting_start: now,
             voting_end: now + now,
             motions: vec![motion1.clone(), motion2.clone()],
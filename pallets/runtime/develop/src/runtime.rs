@@ -6,9 +6,9 @@ use crate::{
 use polymesh_runtime_common::{
     bridge,
     cdd_check::CddChecker,
-    contracts_wrapper, dividend, exemption,
+    contracts_wrapper, corporate_actions, dividend, exemption,
     impls::{Author, CurrencyToVoteHandler, LinearWeightToFee, TargetedFeeAdjustment},
-    merge_active_and_inactive, simple_token, sto_capped, voting, AvailableBlockRatio,
+    merge_active_and_inactive, settlement, simple_token, sto_capped, voting, AvailableBlockRatio,
     BlockHashCount, MaximumBlockLength, MaximumBlockWeight, NegativeImbalance,
 };
 
@@ -30,8 +30,8 @@ use polymesh_common_utilities::{
     CommonTrait,
 };
 use polymesh_primitives::{
-    AccountId, AccountIndex, AccountKey, Balance, BlockNumber, Hash, IdentityId, Index, Moment,
-    Signature, SigningItem, Ticker,
+    AccountId, AccountIndex, AccountKey, Balance, BlockNumber, ClaimType, Hash, IdentityId, Index,
+    Moment, Signature, SigningItem, Ticker,
 };
 
 use sp_api::impl_runtime_apis;
@@ -476,6 +476,8 @@ impl simple_token::Trait for Runtime {
 impl compliance_manager::Trait for Runtime {
     type Event = Event;
     type Asset = Asset;
+    type Statistics = statistics::Module<Runtime>;
+    type Exemption = Exemption;
 }
 
 impl voting::Trait for Runtime {
@@ -492,6 +494,7 @@ impl IdentityTrait for Runtime {
     type Event = Event;
     type Proposal = Call;
     type AddSignerMultiSigTarget = MultiSig;
+    type AssetHoldingsTarget = Asset;
     type CddServiceProviders = CddServiceProviders;
     type Balances = balances::Module<Runtime>;
     type ChargeTxFeeTarget = TransactionPayment;
@@ -512,6 +515,14 @@ impl dividend::Trait for Runtime {
     type Event = Event;
 }
 
+impl settlement::Trait for Runtime {
+    type Event = Event;
+}
+
+impl corporate_actions::Trait for Runtime {
+    type Event = Event;
+}
+
 /// CddProviders instance of group
 impl group::Trait<group::Instance2> for Runtime {
     type Event = Event;
@@ -523,7 +534,11 @@ impl group::Trait<group::Instance2> for Runtime {
     type MembershipChanged = Identity;
 }
 
-impl statistics::Trait for Runtime {}
+impl statistics::Trait for Runtime {
+    type Event = Event;
+    type Asset = Asset;
+    type ComplianceManager = compliance_manager::Module<Runtime>;
+}
 
 /// A runtime transaction submitter for the cdd_offchain_worker
 // Comment it in the favour of Testnet v1 release
@@ -645,8 +660,10 @@ construct_runtime!(
         StoCapped: sto_capped::{Module, Call, Storage, Event<T>},
         Exemption: exemption::{Module, Call, Storage, Event},
         SimpleToken: simple_token::{Module, Call, Storage, Event<T>},
+        Settlement: settlement::{Module, Call, Storage, Event<T>},
+        CorporateActions: corporate_actions::{Module, Call, Storage, Event<T>},
         CddServiceProviders: group::<Instance2>::{Module, Call, Storage, Event<T>, Config<T>},
-        Statistic: statistics::{Module, Call, Storage},
+        Statistic: statistics::{Module, Call, Storage, Event},
         ProtocolFee: protocol_fee::{Module, Call, Storage, Event<T>, Config<T>},
         // Comment it in the favour of Testnet v1 release
         // CddOffchainWorker: pallet_cdd_offchain_worker::{Module, Call, Storage, ValidateUnsigned, Event<T>}
@@ -915,6 +932,33 @@ impl_runtime_apis! {
             Asset::unsafe_can_transfer(sender, ticker, from_did, to_did, value)
                 .map_err(|msg| msg.as_bytes().to_vec())
         }
+
+        #[inline]
+        fn holders(
+            ticker: Ticker,
+            checkpoint_id: Option<u64>,
+            start: u64,
+            count: u64) -> pallet_asset_rpc_runtime_api::HoldersPage<Balance>
+        {
+            let (holders, next) = Asset::holders(&ticker, checkpoint_id, start, count);
+            pallet_asset_rpc_runtime_api::HoldersPage { holders, next }
+        }
+
+        #[inline]
+        fn transfer_report(
+            sender: AccountId,
+            ticker: Ticker,
+            from_did: Option<IdentityId>,
+            to_did: Option<IdentityId>,
+            value: Balance) -> polymesh_primitives::TransferReport<AccountId, Balance>
+        {
+            Asset::transfer_report(sender, ticker, from_did, to_did, value)
+        }
+
+        #[inline]
+        fn exemptions(ticker: Ticker) -> Vec<(IdentityId, Option<ClaimType>)> {
+            Exemption::exemption_list(ticker)
+        }
     }
 
     impl pallet_group_rpc_runtime_api::GroupApi<Block> for Runtime {
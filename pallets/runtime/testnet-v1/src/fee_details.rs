@@ -21,10 +21,10 @@ use pallet_multisig as multisig;
 use pallet_transaction_payment::CddAndFeeDetails;
 use polymesh_common_utilities::Context;
 use polymesh_primitives::{
-    traits::IdentityCurrency, AccountId, AccountKey, AuthorizationData, IdentityId, Signatory,
-    TransactionError,
+    traits::IdentityCurrency, AccountId, AccountKey, AuthorizationData, Balance, DispatchScope,
+    IdentityId, Signatory, TransactionError,
 };
-use polymesh_runtime_common::bridge;
+use polymesh_runtime_common::{bridge, dispatch_scope};
 use sp_runtime::transaction_validity::InvalidTransaction;
 
 use codec::{Decode, Encode};
@@ -85,6 +85,28 @@ impl CddAndFeeDetails<Call> for CddHandler {
                 sp_runtime::print("accept_master_key");
                 is_auth_valid(caller, rotation_auth_id, CallType::AcceptIdentityMaster)
             }
+            // Call made by a guardian key, not linked to any identity, to start or approve the
+            // recovery of the master key of an identity that has a valid CDD.
+            Call::Identity(identity::Call::initiate_recovery(did, ..))
+            | Call::Identity(identity::Call::approve_recovery(did, ..))
+                if is_unlinked_guardian_key(caller, did) =>
+            {
+                sp_runtime::print("recovery guardian");
+                check_cdd(did)
+            }
+            // Call made by the new master key of a recovery of an identity that has a valid CDD.
+            Call::Identity(identity::Call::finalize_recovery(did, ..)) => {
+                sp_runtime::print("finalize_recovery");
+                match caller {
+                    Signatory::AccountKey(key) if Identity::active_recovery(did, key).is_some() => {
+                        check_cdd(did)
+                    }
+                    _ => Err(InvalidTransaction::Custom(
+                        TransactionError::InvalidAuthorization as u8,
+                    )
+                    .into()),
+                }
+            }
             // Call made by an Account key to propose or approve a multisig transaction.
             // The multisig must have valid CDD and the caller must be a signer of the multisig.
             Call::MultiSig(multisig::Call::create_or_approve_proposal_as_key(multisig, ..))
@@ -121,6 +143,7 @@ impl CddAndFeeDetails<Call> for CddHandler {
                 Signatory::AccountKey(key) => {
                     if let Some(did) = Identity::get_identity(key) {
                         if Identity::has_valid_cdd(did) {
+                            check_permissions(call, did, key)?;
                             Context::set_current_identity::<Identity>(Some(did));
                             if *charge_did {
                                 return Ok(Some(Signatory::from(did)));
@@ -149,6 +172,7 @@ impl CddAndFeeDetails<Call> for CddHandler {
                 Signatory::AccountKey(key) => {
                     if let Some(did) = Identity::get_identity(key) {
                         if Identity::has_valid_cdd(did) {
+                            check_permissions(call, did, key)?;
                             Context::set_current_identity::<Identity>(Some(did));
                             if let Some(fee_did) = Balances::charge_fee_to_identity(&key) {
                                 sp_runtime::print("charging identity");
@@ -192,6 +216,18 @@ impl CddAndFeeDetails<Call> for CddHandler {
     fn set_current_identity(did: &IdentityId) {
         Context::set_current_identity::<Identity>(Some(*did));
     }
+
+    /// Counts the transaction and its fee against the spending limit of the signing key.
+    fn charge_spending_limit(caller: &Signatory, fee: Balance) -> Result<(), InvalidTransaction> {
+        match caller {
+            Signatory::AccountKey(key) => {
+                Identity::charge_transaction_spending(key, fee).map_err(|_| {
+                    InvalidTransaction::Custom(TransactionError::SpendingLimitExceeded as u8)
+                })
+            }
+            Signatory::Identity(_) => Ok(()),
+        }
+    }
 }
 
 /// Returns signatory to charge fee if auth is valid.
@@ -252,6 +288,16 @@ fn is_auth_valid(
     Err(InvalidTransaction::Custom(TransactionError::InvalidAuthorization as u8).into())
 }
 
+/// Returns `true` if `caller` is a key not linked to any identity and a guardian of `did`.
+fn is_unlinked_guardian_key(caller: &Signatory, did: &IdentityId) -> bool {
+    match caller {
+        Signatory::AccountKey(key) => {
+            Identity::get_identity(key).is_none() && Identity::is_recovery_guardian(*did, caller)
+        }
+        Signatory::Identity(_) => false,
+    }
+}
+
 /// Returns signatory to charge fee if cdd is valid.
 fn check_cdd(did: &IdentityId) -> Result<Option<Signatory>, InvalidTransaction> {
     if Identity::has_valid_cdd(*did) {
@@ -262,3 +308,82 @@ fn check_cdd(did: &IdentityId) -> Result<Option<Signatory>, InvalidTransaction>
         Err(InvalidTransaction::Custom(TransactionError::CddRequired as u8).into())
     }
 }
+
+/// Returns an error if the signing key `key` of `did` is not permitted to sign `call`.
+fn check_permissions(
+    call: &Call,
+    did: IdentityId,
+    key: &AccountKey,
+) -> Result<(), InvalidTransaction> {
+    if is_call_permitted(call, did, did, key) {
+        Ok(())
+    } else {
+        sp_runtime::print("ERROR: The signing key lacks the permissions for this transaction");
+        Err(InvalidTransaction::Custom(TransactionError::MissingPermissions as u8).into())
+    }
+}
+
+/// Returns true if the signing key `key` of `did` is permitted to sign `call` made on behalf of
+/// `on_behalf_of`, and the proposals forwarded by `call` to other identities.
+fn is_call_permitted(
+    call: &Call,
+    did: IdentityId,
+    on_behalf_of: IdentityId,
+    key: &AccountKey,
+) -> bool {
+    let is_forwarded_call_permitted = match call {
+        Call::Identity(identity::Call::forwarded_call(target_did, proposal)) => {
+            is_call_permitted(proposal, did, *target_did, key)
+        }
+        _ => true,
+    };
+    is_forwarded_call_permitted
+        && Identity::is_key_permitted(did, key, &dispatch_scope(call, on_behalf_of))
+}
+
+/// Returns the scope of `call` made on behalf of `did`.
+fn dispatch_scope(call: &Call, did: IdentityId) -> DispatchScope {
+    match call {
+        Call::System(c) => dispatch_scope::named(b"system", c),
+        Call::Babe(c) => dispatch_scope::named(b"babe", c),
+        Call::Timestamp(c) => dispatch_scope::named(b"timestamp", c),
+        Call::Indices(c) => dispatch_scope::named(b"indices", c),
+        Call::Balances(c) => dispatch_scope::balances(b"balances", did, c),
+        Call::Authorship(c) => dispatch_scope::named(b"authorship", c),
+        Call::Staking(c) => dispatch_scope::named(b"staking", c),
+        Call::Offences(c) => dispatch_scope::named(b"offences", c),
+        Call::Session(c) => dispatch_scope::named(b"session", c),
+        Call::FinalityTracker(c) => dispatch_scope::named(b"finality_tracker", c),
+        Call::Grandpa(c) => dispatch_scope::named(b"grandpa", c),
+        Call::ImOnline(c) => dispatch_scope::named(b"im_online", c),
+        Call::AuthorityDiscovery(c) => dispatch_scope::named(b"authority_discovery", c),
+        Call::RandomnessCollectiveFlip(c) => {
+            dispatch_scope::named(b"randomness_collective_flip", c)
+        }
+        Call::Sudo(c) => dispatch_scope::named(b"sudo", c),
+        Call::MultiSig(c) => dispatch_scope::named(b"multi_sig", c),
+        Call::Contracts(c) => dispatch_scope::named(b"contracts", c),
+        Call::Treasury(c) => dispatch_scope::named(b"treasury", c),
+        Call::PolymeshCommittee(c) => dispatch_scope::named(b"polymesh_committee", c),
+        Call::CommitteeMembership(c) => dispatch_scope::named(b"committee_membership", c),
+        Call::Pips(c) => dispatch_scope::named(b"pips", c),
+        Call::Asset(c) => dispatch_scope::asset(b"asset", did, c),
+        Call::Dividend(c) => dispatch_scope::dividend(b"dividend", did, c),
+        Call::Identity(c) => dispatch_scope::identity(b"identity", did, c),
+        Call::Bridge(c) => dispatch_scope::named(b"bridge", c),
+        Call::ComplianceManager(c) => {
+            dispatch_scope::compliance_manager(b"compliance_manager", did, c)
+        }
+        Call::Voting(c) => dispatch_scope::voting(b"voting", did, c),
+        Call::StoCapped(c) => dispatch_scope::sto_capped(b"sto_capped", did, c),
+        Call::Exemption(c) => dispatch_scope::exemption(b"exemption", did, c),
+        Call::SimpleToken(c) => dispatch_scope::simple_token(b"simple_token", did, c),
+        Call::Settlement(c) => dispatch_scope::settlement(b"settlement", did, c),
+        Call::CorporateActions(c) => {
+            dispatch_scope::corporate_actions(b"corporate_actions", did, c)
+        }
+        Call::CddServiceProviders(c) => dispatch_scope::named(b"cdd_service_providers", c),
+        Call::Statistic(c) => dispatch_scope::statistics(b"statistic", did, c),
+        Call::ProtocolFee(c) => dispatch_scope::named(b"protocol_fee", c),
+    }
+}
@@ -6,9 +6,9 @@ use crate::{
 use polymesh_runtime_common::{
     bridge,
     cdd_check::CddChecker,
-    contracts_wrapper, dividend, exemption,
+    contracts_wrapper, corporate_actions, dividend, exemption,
     impls::{Author, CurrencyToVoteHandler, LinearWeightToFee, TargetedFeeAdjustment},
-    merge_active_and_inactive, simple_token, sto_capped, voting, AvailableBlockRatio,
+    merge_active_and_inactive, settlement, simple_token, sto_capped, voting, AvailableBlockRatio,
     BlockHashCount, MaximumBlockLength, MaximumBlockWeight, NegativeImbalance,
 };
 
@@ -29,8 +29,8 @@ use polymesh_common_utilities::{
     CommonTrait,
 };
 use polymesh_primitives::{
-    AccountId, AccountIndex, AccountKey, Balance, BlockNumber, Hash, IdentityId, Index, Moment,
-    Signature, SigningItem, Ticker,
+    AccountId, AccountIndex, AccountKey, Balance, BlockNumber, ClaimType, Hash, IdentityId, Index,
+    Moment, Signature, SigningItem, Ticker,
 };
 
 use frame_support::{
@@ -466,6 +466,8 @@ impl simple_token::Trait for Runtime {
 impl compliance_manager::Trait for Runtime {
     type Event = Event;
     type Asset = Asset;
+    type Statistics = statistics::Module<Runtime>;
+    type Exemption = Exemption;
 }
 
 impl voting::Trait for Runtime {
@@ -482,6 +484,7 @@ impl IdentityTrait for Runtime {
     type Event = Event;
     type Proposal = Call;
     type AddSignerMultiSigTarget = MultiSig;
+    type AssetHoldingsTarget = Asset;
     type CddServiceProviders = CddServiceProviders;
     type Balances = balances::Module<Runtime>;
     type ChargeTxFeeTarget = TransactionPayment;
@@ -502,6 +505,14 @@ impl dividend::Trait for Runtime {
     type Event = Event;
 }
 
+impl settlement::Trait for Runtime {
+    type Event = Event;
+}
+
+impl corporate_actions::Trait for Runtime {
+    type Event = Event;
+}
+
 /// CddProviders instance of group
 impl group::Trait<group::Instance2> for Runtime {
     type Event = Event;
@@ -513,7 +524,11 @@ impl group::Trait<group::Instance2> for Runtime {
     type MembershipChanged = Identity;
 }
 
-impl statistics::Trait for Runtime {}
+impl statistics::Trait for Runtime {
+    type Event = Event;
+    type Asset = Asset;
+    type ComplianceManager = compliance_manager::Module<Runtime>;
+}
 
 construct_runtime!(
     pub enum Runtime where
@@ -566,8 +581,10 @@ construct_runtime!(
         StoCapped: sto_capped::{Module, Call, Storage, Event<T>},
         Exemption: exemption::{Module, Call, Storage, Event},
         SimpleToken: simple_token::{Module, Call, Storage, Event<T>},
+        Settlement: settlement::{Module, Call, Storage, Event<T>},
+        CorporateActions: corporate_actions::{Module, Call, Storage, Event<T>},
         CddServiceProviders: group::<Instance2>::{Module, Call, Storage, Event<T>, Config<T>},
-        Statistic: statistics::{Module, Call, Storage},
+        Statistic: statistics::{Module, Call, Storage, Event},
         ProtocolFee: protocol_fee::{Module, Call, Storage, Event<T>, Config<T>},
     }
 );
@@ -834,6 +851,33 @@ impl_runtime_apis! {
             Asset::unsafe_can_transfer(sender, ticker, from_did, to_did, value)
                 .map_err(|msg| msg.as_bytes().to_vec())
         }
+
+        #[inline]
+        fn holders(
+            ticker: Ticker,
+            checkpoint_id: Option<u64>,
+            start: u64,
+            count: u64) -> pallet_asset_rpc_runtime_api::HoldersPage<Balance>
+        {
+            let (holders, next) = Asset::holders(&ticker, checkpoint_id, start, count);
+            pallet_asset_rpc_runtime_api::HoldersPage { holders, next }
+        }
+
+        #[inline]
+        fn transfer_report(
+            sender: AccountId,
+            ticker: Ticker,
+            from_did: Option<IdentityId>,
+            to_did: Option<IdentityId>,
+            value: Balance) -> polymesh_primitives::TransferReport<AccountId, Balance>
+        {
+            Asset::transfer_report(sender, ticker, from_did, to_did, value)
+        }
+
+        #[inline]
+        fn exemptions(ticker: Ticker) -> Vec<(IdentityId, Option<ClaimType>)> {
+            Exemption::exemption_list(ticker)
+        }
     }
 
     impl pallet_group_rpc_runtime_api::GroupApi<Block> for Runtime {
@@ -36,7 +36,7 @@ use pallet_group as group;
 use pallet_identity::{self as identity};
 use pallet_protocol_fee as protocol_fee;
 use polymesh_common_utilities::traits::{
-    asset::AcceptTransfer,
+    asset::{AcceptTransfer, AssetHoldings},
     balances::{AccountData, CheckCdd},
     group::{GroupTrait, InactiveMember},
     identity::Trait as IdentityTrait,
@@ -249,6 +249,7 @@ impl IdentityTrait for Test {
     type Event = ();
     type Proposal = Call;
     type AddSignerMultiSigTarget = Test;
+    type AssetHoldingsTarget = Test;
     type CddServiceProviders = group::Module<Test, group::Instance2>;
     type Balances = balances::Module<Test>;
     type ChargeTxFeeTarget = Test;
@@ -268,6 +269,9 @@ impl pallet_transaction_payment::CddAndFeeDetails<Call> for Test {
         None
     }
     fn set_current_identity(_: &IdentityId) {}
+    fn charge_spending_limit(_: &Signatory, _: Balance) -> Result<(), InvalidTransaction> {
+        Ok(())
+    }
 }
 
 impl pallet_transaction_payment::ChargeTxFee for Test {
@@ -332,6 +336,15 @@ impl AcceptTransfer for Test {
     fn accept_asset_ownership_transfer(_: IdentityId, _: u64) -> DispatchResult {
         Ok(())
     }
+    fn accept_portfolio_custody(_: IdentityId, _: u64) -> DispatchResult {
+        Ok(())
+    }
+}
+
+impl AssetHoldings for Test {
+    fn has_asset_holdings(_: IdentityId) -> bool {
+        false
+    }
 }
 
 impl AddSignerMultiSig for Test {
@@ -13,52 +13,380 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+//! # Statistics Module
+//!
+//! The Statistics module keeps the figures of each asset which transfer restrictions are based on.
+//!
+//! ## Overview
+//!
+//! The following statistics are updated on every change of balance:
+//!
+//! - Number of investors per asset, which is also recorded at each checkpoint.
+//! - Number of investors per claim type and scope, for the claims tracked by the asset owner.
+//! - Aggregate balance per jurisdiction, taken from the `Claim::Jurisdiction` issued by a trusted
+//!   claim issuer of the asset and scoped to the asset identity.
+//! - Time of first acquisition per holder, which is reset once the holder balance drops to zero.
+//!
+//! Claims can change without a transfer, so statistics of a holder are only refreshed on their
+//! next change of balance, or through `refresh_holder_stats`.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `track_claim` - Starts counting the investors holding a claim type in a scope.
+//! - `untrack_claim` - Stops counting the investors holding a claim type in a scope.
+//! - `clear_holder_claims` - Continues clearing the holders counted under an untracked claim.
+//! - `refresh_holder_stats` - Recomputes the statistics of the given holders.
+//!
+//! ### Public Functions
+//!
+//! - `update_transfer_stats` - Updates the statistics after a change of balances.
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use polymesh_common_utilities::balances::Trait as BalancesTrait;
-use polymesh_primitives::Ticker;
+use pallet_identity as identity;
+use polymesh_common_utilities::{
+    asset::Trait as AssetTrait, compliance_manager::Trait as ComplianceManagerTrait,
+    identity::Trait as IdentityTrait, statistics::Trait as StatisticsTrait, Context,
+};
+use polymesh_primitives::{
+    migration, AccountKey, Claim, ClaimType, IdentityId, JurisdictionName, Scope, Ticker,
+};
 
-use frame_support::{decl_module, decl_storage};
+use codec::{Decode, Encode};
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage,
+    dispatch::{DispatchError, DispatchResult},
+    ensure,
+    storage::unhashed,
+    weights::{DispatchClass, FunctionOf, SimpleDispatchInfo},
+    StorageDoubleMap,
+};
+use frame_system::ensure_signed;
+use sp_runtime::traits::{Saturating, Zero};
+use sp_std::{convert::TryFrom, prelude::*};
 
 type Counter = u64;
+type Identity<T> = identity::Module<T>;
 
-pub trait Trait: BalancesTrait {}
+/// Maximum number of claims whose investors are counted per asset.
+pub const MAX_TRACKED_CLAIMS: usize = 10;
+
+/// Maximum number of holders cleared per call after a claim is untracked.
+pub const MAX_CLEARED_HOLDERS: u64 = 100;
+
+pub trait Trait: IdentityTrait {
+    /// The overarching event type.
+    type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+    /// Asset module.
+    type Asset: AssetTrait<Self::Balance, Self::AccountId>;
+    /// Compliance manager module, which provides the trusted claim issuers of an asset.
+    type ComplianceManager: ComplianceManagerTrait<Self::Balance>;
+}
 
 decl_storage! {
     trait Store for Module<T: Trait> as statistics {
         /// Number of investor per asset.
         pub InvestorCountPerAsset get(fn investor_count_per_asset): map hasher(blake2_256) Ticker => Counter ;
+        /// Number of investor per asset at a given checkpoint.
+        /// (ticker, checkpoint id) -> investor count
+        pub InvestorCountAtCheckpoint get(fn investor_count_at_checkpoint): map hasher(blake2_128_concat) (Ticker, u64) => Counter;
+        /// Claims whose investors are counted for an asset.
+        pub TrackedClaims get(fn tracked_claims): map hasher(blake2_128_concat) Ticker => Vec<(ClaimType, Scope)>;
+        /// Number of investors per asset holding a tracked claim.
+        /// ticker -> (claim type, scope) -> investor count
+        pub InvestorCountPerClaim get(fn investor_count_per_claim): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) (ClaimType, Scope) => Counter;
+        /// Tracked claims each investor is currently counted under.
+        HolderClaims get(fn holder_claims): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) IdentityId => Vec<(ClaimType, Scope)>;
+        /// Index in the holder list of an asset of the next holder to clear from an untracked
+        /// claim. The claim cannot be tracked again until every holder is cleared.
+        /// ticker -> (claim type, scope) -> holder index
+        pub UntrackCursor get(fn untrack_cursor): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) (ClaimType, Scope) => Option<u64>;
+        /// Aggregate balance per asset held by the investors of a jurisdiction.
+        pub BalancePerJurisdiction get(fn balance_per_jurisdiction): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) JurisdictionName => T::Balance;
+        /// Jurisdiction and balance each investor is currently accounted for.
+        HolderJurisdiction get(fn holder_jurisdiction): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) IdentityId => Option<(JurisdictionName, T::Balance)>;
+        /// Time when an investor acquired an asset after last holding no balance of it.
+        pub FirstAcquisition get(fn first_acquisition): double_map hasher(blake2_128_concat) Ticker, hasher(blake2_128_concat) IdentityId => Option<T::Moment>;
+        /// Version of the storage layout. Version 1 stores claims with `Scope` as an enum.
+        pub StorageVersion get(fn storage_version) build(|_| 1u32): u32;
+        /// Progress of the migration to the next storage version.
+        MigrationCursor get(fn migration_cursor): migration::MigrationCursor;
+    }
+}
+
+decl_event!(
+    pub enum Event {
+        /// Investors holding a claim are counted from now on.
+        /// (caller DID, ticker, claim type, scope)
+        ClaimTracked(IdentityId, Ticker, ClaimType, Scope),
+        /// Investors holding a claim are not counted anymore.
+        /// (caller DID, ticker, claim type, scope)
+        ClaimUntracked(IdentityId, Ticker, ClaimType, Scope),
+    }
+);
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// The sender is not the owner of the asset.
+        Unauthorized,
+        /// The claim is already tracked.
+        ClaimAlreadyTracked,
+        /// The claim is not tracked.
+        ClaimNotTracked,
+        /// The maximum number of tracked claims has been reached.
+        TooManyTrackedClaims,
+        /// The holders of the untracked claim are still being cleared.
+        ClaimBeingCleared,
+        /// No holders of the claim are left to clear.
+        NothingToClear,
     }
 }
 
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
+        fn deposit_event() = default;
+
+        fn on_initialize(_block_number: T::BlockNumber) {
+            if Self::storage_version() < 1 {
+                Self::migrate_storage();
+            }
+        }
+
+        /// Starts counting the investors of `ticker` which hold a claim of `claim_type` in `scope`.
+        /// Current investors are counted on their next change of balance, or once refreshed
+        /// through `refresh_holder_stats`.
+        ///
+        /// # Arguments
+        /// * `origin` - Signer of the dispatchable. It should be the owner of the ticker.
+        /// * `ticker` - Symbol of the asset.
+        /// * `claim_type` - Type of the claim.
+        /// * `scope` - Scope of the claim.
+        #[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+        pub fn track_claim(origin, ticker: Ticker, claim_type: ClaimType, scope: Scope) -> DispatchResult {
+            let did = Self::ensure_owner(origin, &ticker)?;
+            let mut tracked = Self::tracked_claims(&ticker);
+            let key = (claim_type, scope.clone());
+            ensure!(!tracked.contains(&key), Error::<T>::ClaimAlreadyTracked);
+            ensure!(
+                !<UntrackCursor>::contains_key(&ticker, &key),
+                Error::<T>::ClaimBeingCleared
+            );
+            ensure!(tracked.len() < MAX_TRACKED_CLAIMS, Error::<T>::TooManyTrackedClaims);
+            tracked.push(key);
+            <TrackedClaims>::insert(&ticker, tracked);
+            Self::deposit_event(Event::ClaimTracked(did, ticker, claim_type, scope));
+            Ok(())
+        }
+
+        /// Stops counting the investors of `ticker` which hold a claim of `claim_type` in `scope`.
+        /// Up to `MAX_CLEARED_HOLDERS` holders counted under the claim are cleared, and the rest
+        /// through `clear_holder_claims`.
+        ///
+        /// # Arguments
+        /// * `origin` - Signer of the dispatchable. It should be the owner of the ticker.
+        /// * `ticker` - Symbol of the asset.
+        /// * `claim_type` - Type of the claim.
+        /// * `scope` - Scope of the claim.
+        #[weight = SimpleDispatchInfo::FixedNormal(5_000_000)]
+        pub fn untrack_claim(origin, ticker: Ticker, claim_type: ClaimType, scope: Scope) -> DispatchResult {
+            let did = Self::ensure_owner(origin, &ticker)?;
+            let mut tracked = Self::tracked_claims(&ticker);
+            let index = tracked
+                .iter()
+                .position(|(tracked_type, tracked_scope)| *tracked_type == claim_type && *tracked_scope == scope)
+                .ok_or(Error::<T>::ClaimNotTracked)?;
+            let key = tracked.remove(index);
+            <TrackedClaims>::insert(&ticker, tracked);
+            <InvestorCountPerClaim>::remove(&ticker, &key);
+            <UntrackCursor>::insert(&ticker, &key, 0);
+            Self::clear_holder_claims_batch(&ticker, &key);
+            Self::deposit_event(Event::ClaimUntracked(did, ticker, claim_type, scope));
+            Ok(())
+        }
+
+        /// Clears up to `MAX_CLEARED_HOLDERS` more holders of `ticker` counted under the untracked
+        /// claim of `claim_type` in `scope`.
+        ///
+        /// # Arguments
+        /// * `origin` - Any signer.
+        /// * `ticker` - Symbol of the asset.
+        /// * `claim_type` - Type of the claim.
+        /// * `scope` - Scope of the claim.
+        #[weight = SimpleDispatchInfo::FixedNormal(5_000_000)]
+        pub fn clear_holder_claims(origin, ticker: Ticker, claim_type: ClaimType, scope: Scope) -> DispatchResult {
+            ensure_signed(origin)?;
+            let key = (claim_type, scope);
+            ensure!(<UntrackCursor>::contains_key(&ticker, &key), Error::<T>::NothingToClear);
+            Self::clear_holder_claims_batch(&ticker, &key);
+            Ok(())
+        }
+
+        /// Recomputes the statistics of `holders` of `ticker` after their claims have changed.
+        ///
+        /// # Arguments
+        /// * `origin` - Any signer.
+        /// * `ticker` - Symbol of the asset.
+        /// * `holders` - Identities whose statistics are recomputed.
+        ///
+        /// # Weight
+        /// `50_000 + 100_000 * holders.len()`
+        #[weight = FunctionOf(
+            |(_, holders): (&Ticker, &Vec<IdentityId>)| {
+                50_000 + 100_000 * u32::try_from(holders.len()).unwrap_or_default()
+            },
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn refresh_holder_stats(origin, ticker: Ticker, holders: Vec<IdentityId>) -> DispatchResult {
+            ensure_signed(origin)?;
+            for did in holders {
+                Self::update_holder_stats(&ticker, did, T::Asset::balance(&ticker, did));
+            }
+            Ok(())
+        }
     }
 }
 
 impl<T: Trait> Module<T> {
+    /// Runs a batch of the migration to the next storage version, and bumps the storage version
+    /// once the migration is done.
+    fn migrate_storage() {
+        let mut cursor = Self::migration_cursor();
+        let done = migration::run_migration_batch(
+            &mut cursor,
+            &[
+                Self::migrate_tracked_claims,
+                Self::migrate_holder_claims,
+                Self::migrate_claim_counts,
+                Self::unstage_claim_counts,
+            ],
+        );
+        if done {
+            <StorageVersion>::mutate(|version| *version += 1);
+            <MigrationCursor>::kill();
+        } else {
+            <MigrationCursor>::put(cursor);
+        }
+    }
+
+    /// Migrates the claims stored under `item` when `Scope` was an `IdentityId` to
+    /// `Scope::Identity`.
+    fn migrate_claim_list(item: &[u8], after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        let prefix = migration::storage_prefix(b"statistics", item);
+        let (entries, next) =
+            migration::raw_entries_page::<Vec<(ClaimType, IdentityId)>>(&prefix, after, limit);
+        for (raw_key, claims) in entries {
+            let claims = claims
+                .into_iter()
+                .map(|(claim_type, did)| (claim_type, Scope::Identity(did)))
+                .collect::<Vec<_>>();
+            unhashed::put(&raw_key, &claims);
+        }
+        next
+    }
+
+    /// Migrates the tracked claims to `Scope::Identity`.
+    fn migrate_tracked_claims(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        Self::migrate_claim_list(b"TrackedClaims", after, limit)
+    }
+
+    /// Migrates the claims each investor is counted under to `Scope::Identity`.
+    fn migrate_holder_claims(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        Self::migrate_claim_list(b"HolderClaims", after, limit)
+    }
+
+    /// Migrates the investor counts per claim to keys with `Scope::Identity`. Migrated counts are
+    /// staged, and moved to their new keys by `unstage_claim_counts`.
+    fn migrate_claim_counts(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        // Raw key: prefix ++ blake2_128(ticker) ++ ticker ++ blake2_128(claim) ++ claim.
+        let prefix = migration::storage_prefix(b"statistics", b"InvestorCountPerClaim");
+        let staging = migration::storage_prefix(b"statistics", b"InvestorCountPerClaimMigration");
+        let (entries, next) = migration::raw_entries_page::<Counter>(&prefix, after, limit);
+        for (raw_key, count) in entries {
+            let mut input = raw_key.get(prefix.len() + 16..).unwrap_or_default();
+            let ticker = match Ticker::decode(&mut input) {
+                Ok(ticker) => ticker,
+                Err(_) => continue,
+            };
+            let old_key = input
+                .get(16..)
+                .and_then(|mut input| <(ClaimType, IdentityId)>::decode(&mut input).ok());
+            if let Some((claim_type, did)) = old_key {
+                unhashed::kill(&raw_key);
+                let new_key = <InvestorCountPerClaim>::hashed_key_for(
+                    &ticker,
+                    (claim_type, Scope::Identity(did)),
+                );
+                migration::stage_entry(&staging, &prefix, &new_key, &count);
+            }
+        }
+        next
+    }
+
+    /// Moves the investor counts staged by `migrate_claim_counts` to their new keys.
+    fn unstage_claim_counts(after: Option<Vec<u8>>, limit: usize) -> Option<Vec<u8>> {
+        migration::unstage_entries(
+            &migration::storage_prefix(b"statistics", b"InvestorCountPerClaimMigration"),
+            &migration::storage_prefix(b"statistics", b"InvestorCountPerClaim"),
+            after,
+            limit,
+        )
+    }
+
+    /// Removes `key` from the claims counted for the holders of `ticker`, starting at its untrack
+    /// cursor and stopping after `MAX_CLEARED_HOLDERS` holders. The cursor is removed once the
+    /// end of the holder list is reached.
+    fn clear_holder_claims_batch(ticker: &Ticker, key: &(ClaimType, Scope)) {
+        let start = Self::untrack_cursor(ticker, key).unwrap_or_default();
+        let holder_count = T::Asset::holder_count(ticker);
+        let end = holder_count.min(start.saturating_add(MAX_CLEARED_HOLDERS));
+        for index in start..end {
+            let did = T::Asset::holder_at(ticker, index);
+            let mut counted = Self::holder_claims(ticker, did);
+            if let Some(position) = counted.iter().position(|counted_key| counted_key == key) {
+                counted.remove(position);
+                if counted.is_empty() {
+                    <HolderClaims>::remove(ticker, did);
+                } else {
+                    <HolderClaims>::insert(ticker, did, counted);
+                }
+            }
+        }
+        if end == holder_count {
+            <UntrackCursor>::remove(ticker, key);
+        } else {
+            <UntrackCursor>::insert(ticker, key, end);
+        }
+    }
+
     /// It updates our statistics after transfer execution.
     /// The following counters could be updated:
     ///     - *Investor count per asset*.
+    ///     - *Investor count per tracked claim*.
+    ///     - *Balance per jurisdiction*.
+    ///     - *First acquisition time per investor*.
     ///
     pub fn update_transfer_stats(
         ticker: &Ticker,
-        updated_from_balance: Option<T::Balance>,
-        updated_to_balance: Option<T::Balance>,
+        updated_from: Option<(IdentityId, T::Balance)>,
+        updated_to: Option<(IdentityId, T::Balance)>,
         amount: T::Balance,
     ) {
-        // 1. Investor count per asset.
         if amount != 0u128.into() {
+            // 1. Investor count per asset.
             let counter = Self::investor_count_per_asset(ticker);
             let mut new_counter = counter;
 
-            if let Some(from_balance) = updated_from_balance {
+            if let Some((_, from_balance)) = updated_from {
                 if from_balance == 0u128.into() {
                     new_counter = new_counter.checked_sub(1).unwrap_or(new_counter);
                 }
             }
 
-            if let Some(to_balance) = updated_to_balance {
+            if let Some((_, to_balance)) = updated_to {
                 if to_balance == amount {
                     new_counter = new_counter.checked_add(1).unwrap_or(new_counter);
                 }
@@ -68,6 +396,128 @@ impl<T: Trait> Module<T> {
             if new_counter != counter {
                 <InvestorCountPerAsset>::insert(ticker, new_counter)
             }
+
+            // 2. Statistics per investor.
+            for (did, balance) in updated_from.into_iter().chain(updated_to) {
+                Self::update_holder_stats(ticker, did, balance);
+            }
         }
     }
+
+    /// Records the current investor count of `ticker` as the count at `checkpoint_id`.
+    pub fn snapshot_investor_count(ticker: &Ticker, checkpoint_id: u64) {
+        <InvestorCountAtCheckpoint>::insert(
+            (*ticker, checkpoint_id),
+            Self::investor_count_per_asset(ticker),
+        );
+    }
+
+    /// Moves `did` between the counters of tracked claims and jurisdictions according to its
+    /// current claims and `balance`, and keeps its first acquisition time.
+    fn update_holder_stats(ticker: &Ticker, did: IdentityId, balance: T::Balance) {
+        let is_holder = balance != Zero::zero();
+
+        // Investor count per tracked claim.
+        let tracked = Self::tracked_claims(ticker);
+        let counted = Self::holder_claims(ticker, did);
+        let held = if is_holder {
+            tracked
+                .iter()
+                .filter(|(claim_type, scope)| Self::has_claim(ticker, did, *claim_type, scope))
+                .cloned()
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        for key in counted.iter().filter(|key| !held.contains(key)) {
+            if tracked.contains(key) {
+                <InvestorCountPerClaim>::mutate(ticker, key, |count| {
+                    *count = count.saturating_sub(1)
+                });
+            }
+        }
+        for key in held.iter().filter(|key| !counted.contains(key)) {
+            <InvestorCountPerClaim>::mutate(ticker, key, |count| *count = count.saturating_add(1));
+        }
+        if held.is_empty() {
+            <HolderClaims>::remove(ticker, did);
+        } else if held != counted {
+            <HolderClaims>::insert(ticker, did, held);
+        }
+
+        // Balance per jurisdiction.
+        if let Some((jurisdiction, accounted)) = <HolderJurisdiction<T>>::take(ticker, did) {
+            <BalancePerJurisdiction<T>>::mutate(ticker, &jurisdiction, |total| {
+                *total = total.saturating_sub(accounted)
+            });
+        }
+        if is_holder {
+            if let Some(jurisdiction) = Self::jurisdiction_of(ticker, did) {
+                <BalancePerJurisdiction<T>>::mutate(ticker, &jurisdiction, |total| {
+                    *total = total.saturating_add(balance)
+                });
+                <HolderJurisdiction<T>>::insert(ticker, did, (jurisdiction, balance));
+            }
+        }
+
+        // First acquisition time.
+        if !is_holder {
+            <FirstAcquisition<T>>::remove(ticker, did);
+        } else if !<FirstAcquisition<T>>::contains_key(ticker, did) {
+            <FirstAcquisition<T>>::insert(ticker, did, <pallet_timestamp::Module<T>>::get());
+        }
+    }
+
+    /// Checks if `did` holds a claim of `claim_type` in `scope` issued by a trusted claim issuer
+    /// of `ticker`.
+    fn has_claim(ticker: &Ticker, did: IdentityId, claim_type: ClaimType, scope: &Scope) -> bool {
+        T::ComplianceManager::trusted_claim_issuers(ticker, claim_type)
+            .into_iter()
+            .any(|issuer| {
+                <Identity<T>>::fetch_claim(did, claim_type, issuer, Some(scope.clone())).is_some()
+            })
+    }
+
+    /// Returns the jurisdiction of `did` from the `Claim::Jurisdiction` scoped to the identity of
+    /// `ticker` and issued by a trusted claim issuer of `ticker`.
+    pub fn jurisdiction_of(ticker: &Ticker, did: IdentityId) -> Option<JurisdictionName> {
+        let scope = <Identity<T>>::get_token_did(ticker)
+            .ok()
+            .map(Scope::Identity);
+        T::ComplianceManager::trusted_claim_issuers(ticker, ClaimType::Jurisdiction)
+            .into_iter()
+            .filter_map(|issuer| {
+                <Identity<T>>::fetch_claim(did, ClaimType::Jurisdiction, issuer, scope.clone())
+            })
+            .find_map(|id_claim| match id_claim.claim {
+                Claim::Jurisdiction(jurisdiction, _) => Some(jurisdiction),
+                _ => None,
+            })
+    }
+
+    /// Ensures that `origin` is a signing key of the owner of `ticker` and returns its identity.
+    fn ensure_owner(origin: T::Origin, ticker: &Ticker) -> Result<IdentityId, DispatchError> {
+        let sender_key = AccountKey::try_from(ensure_signed(origin)?.encode())?;
+        let did = Context::current_identity_or::<Identity<T>>(&sender_key)?;
+        ensure!(T::Asset::is_owner(ticker, did), Error::<T>::Unauthorized);
+        Ok(did)
+    }
+}
+
+impl<T: Trait> StatisticsTrait<T::Balance, T::Moment> for Module<T> {
+    fn investor_count(ticker: &Ticker) -> u64 {
+        Self::investor_count_per_asset(ticker)
+    }
+
+    fn claim_investor_count(ticker: &Ticker, claim_type: ClaimType, scope: Scope) -> u64 {
+        Self::investor_count_per_claim(ticker, (claim_type, scope))
+    }
+
+    fn jurisdiction_balance(ticker: &Ticker, jurisdiction: &JurisdictionName) -> T::Balance {
+        Self::balance_per_jurisdiction(ticker, jurisdiction)
+    }
+
+    fn holding_since(ticker: &Ticker, did: IdentityId) -> Option<T::Moment> {
+        Self::first_acquisition(ticker, did)
+    }
 }
@@ -42,7 +42,9 @@ use frame_support::{
     weights::{DispatchInfo, GetDispatchInfo, Weight},
 };
 use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
-use primitives::{traits::IdentityCurrency, AccountKey, IdentityId, Signatory, TransactionError};
+use primitives::{
+    traits::IdentityCurrency, AccountKey, Balance, IdentityId, Signatory, TransactionError,
+};
 use sp_runtime::{
     traits::{Convert, SaturatedConversion, Saturating, SignedExtension, Zero},
     transaction_validity::{
@@ -247,6 +249,10 @@ where
         if let Some(payer) =
             T::CddHandler::get_valid_payer(call, &Signatory::from(encoded_transactor))?
         {
+            T::CddHandler::charge_spending_limit(
+                &Signatory::from(encoded_transactor),
+                fee.saturated_into::<Balance>(),
+            )?;
             let imbalance;
             match payer {
                 Signatory::AccountKey(key) => {
@@ -291,6 +297,8 @@ pub trait CddAndFeeDetails<Call> {
     fn set_payer_context(payer: Option<Signatory>);
     fn get_payer_from_context() -> Option<Signatory>;
     fn set_current_identity(did: &IdentityId);
+    /// Counts a transaction of `caller` paying `fee` against its spending limit.
+    fn charge_spending_limit(caller: &Signatory, fee: Balance) -> Result<(), InvalidTransaction>;
 }
 
 // Polymesh note: This was specifically added for Polymesh
@@ -479,6 +487,9 @@ mod tests {
             None
         }
         fn set_current_identity(_: &IdentityId) {}
+        fn charge_spending_limit(_: &Signatory, _: Balance) -> Result<(), InvalidTransaction> {
+            Ok(())
+        }
     }
 
     impl IdentityTrait for Runtime {
@@ -508,6 +519,10 @@ mod tests {
         fn is_master_key(_did: IdentityId, _key: &AccountKey) -> bool {
             unimplemented!()
         }
+        fn ensure_polyx_spending_allowed(_key: &AccountKey, _amount: Balance) -> DispatchResult {
+            Ok(())
+        }
+        fn record_polyx_spending(_key: &AccountKey, _amount: Balance) {}
 
         fn unsafe_add_systematic_cdd_claims(_targets: &[IdentityId], _issuer: SystematicIssuers) {}
         fn unsafe_revoke_systematic_cdd_claims(
@@ -524,6 +539,9 @@ mod tests {
         fn accept_asset_ownership_transfer(_: IdentityId, _: u64) -> DispatchResult {
             Ok(())
         }
+        fn accept_portfolio_custody(_: IdentityId, _: u64) -> DispatchResult {
+            Ok(())
+        }
     }
 
     pub struct TransactionBaseFee;
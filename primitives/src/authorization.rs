@@ -14,6 +14,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use crate::identity_id::IdentityId;
+use crate::portfolio::PortfolioId;
 use crate::signing_item::Signatory;
 use crate::Ticker;
 use codec::{Decode, Encode};
@@ -34,6 +35,8 @@ pub enum AuthorizationData {
     TransferAssetOwnership(Ticker),
     /// Authorization to join an Identity
     JoinIdentity(IdentityId),
+    /// Authorization to take custody of a portfolio
+    PortfolioCustody(PortfolioId),
     /// Any other authorization
     Custom(Ticker),
     /// No authorization data
@@ -19,7 +19,7 @@ use sp_core::sr25519::Public;
 use sp_runtime::{Deserialize, Serialize};
 use sp_std::{convert::From, prelude::Vec};
 
-use crate::{AccountKey, IdentityRole, Signatory, SigningItem};
+use crate::{AccountKey, BlockNumber, IdentityRole, Signatory, SigningItem};
 
 /// Identity information.
 #[allow(missing_docs)]
@@ -29,6 +29,28 @@ pub struct Identity {
     pub roles: Vec<IdentityRole>,
     pub master_key: AccountKey,
     pub signing_items: Vec<SigningItem>,
+    pub pending_master_key_rotation: Option<PendingMasterKeyRotation>,
+}
+
+/// A rotation of the master key of an identity waiting for its delay to expire.
+#[derive(Encode, Decode, Default, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct PendingMasterKeyRotation {
+    /// The key which becomes the master key.
+    pub new_master_key: AccountKey,
+    /// The block at which the rotation is enacted.
+    pub enact_at: BlockNumber,
+}
+
+/// A reduction of the delay of master key rotations of an identity waiting for the current
+/// delay to expire.
+#[derive(Encode, Decode, Default, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct PendingMasterKeyRotationDelay {
+    /// The new delay in blocks.
+    pub delay: BlockNumber,
+    /// The block at which the new delay is enacted.
+    pub enact_at: BlockNumber,
 }
 
 impl Identity {
@@ -13,15 +13,44 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{identity_id::IdentityId, Moment};
+use crate::{identity_id::IdentityId, Moment, Ticker};
 use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use sp_runtime::{Deserialize, Serialize};
 use sp_std::prelude::*;
 
-/// Scope: Almost all claim needs a valid scope identity.
-pub type Scope = IdentityId;
+/// Scope: Almost all claims are only valid within a scope.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum Scope {
+    /// The claim is valid within an identity, e.g. the identity of an asset.
+    Identity(IdentityId),
+    /// The claim is valid for an asset.
+    Ticker(Ticker),
+    /// The claim is valid within an opaque scope defined by its issuer.
+    Custom(Vec<u8>),
+}
+
+impl From<IdentityId> for Scope {
+    fn from(did: IdentityId) -> Self {
+        Scope::Identity(did)
+    }
+}
+
+impl From<Ticker> for Scope {
+    fn from(ticker: Ticker) -> Self {
+        Scope::Ticker(ticker)
+    }
+}
+
+/// Identifier of a claim type registered on-chain.
+#[derive(Encode, Decode, Copy, Clone, Default, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct CustomClaimTypeId(pub u32);
 
 /// All possible claims in polymesh
 #[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum Claim {
     /// User is Accredited
     Accredited(Scope),
@@ -43,6 +72,8 @@ pub enum Claim {
     BlackListed(Scope),
     /// Empty claim
     NoData,
+    /// User holds a claim of a type registered on-chain
+    Custom(CustomClaimTypeId, Scope),
 }
 
 impl Default for Claim {
@@ -65,6 +96,7 @@ impl Claim {
             Claim::Whitelisted(..) => ClaimType::Whitelisted,
             Claim::BlackListed(..) => ClaimType::BlackListed,
             Claim::NoData => ClaimType::NoType,
+            Claim::Custom(id, ..) => ClaimType::Custom(*id),
         }
     }
 
@@ -81,12 +113,14 @@ impl Claim {
             Claim::Whitelisted(ref scope) => Some(scope),
             Claim::BlackListed(ref scope) => Some(scope),
             Claim::NoData => None,
+            Claim::Custom(.., ref scope) => Some(scope),
         }
     }
 }
 
 /// Claim type represent the claim without its data.
 #[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum ClaimType {
     /// User is Accredited
     Accredited,
@@ -108,6 +142,8 @@ pub enum ClaimType {
     BlackListed,
     /// Empty type
     NoType,
+    /// Claim type registered on-chain
+    Custom(CustomClaimTypeId),
 }
 
 impl Default for ClaimType {
@@ -118,6 +154,7 @@ impl Default for ClaimType {
 
 /// A wrapper for Jurisdiction name.
 #[derive(Decode, Encode, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct JurisdictionName(pub Vec<u8>);
 
 impl<T: AsRef<[u8]>> From<T> for JurisdictionName {
@@ -131,6 +168,7 @@ impl<T: AsRef<[u8]>> From<T> for JurisdictionName {
 
 /// All information of a particular claim
 #[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct IdentityClaim {
     /// Issuer of the claim
     pub claim_issuer: IdentityId,
@@ -102,12 +102,14 @@ pub use identity_id::IdentityId;
 /// Identity information.
 /// Each DID is associated with this kind of record.
 pub mod identity;
-pub use identity::Identity;
+pub use identity::{Identity, PendingMasterKeyRotation, PendingMasterKeyRotationDelay};
 
 /// Claim information.
 /// Each claim is associated with this kind of record.
 pub mod identity_claim;
-pub use identity_claim::{Claim, ClaimType, IdentityClaim, JurisdictionName, Scope};
+pub use identity_claim::{
+    Claim, ClaimType, CustomClaimTypeId, IdentityClaim, JurisdictionName, Scope,
+};
 
 /// Key is strong type which stores bytes representing the key.
 pub mod account_key;
@@ -115,7 +117,9 @@ pub use account_key::AccountKey;
 
 /// This module contains entities related with signing keys.
 pub mod signing_item;
-pub use signing_item::{Permission, Signatory, SignatoryType, SigningItem};
+pub use signing_item::{
+    DispatchScope, DispatchableName, PalletName, Permission, Signatory, SignatoryType, SigningItem,
+};
 
 /// Generic authorization data types for all two step processes
 pub mod authorization;
@@ -136,11 +140,25 @@ pub use ticker::Ticker;
 
 /// This module defines types used by smart extensions
 pub mod smart_extension;
-pub use smart_extension::{SmartExtension, SmartExtensionName, SmartExtensionType};
+pub use smart_extension::{
+    RestrictionResult, SmartExtension, SmartExtensionName, SmartExtensionType,
+};
 
 pub mod document;
 pub use document::{Document, DocumentHash, DocumentName, DocumentUri};
 
+/// Portfolios that segregate the holdings of an identity.
+pub mod portfolio;
+pub use portfolio::{PortfolioId, PortfolioKind, PortfolioName, PortfolioNumber};
+
+/// Spending limits of signing keys.
+pub mod spending_limit;
+pub use spending_limit::{SpendingLimit, SpendingLimitKind, SpendingUsage};
+
+/// Social recovery of master keys.
+pub mod recovery;
+pub use recovery::{ActiveRecovery, RecoveryConfig, MAX_RECOVERY_GUARDIANS};
+
 /// Rules for claims.
 pub mod rule;
 pub use rule::{Rule, RuleType};
@@ -149,6 +167,13 @@ pub use rule::{Rule, RuleType};
 pub mod predicate;
 pub use predicate::{AndPredicate, Context, NotPredicate, OrPredicate, Predicate};
 
+/// Types and helpers of storage migrations.
+pub mod migration;
+
+/// Reports of transfer verifications.
+pub mod transfer_report;
+pub use transfer_report::{AssetTransferRuleReport, ComplianceReport, RuleReport, TransferReport};
+
 /// Represents custom transaction errors.
 #[repr(u8)]
 pub enum TransactionError {
@@ -160,6 +185,10 @@ pub enum TransactionError {
     CddRequired = 2,
     /// Invalid auth id
     InvalidAuthorization = 3,
+    /// The signing key lacks the permissions for the call
+    MissingPermissions = 4,
+    /// The signing key exceeded one of its spending limits
+    SpendingLimitExceeded = 5,
 }
 
 /// Represents the target identity and the amount requested by a beneficiary.
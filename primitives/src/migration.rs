@@ -0,0 +1,240 @@
+// This file is part of the Polymesh distribution (https://github.com/PolymathNetwork/Polymesh).
+// Copyright (c) 2020 Polymath
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+    AccountKey, Claim, Identity, IdentityClaim, IdentityId, IdentityRole, JurisdictionName, Moment,
+    Rule, RuleType, SigningItem,
+};
+use codec::{Decode, Encode};
+use frame_support::{storage::unhashed, weights::Weight};
+use sp_std::prelude::*;
+
+/// Weight of migrating a storage entry.
+pub const MIGRATED_ENTRY_WEIGHT: Weight = 100_000;
+
+/// Weight which the migrations of a module use per block. Migrations run in `on_initialize`,
+/// which cannot report its weight, so each module keeps them within a small share of the block.
+pub const MIGRATION_WEIGHT_PER_BLOCK: Weight = 5_000_000;
+
+/// Maximum number of storage entries visited by the migrations of a module per block.
+pub const MAX_MIGRATED_ENTRIES: usize =
+    (MIGRATION_WEIGHT_PER_BLOCK / MIGRATED_ENTRY_WEIGHT) as usize;
+
+/// Progress of a migration which runs over several blocks.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+pub struct MigrationCursor {
+    /// Index of the current step of the migration.
+    pub step: u32,
+    /// Raw key of the last entry visited by the current step.
+    pub last_key: Option<Vec<u8>>,
+}
+
+/// A step of a migration. It migrates the entries of a storage item found after the given raw
+/// key, visiting at most the given number of keys, and returns the raw key to resume from, or
+/// `None` once every entry has been visited.
+pub type MigrationStep = fn(Option<Vec<u8>>, usize) -> Option<Vec<u8>>;
+
+/// It runs a batch of at most `MAX_MIGRATED_ENTRIES` entries of the current step of `steps`,
+/// and returns `true` once every step is done.
+pub fn run_migration_batch(cursor: &mut MigrationCursor, steps: &[MigrationStep]) -> bool {
+    if let Some(step) = steps.get(cursor.step as usize) {
+        match step(cursor.last_key.take(), MAX_MIGRATED_ENTRIES) {
+            Some(last_key) => cursor.last_key = Some(last_key),
+            None => cursor.step += 1,
+        }
+    }
+    cursor.step as usize >= steps.len()
+}
+
+/// It returns the raw prefix of the storage item `item` of the module `module`.
+pub fn storage_prefix(module: &[u8], item: &[u8]) -> Vec<u8> {
+    [
+        sp_io::hashing::twox_128(module),
+        sp_io::hashing::twox_128(item),
+    ]
+    .concat()
+}
+
+/// It returns the raw key and the value of each entry under `prefix` whose value decodes as `V`,
+/// visiting at most `limit` keys after the raw key `after`, or from the start of `prefix`. It also
+/// returns the raw key to resume from, or `None` once every entry under `prefix` has been visited.
+pub fn raw_entries_page<V: Decode>(
+    prefix: &[u8],
+    after: Option<Vec<u8>>,
+    limit: usize,
+) -> (Vec<(Vec<u8>, V)>, Option<Vec<u8>>) {
+    let mut entries = Vec::new();
+    let mut key = after.unwrap_or_else(|| prefix.to_vec());
+    for _ in 0..limit {
+        match sp_io::storage::next_key(&key) {
+            Some(next) if next.starts_with(prefix) => {
+                if let Some(value) = unhashed::get::<V>(&next) {
+                    entries.push((next.clone(), value));
+                }
+                key = next;
+            }
+            _ => return (entries, None),
+        }
+    }
+    (entries, Some(key))
+}
+
+/// It stages an entry which a migration moves to the raw key `new_key` under `prefix`. Moving
+/// entries through `staging` keeps a migration which resumes after a raw key from visiting the
+/// entries it has already moved.
+pub fn stage_entry<V: Encode>(staging: &[u8], prefix: &[u8], new_key: &[u8], value: &V) {
+    let suffix = new_key.get(prefix.len()..).unwrap_or_default();
+    unhashed::put(&[staging, suffix].concat(), value);
+}
+
+/// It moves the entries staged under `staging` to their raw keys under `prefix`, visiting at
+/// most `limit` keys after the raw key `after`, and returns the raw key to resume from as
+/// `raw_entries_page` does.
+pub fn unstage_entries(
+    staging: &[u8],
+    prefix: &[u8],
+    after: Option<Vec<u8>>,
+    limit: usize,
+) -> Option<Vec<u8>> {
+    let mut key = after.unwrap_or_else(|| staging.to_vec());
+    for _ in 0..limit {
+        match sp_io::storage::next_key(&key) {
+            Some(next) if next.starts_with(staging) => {
+                if let Some(value) = sp_io::storage::get(&next) {
+                    sp_io::storage::set(&[prefix, &next[staging.len()..]].concat(), &value);
+                    unhashed::kill(&next);
+                }
+                key = next;
+            }
+            _ => return None,
+        }
+    }
+    Some(key)
+}
+
+/// Claims as they were encoded when `Scope` was an `IdentityId`.
+#[allow(missing_docs)]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum ClaimV1 {
+    Accredited(IdentityId),
+    Affiliate(IdentityId),
+    BuyLockup(IdentityId),
+    SellLockup(IdentityId),
+    CustomerDueDiligence,
+    KnowYourCustomer(IdentityId),
+    Jurisdiction(JurisdictionName, IdentityId),
+    Whitelisted(IdentityId),
+    BlackListed(IdentityId),
+    NoData,
+}
+
+impl From<ClaimV1> for Claim {
+    fn from(old: ClaimV1) -> Self {
+        match old {
+            ClaimV1::Accredited(did) => Claim::Accredited(did.into()),
+            ClaimV1::Affiliate(did) => Claim::Affiliate(did.into()),
+            ClaimV1::BuyLockup(did) => Claim::BuyLockup(did.into()),
+            ClaimV1::SellLockup(did) => Claim::SellLockup(did.into()),
+            ClaimV1::CustomerDueDiligence => Claim::CustomerDueDiligence,
+            ClaimV1::KnowYourCustomer(did) => Claim::KnowYourCustomer(did.into()),
+            ClaimV1::Jurisdiction(name, did) => Claim::Jurisdiction(name, did.into()),
+            ClaimV1::Whitelisted(did) => Claim::Whitelisted(did.into()),
+            ClaimV1::BlackListed(did) => Claim::BlackListed(did.into()),
+            ClaimV1::NoData => Claim::NoData,
+        }
+    }
+}
+
+/// `IdentityClaim` as it was encoded when `Scope` was an `IdentityId`.
+#[allow(missing_docs)]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct IdentityClaimV1 {
+    pub claim_issuer: IdentityId,
+    pub issuance_date: Moment,
+    pub last_update_date: Moment,
+    pub expiry: Option<Moment>,
+    pub claim: ClaimV1,
+}
+
+impl From<IdentityClaimV1> for IdentityClaim {
+    fn from(old: IdentityClaimV1) -> Self {
+        IdentityClaim {
+            claim_issuer: old.claim_issuer,
+            issuance_date: old.issuance_date,
+            last_update_date: old.last_update_date,
+            expiry: old.expiry,
+            claim: old.claim.into(),
+        }
+    }
+}
+
+/// Rule types as they were encoded when `Scope` was an `IdentityId`.
+#[allow(missing_docs)]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum RuleTypeV1 {
+    IsPresent(ClaimV1),
+    IsAbsent(ClaimV1),
+    IsAnyOf(Vec<ClaimV1>),
+    IsNoneOf(Vec<ClaimV1>),
+}
+
+impl From<RuleTypeV1> for RuleType {
+    fn from(old: RuleTypeV1) -> Self {
+        let claims = |claims: Vec<ClaimV1>| claims.into_iter().map(Claim::from).collect();
+        match old {
+            RuleTypeV1::IsPresent(claim) => RuleType::IsPresent(claim.into()),
+            RuleTypeV1::IsAbsent(claim) => RuleType::IsAbsent(claim.into()),
+            RuleTypeV1::IsAnyOf(old_claims) => RuleType::IsAnyOf(claims(old_claims)),
+            RuleTypeV1::IsNoneOf(old_claims) => RuleType::IsNoneOf(claims(old_claims)),
+        }
+    }
+}
+
+/// `Rule` as it was encoded when `Scope` was an `IdentityId`.
+#[allow(missing_docs)]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct RuleV1 {
+    pub rule_type: RuleTypeV1,
+    pub issuers: Vec<IdentityId>,
+}
+
+impl From<RuleV1> for Rule {
+    fn from(old: RuleV1) -> Self {
+        Rule {
+            rule_type: old.rule_type.into(),
+            issuers: old.issuers,
+        }
+    }
+}
+
+/// `Identity` as it was encoded before master key rotations could be delayed.
+#[allow(missing_docs)]
+#[derive(Encode, Decode, Clone, PartialEq, Debug)]
+pub struct IdentityV1 {
+    pub roles: Vec<IdentityRole>,
+    pub master_key: AccountKey,
+    pub signing_items: Vec<SigningItem>,
+}
+
+impl From<IdentityV1> for Identity {
+    fn from(old: IdentityV1) -> Self {
+        Identity {
+            roles: old.roles,
+            master_key: old.master_key,
+            signing_items: old.signing_items,
+            pending_master_key_rotation: None,
+        }
+    }
+}
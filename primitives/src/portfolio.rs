@@ -0,0 +1,88 @@
+// This file is part of the Polymesh distribution (https://github.com/PolymathNetwork/Polymesh).
+// Copyright (c) 2020 Polymath
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Portfolio types
+
+use crate::IdentityId;
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use sp_runtime::{Deserialize, Serialize};
+use sp_std::prelude::Vec;
+
+/// The number of a user-created portfolio, unique within an identity.
+pub type PortfolioNumber = u64;
+
+/// A wrapper for a portfolio name.
+#[derive(Decode, Encode, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PortfolioName(pub Vec<u8>);
+
+impl<T: AsRef<[u8]>> From<T> for PortfolioName {
+    fn from(s: T) -> Self {
+        let s = s.as_ref();
+        let mut v = Vec::with_capacity(s.len());
+        v.extend_from_slice(s);
+        PortfolioName(v)
+    }
+}
+
+/// The kind of a portfolio held by an identity.
+#[derive(Decode, Encode, Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum PortfolioKind {
+    /// The default portfolio. Every identity has one and it holds every token that has not
+    /// been explicitly moved into a user portfolio.
+    Default,
+    /// A portfolio created by the identity.
+    User(PortfolioNumber),
+}
+
+impl Default for PortfolioKind {
+    fn default() -> Self {
+        PortfolioKind::Default
+    }
+}
+
+/// Identifies a portfolio by its owner and kind.
+#[derive(Decode, Encode, Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct PortfolioId {
+    /// The identity which owns the portfolio.
+    pub did: IdentityId,
+    /// The kind of the portfolio.
+    pub kind: PortfolioKind,
+}
+
+impl PortfolioId {
+    /// Returns the default portfolio of `did`.
+    pub fn default_portfolio(did: IdentityId) -> Self {
+        Self {
+            did,
+            kind: PortfolioKind::Default,
+        }
+    }
+
+    /// Returns the user portfolio number `num` of `did`.
+    pub fn user_portfolio(did: IdentityId, num: PortfolioNumber) -> Self {
+        Self {
+            did,
+            kind: PortfolioKind::User(num),
+        }
+    }
+
+    /// Returns `true` if this is a default portfolio.
+    pub fn is_default(&self) -> bool {
+        self.kind == PortfolioKind::Default
+    }
+}
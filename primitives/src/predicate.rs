@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{Claim, Rule, RuleType};
+use crate::{Claim, IdentityClaim, JurisdictionName, Moment, Rule, RuleType, Scope};
 use codec::{Decode, Encode};
 
 use sp_std::prelude::*;
@@ -27,14 +27,36 @@ use sp_std::prelude::*;
 pub struct Context {
     /// Predicate evaluation will use those claims.
     pub claims: Vec<Claim>,
+    /// Claims with their issuance and expiry dates, used by predicates over dates.
+    pub identity_claims: Vec<IdentityClaim>,
+    /// Time of the evaluation.
+    pub now: Moment,
+    /// Balance of the evaluated identity after the transfer.
+    pub balance: u128,
 }
 
 impl From<Vec<Claim>> for Context {
     fn from(claims: Vec<Claim>) -> Self {
-        Context { claims }
+        Context {
+            claims,
+            ..Default::default()
+        }
     }
 }
 
+impl From<Vec<IdentityClaim>> for Context {
+    fn from(identity_claims: Vec<IdentityClaim>) -> Self {
+        Context {
+            claims: identity_claims.iter().map(|c| c.claim.clone()).collect(),
+            identity_claims,
+            ..Default::default()
+        }
+    }
+}
+
+/// Number of milliseconds in a day.
+pub const MILLISECONDS_PER_DAY: Moment = 86_400_000;
+
 // Predicate Trait
 // ==================================
 
@@ -91,6 +113,43 @@ pub fn any(claims: &'_ [Claim]) -> AnyPredicate<'_> {
     AnyPredicate { claims }
 }
 
+/// It creates a predicate to evaluate if a jurisdiction claim in `scope` has any of
+/// `jurisdictions`. Country codes are compared ignoring the ASCII case.
+#[inline]
+pub fn jurisdiction_in<'a>(
+    scope: &'a Scope,
+    jurisdictions: &'a [JurisdictionName],
+) -> JurisdictionPredicate<'a> {
+    JurisdictionPredicate {
+        scope,
+        jurisdictions,
+    }
+}
+
+/// It creates a predicate to evaluate if `claim` was issued at least `days` days ago.
+#[inline]
+pub fn issued_days_ago(claim: &'_ Claim, days: u32) -> IssuedDaysAgoPredicate<'_> {
+    IssuedDaysAgoPredicate { claim, days }
+}
+
+/// It creates a predicate to evaluate if the lockup `claim` is active.
+#[inline]
+pub fn locked_up(claim: &'_ Claim) -> LockupPredicate<'_> {
+    LockupPredicate { claim }
+}
+
+/// It creates a predicate to evaluate if the balance is below `limit`.
+#[inline]
+pub fn balance_below(limit: u128) -> BalanceBelowPredicate {
+    BalanceBelowPredicate { limit }
+}
+
+/// It creates a predicate to evaluate `rule`, including its nested rules, in the context.
+#[inline]
+pub fn rule(rule: &'_ Rule) -> RulePredicate<'_> {
+    RulePredicate { rule }
+}
+
 /// It create a negate predicate of `predicate`.
 #[inline]
 pub fn not<P>(predicate: P) -> NotPredicate<P>
@@ -102,12 +161,7 @@ where
 
 /// Helper function to run predicates from a context.
 pub fn run(rule: Rule, context: &Context) -> bool {
-    match rule.rule_type {
-        RuleType::IsPresent(ref claim) => exists(claim).evaluate(context),
-        RuleType::IsAbsent(ref claim) => not(exists(claim)).evaluate(context),
-        RuleType::IsAnyOf(ref claims) => any(claims).evaluate(context),
-        RuleType::IsNoneOf(ref claims) => not(any(claims)).evaluate(context),
-    }
+    self::rule(&rule).evaluate(context)
 }
 
 // ExistentialPredicate
@@ -234,6 +288,136 @@ impl<P: Predicate + Sized> Predicate for NotPredicate<P> {
     }
 }
 
+// RulePredicate
+// =========================================================
+
+/// Predicate that checks a rule, composing the predicates of its nested rules.
+#[derive(Clone, Debug)]
+pub struct RulePredicate<'a> {
+    /// Rule to check against the context.
+    pub rule: &'a Rule,
+}
+
+impl<'a> Predicate for RulePredicate<'a> {
+    /// Evaluate predicate against `context`.
+    fn evaluate(&self, context: &Context) -> bool {
+        match self.rule.rule_type {
+            RuleType::IsPresent(ref claim) => exists(claim).evaluate(context),
+            RuleType::IsAbsent(ref claim) => not(exists(claim)).evaluate(context),
+            RuleType::IsAnyOf(ref claims) => any(claims).evaluate(context),
+            RuleType::IsNoneOf(ref claims) => not(any(claims)).evaluate(context),
+            RuleType::All(ref rules) => rules.iter().all(|r| rule(r).evaluate(context)),
+            RuleType::Any(ref rules) => rules.iter().any(|r| rule(r).evaluate(context)),
+            RuleType::Not(ref r) => not(rule(r)).evaluate(context),
+            RuleType::IsJurisdictionIn(ref scope, ref jurisdictions) => {
+                jurisdiction_in(scope, jurisdictions).evaluate(context)
+            }
+            RuleType::IsJurisdictionNotIn(ref scope, ref jurisdictions) => {
+                // A jurisdiction claim is required, and it has to be out of `jurisdictions`.
+                jurisdiction_in(scope, &[])
+                    .and(not(jurisdiction_in(scope, jurisdictions)))
+                    .evaluate(context)
+            }
+            RuleType::IsIssuedDaysAgo(ref claim, days) => {
+                issued_days_ago(claim, days).evaluate(context)
+            }
+            RuleType::IsNotLockedUp(ref claim) => not(locked_up(claim)).evaluate(context),
+            RuleType::IsBalanceBelow(limit) => balance_below(limit).evaluate(context),
+        }
+    }
+}
+
+// JurisdictionPredicate
+// =========================================================
+
+/// Predicate that checks if a jurisdiction claim of the context in `scope` has any of
+/// `jurisdictions`. An empty list of jurisdictions matches any jurisdiction claim in `scope`.
+#[derive(Clone, Debug)]
+pub struct JurisdictionPredicate<'a> {
+    /// Scope of the jurisdiction claim.
+    pub scope: &'a Scope,
+    /// Country codes to find in context.
+    pub jurisdictions: &'a [JurisdictionName],
+}
+
+impl<'a> Predicate for JurisdictionPredicate<'a> {
+    /// Evaluate predicate against `context`.
+    fn evaluate(&self, context: &Context) -> bool {
+        context.claims.iter().any(|ctx_claim| match ctx_claim {
+            Claim::Jurisdiction(ref name, ref scope) if scope == self.scope => {
+                self.jurisdictions.is_empty()
+                    || self
+                        .jurisdictions
+                        .iter()
+                        .any(|valid| valid.0.eq_ignore_ascii_case(&name.0))
+            }
+            _ => false,
+        })
+    }
+}
+
+// IssuedDaysAgoPredicate
+// =========================================================
+
+/// Predicate that checks if a claim of the context was issued at least `days` days ago.
+#[derive(Clone, Debug)]
+pub struct IssuedDaysAgoPredicate<'a> {
+    /// Claim to find in context.
+    pub claim: &'a Claim,
+    /// Minimum age of the claim in days.
+    pub days: u32,
+}
+
+impl<'a> Predicate for IssuedDaysAgoPredicate<'a> {
+    /// Evaluate predicate against `context`.
+    fn evaluate(&self, context: &Context) -> bool {
+        let min_age = MILLISECONDS_PER_DAY.saturating_mul(self.days.into());
+        context.identity_claims.iter().any(|id_claim| {
+            id_claim.claim == *self.claim
+                && context.now.saturating_sub(id_claim.issuance_date) >= min_age
+        })
+    }
+}
+
+// LockupPredicate
+// =========================================================
+
+/// Predicate that checks if a lockup claim of the context is active at the evaluation time.
+/// Lockups without expiry never end.
+#[derive(Clone, Debug)]
+pub struct LockupPredicate<'a> {
+    /// Lockup claim to find in context.
+    pub claim: &'a Claim,
+}
+
+impl<'a> Predicate for LockupPredicate<'a> {
+    /// Evaluate predicate against `context`.
+    fn evaluate(&self, context: &Context) -> bool {
+        context.identity_claims.iter().any(|id_claim| {
+            id_claim.claim == *self.claim
+                && id_claim.expiry.map_or(true, |expiry| expiry > context.now)
+        })
+    }
+}
+
+// BalanceBelowPredicate
+// =========================================================
+
+/// Predicate that checks if the balance of the context is below `limit`.
+#[derive(Clone, Debug)]
+pub struct BalanceBelowPredicate {
+    /// Exclusive upper bound of the balance.
+    pub limit: u128,
+}
+
+impl Predicate for BalanceBelowPredicate {
+    /// Evaluate predicate against `context`.
+    #[inline]
+    fn evaluate(&self, context: &Context) -> bool {
+        context.balance < self.limit
+    }
+}
+
 // AnyPredicate
 // =========================================================
 
@@ -259,17 +443,20 @@ impl<'a> Predicate for AnyPredicate<'a> {
 mod tests {
     use crate::{
         predicate::{self, Context, Predicate},
-        Claim, Rule, RuleType, Scope,
+        Claim, IdentityClaim, IdentityId, JurisdictionName, Rule, RuleType, Scope,
     };
     use std::convert::From;
 
     #[test]
     fn existential_operators_test() {
-        let scope = Scope::from(0);
-        let context = Context::from(vec![Claim::CustomerDueDiligence, Claim::Affiliate(scope)]);
+        let scope = Scope::Identity(IdentityId::from(0));
+        let context = Context::from(vec![
+            Claim::CustomerDueDiligence,
+            Claim::Affiliate(scope.clone()),
+        ]);
 
         // Affiliate && CustommerDueDiligenge
-        let affiliate_claim = Claim::Affiliate(scope);
+        let affiliate_claim = Claim::Affiliate(scope.clone());
         let cdd_claim = Claim::CustomerDueDiligence;
         let affiliate_and_cdd_pred =
             predicate::exists(&affiliate_claim).and(predicate::exists(&cdd_claim));
@@ -279,21 +466,21 @@ mod tests {
 
     #[test]
     fn collection_operators_test() {
-        let scope = Scope::from(0);
+        let scope = Scope::Identity(IdentityId::from(0));
 
         // 1. Check jurisdiction "CAN" belongs to {ESP, CAN, IND}
         let valid_jurisdictions = vec![
-            Claim::Jurisdiction(b"Spain".into(), scope),
-            Claim::Jurisdiction(b"Canada".into(), scope),
-            Claim::Jurisdiction(b"India".into(), scope),
+            Claim::Jurisdiction(b"Spain".into(), scope.clone()),
+            Claim::Jurisdiction(b"Canada".into(), scope.clone()),
+            Claim::Jurisdiction(b"India".into(), scope.clone()),
         ];
 
-        let context = Context::from(vec![Claim::Jurisdiction(b"Canada".into(), scope)]);
+        let context = Context::from(vec![Claim::Jurisdiction(b"Canada".into(), scope.clone())]);
         let in_juridisction_pre = predicate::any(&valid_jurisdictions);
         assert_eq!(in_juridisction_pre.evaluate(&context), true);
 
         // 2. Check USA does not belong to {ESP, CAN, IND}.
-        let context = Context::from(vec![Claim::Jurisdiction(b"USA".into(), scope)]);
+        let context = Context::from(vec![Claim::Jurisdiction(b"USA".into(), scope.clone())]);
         assert_eq!(in_juridisction_pre.evaluate(&context), false);
 
         // 3. Check NOT in jurisdiction.
@@ -303,23 +490,23 @@ mod tests {
 
     #[test]
     fn run_predicate() {
-        let scope = Scope::from(0);
+        let scope = Scope::Identity(IdentityId::from(0));
 
         let rules: Vec<Rule> = vec![
-            RuleType::IsPresent(Claim::Accredited(scope)).into(),
-            RuleType::IsAbsent(Claim::BuyLockup(scope)).into(),
+            RuleType::IsPresent(Claim::Accredited(scope.clone())).into(),
+            RuleType::IsAbsent(Claim::BuyLockup(scope.clone())).into(),
             RuleType::IsAnyOf(vec![
-                Claim::Jurisdiction(b"USA".into(), scope),
-                Claim::Jurisdiction(b"Canada".into(), scope),
+                Claim::Jurisdiction(b"USA".into(), scope.clone()),
+                Claim::Jurisdiction(b"Canada".into(), scope.clone()),
             ])
             .into(),
-            RuleType::IsNoneOf(vec![Claim::Jurisdiction(b"Cuba".into(), scope)]).into(),
+            RuleType::IsNoneOf(vec![Claim::Jurisdiction(b"Cuba".into(), scope.clone())]).into(),
         ];
 
         // Valid case
         let context: Context = vec![
-            Claim::Accredited(scope),
-            Claim::Jurisdiction(b"Canada".into(), scope),
+            Claim::Accredited(scope.clone()),
+            Claim::Jurisdiction(b"Canada".into(), scope.clone()),
         ]
         .into();
 
@@ -330,9 +517,9 @@ mod tests {
 
         // Invalid case: `BuyLockup` is present.
         let context: Context = vec![
-            Claim::Accredited(scope),
-            Claim::BuyLockup(scope),
-            Claim::Jurisdiction(b"Canada".into(), scope),
+            Claim::Accredited(scope.clone()),
+            Claim::BuyLockup(scope.clone()),
+            Claim::Jurisdiction(b"Canada".into(), scope.clone()),
         ]
         .into();
 
@@ -343,8 +530,8 @@ mod tests {
 
         // Invalid case: Missing `Accredited`
         let context: Context = vec![
-            Claim::BuyLockup(scope),
-            Claim::Jurisdiction(b"Canada".into(), scope),
+            Claim::BuyLockup(scope.clone()),
+            Claim::Jurisdiction(b"Canada".into(), scope.clone()),
         ]
         .into();
 
@@ -355,8 +542,8 @@ mod tests {
 
         // Invalid case: Missing `Jurisdiction`
         let context: Context = vec![
-            Claim::Accredited(scope),
-            Claim::Jurisdiction(b"Spain".into(), scope),
+            Claim::Accredited(scope.clone()),
+            Claim::Jurisdiction(b"Spain".into(), scope.clone()),
         ]
         .into();
 
@@ -367,8 +554,8 @@ mod tests {
 
         // Check NoneOf
         let context: Context = vec![
-            Claim::Accredited(scope),
-            Claim::Jurisdiction(b"Cuba".into(), scope),
+            Claim::Accredited(scope.clone()),
+            Claim::Jurisdiction(b"Cuba".into(), scope.clone()),
         ]
         .into();
         let out = !rules
@@ -376,4 +563,92 @@ mod tests {
             .any(|rule| !predicate::run(rule.clone(), &context));
         assert_eq!(out, false);
     }
+
+    #[test]
+    fn run_nested_predicate() {
+        let scope = Scope::Identity(IdentityId::from(0));
+
+        // (Accredited AND KYC) OR Affiliate
+        let rule: Rule = RuleType::Any(vec![
+            RuleType::All(vec![
+                RuleType::IsPresent(Claim::Accredited(scope.clone())).into(),
+                RuleType::IsPresent(Claim::KnowYourCustomer(scope.clone())).into(),
+            ])
+            .into(),
+            RuleType::IsPresent(Claim::Affiliate(scope.clone())).into(),
+        ])
+        .into();
+        assert_eq!(rule.depth(), 3);
+        assert_eq!(rule.size(), 5);
+
+        let context: Context = vec![
+            Claim::Accredited(scope.clone()),
+            Claim::KnowYourCustomer(scope.clone()),
+        ]
+        .into();
+        assert_eq!(predicate::run(rule.clone(), &context), true);
+
+        let context: Context = vec![Claim::Accredited(scope.clone())].into();
+        assert_eq!(predicate::run(rule.clone(), &context), false);
+
+        let context: Context = vec![Claim::Affiliate(scope.clone())].into();
+        assert_eq!(predicate::run(rule.clone(), &context), true);
+
+        // NOT Affiliate
+        let not_affiliate: Rule = RuleType::Not(Box::new(
+            RuleType::IsPresent(Claim::Affiliate(scope.clone())).into(),
+        ))
+        .into();
+        assert_eq!(predicate::run(not_affiliate, &context), false);
+    }
+
+    #[test]
+    fn run_value_predicates() {
+        let scope = Scope::Identity(IdentityId::from(0));
+        let day = predicate::MILLISECONDS_PER_DAY;
+        let id_claim = |claim, issuance_date, expiry| IdentityClaim {
+            claim,
+            issuance_date,
+            expiry,
+            ..Default::default()
+        };
+
+        let mut context: Context = vec![
+            id_claim(Claim::Jurisdiction(b"ca".into(), scope.clone()), 0, None),
+            id_claim(Claim::Accredited(scope.clone()), 10 * day, None),
+            id_claim(Claim::BuyLockup(scope.clone()), 0, Some(40 * day)),
+        ]
+        .into();
+        context.now = 30 * day;
+        context.balance = 1_000;
+
+        let jurisdictions = vec![JurisdictionName::from(b"US"), JurisdictionName::from(b"CA")];
+        let in_rule: Rule = RuleType::IsJurisdictionIn(scope.clone(), jurisdictions.clone()).into();
+        let not_in_rule: Rule = RuleType::IsJurisdictionNotIn(scope.clone(), jurisdictions).into();
+        assert_eq!(predicate::run(in_rule, &context), true);
+        assert_eq!(predicate::run(not_in_rule.clone(), &context), false);
+        // A jurisdiction is required to be out of the list.
+        assert_eq!(predicate::run(not_in_rule, &Context::default()), false);
+
+        let issued_20_days_ago: Rule =
+            RuleType::IsIssuedDaysAgo(Claim::Accredited(scope.clone()), 20).into();
+        let issued_21_days_ago: Rule =
+            RuleType::IsIssuedDaysAgo(Claim::Accredited(scope.clone()), 21).into();
+        assert_eq!(predicate::run(issued_20_days_ago, &context), true);
+        assert_eq!(predicate::run(issued_21_days_ago, &context), false);
+
+        let not_locked_up: Rule = RuleType::IsNotLockedUp(Claim::BuyLockup(scope.clone())).into();
+        assert_eq!(predicate::run(not_locked_up.clone(), &context), false);
+        context.now = 40 * day;
+        assert_eq!(predicate::run(not_locked_up, &context), true);
+
+        assert_eq!(
+            predicate::run(RuleType::IsBalanceBelow(1_001).into(), &context),
+            true
+        );
+        assert_eq!(
+            predicate::run(RuleType::IsBalanceBelow(1_000).into(), &context),
+            false
+        );
+    }
 }
@@ -0,0 +1,94 @@
+// This file is part of the Polymesh distribution (https://github.com/PolymathNetwork/Polymesh).
+// Copyright (c) 2020 Polymath
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{AccountKey, BlockNumber, Signatory};
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use sp_runtime::{Deserialize, Serialize};
+use sp_std::prelude::Vec;
+
+/// Maximum number of guardians of a recovery configuration.
+pub const MAX_RECOVERY_GUARDIANS: usize = 16;
+
+/// Guardians able to install a new master key in an identity which lost its master key.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct RecoveryConfig {
+    /// Identities or keys which approve a recovery.
+    pub guardians: Vec<Signatory>,
+    /// Number of guardians that have to approve a recovery.
+    pub threshold: u32,
+    /// Number of blocks between the last required approval and the installation of the new
+    /// master key, during which the current master key can cancel the recovery. The master key
+    /// rotation delay of the identity applies too if it is longer.
+    pub delay: BlockNumber,
+}
+
+impl RecoveryConfig {
+    /// Returns `true` if the guardians are unique, at most `MAX_RECOVERY_GUARDIANS` and can reach
+    /// the threshold.
+    pub fn is_valid(&self) -> bool {
+        let mut guardians = self.guardians.clone();
+        guardians.sort();
+        guardians.dedup();
+
+        guardians.len() == self.guardians.len()
+            && guardians.len() <= MAX_RECOVERY_GUARDIANS
+            && self.threshold > 0
+            && self.threshold as usize <= self.guardians.len()
+    }
+}
+
+/// A recovery of the master key of an identity in progress.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct ActiveRecovery {
+    /// The key to install as the new master key.
+    pub new_master_key: AccountKey,
+    /// Guardians which approved the recovery.
+    pub approvals: Vec<Signatory>,
+    /// The block at which the recovery reached its threshold of approvals.
+    pub approved_at: Option<BlockNumber>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdentityId;
+
+    #[test]
+    fn recovery_config_validity_test() {
+        let guardians = vec![
+            Signatory::from(IdentityId::from(1)),
+            Signatory::from(AccountKey::from([2u8; 32])),
+        ];
+        let config = |guardians: Vec<Signatory>, threshold| RecoveryConfig {
+            guardians,
+            threshold,
+            delay: 0,
+        };
+
+        assert!(config(guardians.clone(), 1).is_valid());
+        assert!(config(guardians.clone(), 2).is_valid());
+        assert!(!config(guardians.clone(), 0).is_valid());
+        assert!(!config(guardians.clone(), 3).is_valid());
+        assert!(!config(vec![guardians[0], guardians[0]], 1).is_valid());
+        assert!(!config(vec![], 0).is_valid());
+        let too_many = (0..=MAX_RECOVERY_GUARDIANS as u128)
+            .map(|i| Signatory::from(IdentityId::from(i)))
+            .collect::<Vec<_>>();
+        assert!(!config(too_many, 1).is_valid());
+    }
+}
@@ -13,11 +13,14 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{Claim, ClaimType, IdentityId};
+use crate::{Claim, ClaimType, IdentityId, JurisdictionName, Scope};
 use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use sp_runtime::{Deserialize, Serialize};
 use sp_std::prelude::*;
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 /// It defines the type of rule supported, and the filter information we will use to evaluate as a
 /// predicate.
 pub enum RuleType {
@@ -29,6 +32,23 @@ pub enum RuleType {
     IsAnyOf(Vec<Claim>),
     /// Rule to ensure that at none of claims is fetched when filter is applied.
     IsNoneOf(Vec<Claim>),
+    /// Rule to ensure that all nested rules are satisfied.
+    All(Vec<Rule>),
+    /// Rule to ensure that at least one of nested rules is satisfied.
+    Any(Vec<Rule>),
+    /// Rule to ensure that the nested rule is not satisfied.
+    Not(Box<Rule>),
+    /// Rule to ensure that a jurisdiction claim in the scope has one of the given country codes.
+    IsJurisdictionIn(Scope, Vec<JurisdictionName>),
+    /// Rule to ensure that a jurisdiction claim in the scope has none of the given country codes.
+    IsJurisdictionNotIn(Scope, Vec<JurisdictionName>),
+    /// Rule to ensure that the claim was issued at least the given number of days ago.
+    IsIssuedDaysAgo(Claim, u32),
+    /// Rule to ensure that no lockup claim (`BuyLockup` or `SellLockup`) is active at the time
+    /// of the transfer. Lockups without expiry never end.
+    IsNotLockedUp(Claim),
+    /// Rule to ensure that the balance after the transfer is below the given amount.
+    IsBalanceBelow(u128),
 }
 
 impl RuleType {
@@ -39,12 +59,34 @@ impl RuleType {
     /// will be ignored.
     /// If user defines a empty list of claims in `IsAnyOf`, `Jurisdiction` type will be used by
     /// default.
+    /// Composite rules (`All`, `Any` and `Not`) fetch no claims by themselves, so `NoType` is
+    /// returned.
     pub fn as_claim_type(&self) -> ClaimType {
         match self {
             RuleType::IsPresent(ref claim) => claim.claim_type(),
             RuleType::IsAbsent(ref claim) => claim.claim_type(),
             RuleType::IsNoneOf(ref claims) => Self::get_claim_type(claims.as_slice()),
             RuleType::IsAnyOf(ref claims) => Self::get_claim_type(claims.as_slice()),
+            RuleType::IsJurisdictionIn(..) | RuleType::IsJurisdictionNotIn(..) => {
+                ClaimType::Jurisdiction
+            }
+            RuleType::IsIssuedDaysAgo(ref claim, _) | RuleType::IsNotLockedUp(ref claim) => {
+                claim.claim_type()
+            }
+            RuleType::All(..) | RuleType::Any(..) | RuleType::Not(..) => ClaimType::NoType,
+            RuleType::IsBalanceBelow(..) => ClaimType::NoType,
+        }
+    }
+
+    /// It returns the claims referenced by this rule type, excluding those of nested rules.
+    pub fn claims(&self) -> &[Claim] {
+        match self {
+            RuleType::IsPresent(ref claim)
+            | RuleType::IsAbsent(ref claim)
+            | RuleType::IsIssuedDaysAgo(ref claim, _)
+            | RuleType::IsNotLockedUp(ref claim) => sp_std::slice::from_ref(claim),
+            RuleType::IsAnyOf(ref claims) | RuleType::IsNoneOf(ref claims) => claims.as_slice(),
+            _ => &[],
         }
     }
 
@@ -59,6 +101,7 @@ impl RuleType {
 
 /// Type of claim requirements that a rule can have
 #[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct Rule {
     /// Type of rule.
     pub rule_type: RuleType,
@@ -66,6 +109,62 @@ pub struct Rule {
     pub issuers: Vec<IdentityId>,
 }
 
+impl Rule {
+    /// It returns the nested rules of a composite rule, or an empty slice otherwise.
+    pub fn nested_rules(&self) -> &[Rule] {
+        match self.rule_type {
+            RuleType::All(ref rules) | RuleType::Any(ref rules) => rules.as_slice(),
+            RuleType::Not(ref rule) => sp_std::slice::from_ref(rule.as_ref()),
+            _ => &[],
+        }
+    }
+
+    /// Number of levels of this rule tree. A simple rule has depth 1.
+    pub fn depth(&self) -> usize {
+        1 + self
+            .nested_rules()
+            .iter()
+            .map(Rule::depth)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Claim types checked by the simple rules of this rule tree, without duplicates.
+    pub fn claim_types(&self) -> Vec<ClaimType> {
+        let mut claim_types = self
+            .nested_rules()
+            .iter()
+            .flat_map(Rule::claim_types)
+            .collect::<Vec<_>>();
+        match self.rule_type {
+            RuleType::IsJurisdictionIn(..) | RuleType::IsJurisdictionNotIn(..) => {
+                claim_types.push(ClaimType::Jurisdiction)
+            }
+            ref rule_type => claim_types.extend(rule_type.claims().iter().map(Claim::claim_type)),
+        }
+        claim_types.sort();
+        claim_types.dedup();
+        claim_types
+    }
+
+    /// It returns true if a simple rule of this rule tree checks no claim, like `IsBalanceBelow`.
+    pub fn has_rule_without_claims(&self) -> bool {
+        match self.rule_type {
+            RuleType::All(..) | RuleType::Any(..) | RuleType::Not(..) => self
+                .nested_rules()
+                .iter()
+                .any(Rule::has_rule_without_claims),
+            RuleType::IsJurisdictionIn(..) | RuleType::IsJurisdictionNotIn(..) => false,
+            ref rule_type => rule_type.claims().is_empty(),
+        }
+    }
+
+    /// Number of rules of this rule tree, including itself.
+    pub fn size(&self) -> usize {
+        1 + self.nested_rules().iter().map(Rule::size).sum::<usize>()
+    }
+}
+
 impl From<RuleType> for Rule {
     fn from(rule_type: RuleType) -> Self {
         Rule {
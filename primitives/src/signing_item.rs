@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{AccountKey, IdentityId};
+use crate::{AccountKey, IdentityId, PortfolioId, Ticker};
 use codec::{Decode, Encode};
 #[cfg(feature = "std")]
 use sp_runtime::{Deserialize, Serialize};
@@ -30,8 +30,15 @@ use sp_std::{
 /// 2. Review documents:
 ///     - [MESH-235](https://polymath.atlassian.net/browse/MESH-235)
 ///     - [Polymesh: Roles/Permissions](https://docs.google.com/document/d/12u-rMavow4fvidsFlLcLe7DAXuqWk8XUHOBV9kw05Z8/)
+///
+/// The scoped permissions restrict a signing key to what they name:
+/// - `Pallet` and `Extrinsic` entries form an allow list of dispatchables,
+/// - `Ticker`, `Portfolio` and `Counterparty` entries form allow lists of the tickers,
+/// portfolios and identities that a call may touch.
+///
+/// A key without entries of one kind is not restricted along that dimension.
 #[allow(missing_docs)]
-#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord)]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, PartialOrd, Ord)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum Permission {
     Full,
@@ -39,6 +46,51 @@ pub enum Permission {
     Operator,
     SpendFunds,
     Custom(u8),
+    /// Any dispatchable of the named pallet.
+    Pallet(PalletName),
+    /// A single dispatchable of a pallet.
+    Extrinsic(PalletName, DispatchableName),
+    /// Calls on the given ticker.
+    Ticker(Ticker),
+    /// Calls moving funds out of or managing the given portfolio.
+    Portfolio(PortfolioId),
+    /// Calls whose counterparty is the given identity.
+    Counterparty(IdentityId),
+}
+
+/// The name of a pallet as used in signing key permissions, e.g. `asset`.
+pub type PalletName = Vec<u8>;
+
+/// The name of a dispatchable as used in signing key permissions, e.g. `transfer`.
+pub type DispatchableName = Vec<u8>;
+
+/// What a call touches, as far as signing key permissions are concerned.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct DispatchScope {
+    /// Name of the pallet of the call.
+    pub pallet: PalletName,
+    /// Name of the dispatchable.
+    pub dispatchable: DispatchableName,
+    /// Tickers the call operates on.
+    pub tickers: Vec<Ticker>,
+    /// Portfolios the call moves funds out of or manages.
+    pub portfolios: Vec<PortfolioId>,
+    /// Identities on the other side of the call.
+    pub counterparties: Vec<IdentityId>,
+    /// Whether the other side of the call includes accounts without an identity, which no
+    /// counterparty permission can list.
+    pub unresolved_counterparties: bool,
+}
+
+impl DispatchScope {
+    /// Creates the scope of the dispatchable `dispatchable` of the pallet `pallet`.
+    pub fn new(pallet: &[u8], dispatchable: &[u8]) -> Self {
+        Self {
+            pallet: pallet.to_vec(),
+            dispatchable: dispatchable.to_vec(),
+            ..Default::default()
+        }
+    }
 }
 
 /// Signing key type.
@@ -179,6 +231,61 @@ impl SigningItem {
             .iter()
             .any(|r| permission == *r || *r == Permission::Full)
     }
+
+    /// It checks if this key may be used for a call with the given `scope`.
+    pub fn is_permitted(&self, scope: &DispatchScope) -> bool {
+        if self.permissions.contains(&Permission::Full) {
+            return true;
+        }
+
+        let dispatchables = self
+            .permissions
+            .iter()
+            .filter_map(|p| match p {
+                Permission::Pallet(pallet) => Some((pallet, None)),
+                Permission::Extrinsic(pallet, dispatchable) => Some((pallet, Some(dispatchable))),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let dispatchable_allowed = dispatchables.is_empty()
+            || dispatchables.iter().any(|(pallet, dispatchable)| {
+                **pallet == scope.pallet && dispatchable.map_or(true, |d| *d == scope.dispatchable)
+            });
+
+        dispatchable_allowed
+            && self.allows_all(&scope.tickers, |p| match p {
+                Permission::Ticker(ticker) => Some(ticker),
+                _ => None,
+            })
+            && self.allows_all(&scope.portfolios, |p| match p {
+                Permission::Portfolio(portfolio) => Some(portfolio),
+                _ => None,
+            })
+            && self.allows_all(&scope.counterparties, |p| match p {
+                Permission::Counterparty(did) => Some(did),
+                _ => None,
+            })
+            && !(scope.unresolved_counterparties
+                && self.permissions.iter().any(|p| match p {
+                    Permission::Counterparty(_) => true,
+                    _ => false,
+                }))
+    }
+
+    /// Checks that every item in `items` is listed among the permissions selected by `select`,
+    /// unless there is no such permission at all.
+    fn allows_all<V: PartialEq>(
+        &self,
+        items: &[V],
+        select: impl Fn(&Permission) -> Option<&V>,
+    ) -> bool {
+        let allowed = self
+            .permissions
+            .iter()
+            .filter_map(select)
+            .collect::<Vec<_>>();
+        allowed.is_empty() || items.iter().all(|item| allowed.contains(&item))
+    }
 }
 
 impl From<AccountKey> for SigningItem {
@@ -228,8 +335,8 @@ impl Ord for SigningItem {
 
 #[cfg(test)]
 mod tests {
-    use super::{AccountKey, Permission, Signatory, SigningItem};
-    use crate::IdentityId;
+    use super::{AccountKey, DispatchScope, Permission, Signatory, SigningItem};
+    use crate::{IdentityId, PortfolioId, Ticker};
     use std::convert::{From, TryFrom};
 
     #[test]
@@ -271,6 +378,68 @@ mod tests {
         assert_eq!(not_full_key.has_permission(Permission::Admin), false);
     }
 
+    #[test]
+    fn scoped_permission_test() {
+        let key = AccountKey::try_from("ABCDABCD".as_bytes()).unwrap();
+        let ticker = Ticker::try_from(&b"ACME"[..]).unwrap();
+        let other_ticker = Ticker::try_from(&b"EMCA"[..]).unwrap();
+        let bob = IdentityId::from(2u128);
+
+        let mut transfer = DispatchScope::new(b"asset", b"transfer");
+        transfer.tickers = vec![ticker];
+        transfer.counterparties = vec![bob];
+        let mut issue = DispatchScope::new(b"asset", b"issue");
+        issue.tickers = vec![other_ticker];
+        let freeze = DispatchScope::new(b"asset", b"freeze");
+        let add_claim = DispatchScope::new(b"identity", b"add_claim");
+
+        let unrestricted = SigningItem::new(Signatory::AccountKey(key), vec![Permission::Admin]);
+        assert!(unrestricted.is_permitted(&transfer));
+        assert!(unrestricted.is_permitted(&add_claim));
+
+        let desk = SigningItem::new(
+            Signatory::AccountKey(key),
+            vec![
+                Permission::Extrinsic(b"asset".to_vec(), b"issue".to_vec()),
+                Permission::Extrinsic(b"asset".to_vec(), b"transfer".to_vec()),
+                Permission::Ticker(ticker),
+            ],
+        );
+        assert!(desk.is_permitted(&transfer));
+        assert!(!desk.is_permitted(&issue));
+        assert!(!desk.is_permitted(&freeze));
+        assert!(!desk.is_permitted(&add_claim));
+
+        let pallet = SigningItem::new(
+            Signatory::AccountKey(key),
+            vec![
+                Permission::Pallet(b"asset".to_vec()),
+                Permission::Counterparty(IdentityId::from(3u128)),
+                Permission::Portfolio(PortfolioId::default_portfolio(bob)),
+            ],
+        );
+        assert!(pallet.is_permitted(&issue));
+        assert!(pallet.is_permitted(&freeze));
+        assert!(!pallet.is_permitted(&transfer));
+        assert!(!pallet.is_permitted(&add_claim));
+
+        // Accounts without an identity can't be listed as counterparties.
+        let mut polyx_transfer = DispatchScope::new(b"balances", b"transfer");
+        polyx_transfer.unresolved_counterparties = true;
+        let payments = SigningItem::new(
+            Signatory::AccountKey(key),
+            vec![Permission::Counterparty(bob)],
+        );
+        assert!(!payments.is_permitted(&polyx_transfer));
+        assert!(unrestricted.is_permitted(&polyx_transfer));
+
+        let full = SigningItem::new(
+            Signatory::AccountKey(key),
+            vec![Permission::Ticker(other_ticker), Permission::Full],
+        );
+        assert!(full.is_permitted(&transfer));
+    }
+
     #[test]
     fn signer_build_and_eq_tests() {
         let k = "ABCDABCD".as_bytes().to_vec();
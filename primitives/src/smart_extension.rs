@@ -14,6 +14,8 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use sp_runtime::{Deserialize, Serialize};
 use sp_std::prelude::Vec;
 
 /// Smart Extension types
@@ -57,3 +59,19 @@ pub struct SmartExtension<U> {
     /// Status of the smart extension
     pub is_archive: bool,
 }
+
+/// Enum that uses as the return type for the restriction verification.
+#[allow(missing_docs)]
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum RestrictionResult {
+    Valid,
+    Invalid,
+    ForceValid,
+}
+
+impl Default for RestrictionResult {
+    fn default() -> Self {
+        RestrictionResult::Invalid
+    }
+}
@@ -0,0 +1,62 @@
+// This file is part of the Polymesh distribution (https://github.com/PolymathNetwork/Polymesh).
+// Copyright (c) 2020 Polymath
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::Ticker;
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use sp_runtime::{Deserialize, Serialize};
+use sp_std::prelude::Vec;
+
+/// Limits on what a signing key may spend on behalf of its identity.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct SpendingLimit<Balance, Moment> {
+    /// Length of the period over which POLYX and asset spending is accumulated.
+    pub period: Moment,
+    /// Maximum POLYX spent per period, in transfers and transaction fees.
+    pub max_polyx: Option<Balance>,
+    /// Maximum amount of each listed asset transferred per period.
+    pub max_assets: Vec<(Ticker, Balance)>,
+    /// Maximum number of transactions per day.
+    pub max_daily_transactions: Option<u32>,
+}
+
+/// What a signing key has spent in its current period and day.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct SpendingUsage<Balance, Moment> {
+    /// Start of the current spending period.
+    pub period_start: Moment,
+    /// POLYX spent in the current period.
+    pub polyx: Balance,
+    /// Amount of each asset transferred in the current period.
+    pub assets: Vec<(Ticker, Balance)>,
+    /// Start of the current day.
+    pub day_start: Moment,
+    /// Transactions made in the current day.
+    pub transactions: u32,
+}
+
+/// A spending limit of a signing key.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum SpendingLimitKind {
+    /// The maximum POLYX spent per period.
+    Polyx,
+    /// The maximum amount of an asset transferred per period.
+    Asset(Ticker),
+    /// The maximum number of transactions per day.
+    DailyTransactions,
+}
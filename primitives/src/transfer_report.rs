@@ -0,0 +1,94 @@
+// This file is part of the Polymesh distribution (https://github.com/PolymathNetwork/Polymesh).
+// Copyright (c) 2020 Polymath
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{IdentityClaim, IdentityId, RestrictionResult, Rule};
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use sp_runtime::{Deserialize, Serialize};
+use sp_std::prelude::*;
+
+/// Evaluation of a rule for an identity.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RuleReport {
+    /// Evaluated rule.
+    pub rule: Rule,
+    /// Result of the evaluation. It is true if the identity is exempted from the rule.
+    pub result: bool,
+    /// It is true if the identity is exempted from the rule, so it is not checked on transfers.
+    pub exempted: bool,
+    /// Trusted issuers used to evaluate the rule.
+    pub issuers: Vec<IdentityId>,
+    /// Claims consulted by a simple rule.
+    pub claims: Vec<IdentityClaim>,
+    /// Reports of the nested rules of a composite rule.
+    pub nested: Vec<RuleReport>,
+}
+
+/// Evaluation of an asset rule.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct AssetTransferRuleReport {
+    /// Identifier of the asset rule.
+    pub rule_id: u32,
+    /// Reports of the sender rules. It is empty if there is no sender.
+    pub sender_rules: Vec<RuleReport>,
+    /// Reports of the receiver rules. It is empty if there is no receiver.
+    pub receiver_rules: Vec<RuleReport>,
+    /// It is true if all sender and receiver rules are satisfied.
+    pub result: bool,
+}
+
+/// Evaluation of the compliance requirements of an asset.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ComplianceReport {
+    /// It is true if no transfer restriction is broken.
+    pub restrictions_satisfied: bool,
+    /// It is true if the asset rules are paused.
+    pub paused: bool,
+    /// Reports of each asset rule.
+    pub rules: Vec<AssetTransferRuleReport>,
+    /// Final result of the compliance verification.
+    pub result: bool,
+}
+
+/// Evaluation of every check of a transfer.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct TransferReport<AccountId, Balance> {
+    /// Status code of the transfer, as returned by `can_transfer`.
+    pub status: u8,
+    /// It is true if the amount respects the granularity of the asset.
+    pub granularity: bool,
+    /// Balance of the sender before the transfer, if there is a sender.
+    pub sender_balance: Option<Balance>,
+    /// It is true if the sender has enough balance in its default portfolio.
+    pub sufficient_balance: bool,
+    /// It is true if the sender has a valid CDD claim, if there is a sender.
+    pub sender_cdd: Option<bool>,
+    /// It is true if the receiver has a valid CDD claim, if there is a receiver.
+    pub receiver_cdd: Option<bool>,
+    /// It is true if the transfers of the asset are frozen.
+    pub frozen: bool,
+    /// Compliance manager report.
+    pub compliance: ComplianceReport,
+    /// Result of each active transfer manager extension.
+    pub extensions: Vec<(AccountId, RestrictionResult)>,
+}